@@ -0,0 +1,62 @@
+import { EventEmitter } from 'events'
+
+export interface CursorState {
+  position: [number, number]
+  cursor_type: string
+  left_click: boolean
+  right_click: boolean
+  visible: boolean
+  timestamp: string
+  [field: string]: unknown
+}
+
+export type MouseButton = 'Left' | 'Right' | 'Middle'
+
+/** Pen or touch contact; each field is absent when the device does not report it */
+export interface ContactData {
+  /** 0.0 to 1.0 */
+  pressure?: number
+  /** X/Y tilt in degrees */
+  tilt?: [number, number]
+}
+
+export interface MoveEvent {
+  position: [number, number]
+  smoothed?: [number, number]
+  contact?: ContactData
+  timestamp: string
+  [field: string]: unknown
+}
+
+export interface ClickEvent {
+  button: MouseButton
+  position: [number, number]
+  contact?: ContactData
+  /** Pixels the cursor went past the click point before coming back */
+  overshoot_px?: number
+  timestamp: string
+  [field: string]: unknown
+}
+
+export interface ReleaseEvent {
+  button: MouseButton
+  /** Where the button went down, absent when the press was not seen */
+  press_position?: [number, number]
+  /** Milliseconds the button was held */
+  held_for: number
+  timestamp: string
+  [field: string]: unknown
+}
+
+export declare class CursorDetector extends EventEmitter {
+  constructor()
+  start(): this
+  stop(): this
+  getState(): CursorState
+  on(event: 'move', listener: (event: MoveEvent) => void): this
+  on(event: 'click', listener: (event: ClickEvent) => void): this
+  on(event: 'release', listener: (event: ReleaseEvent) => void): this
+  on(event: 'event', listener: (kind: string, event: Record<string, unknown>) => void): this
+  on(event: 'error', listener: (error: Error) => void): this
+  on(event: string, listener: (event: Record<string, unknown>) => void): this
+}
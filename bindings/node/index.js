@@ -0,0 +1,57 @@
+'use strict'
+
+const { EventEmitter } = require('events')
+const { join } = require('path')
+
+const { NativeDetector } = require(join(__dirname, `luuma-cursor-helper.${process.platform}-${process.arch}-msvc.node`))
+
+/** Event name for a Rust variant name, e.g. `ViewportUpdate` -> `viewportUpdate` */
+function eventName(kind) {
+  return kind.charAt(0).toLowerCase() + kind.slice(1)
+}
+
+/**
+ * Cursor detector emitting one event per cursor event kind
+ *
+ * Listen with `detector.on('click', (click) => ...)`; every event is also
+ * emitted as `'event'` with its kind, and `'error'` reports undecodable payloads.
+ */
+class CursorDetector extends EventEmitter {
+  constructor() {
+    super()
+    this._native = new NativeDetector((json) => this._dispatch(json))
+  }
+
+  /** Start monitoring on a background thread */
+  start() {
+    this._native.start()
+    return this
+  }
+
+  /** Stop monitoring; the detector can be started again */
+  stop() {
+    this._native.stop()
+    return this
+  }
+
+  /** Current cursor position, type and button state */
+  getState() {
+    return JSON.parse(this._native.stateJson())
+  }
+
+  _dispatch(json) {
+    let event
+    try {
+      event = JSON.parse(json)
+    } catch (error) {
+      this.emit('error', error)
+      return
+    }
+    const kind = Object.keys(event)[0]
+    const name = eventName(kind)
+    this.emit(name, event[kind])
+    this.emit('event', name, event[kind])
+  }
+}
+
+module.exports = { CursorDetector }
@@ -0,0 +1,91 @@
+//! Native addon behind the `luuma-cursor-helper` npm package
+//!
+//! Wraps the crate's C API so the monitor thread, restart and shutdown logic is
+//! shared with other embedders. `index.js` turns the JSON events into
+//! `EventEmitter` events.
+
+use luuma_cursor_helper::ffi::{
+    luuma_create, luuma_destroy, luuma_free_string, luuma_get_state_json, luuma_set_event_callback, luuma_start,
+    luuma_stop, LuumaDetector, LUUMA_ERR_NOT_RUNNING, LUUMA_ERR_RUNNING, LUUMA_OK,
+};
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, Error, JsFunction, Result, Status};
+use napi_derive::napi;
+use std::ffi::{c_char, c_void, CStr};
+
+type EventFunction = ThreadsafeFunction<String, ErrorStrategy::Fatal>;
+
+/// Forward an event from the processing thread to the JS thread
+extern "C" fn forward_event(event_json: *const c_char, user_data: *mut c_void) {
+    let function = unsafe { &*(user_data as *const EventFunction) };
+    let json = unsafe { CStr::from_ptr(event_json) }.to_string_lossy().into_owned();
+    function.call(json, ThreadsafeFunctionCallMode::NonBlocking);
+}
+
+fn check(code: i32, action: &str) -> Result<()> {
+    match code {
+        LUUMA_OK => Ok(()),
+        LUUMA_ERR_RUNNING => Err(Error::new(Status::GenericFailure, "Monitoring already started".to_string())),
+        LUUMA_ERR_NOT_RUNNING => Err(Error::new(Status::GenericFailure, "Monitoring is not running".to_string())),
+        _ => Err(Error::new(Status::GenericFailure, format!("Failed to {} monitoring", action))),
+    }
+}
+
+/// Detector driven from JavaScript, calling `onEvent` with each event as JSON
+#[napi]
+pub struct NativeDetector {
+    raw: *mut LuumaDetector,
+    /// Boxed so the pointer handed to the C callback stays put
+    on_event: Box<EventFunction>,
+}
+
+#[napi]
+impl NativeDetector {
+    #[napi(constructor)]
+    pub fn new(env: Env, on_event: JsFunction) -> Result<Self> {
+        let mut on_event: EventFunction = on_event
+            .create_threadsafe_function(0, |context: ThreadSafeCallContext<String>| {
+                Ok(vec![context.env.create_string(&context.value)?])
+            })?;
+        // Only a running detector keeps the Node event loop alive
+        on_event.unref(&env)?;
+
+        let mut detector = Self { raw: luuma_create(), on_event: Box::new(on_event) };
+        let user_data = &mut *detector.on_event as *mut EventFunction as *mut c_void;
+        check(unsafe { luuma_set_event_callback(detector.raw, Some(forward_event), user_data) }, "configure")?;
+        Ok(detector)
+    }
+
+    /// Start monitoring on a background thread
+    #[napi]
+    pub fn start(&mut self, env: Env) -> Result<()> {
+        check(unsafe { luuma_start(self.raw) }, "start")?;
+        self.on_event.refer(&env)
+    }
+
+    /// Stop monitoring; the detector can be started again
+    #[napi]
+    pub fn stop(&mut self, env: Env) -> Result<()> {
+        check(unsafe { luuma_stop(self.raw) }, "stop")?;
+        self.on_event.unref(&env)
+    }
+
+    /// Current cursor state as JSON
+    #[napi]
+    pub fn state_json(&self) -> String {
+        let state = unsafe { luuma_get_state_json(self.raw) };
+        if state.is_null() {
+            return String::new();
+        }
+        let json = unsafe { CStr::from_ptr(state) }.to_string_lossy().into_owned();
+        unsafe { luuma_free_string(state) };
+        json
+    }
+}
+
+impl Drop for NativeDetector {
+    fn drop(&mut self) {
+        // Stops the detector before the callback it points to is freed
+        unsafe { luuma_destroy(self.raw) };
+    }
+}
@@ -1,5 +1,7 @@
-use luuma_cursor_helper::{CursorDetector, CursorState, CursorEvent, MouseButton};
+#[cfg(windows)]
+use luuma_cursor_helper::{CursorDetector, CursorState, CursorEvent, CursorType, InputSource, MouseButton, Timestamp};
 
+#[cfg(windows)]
 fn main() {
     println!("=== Luuma Cursor Helper Library Example ===\n");
 
@@ -14,10 +16,13 @@ fn main() {
     println!("2. CursorState Creation:");
     let state = CursorState {
         position: (100.0, 200.0),
-        cursor_type: "arrow".to_string(),
+        cursor_type: CursorType::Arrow,
         left_click: false,
         right_click: false,
-        timestamp: CursorDetector::get_timestamp(),
+        visible: true,
+        animation: None,
+        geometry: None,
+        timestamp: Timestamp::now(),
     };
     println!("   Created state: {:?}", state);
     println!("   JSON: {}", state.to_json());
@@ -27,22 +32,129 @@ fn main() {
     println!("3. Event Handler Setup:");
     detector.set_event_handler(|event: CursorEvent| {
         match event {
-            CursorEvent::Move { position, cursor_type, timestamp } => {
+            CursorEvent::Move { position, cursor_type, timestamp, .. } => {
                 println!("   [EVENT] Cursor moved to {:?} with type '{}' at {}", 
                          position, cursor_type, timestamp);
             }
-            CursorEvent::Click { button, position, timestamp } => {
+            CursorEvent::Click { button, position, timestamp, .. } => {
                 println!("   [EVENT] {} click at {:?} at {}", 
                          button, position, timestamp);
             }
-            CursorEvent::Release { button, timestamp } => {
+            CursorEvent::Release { button, timestamp, .. } => {
                 println!("   [EVENT] {} button released at {}", 
                          button, timestamp);
             }
+            CursorEvent::LongPress { button, position, duration, timestamp } => {
+                println!("   [EVENT] {} button held for {:?} at {:?} at {}", 
+                         button, duration, position, timestamp);
+            }
+            CursorEvent::RageClick { button, position, count, timestamp, .. } => {
+                println!("   [EVENT] Rage click: {} {} clicks at {:?} at {}", 
+                         count, button, position, timestamp);
+            }
+            CursorEvent::Scroll { axis, delta, position, timestamp, .. } => {
+                println!("   [EVENT] Scrolled {} by {} at {:?} at {}", 
+                         axis, delta, position, timestamp);
+            }
+            CursorEvent::ScrollSession { axis, total_delta, duration, reversals, timestamp, .. } => {
+                println!("   [EVENT] Scroll session: {} {} over {:?}, {} reversal(s), at {}", 
+                         axis, total_delta, duration, reversals, timestamp);
+            }
             CursorEvent::TypeChange { new_type, position, timestamp } => {
                 println!("   [EVENT] Cursor type changed to '{}' at {:?} at {}", 
                          new_type, position, timestamp);
             }
+            CursorEvent::TypeDwell { cursor_type, duration, timestamp, .. } => {
+                println!("   [EVENT] Cursor was '{}' for {:?} until {}", 
+                         cursor_type, duration, timestamp);
+            }
+            CursorEvent::ConfinementChanged { rect, timestamp, .. } => {
+                println!("   [EVENT] Cursor confinement changed to {:?} at {}", 
+                         rect, timestamp);
+            }
+            CursorEvent::VisibilityChanged { visible, position, timestamp } => {
+                println!("   [EVENT] Cursor {} at {:?} at {}", 
+                         if visible { "shown" } else { "hidden" }, position, timestamp);
+            }
+            CursorEvent::WindowChanged { window, timestamp, .. } => {
+                println!("   [EVENT] Hovering '{}' ({}) at {}", 
+                         window.title, window.process_name, timestamp);
+            }
+            CursorEvent::FocusChanged { process_name, title, timestamp, .. } => {
+                println!("   [EVENT] Focus moved to '{}' ({}) at {}", 
+                         title, process_name, timestamp);
+            }
+            CursorEvent::RegionEnter { name, position, timestamp } => {
+                println!("   [EVENT] Entered region '{}' at {:?} at {}", 
+                         name, position, timestamp);
+            }
+            CursorEvent::RegionExit { name, position, timestamp } => {
+                println!("   [EVENT] Left region '{}' at {:?} at {}", 
+                         name, position, timestamp);
+            }
+            CursorEvent::DesktopChanged { state, timestamp } => {
+                println!("   [EVENT] Desktop changed to {:?} at {}", 
+                         state, timestamp);
+            }
+            CursorEvent::RemoteSessionChanged { remote, timestamp } => {
+                println!("   [EVENT] Session is now {} at {}", 
+                         if remote { "remote" } else { "local" }, timestamp);
+            }
+            CursorEvent::Gesture { kind, position, timestamp } => {
+                println!("   [EVENT] Gesture {:?} at {:?} at {}", 
+                         kind, position, timestamp);
+            }
+            CursorEvent::MonitorChanged { from, to, position, timestamp } => {
+                println!("   [EVENT] Moved from monitor {} to {} at {:?} at {}", 
+                         from, to, position, timestamp);
+            }
+            CursorEvent::RawMotion { delta, position, timestamp, .. } => {
+                println!("   [EVENT] Raw motion {:?} at {:?} at {}", 
+                         delta, position, timestamp);
+            }
+            #[cfg(feature = "pointer")]
+            CursorEvent::Touch { id, phase, position, timestamp, .. } => {
+                println!("   [EVENT] Touch {} {:?} at {:?} at {}", 
+                         id, phase, position, timestamp);
+            }
+            #[cfg(feature = "pointer")]
+            CursorEvent::Pen { phase, position, pressure, timestamp, .. } => {
+                println!("   [EVENT] Pen {:?} at {:?} with pressure {:.2} at {}", 
+                         phase, position, pressure, timestamp);
+            }
+            #[cfg(feature = "uia")]
+            CursorEvent::Element { trigger, element, timestamp, .. } => {
+                println!("   [EVENT] {:?} on {} '{}' ({}) at {}", 
+                         trigger, element.control_type, element.name, element.automation_id, timestamp);
+            }
+            CursorEvent::ViewportUpdate { viewport, timestamp } => {
+                println!("   [EVENT] Viewport moved to {:?} at {}", 
+                         viewport.to_rect(), timestamp);
+            }
+            CursorEvent::Marker { label, timestamp, .. } => {
+                println!("   [EVENT] Marker '{}' at {}", 
+                         label, timestamp);
+            }
+            CursorEvent::Redacted { kind, timestamp } => {
+                println!("   [EVENT] {:?} event redacted at {}", 
+                         kind, timestamp);
+            }
+            CursorEvent::ListenerRestarted { attempts, gap, timestamp } => {
+                println!("   [EVENT] Mouse hook restarted after {} attempt(s), {:?} missed at {}", 
+                         attempts, gap, timestamp);
+            }
+            CursorEvent::FlowStalled { silent, position, timestamp } => {
+                println!("   [EVENT] No hook events for {:?} while moving at {:?} at {}", 
+                         silent, position, timestamp);
+            }
+            CursorEvent::HandlerPanicked { handler, message, timestamp } => {
+                println!("   [EVENT] {} panicked: {} at {}", 
+                         handler, message, timestamp);
+            }
+            CursorEvent::SessionEnd { duration, stats, timestamp } => {
+                println!("   [EVENT] Session ended after {:?} with {} clicks at {}", 
+                         duration, stats.total_clicks(), timestamp);
+            }
         }
     });
     println!("   Event handler configured to log all cursor events\n");
@@ -68,13 +180,43 @@ fn main() {
     println!("6. JSON Serialization:");
     let sample_event = CursorEvent::Move {
         position: (500.0, 600.0),
-        cursor_type: "hand".to_string(),
-        timestamp: CursorDetector::get_timestamp(),
+        cursor_type: CursorType::Hand,
+        smoothed: None,
+        monitor: None,
+        window: None,
+        client: None,
+        source: InputSource::default(),
+        contact: None,
+        device_id: None,
+        precise_time: None,
+        timestamp: Timestamp::now(),
     };
     println!("   Event JSON: {}", sample_event.to_json());
     println!();
 
-    println!("7. Starting Monitoring:");
+    // @note demonstrate re-emitting external events through the pipeline
+    println!("7. Emitting External Events:");
+    let handle = detector.handle();
+    let external_click = CursorEvent::Click {
+        button: MouseButton::Left,
+        position: (320.0, 240.0),
+        monitor: None,
+        window: None,
+        client: None,
+        // @note mark re-emitted events as injected so analytics can tell them apart
+        source: InputSource { injected: true, ..InputSource::default() },
+        contact: None,
+        overshoot_px: None,
+        device_id: None,
+        precise_time: None,
+        timestamp: Timestamp::now(),
+    };
+    if let Err(error) = handle.emit(external_click) {
+        eprintln!("   Failed to emit event: {}", error);
+    }
+    println!("   Queued an external click, delivered once monitoring starts\n");
+
+    println!("8. Starting Monitoring:");
     println!("   The detector will now start monitoring cursor activities.");
     println!("   Move your mouse and click to see events in action.");
     println!("   Press Ctrl+C to stop.\n");
@@ -84,3 +226,8 @@ fn main() {
         eprintln!("Error starting monitoring: {}", error);
     }
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example is only available on Windows");
+}
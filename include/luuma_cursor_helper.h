@@ -0,0 +1,93 @@
+#ifndef LUUMA_CURSOR_HELPER_H
+#define LUUMA_CURSOR_HELPER_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The call succeeded
+#define LUUMA_OK 0
+
+// The detector pointer was null
+#define LUUMA_ERR_NULL -1
+
+// The call needs a stopped detector but monitoring is running
+#define LUUMA_ERR_RUNNING -2
+
+// The call needs running monitoring but the detector is stopped
+#define LUUMA_ERR_NOT_RUNNING -3
+
+// Monitoring failed to start or stop; details are in the detector log
+#define LUUMA_ERR_FAILED -4
+
+// Opaque detector owned by the C caller
+typedef struct LuumaDetector LuumaDetector;
+
+// Called with each event as a NUL-terminated JSON string and the registered user data
+//
+// Runs on the detector's processing thread. The string is only valid during the call.
+typedef void (*LuumaEventCallback)(const char *event_json, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create a detector with default settings; free it with [`luuma_destroy`]
+LuumaDetector *luuma_create(void);
+
+// Stop monitoring if needed and free the detector
+//
+// # Safety
+//
+// `detector` must come from [`luuma_create`] and not be used afterwards.
+void luuma_destroy(LuumaDetector *detector);
+
+// Register the event callback, or clear it with `NULL`; only while stopped
+//
+// # Safety
+//
+// `detector` must come from [`luuma_create`]. `user_data` is passed to the
+// callback from another thread and must stay valid until the detector is
+// stopped.
+int32_t luuma_set_event_callback(LuumaDetector *detector, LuumaEventCallback callback, void *user_data);
+
+// Start monitoring on a background thread and return immediately
+//
+// # Safety
+//
+// `detector` must come from [`luuma_create`].
+int32_t luuma_start(LuumaDetector *detector);
+
+// Current cursor state as a JSON string, or `NULL` for a null detector
+//
+// Free the string with [`luuma_free_string`].
+//
+// # Safety
+//
+// `detector` must come from [`luuma_create`].
+char *luuma_get_state_json(const LuumaDetector *detector);
+
+// Free a string returned by this library
+//
+// # Safety
+//
+// `string` must come from this library and not be used afterwards.
+void luuma_free_string(char *string);
+
+// Stop monitoring and wait for the detector's threads to finish
+//
+// The detector can be started again afterwards.
+//
+// # Safety
+//
+// `detector` must come from [`luuma_create`].
+int32_t luuma_stop(LuumaDetector *detector);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LUUMA_CURSOR_HELPER_H */
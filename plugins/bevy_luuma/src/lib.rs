@@ -0,0 +1,92 @@
+//! Bevy plugin exposing the global cursor as ECS events and a resource
+//!
+//! Unlike Bevy's own cursor input, positions are in physical desktop pixels and
+//! keep coming while the cursor is outside the game's windows.
+//!
+//! ```ignore
+//! App::new()
+//!     .add_plugins((DefaultPlugins, LuumaPlugin::default()))
+//!     .add_systems(Update, |mut events: EventReader<LuumaEvent>, cursor: Res<GlobalCursor>| {
+//!         for LuumaEvent(event) in events.read() {
+//!             println!("{:?} with the cursor at {:?}", event.kind(), cursor.position);
+//!         }
+//!     });
+//! ```
+
+use bevy_app::{App, Plugin, PreUpdate};
+use bevy_ecs::prelude::*;
+use luuma_cursor_helper::{BackgroundDetector, CursorDetector, CursorEvent, CursorState};
+use std::ops::Deref;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+
+/// A cursor event, sent in `PreUpdate` of the frame after it happened
+#[derive(Event, Debug, Clone)]
+pub struct LuumaEvent(pub CursorEvent);
+
+/// Cursor state as of the start of this frame
+#[derive(Resource, Debug, Clone)]
+pub struct GlobalCursor(pub CursorState);
+
+impl Deref for GlobalCursor {
+    type Target = CursorState;
+
+    fn deref(&self) -> &CursorState {
+        &self.0
+    }
+}
+
+/// The running detector and the events it queued for the next frame
+#[derive(Resource)]
+struct LuumaDetector {
+    background: Mutex<BackgroundDetector>,
+    events: Mutex<Receiver<CursorEvent>>,
+}
+
+/// Starts a detector when added and feeds [`LuumaEvent`] and [`GlobalCursor`] each frame
+pub struct LuumaPlugin {
+    detector: Box<dyn Fn() -> CursorDetector + Send + Sync>,
+}
+
+impl LuumaPlugin {
+    /// Plugin with a detector built by `detector`, e.g. from [`CursorDetector::builder`]
+    pub fn with_detector<F>(detector: F) -> Self
+    where
+        F: Fn() -> CursorDetector + Send + Sync + 'static,
+    {
+        Self { detector: Box::new(detector) }
+    }
+}
+
+impl Default for LuumaPlugin {
+    fn default() -> Self {
+        Self::with_detector(CursorDetector::new)
+    }
+}
+
+impl Plugin for LuumaPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        let mut detector = (self.detector)();
+        detector.set_event_handler(move |event| {
+            let _ = sender.send(event);
+        });
+
+        let mut background = BackgroundDetector::new(detector);
+        if let Err(error) = background.start() {
+            CursorDetector::log_message(&format!("Failed to start cursor tracking: {}", error));
+        }
+        let state = background.handle().get_state();
+
+        app.add_event::<LuumaEvent>()
+            .insert_resource(GlobalCursor(state))
+            .insert_resource(LuumaDetector { background: Mutex::new(background), events: Mutex::new(receiver) })
+            .add_systems(PreUpdate, update_cursor);
+    }
+}
+
+fn update_cursor(detector: Res<LuumaDetector>, mut cursor: ResMut<GlobalCursor>, mut events: EventWriter<LuumaEvent>) {
+    let receiver = detector.events.lock().unwrap_or_else(|e| e.into_inner());
+    events.send_batch(receiver.try_iter().map(LuumaEvent));
+    cursor.0 = detector.background.lock().unwrap_or_else(|e| e.into_inner()).handle().get_state();
+}
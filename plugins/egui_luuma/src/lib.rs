@@ -0,0 +1,194 @@
+//! Draining detector events into an egui app, with a ready-made debug window
+//!
+//! ```ignore
+//! struct App {
+//!     cursor: LuumaEgui,
+//!     show_monitor: bool,
+//! }
+//!
+//! impl eframe::App for App {
+//!     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+//!         for event in self.cursor.poll() {
+//!             // react to this frame's events
+//!         }
+//!         self.cursor.debug_window(ctx, &mut self.show_monitor);
+//!     }
+//! }
+//! ```
+
+use egui::{Color32, Context, Grid, RichText, ScrollArea, Ui, Window};
+use luuma_cursor_helper::{BackgroundDetector, CursorDetector, CursorEvent, CursorState, SessionStats};
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// Events kept in the debug window's log
+const LOG_CAPACITY: usize = 500;
+
+/// How often the debug window refreshes statistics
+const STATS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A running detector feeding an egui app
+pub struct LuumaEgui {
+    background: BackgroundDetector,
+    ctx: Context,
+    sender: Sender<CursorEvent>,
+    receiver: Receiver<CursorEvent>,
+    frame_events: Vec<CursorEvent>,
+    state: CursorState,
+    log: VecDeque<CursorEvent>,
+    log_moves: bool,
+    log_paused: bool,
+    stats: Option<(SessionStats, Instant)>,
+}
+
+impl LuumaEgui {
+    /// Start a default detector that repaints `ctx` when events arrive
+    pub fn start(ctx: &Context) -> Result<Self, String> {
+        Self::start_with(ctx, CursorDetector::new())
+    }
+
+    /// Start a configured detector that repaints `ctx` when events arrive
+    pub fn start_with(ctx: &Context, detector: CursorDetector) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::channel();
+        let mut egui = Self {
+            background: BackgroundDetector::new(detector),
+            ctx: ctx.clone(),
+            sender,
+            receiver,
+            frame_events: Vec::new(),
+            state: CursorState::new(),
+            log: VecDeque::with_capacity(LOG_CAPACITY),
+            log_moves: false,
+            log_paused: false,
+            stats: None,
+        };
+        egui.start_detector()?;
+        egui.state = egui.background.handle().get_state();
+        Ok(egui)
+    }
+
+    /// Start the detector with a fresh handler feeding this app, whatever was configured while stopped
+    fn start_detector(&mut self) -> Result<(), String> {
+        if let Some(detector) = self.background.detector_mut() {
+            let (sender, ctx) = (self.sender.clone(), self.ctx.clone());
+            detector.set_event_handler(move |event| {
+                if sender.send(event).is_ok() {
+                    ctx.request_repaint();
+                }
+            });
+        }
+        self.background.start()
+    }
+
+    /// Take the events that arrived since the last call and refresh the state; call once per frame
+    pub fn poll(&mut self) -> &[CursorEvent] {
+        self.frame_events.clear();
+        self.frame_events.extend(self.receiver.try_iter());
+        self.state = self.background.handle().get_state();
+
+        if !self.log_paused {
+            // Moves drown out everything else unless asked for
+            let log_moves = self.log_moves;
+            let logged =
+                self.frame_events.iter().filter(|event| log_moves || !matches!(event, CursorEvent::Move { .. }));
+            for event in logged {
+                if self.log.len() == LOG_CAPACITY {
+                    self.log.pop_front();
+                }
+                self.log.push_back(event.clone());
+            }
+        }
+        &self.frame_events
+    }
+
+    /// Cursor state as of the last [`poll`](Self::poll)
+    pub fn state(&self) -> &CursorState {
+        &self.state
+    }
+
+    /// The detector, for stopping, restarting or querying it directly
+    pub fn detector(&mut self) -> &mut BackgroundDetector {
+        &mut self.background
+    }
+
+    /// Window with live state, statistics and the event log
+    pub fn debug_window(&mut self, ctx: &Context, open: &mut bool) {
+        Window::new("Cursor monitor").open(open).default_width(360.0).show(ctx, |ui| self.debug_ui(ui));
+    }
+
+    /// Contents of the debug window, for embedding in a panel of your own
+    pub fn debug_ui(&mut self, ui: &mut Ui) {
+        let running = self.background.is_running();
+        ui.horizontal(|ui| {
+            let (status, color) = if running { ("Running", Color32::GREEN) } else { ("Stopped", Color32::RED) };
+            ui.label(RichText::new(status).color(color));
+            let toggle = if running { "Stop" } else { "Start" };
+            if ui.button(toggle).clicked() {
+                let result = if running { self.background.stop() } else { self.start_detector() };
+                if let Err(error) = result {
+                    CursorDetector::log_message(&error);
+                }
+            }
+        });
+
+        ui.separator();
+        Grid::new("luuma_state").num_columns(2).striped(true).show(ui, |ui| {
+            let state = &self.state;
+            ui.label("Position");
+            ui.label(format!("{:.0}, {:.0}", state.position.0, state.position.1));
+            ui.end_row();
+            ui.label("Cursor");
+            ui.label(state.cursor_type.to_string());
+            ui.end_row();
+            ui.label("Buttons");
+            ui.label(format!(
+                "{}{}",
+                if state.left_click { "Left " } else { "" },
+                if state.right_click { "Right" } else { "" }
+            ));
+            ui.end_row();
+            ui.label("Visible");
+            ui.label(if state.visible { "Yes" } else { "No" });
+            ui.end_row();
+        });
+
+        if self.stats.as_ref().is_none_or(|(_, at)| at.elapsed() >= STATS_INTERVAL) {
+            self.stats = Some((self.background.handle().stats(), Instant::now()));
+        }
+        if let Some((stats, _)) = &self.stats {
+            ui.collapsing("Statistics", |ui| {
+                Grid::new("luuma_stats").num_columns(2).striped(true).show(ui, |ui| {
+                    ui.label("Clicks");
+                    ui.label(stats.total_clicks().to_string());
+                    ui.end_row();
+                    ui.label("Distance");
+                    ui.label(format!("{:.0} px ({:.2} m)", stats.distance_px, stats.distance_m));
+                    ui.end_row();
+                    ui.label("Active");
+                    ui.label(format!("{:.0} s", stats.active_time.as_secs_f64()));
+                    ui.end_row();
+                    ui.label("Idle");
+                    ui.label(format!("{:.0} s", stats.idle_time.as_secs_f64()));
+                    ui.end_row();
+                });
+            });
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!("Events ({})", self.log.len()));
+            ui.checkbox(&mut self.log_moves, "Moves");
+            ui.checkbox(&mut self.log_paused, "Pause");
+            if ui.button("Clear").clicked() {
+                self.log.clear();
+            }
+        });
+        ScrollArea::vertical().max_height(240.0).stick_to_bottom(true).show(ui, |ui| {
+            for event in &self.log {
+                let position = event.position().map(|(x, y)| format!(" at {:.0}, {:.0}", x, y)).unwrap_or_default();
+                ui.monospace(format!("{} {:?}{}", event.timestamp(), event.kind(), position));
+            }
+        });
+    }
+}
@@ -0,0 +1,62 @@
+//! Cursor events delivered through iced's message loop
+//!
+//! ```ignore
+//! fn subscription(&self) -> Subscription<Message> {
+//!     iced_luuma::cursor_events().map(Message::Cursor)
+//! }
+//! ```
+//!
+//! The detector starts when the subscription is first returned and stops when
+//! the application stops returning it.
+
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, StreamExt};
+use iced::Subscription;
+use luuma_cursor_helper::{BackgroundDetector, CursorDetector, CursorEvent};
+use std::hash::Hash;
+
+/// Events buffered between the detector and the application before it waits
+const CHANNEL_SIZE: usize = 256;
+
+/// Events from a default detector
+pub fn cursor_events() -> Subscription<CursorEvent> {
+    Subscription::run(default_stream)
+}
+
+/// Events from a detector built by `detector`; `id` tells iced's subscriptions apart
+pub fn cursor_events_with<I>(id: I, detector: fn() -> CursorDetector) -> Subscription<CursorEvent>
+where
+    I: Hash + 'static,
+{
+    Subscription::run_with_id(id, detector_stream(detector))
+}
+
+fn default_stream() -> impl Stream<Item = CursorEvent> {
+    detector_stream(CursorDetector::new)
+}
+
+/// Stream running a detector for as long as it is polled
+pub fn detector_stream<F>(detector: F) -> impl Stream<Item = CursorEvent>
+where
+    F: FnOnce() -> CursorDetector + Send + 'static,
+{
+    iced::stream::channel(CHANNEL_SIZE, move |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        let mut detector = detector();
+        detector.set_event_handler(move |event| {
+            let _ = sender.unbounded_send(event);
+        });
+
+        // Dropping the background detector when the stream ends stops monitoring
+        let mut background = BackgroundDetector::new(detector);
+        if let Err(error) = background.start() {
+            CursorDetector::log_message(&format!("Failed to start cursor tracking: {}", error));
+            return;
+        }
+        while let Some(event) = receiver.next().await {
+            if output.send(event).await.is_err() {
+                break;
+            }
+        }
+    })
+}
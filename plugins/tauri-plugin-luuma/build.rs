@@ -0,0 +1,5 @@
+const COMMANDS: &[&str] = &["start", "stop", "is_running", "get_state", "set_filter"];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
@@ -0,0 +1,74 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export type EventKind =
+  | 'Move'
+  | 'Click'
+  | 'Release'
+  | 'LongPress'
+  | 'RageClick'
+  | 'Scroll'
+  | 'ScrollSession'
+  | 'TypeChange'
+  | 'TypeDwell'
+  | 'VisibilityChanged'
+  | 'ConfinementChanged'
+  | 'RegionEnter'
+  | 'RegionExit'
+  | 'Gesture'
+  | 'MonitorChanged'
+  | 'WindowChanged'
+  | 'FocusChanged'
+  | 'DesktopChanged'
+  | 'RemoteSessionChanged'
+  | 'RawMotion'
+  | 'ViewportUpdate'
+  | 'Marker'
+  | 'Redacted'
+  | 'ListenerRestarted'
+  | 'FlowStalled'
+  | 'HandlerPanicked'
+  | 'SessionEnd'
+
+export interface CursorState {
+  position: [number, number]
+  cursor_type: string
+  left_click: boolean
+  right_click: boolean
+  visible: boolean
+  timestamp: string
+  [field: string]: unknown
+}
+
+/** Start tracking and emitting events */
+export async function start(): Promise<void> {
+  await invoke('plugin:luuma|start')
+}
+
+/** Stop tracking; it can be started again */
+export async function stop(): Promise<void> {
+  await invoke('plugin:luuma|stop')
+}
+
+export async function isRunning(): Promise<boolean> {
+  return await invoke('plugin:luuma|is_running')
+}
+
+/** Current cursor position, type and button state */
+export async function getState(): Promise<CursorState> {
+  return await invoke('plugin:luuma|get_state')
+}
+
+/** Only emit events of these kinds, or every kind when omitted */
+export async function setFilter(kinds?: EventKind[]): Promise<void> {
+  await invoke('plugin:luuma|set_filter', { kinds: kinds ?? null })
+}
+
+/** Listen for one kind of event, e.g. `onCursorEvent('Click', (click) => ...)` */
+export async function onCursorEvent<T = Record<string, unknown>>(
+  kind: EventKind,
+  handler: (event: T) => void
+): Promise<UnlistenFn> {
+  const name = kind.replace(/(?!^)([A-Z])/g, '-$1').toLowerCase()
+  return await listen<T>(`luuma://${name}`, (event) => handler(event.payload))
+}
@@ -0,0 +1,28 @@
+use crate::LuumaExt;
+use luuma_cursor_helper::{CursorState, EventKind};
+use tauri::{command, AppHandle, Runtime};
+
+#[command]
+pub(crate) fn start<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    app.luuma().start(&app)
+}
+
+#[command]
+pub(crate) fn stop<R: Runtime>(app: AppHandle<R>) -> Result<(), String> {
+    app.luuma().stop()
+}
+
+#[command]
+pub(crate) fn is_running<R: Runtime>(app: AppHandle<R>) -> bool {
+    app.luuma().is_running()
+}
+
+#[command]
+pub(crate) fn get_state<R: Runtime>(app: AppHandle<R>) -> CursorState {
+    app.luuma().get_state()
+}
+
+#[command]
+pub(crate) fn set_filter<R: Runtime>(app: AppHandle<R>, kinds: Option<Vec<EventKind>>) {
+    app.luuma().set_filter(kinds.as_deref());
+}
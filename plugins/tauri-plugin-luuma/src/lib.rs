@@ -0,0 +1,111 @@
+//! Tauri plugin forwarding cursor events to the webview
+//!
+//! Register it with `.plugin(tauri_plugin_luuma::init())`. Once started, every
+//! event is emitted as `luuma://<kind>`, e.g. `luuma://click` or
+//! `luuma://viewport-update`, with the event's fields as payload. The guest
+//! bindings in `guest-js` wrap the commands and listeners.
+
+use luuma_cursor_helper::{BackgroundDetector, CursorDetector, CursorEvent, CursorState, EventKind, EventKindMask};
+use std::sync::Mutex;
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+mod commands;
+
+/// Event name for a kind, e.g. `ViewportUpdate` -> `luuma://viewport-update`
+fn event_name(kind: EventKind) -> String {
+    let mut name = String::from("luuma://");
+    for (index, character) in format!("{:?}", kind).chars().enumerate() {
+        if character.is_ascii_uppercase() && index > 0 {
+            name.push('-');
+        }
+        name.push(character.to_ascii_lowercase());
+    }
+    name
+}
+
+/// The app's detector, managed as plugin state
+pub struct Luuma {
+    background: Mutex<BackgroundDetector>,
+}
+
+impl Luuma {
+    fn new(detector: CursorDetector) -> Self {
+        Self { background: Mutex::new(BackgroundDetector::new(detector)) }
+    }
+
+    /// Start tracking and emitting events to every webview
+    pub fn start<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        let mut background = self.background.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(detector) = background.detector_mut() else {
+            return Err("Monitoring already started".to_string());
+        };
+        let app = app.clone();
+        detector.set_event_handler(move |event: CursorEvent| {
+            // Emit the variant's fields rather than the externally tagged enum
+            let payload = match serde_json::to_value(&event) {
+                Ok(serde_json::Value::Object(mut tagged)) => tagged.values_mut().next().map(serde_json::Value::take),
+                _ => None,
+            };
+            let _ = app.emit(&event_name(event.kind()), payload.unwrap_or_default());
+        });
+        background.start()
+    }
+
+    /// Stop tracking; it can be started again
+    pub fn stop(&self) -> Result<(), String> {
+        self.background.lock().unwrap_or_else(|e| e.into_inner()).stop()
+    }
+
+    /// Whether tracking is running
+    pub fn is_running(&self) -> bool {
+        self.background.lock().unwrap_or_else(|e| e.into_inner()).is_running()
+    }
+
+    /// Current cursor position, type and button state
+    pub fn get_state(&self) -> CursorState {
+        self.background.lock().unwrap_or_else(|e| e.into_inner()).handle().get_state()
+    }
+
+    /// Only emit events of these kinds, or every kind with `None`; applies while running
+    pub fn set_filter(&self, kinds: Option<&[EventKind]>) {
+        let mask = kinds.map_or_else(EventKindMask::default, EventKindMask::of);
+        self.background.lock().unwrap_or_else(|e| e.into_inner()).handle().set_event_mask(mask);
+    }
+}
+
+/// Access to the plugin's [`Luuma`] state from the app, windows and webviews
+pub trait LuumaExt<R: Runtime> {
+    fn luuma(&self) -> &Luuma;
+}
+
+impl<R: Runtime, T: Manager<R>> LuumaExt<R> for T {
+    fn luuma(&self) -> &Luuma {
+        self.state::<Luuma>().inner()
+    }
+}
+
+/// Plugin with a default detector
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    init_with(CursorDetector::new)
+}
+
+/// Plugin with a detector built by `detector`, e.g. from [`CursorDetector::builder`]
+pub fn init_with<R: Runtime, F>(detector: F) -> TauriPlugin<R>
+where
+    F: FnOnce() -> CursorDetector + Send + 'static,
+{
+    Builder::new("luuma")
+        .invoke_handler(tauri::generate_handler![
+            commands::start,
+            commands::stop,
+            commands::is_running,
+            commands::get_state,
+            commands::set_filter
+        ])
+        .setup(move |app, _api| {
+            app.manage(Luuma::new(detector()));
+            Ok(())
+        })
+        .build()
+}
@@ -0,0 +1,115 @@
+//! Global cursor events in a winit window's terms
+//!
+//! The detector reports physical desktop coordinates from a global hook, so it
+//! sees the cursor everywhere. [`WindowBridge`] turns those events into logical
+//! coordinates relative to one window's client area and synthesizes the enter
+//! and leave transitions winit would report for it, counting the window as
+//! left when another window covers the cursor.
+
+use luuma_cursor_helper::window::{client_position, window_handle_at};
+use luuma_cursor_helper::{CursorEvent, MouseButton};
+use winit::dpi::{LogicalPosition, PhysicalPosition};
+use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use winit::window::Window;
+
+/// A global event as seen from the bridged window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BridgeEvent {
+    /// The cursor moved onto the window's visible client area
+    CursorEntered { position: LogicalPosition<f64> },
+    /// The cursor moved off the window or behind another window
+    CursorLeft { position: LogicalPosition<f64> },
+    /// The cursor moved, `inside` telling whether it is over the window
+    CursorMoved { position: LogicalPosition<f64>, inside: bool },
+    /// A button changed state at the last known position
+    MouseInput { button: MouseButton, pressed: bool, position: LogicalPosition<f64>, inside: bool },
+}
+
+/// Converts global events for one window and tracks whether the cursor is over it
+pub struct WindowBridge {
+    hwnd: usize,
+    inside: bool,
+    position: Option<LogicalPosition<f64>>,
+}
+
+impl WindowBridge {
+    /// Bridge for a window, failing when it is not a Win32 window
+    pub fn new(window: &Window) -> Result<Self, String> {
+        let handle = window.window_handle().map_err(|e| format!("Failed to get window handle: {}", e))?;
+        let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+            return Err("Window is not a Win32 window".to_string());
+        };
+        Ok(Self { hwnd: handle.hwnd.get() as usize, inside: false, position: None })
+    }
+
+    /// Raw `HWND` of the bridged window
+    pub fn hwnd(&self) -> usize {
+        self.hwnd
+    }
+
+    /// Whether the cursor was over the window at the last translated event
+    pub fn is_inside(&self) -> bool {
+        self.inside
+    }
+
+    /// Logical position of a global physical position relative to the client area
+    pub fn to_logical(&self, window: &Window, global: (f64, f64)) -> Option<LogicalPosition<f64>> {
+        let client = client_position(self.hwnd, global)?;
+        Some(PhysicalPosition::new(client.position.0, client.position.1).to_logical(window.scale_factor()))
+    }
+
+    /// Translate a detector event, returning the window events it produces
+    ///
+    /// A move across the window edge yields the enter or leave event before
+    /// the move itself; events without a position use the last known one.
+    pub fn translate(&mut self, window: &Window, event: &CursorEvent) -> Vec<BridgeEvent> {
+        let mut events = Vec::new();
+        match event {
+            CursorEvent::Move { position, .. } => {
+                let Some((local, inside)) = self.locate(window, *position) else {
+                    return events;
+                };
+                self.transition(local, inside, &mut events);
+                events.push(BridgeEvent::CursorMoved { position: local, inside });
+            }
+            CursorEvent::Click { button, position, .. } => {
+                let Some((local, inside)) = self.locate(window, *position) else {
+                    return events;
+                };
+                self.transition(local, inside, &mut events);
+                events.push(BridgeEvent::MouseInput { button: *button, pressed: true, position: local, inside });
+            }
+            CursorEvent::Release { button, .. } => {
+                if let Some(position) = self.position {
+                    events.push(BridgeEvent::MouseInput {
+                        button: *button,
+                        pressed: false,
+                        position,
+                        inside: self.inside,
+                    });
+                }
+            }
+            _ => {}
+        }
+        events
+    }
+
+    /// Local position and whether it is on the visible part of the window
+    fn locate(&self, window: &Window, global: (f64, f64)) -> Option<(LogicalPosition<f64>, bool)> {
+        let client = client_position(self.hwnd, global)?;
+        // Hit testing reports top-level windows, which winit windows are
+        let visible = client.inside && window_handle_at(global) == Some(self.hwnd);
+        let local = PhysicalPosition::new(client.position.0, client.position.1).to_logical(window.scale_factor());
+        Some((local, visible))
+    }
+
+    fn transition(&mut self, position: LogicalPosition<f64>, inside: bool, events: &mut Vec<BridgeEvent>) {
+        match (self.inside, inside) {
+            (false, true) => events.push(BridgeEvent::CursorEntered { position }),
+            (true, false) => events.push(BridgeEvent::CursorLeft { position }),
+            _ => {}
+        }
+        self.inside = inside;
+        self.position = Some(position);
+    }
+}
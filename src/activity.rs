@@ -0,0 +1,129 @@
+//! Activity totals per foreground application, for time tracking
+
+use crate::window::WindowContext;
+use crate::{duration_ms, CursorEvent, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Application name used when no process can be attributed
+const UNKNOWN_APPLICATION: &str = "unknown";
+
+/// Mouse activity while one application was in use
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AppActivity {
+    /// Clicks of every button
+    pub clicks: u64,
+    /// Distance traveled in physical pixels
+    pub distance_px: f64,
+    /// Time with activity no more than the idle threshold apart
+    #[serde(with = "duration_ms")]
+    pub active_time: Duration,
+    /// Wheel distance scrolled in either direction on both axes, 120 per notch
+    pub scroll_delta: u64,
+}
+
+/// Splits activity between applications on the processing thread
+///
+/// Events go to the foreground process from `FocusChanged` events when focus is
+/// tracked, otherwise to the process of the window the event carries, and else to
+/// the application of the previous activity. Time between two pieces of activity
+/// goes to the application the first happened in.
+#[derive(Debug, Default)]
+pub(crate) struct ActivityTracker {
+    applications: HashMap<String, AppActivity>,
+    foreground: Option<String>,
+    last_position: Option<(f64, f64)>,
+    /// Application and time of the latest activity
+    last_activity: Option<(String, Timestamp)>,
+}
+
+impl ActivityTracker {
+    /// Start over, still knowing which application is in the foreground
+    pub(crate) fn restart(&self) -> Self {
+        Self { foreground: self.foreground.clone(), ..Self::default() }
+    }
+
+    pub(crate) fn record(&mut self, event: &CursorEvent, idle_threshold: Duration) {
+        match event {
+            CursorEvent::FocusChanged { process_name, timestamp, .. } => {
+                let process_name = Some(process_name.clone()).filter(|name| !name.is_empty());
+                // Switching applications counts as activity only when the user was active just before
+                let active = self.close_gap(*timestamp, idle_threshold);
+                self.foreground = process_name;
+                if active {
+                    self.last_activity = Some((self.application(None), *timestamp));
+                }
+            }
+            CursorEvent::Move { position, window, timestamp, .. } => {
+                let distance = self.travel(*position);
+                self.activity(window.as_ref(), *timestamp, idle_threshold).distance_px += distance;
+            }
+            CursorEvent::Click { position, window, timestamp, .. } => {
+                let distance = self.travel(*position);
+                let application = self.activity(window.as_ref(), *timestamp, idle_threshold);
+                application.clicks += 1;
+                application.distance_px += distance;
+            }
+            CursorEvent::Scroll { delta, timestamp, .. } => {
+                self.activity(None, *timestamp, idle_threshold).scroll_delta += delta.unsigned_abs() as u64;
+            }
+            CursorEvent::Release { timestamp, .. } | CursorEvent::RawMotion { timestamp, .. } => {
+                self.activity(None, *timestamp, idle_threshold);
+            }
+            _ => {}
+        }
+    }
+
+    /// Totals as of `now`, counting the gap since the last activity
+    pub(crate) fn report_at(&self, now: Timestamp, idle_threshold: Duration) -> HashMap<String, AppActivity> {
+        let mut applications = self.applications.clone();
+        if let Some((application, since)) = &self.last_activity {
+            let gap = now.duration_since(*since);
+            if gap <= idle_threshold {
+                applications.entry(application.clone()).or_default().active_time += gap;
+            }
+        }
+        applications
+    }
+
+    /// Credit the time since the last activity and return the totals of the application active now
+    fn activity(
+        &mut self,
+        window: Option<&Arc<WindowContext>>,
+        timestamp: Timestamp,
+        idle_threshold: Duration,
+    ) -> &mut AppActivity {
+        let application = self.application(window);
+        self.close_gap(timestamp, idle_threshold);
+        self.last_activity = Some((application.clone(), timestamp));
+        self.applications.entry(application).or_default()
+    }
+
+    /// Credit the time since the last activity to its application, returning whether it was short enough
+    fn close_gap(&mut self, timestamp: Timestamp, idle_threshold: Duration) -> bool {
+        let Some((application, since)) = &mut self.last_activity else {
+            return false;
+        };
+        let gap = timestamp.duration_since(*since);
+        if gap > idle_threshold {
+            return false;
+        }
+        self.applications.entry(application.clone()).or_default().active_time += gap;
+        *since = (*since).max(timestamp);
+        true
+    }
+
+    fn application(&self, window: Option<&Arc<WindowContext>>) -> String {
+        self.foreground
+            .clone()
+            .or_else(|| window.map(|window| window.process_name.clone()).filter(|name| !name.is_empty()))
+            .or_else(|| self.last_activity.as_ref().map(|(application, _)| application.clone()))
+            .unwrap_or_else(|| UNKNOWN_APPLICATION.to_string())
+    }
+
+    fn travel(&mut self, position: (f64, f64)) -> f64 {
+        self.last_position.replace(position).map_or(0.0, |last| (position.0 - last.0).hypot(position.1 - last.1))
+    }
+}
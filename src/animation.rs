@@ -0,0 +1,60 @@
+//! Frame information for animated (.ani) cursors
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::HCURSOR;
+
+#[cfg(windows)]
+#[link(name = "user32")]
+extern "system" {
+    // Undocumented but exported by user32 since Windows XP; the only API that reads .ani frame data
+    fn GetCursorFrameInfo(cursor: HCURSOR, reserved: u32, step: u32, rate: *mut u32, steps: *mut u32) -> HCURSOR;
+}
+
+/// Animated cursors rarely exceed a few dozen frames, so anything above this is treated as bogus
+#[cfg(windows)]
+const MAX_FRAMES: u32 = 256;
+
+/// Animation timing of a cursor with more than one frame
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CursorAnimation {
+    pub frame_count: u32,
+    /// Display time of each frame
+    #[serde(with = "crate::duration_ms")]
+    pub frame_duration: Duration,
+}
+
+/// Animation timing of a cursor handle, `None` for static cursors
+///
+/// `handle` is the raw `HCURSOR` value.
+#[cfg(windows)]
+pub fn cursor_animation(handle: usize) -> Option<CursorAnimation> {
+    let (mut rate, mut steps) = (0u32, 0u32);
+    let first = unsafe { GetCursorFrameInfo(HCURSOR(handle as *mut _), 0, 0, &mut rate, &mut steps) };
+    if first.is_invalid() || steps <= 1 || steps > MAX_FRAMES {
+        return None;
+    }
+
+    // Rates are in jiffies of 1/60 second
+    Some(CursorAnimation { frame_count: steps, frame_duration: Duration::from_millis(rate as u64 * 1000 / 60) })
+}
+
+/// Handles of every frame of an animated cursor, empty for static cursors
+///
+/// The system may report the current frame's handle instead of the cursor's own,
+/// so all of them are mapped to the same cursor type.
+#[cfg(windows)]
+pub(crate) fn frame_handles(handle: usize) -> Vec<usize> {
+    let Some(animation) = cursor_animation(handle) else {
+        return Vec::new();
+    };
+
+    (0..animation.frame_count)
+        .filter_map(|step| {
+            let (mut rate, mut steps) = (0u32, 0u32);
+            let frame = unsafe { GetCursorFrameInfo(HCURSOR(handle as *mut _), 0, step, &mut rate, &mut steps) };
+            (!frame.is_invalid()).then_some(frame.0 as usize)
+        })
+        .collect()
+}
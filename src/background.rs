@@ -0,0 +1,129 @@
+//! Running the detector off the caller's thread, for embedders without a message loop
+
+use crate::interrupt::Interrupt;
+use crate::{CursorDetector, DetectorHandle};
+use std::sync::mpsc;
+use std::thread;
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{PeekMessageW, PostThreadMessageW, MSG, PM_NOREMOVE, WM_QUIT};
+
+/// Thread pumping the hook's message loop, returning the detector when it quits
+struct MonitorThread {
+    handle: thread::JoinHandle<(CursorDetector, Result<(), String>)>,
+    thread_id: u32,
+}
+
+/// Detector whose blocking [`start_monitoring`](CursorDetector::start_monitoring)
+/// runs on a thread of its own
+///
+/// `start` returns once the thread is up and `stop` quits the hook's message loop
+/// and hands the detector back, so it can be reconfigured and started again.
+/// Used by the C API and the language and framework bindings.
+pub struct BackgroundDetector {
+    /// Present while stopped; moves to the monitor thread while running
+    detector: Option<CursorDetector>,
+    handle: DetectorHandle,
+    monitor: Option<MonitorThread>,
+}
+
+impl BackgroundDetector {
+    /// Wrap a configured detector without starting it
+    pub fn new(detector: CursorDetector) -> Self {
+        let handle = detector.handle();
+        Self { detector: Some(detector), handle, monitor: None }
+    }
+
+    /// Handle for state queries and emitting events, valid while running or stopped
+    pub fn handle(&self) -> &DetectorHandle {
+        &self.handle
+    }
+
+    /// The detector for configuration, `None` while running
+    pub fn detector_mut(&mut self) -> Option<&mut CursorDetector> {
+        self.detector.as_mut()
+    }
+
+    /// Whether monitoring is running
+    pub fn is_running(&self) -> bool {
+        self.monitor.is_some()
+    }
+
+    /// Start monitoring on a background thread
+    pub fn start(&mut self) -> Result<(), String> {
+        let Some(mut detector) = self.detector.take() else {
+            return Err("Monitoring already started".to_string());
+        };
+
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let spawned = thread::Builder::new().name("luuma-monitor".to_string()).spawn(move || {
+            // Create the message queue up front so the quit message posted by stop is never lost
+            let mut message = MSG::default();
+            unsafe {
+                let _ = PeekMessageW(&mut message, None, 0, 0, PM_NOREMOVE);
+            }
+            let _ = ready_tx.send(unsafe { GetCurrentThreadId() });
+            let result = detector.start_monitoring();
+            (detector, result)
+        });
+        let handle = match spawned {
+            Ok(handle) => handle,
+            Err(error) => return Err(format!("Failed to spawn monitor thread: {}", error)),
+        };
+
+        match ready_rx.recv() {
+            Ok(thread_id) => {
+                self.monitor = Some(MonitorThread { handle, thread_id });
+                Ok(())
+            }
+            Err(_) => {
+                let (detector, _) = handle.join().map_err(|e| format!("Failed to join monitor thread: {:?}", e))?;
+                self.detector = Some(detector);
+                Err("Monitor thread exited before starting".to_string())
+            }
+        }
+    }
+
+    /// Monitor until Ctrl+C or Ctrl+Break, then stop so queued events and sinks are flushed
+    ///
+    /// Starts monitoring if needed and installs the [`Interrupt`] console handler.
+    /// Returns whether Ctrl+C ended the run, `false` when
+    /// [`Interrupt::request_stop`] did, so command-line tools can exit accordingly.
+    pub fn run_until_interrupt(&mut self) -> Result<bool, String> {
+        let interrupt = Interrupt::install()?;
+        if !self.is_running() {
+            self.start()?;
+        }
+        let interrupted = interrupt.wait();
+        self.stop()?;
+        Ok(interrupted)
+    }
+
+    /// Stop monitoring and wait for the detector's threads to finish
+    ///
+    /// Events already queued are delivered to the handler and sinks, and sinks are
+    /// flushed, before this returns.
+    pub fn stop(&mut self) -> Result<(), String> {
+        let Some(monitor) = self.monitor.take() else {
+            return Err("Monitoring is not running".to_string());
+        };
+        unsafe {
+            let _ = PostThreadMessageW(monitor.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        let (mut detector, result) =
+            monitor.handle.join().map_err(|e| format!("Failed to join monitor thread: {:?}", e))?;
+        let stopped = detector.stop();
+        self.detector = Some(detector);
+        result.and(stopped)
+    }
+}
+
+impl Drop for BackgroundDetector {
+    fn drop(&mut self) {
+        if self.is_running() {
+            if let Err(error) = self.stop() {
+                CursorDetector::log_message(&error);
+            }
+        }
+    }
+}
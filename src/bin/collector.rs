@@ -0,0 +1,50 @@
+#[cfg(windows)]
+use luuma_cursor_helper::Collector;
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+
+/// How often per-host counters are printed
+#[cfg(windows)]
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+#[cfg(windows)]
+fn main() {
+    let address = std::env::args().nth(1).unwrap_or_else(|| format!("0.0.0.0:{}", Collector::DEFAULT_PORT));
+    let collector = match Collector::bind(&address) {
+        Ok(collector) => collector,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    eprintln!("Collecting cursor events on {}", collector.local_address());
+    eprintln!("Merged stream on stdout, host stats on stderr. Press Ctrl+C to exit\n");
+
+    let mut last_stats = Instant::now();
+    loop {
+        if let Some(event) = collector.recv_timeout(Duration::from_millis(500)) {
+            if let Ok(line) = serde_json::to_string(&event) {
+                println!("{}", line);
+            }
+        }
+
+        if last_stats.elapsed() >= STATS_INTERVAL {
+            last_stats = Instant::now();
+            for host in collector.hosts() {
+                eprintln!(
+                    "{} ({}): {} events, {} malformed, {} connections",
+                    host.host, host.address, host.events, host.malformed, host.connections
+                );
+            }
+            if collector.dropped_events() > 0 {
+                eprintln!("{} events dropped", collector.dropped_events());
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("The collector is only available on Windows");
+}
@@ -0,0 +1,66 @@
+//! Swallowing mouse input before it reaches other applications
+
+use crate::hook::HookAction;
+use crate::scroll::ScrollAxis;
+use crate::source::InputSource;
+use crate::MouseButton;
+use std::collections::HashSet;
+
+/// What the pending mouse input would do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputAction {
+    Move,
+    Press(MouseButton),
+    Release(MouseButton),
+    /// Wheel turned by `delta`, 120 per notch
+    Scroll { axis: ScrollAxis, delta: i32 },
+}
+
+/// Mouse input offered to an [`InputBlocker`] before the system acts on it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingInput {
+    pub action: InputAction,
+    /// Screen position of the input in physical pixels
+    pub position: (f64, f64),
+    pub source: InputSource,
+}
+
+/// Predicate run on the hook thread, returning `true` to swallow the input
+///
+/// Windows removes low-level hooks whose callback is too slow, so the predicate
+/// must not block, e.g. on I/O or locks held by other threads.
+pub type InputBlocker = Box<dyn FnMut(&PendingInput) -> bool + Send>;
+
+/// Applies the blocker and keeps button presses and releases paired
+pub(crate) struct InputGuard {
+    blocker: InputBlocker,
+    swallowed: HashSet<MouseButton>,
+}
+
+impl InputGuard {
+    pub(crate) fn new(blocker: InputBlocker) -> Self {
+        Self { blocker, swallowed: HashSet::new() }
+    }
+
+    pub(crate) fn into_blocker(self) -> InputBlocker {
+        self.blocker
+    }
+
+    /// Whether the hook should swallow this input
+    pub(crate) fn should_block(&mut self, action: HookAction, position: (f64, f64), source: InputSource) -> bool {
+        let action = match action {
+            HookAction::Move { .. } => InputAction::Move,
+            HookAction::ButtonPress(button) => InputAction::Press(button),
+            // A swallowed press takes its release with it, so applications never see half a click
+            HookAction::ButtonRelease(button) if self.swallowed.remove(&button) => return true,
+            HookAction::ButtonRelease(button) => InputAction::Release(button),
+            HookAction::Wheel { axis, delta } => InputAction::Scroll { axis, delta },
+        };
+
+        let block = (self.blocker)(&PendingInput { action, position, source });
+        if let (InputAction::Press(button), true) = (action, block) {
+            self.swallowed.insert(button);
+        }
+        block
+    }
+}
@@ -0,0 +1,306 @@
+//! Builder for detectors that need configuration before monitoring starts
+
+use crate::clock::{Clock, SharedClock, SystemClock};
+use crate::gesture::GestureConfig;
+use crate::hotkey::{Hotkey, HotkeyAction};
+use crate::prediction::PredictionModel;
+use crate::privacy::PrivacyMask;
+use crate::overshoot::OvershootConfig;
+use crate::rage_click::RageClickConfig;
+use crate::sampling::SamplingMode;
+use crate::smoothing::SmoothingFilter;
+use crate::threading::{ThreadConfig, ThreadPriority};
+use crate::viewport::ViewportConfig;
+use crate::{CursorDetector, SanitizeMode};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configures a [`CursorDetector`] before it is created
+#[derive(Debug, Clone)]
+pub struct CursorDetectorBuilder {
+    pub(crate) queue_capacity: usize,
+    pub(crate) sanitize_mode: Option<SanitizeMode>,
+    pub(crate) sampling_mode: SamplingMode,
+    pub(crate) smoothing: Option<SmoothingFilter>,
+    pub(crate) prediction: Option<PredictionModel>,
+    pub(crate) gestures: Option<GestureConfig>,
+    pub(crate) rage_clicks: Option<RageClickConfig>,
+    pub(crate) scroll_sessions: Option<Duration>,
+    pub(crate) overshoot: Option<OvershootConfig>,
+    pub(crate) type_change_hysteresis: Duration,
+    pub(crate) type_change_interval: Duration,
+    pub(crate) long_press: Option<Duration>,
+    pub(crate) listener_thread: ThreadConfig,
+    pub(crate) processing_thread: ThreadConfig,
+    pub(crate) raw_input: bool,
+    pub(crate) track_devices: bool,
+    pub(crate) pointer_input: bool,
+    pub(crate) track_monitors: bool,
+    pub(crate) track_windows: bool,
+    pub(crate) track_focus: bool,
+    pub(crate) normalize_buttons: bool,
+    pub(crate) privacy_mask: Option<PrivacyMask>,
+    pub(crate) hotkeys: Vec<(Hotkey, HotkeyAction)>,
+    pub(crate) viewport: Option<ViewportConfig>,
+    pub(crate) clock: SharedClock,
+    #[cfg(feature = "uia")]
+    pub(crate) resolve_elements: bool,
+    #[cfg(feature = "uia")]
+    pub(crate) element_dwell: Duration,
+}
+
+impl CursorDetectorBuilder {
+    /// Start from the same defaults as [`CursorDetector::new`]
+    pub fn new() -> Self {
+        Self {
+            queue_capacity: CursorDetector::DEFAULT_QUEUE_CAPACITY,
+            sanitize_mode: None,
+            sampling_mode: SamplingMode::All,
+            smoothing: None,
+            prediction: None,
+            gestures: None,
+            rage_clicks: None,
+            scroll_sessions: None,
+            overshoot: None,
+            type_change_hysteresis: Duration::ZERO,
+            type_change_interval: Duration::ZERO,
+            long_press: None,
+            listener_thread: ThreadConfig::default(),
+            processing_thread: ThreadConfig::default(),
+            raw_input: false,
+            track_devices: false,
+            pointer_input: false,
+            track_monitors: false,
+            track_windows: false,
+            track_focus: false,
+            normalize_buttons: false,
+            privacy_mask: None,
+            hotkeys: Vec::new(),
+            viewport: None,
+            clock: Arc::new(SystemClock),
+            #[cfg(feature = "uia")]
+            resolve_elements: false,
+            #[cfg(feature = "uia")]
+            element_dwell: crate::uia::DEFAULT_DWELL_TIME,
+        }
+    }
+
+    /// Number of events that can be queued between the hook and the processing thread
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity;
+        self
+    }
+
+    /// Validate move positions against the virtual desktop bounds
+    pub fn position_sanitizer(mut self, mode: SanitizeMode) -> Self {
+        self.sanitize_mode = Some(mode);
+        self
+    }
+
+    /// Choose how Move events are sampled
+    pub fn sampling_mode(mut self, mode: SamplingMode) -> Self {
+        self.sampling_mode = mode;
+        self
+    }
+
+    /// Add a jitter-filtered `smoothed` position to Move events, e.g. [`SmoothingFilter::one_euro`]
+    pub fn smoothing(mut self, filter: SmoothingFilter) -> Self {
+        self.smoothing = Some(filter);
+        self
+    }
+
+    /// Track cursor velocity so `predict_position` can extrapolate ahead of render latency
+    pub fn prediction(mut self, model: PredictionModel) -> Self {
+        self.prediction = Some(model);
+        self
+    }
+
+    /// Report swipes, circles and shakes as `Gesture` events
+    ///
+    /// E.g. [`GestureConfig::shake_only`] for shake-to-find-cursor style triggers.
+    pub fn gestures(mut self, config: GestureConfig) -> Self {
+        self.gestures = Some(config);
+        self
+    }
+
+    /// Report bursts of rapid clicks on one spot as `RageClick` events
+    pub fn rage_clicks(mut self, config: RageClickConfig) -> Self {
+        self.rage_clicks = Some(config);
+        self
+    }
+
+    /// Group wheel input into `ScrollSession` events, see [`CursorDetector::set_scroll_sessions`]
+    pub fn scroll_sessions(mut self, idle_gap: Duration) -> Self {
+        self.scroll_sessions = Some(idle_gap);
+        self
+    }
+
+    /// Annotate clicks that follow an overshoot, see [`CursorDetector::set_overshoot_detection`]
+    pub fn overshoot_detection(mut self, config: OvershootConfig) -> Self {
+        self.overshoot = Some(config);
+        self
+    }
+
+    /// Report a cursor type only once it persisted for `hysteresis`
+    ///
+    /// Rapid flicker between two cursors then produces no `TypeChange` storm.
+    pub fn type_change_hysteresis(mut self, hysteresis: Duration) -> Self {
+        self.type_change_hysteresis = hysteresis;
+        self
+    }
+
+    /// Minimum time between two `TypeChange` events; a change arriving sooner is reported once it has passed
+    pub fn type_change_interval(mut self, min_interval: Duration) -> Self {
+        self.type_change_interval = min_interval;
+        self
+    }
+
+    /// Raise `LongPress` when a button is held still for `threshold`, see [`CursorDetector::set_long_press`]
+    pub fn long_press(mut self, threshold: Duration) -> Self {
+        self.long_press = Some(threshold);
+        self
+    }
+
+    /// Priority of the thread running the mouse hook (the one calling `start_monitoring`)
+    ///
+    /// Windows silently removes low-level hooks whose callback misses the system
+    /// timeout, so raising this helps the hook survive heavy system load.
+    pub fn listener_priority(mut self, priority: ThreadPriority) -> Self {
+        self.listener_thread.priority = Some(priority);
+        self
+    }
+
+    /// Pin the hook thread to a logical core
+    pub fn listener_affinity(mut self, core: usize) -> Self {
+        self.listener_thread.affinity = Some(core);
+        self
+    }
+
+    /// Priority of the thread dispatching events to handlers and sinks
+    pub fn processing_priority(mut self, priority: ThreadPriority) -> Self {
+        self.processing_thread.priority = Some(priority);
+        self
+    }
+
+    /// Pin the processing thread to a logical core
+    pub fn processing_affinity(mut self, core: usize) -> Self {
+        self.processing_thread.affinity = Some(core);
+        self
+    }
+
+    /// Also report unaccelerated device deltas as `RawMotion` events via the Raw Input API
+    ///
+    /// Raw motion ignores pointer acceleration and screen edges, so it keeps reporting
+    /// while the cursor is pinned against a border or clipped to a rectangle.
+    pub fn raw_input(mut self, enabled: bool) -> Self {
+        self.raw_input = enabled;
+        self
+    }
+
+    /// Tag events with the `device_id` of the mouse or trackpad that produced them
+    ///
+    /// Ids match [`crate::list_pointer_devices`]. Hook events are attributed to the
+    /// device of the most recent raw input packet.
+    pub fn track_devices(mut self, enabled: bool) -> Self {
+        self.track_devices = enabled;
+        self
+    }
+
+    /// Attach monitor-relative positions to events and emit `MonitorChanged` on crossings
+    pub fn track_monitors(mut self, enabled: bool) -> Self {
+        self.track_monitors = enabled;
+        self
+    }
+
+    /// Attach the hovered top-level window to events and emit `WindowChanged` when it changes
+    pub fn track_windows(mut self, enabled: bool) -> Self {
+        self.track_windows = enabled;
+        self
+    }
+
+    /// Emit `FocusChanged` whenever a different window comes to the foreground
+    pub fn track_focus(mut self, enabled: bool) -> Self {
+        self.track_focus = enabled;
+        self
+    }
+
+    /// Report logical buttons, so the primary click is `Left` even with swapped buttons
+    ///
+    /// Without this, buttons are physical: a left-handed user's primary click arrives as `Right`.
+    pub fn normalize_buttons(mut self, enabled: bool) -> Self {
+        self.normalize_buttons = enabled;
+        self
+    }
+
+    /// Redact or suppress events inside exclusion zones or over excluded processes
+    ///
+    /// Masking happens before handlers and sinks, so masked positions and window
+    /// titles are never delivered, e.g. for studies with consent constraints.
+    pub fn privacy_mask(mut self, mask: PrivacyMask) -> Self {
+        self.privacy_mask = Some(mask).filter(|mask| !mask.is_empty());
+        self
+    }
+
+    /// Register a global hotkey that pauses capture or drops a `Marker` into the stream
+    ///
+    /// E.g. `hotkey(Hotkey::parse("Ctrl+Alt+L")?, HotkeyAction::TogglePause)`. Hotkeys
+    /// are registered when monitoring starts, which fails if another application owns one.
+    pub fn hotkey(mut self, hotkey: Hotkey, action: HotkeyAction) -> Self {
+        self.hotkeys.push((hotkey, action));
+        self
+    }
+
+    /// Emit `ViewportUpdate` events for a camera that eases after the cursor, e.g. for auto-zoom
+    pub fn viewport(mut self, config: ViewportConfig) -> Self {
+        self.viewport = Some(config);
+        self
+    }
+
+    /// Take event timestamps and session durations from `clock` instead of the real time
+    ///
+    /// E.g. `clock(Arc::clone(&manual))` with a shared [`ManualClock`](crate::ManualClock)
+    /// that a test advances between synthetic events.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Report touch screen contacts and pen strokes as `Touch` and `Pen` events
+    ///
+    /// Digitizer reports are read through the Raw Input API, so contacts are seen
+    /// system-wide rather than only over this application's windows.
+    #[cfg(feature = "pointer")]
+    pub fn pointer_input(mut self, enabled: bool) -> Self {
+        self.pointer_input = enabled;
+        self
+    }
+
+    /// Resolve the UI Automation element under the cursor on clicks and dwells as `Element` events
+    ///
+    /// Lookups run on their own thread, so slow applications never stall the mouse hook.
+    #[cfg(feature = "uia")]
+    pub fn resolve_elements(mut self, enabled: bool) -> Self {
+        self.resolve_elements = enabled;
+        self
+    }
+
+    /// How long the cursor must rest before a dwell resolves the element under it
+    #[cfg(feature = "uia")]
+    pub fn element_dwell(mut self, dwell: Duration) -> Self {
+        self.element_dwell = dwell;
+        self
+    }
+
+    /// Create the detector
+    pub fn build(self) -> Result<CursorDetector, String> {
+        self.listener_thread.validate("listener")?;
+        self.processing_thread.validate("processing")?;
+        Ok(CursorDetector::from_builder(self))
+    }
+}
+
+impl Default for CursorDetectorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,100 @@
+//! Time source behind event timestamps, replaceable for deterministic runs
+
+use crate::Timestamp;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Where a detector and session replays take the current time from
+///
+/// [`SystemClock`] reads the real time; tests inject a [`ManualClock`] through
+/// [`CursorDetectorBuilder::clock`](crate::CursorDetectorBuilder::clock) or
+/// [`SessionPlayer::clock`](crate::SessionPlayer::clock) to get the same
+/// timestamps and durations on every run. Polling intervals of background
+/// threads stay on real time.
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    /// Current wall-clock time, stamped on events
+    fn now(&self) -> Timestamp;
+
+    /// Current monotonic time, for durations between events
+    fn instant(&self) -> Instant;
+
+    /// Let `duration` pass on this clock
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+
+    fn instant(&self) -> Instant {
+        (**self).instant()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+}
+
+#[cfg(windows)]
+pub(crate) type SharedClock = Arc<dyn Clock>;
+
+/// The real time, used unless another clock is injected
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Sleeping on it advances it instantly, so a replay driven by it finishes
+/// immediately with the recorded spacing in its timestamps. Pass it in an
+/// `Arc` to keep a handle for advancing it while the detector runs.
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Timestamp,
+    origin: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl ManualClock {
+    /// Clock standing at `start`
+    pub fn new(start: Timestamp) -> Self {
+        Self { start, origin: Instant::now(), elapsed: Mutex::new(Duration::ZERO) }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+
+    /// Time advanced since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::from_millis(self.start.as_millis() + self.elapsed().as_millis() as u64)
+    }
+
+    fn instant(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
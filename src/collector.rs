@@ -0,0 +1,423 @@
+//! Aggregating event streams from many machines
+
+use crate::json::JsonFormat;
+use crate::sink::EventSink;
+use crate::{CursorDetector, CursorEvent, EventKind, Timestamp};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Events buffered in the merged stream before new ones are dropped
+const STREAM_CAPACITY: usize = 65536;
+
+/// How often the accept loop checks for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Delay between reconnection attempts of a [`CollectorSink`]
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Largest webhook body accepted, to keep a bad client from exhausting memory
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// First line a [`CollectorSink`] sends, naming the machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Hello {
+    host: String,
+}
+
+/// An event from the merged stream, tagged with the machine it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostEvent {
+    /// Host ID the sender announced, or its IP address
+    pub host: String,
+    /// When the collector received the event, on the collector's clock
+    pub received: Timestamp,
+    pub event: CursorEvent,
+}
+
+/// What the collector knows about one machine
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HostStats {
+    pub host: String,
+    /// Address of the most recent connection
+    pub address: String,
+    /// Connections currently open from this host
+    pub connections: u32,
+    /// Events received in total
+    pub events: u64,
+    /// Events received per kind
+    pub events_by_kind: HashMap<EventKind, u64>,
+    /// Lines or bodies that could not be parsed
+    pub malformed: u64,
+    pub first_seen: Timestamp,
+    pub last_event: Option<Timestamp>,
+}
+
+impl HostStats {
+    fn new(host: &str, address: SocketAddr) -> Self {
+        Self {
+            host: host.to_string(),
+            address: address.to_string(),
+            connections: 0,
+            events: 0,
+            events_by_kind: HashMap::new(),
+            malformed: 0,
+            first_seen: Timestamp::now(),
+            last_event: None,
+        }
+    }
+}
+
+/// State shared between the collector and its connection threads
+struct CollectorShared {
+    hosts: Mutex<HashMap<String, HostStats>>,
+    connections: Mutex<HashMap<u64, TcpStream>>,
+    next_connection: AtomicU64,
+    dropped: AtomicU64,
+    stream: SyncSender<HostEvent>,
+}
+
+impl CollectorShared {
+    fn with_host(&self, host: &str, address: SocketAddr, update: impl FnOnce(&mut HostStats)) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        update(hosts.entry(host.to_string()).or_insert_with(|| HostStats::new(host, address)));
+    }
+
+    fn receive(&self, host: &str, address: SocketAddr, event: CursorEvent) {
+        let received = Timestamp::now();
+        self.with_host(host, address, |stats| {
+            stats.events += 1;
+            *stats.events_by_kind.entry(event.kind()).or_insert(0) += 1;
+            stats.last_event = Some(received);
+        });
+        let host_event = HostEvent { host: host.to_string(), received, event };
+        if let Err(TrySendError::Full(_)) = self.stream.try_send(host_event) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn malformed(&self, host: &str, address: SocketAddr) {
+        self.with_host(host, address, |stats| stats.malformed += 1);
+    }
+}
+
+/// Server accepting event streams from many machines
+///
+/// Machines connect with a [`CollectorSink`], which announces a host ID, or post
+/// batches with a [`WebhookSink`](crate::WebhookSink) pointed at
+/// `http://<collector>/`, in which case the host is the sender's IP address or
+/// the `X-Luuma-Host` header. Events from every host come out of one merged
+/// stream, and per-host counters are kept alongside.
+pub struct Collector {
+    shared: Arc<CollectorShared>,
+    stream: Receiver<HostEvent>,
+    local_address: SocketAddr,
+    running: Arc<AtomicBool>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl Collector {
+    /// Port the collector binary listens on by default
+    pub const DEFAULT_PORT: u16 = 7879;
+
+    /// Listen on `address`, e.g. `"0.0.0.0:7879"`
+    pub fn bind(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("Failed to bind {}: {}", address, e))?;
+        let local_address = listener.local_addr().map_err(|e| format!("Failed to read bound address: {}", e))?;
+        listener.set_nonblocking(true).map_err(|e| format!("Failed to configure listener: {}", e))?;
+
+        let (sender, stream) = mpsc::sync_channel(STREAM_CAPACITY);
+        let shared = Arc::new(CollectorShared {
+            hosts: Mutex::new(HashMap::new()),
+            connections: Mutex::new(HashMap::new()),
+            next_connection: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            stream: sender,
+        });
+        let running = Arc::new(AtomicBool::new(true));
+
+        let (worker_shared, worker_running) = (Arc::clone(&shared), Arc::clone(&running));
+        let worker = thread::Builder::new()
+            .name("luuma-collector".to_string())
+            .spawn(move || {
+                while worker_running.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, address)) => accept(&worker_shared, stream, address),
+                        Err(error) if error.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                        Err(_) => {}
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn collector thread: {}", e))?;
+
+        Ok(Self { shared, stream, local_address, running, worker: Some(worker) })
+    }
+
+    /// Address the collector is listening on, useful after binding port 0
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    /// Next event from any host, waiting up to `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<HostEvent> {
+        self.stream.recv_timeout(timeout).ok()
+    }
+
+    /// Events received since the last call, in arrival order
+    pub fn drain(&self) -> Vec<HostEvent> {
+        self.stream.try_iter().collect()
+    }
+
+    /// Counters of every host seen so far, sorted by host ID
+    pub fn hosts(&self) -> Vec<HostStats> {
+        let hosts = self.shared.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let mut hosts: Vec<HostStats> = hosts.values().cloned().collect();
+        hosts.sort_by(|a, b| a.host.cmp(&b.host));
+        hosts
+    }
+
+    /// Counters of one host
+    pub fn host(&self, host: &str) -> Option<HostStats> {
+        self.shared.hosts.lock().unwrap_or_else(|e| e.into_inner()).get(host).cloned()
+    }
+
+    /// Events dropped because the merged stream was not drained fast enough
+    pub fn dropped_events(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        // Unblock connection threads waiting on reads
+        for (_, stream) in self.shared.connections.lock().unwrap_or_else(|e| e.into_inner()).drain() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Register a connection and read it on its own thread
+fn accept(shared: &Arc<CollectorShared>, stream: TcpStream, address: SocketAddr) {
+    let _ = stream.set_nonblocking(false);
+    let Ok(registered) = stream.try_clone() else {
+        return;
+    };
+    let id = shared.next_connection.fetch_add(1, Ordering::Relaxed);
+    shared.connections.lock().unwrap_or_else(|e| e.into_inner()).insert(id, registered);
+
+    let worker_shared = Arc::clone(shared);
+    let spawned = thread::Builder::new().name("luuma-collector-connection".to_string()).spawn(move || {
+        if let Err(error) = read_connection(&worker_shared, stream, address) {
+            CursorDetector::log_message(&format!("Collector connection from {} failed: {}", address, error));
+        }
+        worker_shared.connections.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+    });
+    if spawned.is_err() {
+        shared.connections.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+    }
+}
+
+/// Read a connection until it closes, as an event stream or as webhook requests
+fn read_connection(shared: &CollectorShared, stream: TcpStream, address: SocketAddr) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(());
+    }
+
+    if line.starts_with("POST ") {
+        return read_webhook(shared, reader, &mut writer, address, line);
+    }
+
+    // Streams without a hello are attributed to the sender's address
+    let (host, pending) = match serde_json::from_str::<Hello>(&line) {
+        Ok(hello) => (hello.host, None),
+        Err(_) => (address.ip().to_string(), Some(line.clone())),
+    };
+    shared.with_host(&host, address, |stats| {
+        stats.address = address.to_string();
+        stats.connections += 1;
+    });
+
+    let handle_line = |line: &str| {
+        if line.trim().is_empty() {
+            return;
+        }
+        match CursorEvent::from_json(line) {
+            Ok(event) => shared.receive(&host, address, event),
+            Err(_) => shared.malformed(&host, address),
+        }
+    };
+    if let Some(line) = pending {
+        handle_line(&line);
+    }
+    let result = loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => break Ok(()),
+            Ok(_) => handle_line(&line),
+            Err(error) => break Err(error),
+        }
+    };
+
+    shared.with_host(&host, address, |stats| stats.connections = stats.connections.saturating_sub(1));
+    result
+}
+
+/// Answer keep-alive HTTP POSTs whose bodies are JSON arrays of events
+fn read_webhook(
+    shared: &CollectorShared,
+    mut reader: BufReader<TcpStream>,
+    writer: &mut TcpStream,
+    address: SocketAddr,
+    mut request_line: String,
+) -> std::io::Result<()> {
+    loop {
+        if !request_line.starts_with("POST ") {
+            writer.write_all(b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+            return Ok(());
+        }
+
+        let mut host = address.ip().to_string();
+        let mut content_length = 0;
+        let mut header = String::new();
+        loop {
+            header.clear();
+            if reader.read_line(&mut header)? == 0 {
+                return Ok(());
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                } else if name.eq_ignore_ascii_case("x-luuma-host") && !value.trim().is_empty() {
+                    host = value.trim().to_string();
+                }
+            }
+        }
+        if content_length > MAX_BODY {
+            writer.write_all(b"HTTP/1.1 413 Payload Too Large\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+            return Ok(());
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        shared.with_host(&host, address, |stats| stats.address = address.to_string());
+        let batch = serde_json::from_slice::<Vec<Value>>(&body).and_then(|values| {
+            values.into_iter().map(|value| JsonFormat::default().from_value(value)).collect::<Result<Vec<_>, _>>()
+        });
+        match batch {
+            Ok(events) => {
+                for event in events {
+                    shared.receive(&host, address, event);
+                }
+                writer.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")?;
+            }
+            Err(_) => {
+                shared.malformed(&host, address);
+                writer.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+            }
+        }
+
+        request_line.clear();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Sink streaming events to a [`Collector`] as newline-delimited JSON
+///
+/// Events are written from a background thread. While the collector is
+/// unreachable the sink retries every couple of seconds and discards events in
+/// the meantime rather than buffering without bound.
+pub struct CollectorSink {
+    kinds: Option<Vec<EventKind>>,
+    sender: Option<SyncSender<CursorEvent>>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl CollectorSink {
+    /// Stream to a collector at `address`, announcing this machine as `host`
+    pub fn connect(address: &str, host: &str) -> Result<Self, String> {
+        let address = address.to_string();
+        let hello = serde_json::to_string(&Hello { host: host.to_string() })
+            .map_err(|e| format!("Failed to encode collector hello: {}", e))?;
+        let (sender, receiver) = mpsc::sync_channel::<CursorEvent>(STREAM_CAPACITY);
+
+        let worker = thread::Builder::new()
+            .name("luuma-collector-sink".to_string())
+            .spawn(move || {
+                let mut stream: Option<TcpStream> = None;
+                let mut last_attempt: Option<Instant> = None;
+                for event in receiver {
+                    if stream.is_none() && last_attempt.is_none_or(|at| at.elapsed() >= RECONNECT_DELAY) {
+                        last_attempt = Some(Instant::now());
+                        stream = TcpStream::connect(&address)
+                            .and_then(|mut stream| {
+                                stream.write_all(hello.as_bytes())?;
+                                stream.write_all(b"\n")?;
+                                Ok(stream)
+                            })
+                            .ok();
+                    }
+                    let Some(connection) = stream.as_mut() else {
+                        continue;
+                    };
+                    let mut line = event.to_json().into_bytes();
+                    line.push(b'\n');
+                    if let Err(error) = connection.write_all(&line) {
+                        CursorDetector::log_message(&format!("Collector connection lost: {}", error));
+                        stream = None;
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn collector sink thread: {}", e))?;
+
+        Ok(Self { kinds: None, sender: Some(sender), worker: Some(worker) })
+    }
+
+    /// Only forward events of the given kinds (all kinds by default)
+    pub fn with_kinds(mut self, kinds: &[EventKind]) -> Self {
+        self.kinds = Some(kinds.to_vec());
+        self
+    }
+}
+
+impl EventSink for CollectorSink {
+    fn handle(&mut self, event: &CursorEvent) {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&event.kind()) {
+                return;
+            }
+        }
+        if let Some(sender) = &self.sender {
+            let _ = sender.try_send(event.clone());
+        }
+    }
+}
+
+impl Drop for CollectorSink {
+    fn drop(&mut self) {
+        // Closing the channel makes the worker write what is queued and exit
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Detector settings loaded from a TOML file and reloaded while monitoring
+
+use crate::filter::EventKindMask;
+use crate::json::{FieldCase, JsonFormat};
+use crate::middleware::RateLimit;
+use crate::privacy::PrivacyMask;
+use crate::recorder::SessionRecorder;
+use crate::region::{Region, RegionFilter};
+use crate::sink::{EventSink, SinkFilter};
+use crate::transform::Normalization;
+use crate::webhook::WebhookSink;
+use crate::websocket::WebSocketSink;
+use crate::{CursorDetector, DetectorHandle, EventKind, TimestampFormat};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the watcher checks the file's modification time
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Wait after a change is seen, so an editor has finished writing before the file is read
+const SETTLE_DELAY: Duration = Duration::from_millis(100);
+
+/// Detector settings kept in a TOML file
+///
+/// Every section is optional:
+///
+/// ```toml
+/// events = ["Click", "Release", "Scroll"]
+/// idle_threshold_ms = 5000
+/// region_filter = "Inside"
+///
+/// [[regions]]
+/// name = "canvas"
+/// area = { Screen = { left = 0, top = 0, right = 1280, bottom = 720 } }
+///
+/// [[privacy.processes]]
+/// process_name = "keepass.exe"
+/// action = "Suppress"
+///
+/// [[sinks]]
+/// name = "hooks"
+/// type = "webhook"
+/// url = "https://example.com/events"
+/// events = ["Click"]
+/// batch_size = 50
+/// debounce_ms = 250
+/// ```
+///
+/// [`apply`](Self::apply) configures a detector before it starts; a [`ConfigWatcher`]
+/// keeps a running detector in step with the file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Event kinds delivered, all kinds when absent
+    pub events: Option<Vec<EventKind>>,
+    /// Capacity of the event queue, only applied at start
+    pub queue_capacity: Option<usize>,
+    /// Gaps between activity longer than this count as idle, only applied at start
+    pub idle_threshold_ms: Option<u64>,
+    pub regions: Vec<Region>,
+    pub region_filter: Option<RegionFilter>,
+    pub privacy: PrivacyMask,
+    pub sinks: Vec<SinkConfig>,
+}
+
+/// Named sink created from the settings file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SinkConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: SinkKind,
+    /// Event kinds the sink receives, all kinds when absent
+    #[serde(default)]
+    pub events: Option<Vec<EventKind>>,
+    /// Events per kind and second the sink receives at most
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+    /// Bounds positions are normalized against, screen pixels when absent
+    #[serde(default)]
+    pub normalize: Option<Normalization>,
+}
+
+/// Where a configured sink sends its events
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkKind {
+    /// [`WebhookSink`] posting batches to a URL
+    Webhook {
+        url: String,
+        batch_size: Option<usize>,
+        /// Quiet period before a partial batch is posted
+        debounce_ms: Option<u64>,
+        max_retries: Option<u32>,
+        /// Naming of event fields, snake_case when absent
+        field_case: Option<FieldCase>,
+        /// How timestamps are written, UTC when absent
+        timestamps: Option<TimestampFormat>,
+    },
+    /// [`WebSocketSink`] listening on an address such as `127.0.0.1:9001`
+    WebSocket { address: String, field_case: Option<FieldCase>, timestamps: Option<TimestampFormat> },
+    /// [`SessionRecorder`] writing a session file, truncated when the sink is created
+    Record { path: PathBuf, idle_gap_ms: Option<u64> },
+}
+
+/// Settings read by a [`ConfigWatcher`] after the file changed
+#[derive(Debug, Clone)]
+pub struct ConfigReload {
+    /// The settings now in effect, apart from those listed in `restart_required`
+    pub config: Config,
+    /// Changed settings that only take effect when monitoring restarts
+    pub restart_required: Vec<String>,
+}
+
+impl Config {
+    /// Read settings from a TOML file
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_toml_str(&text).map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))
+    }
+
+    /// Parse settings from TOML text
+    pub fn from_toml_str(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Configure a detector before monitoring starts
+    pub fn apply(&self, detector: &mut CursorDetector) -> Result<(), String> {
+        if let Some(capacity) = self.queue_capacity {
+            detector.set_queue_capacity(capacity)?;
+        }
+        if let Some(threshold) = self.idle_threshold_ms {
+            detector.set_idle_threshold(Duration::from_millis(threshold));
+        }
+        detector.set_event_mask(self.event_mask());
+        for region in &self.regions {
+            detector.add_region(&region.name, region.area);
+        }
+        detector.set_region_filter(self.region_filter);
+        detector.set_privacy_mask(Some(self.privacy.clone()));
+        for sink in &self.sinks {
+            detector.add_sink(&sink.name, sink.create()?, sink.filter());
+        }
+        Ok(())
+    }
+
+    /// Bring a running detector from `previous` to these settings
+    ///
+    /// Only settings that differ from `previous` are touched, so changes made
+    /// through the handle in the meantime survive an unrelated edit. Sinks that
+    /// were added or removed are attached or detached; queue capacity, idle
+    /// threshold and changed sinks are returned as needing a restart, since
+    /// recreating a sink would e.g. truncate its session file. Nothing is applied
+    /// when a new sink fails to start.
+    pub fn apply_live(&self, handle: &DetectorHandle, previous: &Config) -> Result<Vec<String>, String> {
+        let added = self
+            .sinks
+            .iter()
+            .filter(|sink| !previous.sinks.iter().any(|old| old.name == sink.name))
+            .map(|sink| Ok((sink, sink.create()?)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if self.events != previous.events {
+            handle.set_event_mask(self.event_mask());
+        }
+        if self.regions != previous.regions {
+            for region in &previous.regions {
+                if !self.regions.iter().any(|new| new.name == region.name) {
+                    handle.remove_region(&region.name);
+                }
+            }
+            for region in &self.regions {
+                handle.add_region(&region.name, region.area);
+            }
+        }
+        if self.region_filter != previous.region_filter {
+            handle.set_region_filter(self.region_filter);
+        }
+        if self.privacy != previous.privacy {
+            handle.set_privacy_mask(Some(self.privacy.clone()));
+        }
+        for sink in &previous.sinks {
+            if !self.sinks.iter().any(|new| new.name == sink.name) {
+                handle.remove_sink(&sink.name);
+            }
+        }
+        for (sink, created) in added {
+            handle.add_sink(&sink.name, created, sink.filter());
+        }
+
+        let mut restart_required = Vec::new();
+        if self.queue_capacity != previous.queue_capacity {
+            restart_required.push("queue_capacity".to_string());
+        }
+        if self.idle_threshold_ms != previous.idle_threshold_ms {
+            restart_required.push("idle_threshold_ms".to_string());
+        }
+        for sink in &self.sinks {
+            if previous.sinks.iter().any(|old| old.name == sink.name && old != sink) {
+                restart_required.push(format!("sink {}", sink.name));
+            }
+        }
+        Ok(restart_required)
+    }
+
+    fn event_mask(&self) -> EventKindMask {
+        self.events.as_deref().map_or(EventKindMask::ALL, EventKindMask::of)
+    }
+}
+
+impl SinkConfig {
+    /// Start the sink
+    pub fn create(&self) -> Result<Box<dyn EventSink>, String> {
+        Ok(match &self.kind {
+            SinkKind::Webhook { url, batch_size, debounce_ms, max_retries, field_case, timestamps } => {
+                let mut sink = WebhookSink::new(url)?.with_json_format(json_format(*field_case, *timestamps));
+                if let Some(batch_size) = batch_size {
+                    sink = sink.with_batch_size(*batch_size);
+                }
+                if let Some(debounce) = debounce_ms {
+                    sink = sink.with_debounce(Duration::from_millis(*debounce));
+                }
+                if let Some(max_retries) = max_retries {
+                    sink = sink.with_retries(*max_retries, Duration::from_millis(500));
+                }
+                Box::new(sink)
+            }
+            SinkKind::WebSocket { address, field_case, timestamps } => {
+                Box::new(WebSocketSink::bind(address)?.with_json_format(json_format(*field_case, *timestamps)))
+            }
+            SinkKind::Record { path, idle_gap_ms } => {
+                let mut recorder = SessionRecorder::create(path)?;
+                if let Some(threshold) = idle_gap_ms {
+                    recorder = recorder.with_idle_gap_compression(Duration::from_millis(*threshold));
+                }
+                Box::new(recorder)
+            }
+        })
+    }
+
+    /// Which events the sink receives
+    pub fn filter(&self) -> SinkFilter {
+        let filter = SinkFilter::kinds(self.events.as_deref().map_or(EventKindMask::ALL, EventKindMask::of));
+        let filter = match self.rate_limit {
+            Some(per_second) => filter.rate_limit(RateLimit::per_second(per_second)),
+            None => filter,
+        };
+        match self.normalize {
+            Some(normalization) => filter.normalized(normalization),
+            None => filter,
+        }
+    }
+}
+
+/// Background thread applying changes to a settings file to a running detector
+///
+/// The file is polled rather than watched through the file system, which also
+/// picks up editors that save by replacing the file. A file that fails to parse
+/// is reported and the previous settings stay in effect.
+pub struct ConfigWatcher {
+    /// Dropped to wake the thread and make it exit
+    stop: Option<Sender<()>>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Watch `path`, whose settings `current` are already applied to the detector behind `handle`
+    ///
+    /// `on_reload` runs on the watcher thread after each change, with the settings
+    /// applied or the reason they were not.
+    pub fn spawn<F>(path: &Path, current: Config, handle: DetectorHandle, mut on_reload: F) -> Result<Self, String>
+    where
+        F: FnMut(Result<ConfigReload, String>) + Send + 'static,
+    {
+        let path = path.to_path_buf();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let worker = thread::Builder::new()
+            .name("luuma-config-watch".to_string())
+            .spawn(move || {
+                let mut current = current;
+                let mut modified = modified_time(&path);
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(POLL_INTERVAL) {
+                    let now = modified_time(&path);
+                    // Missing while an editor replaces it, picked up once it is back
+                    if now.is_none() || now == modified {
+                        continue;
+                    }
+                    thread::sleep(SETTLE_DELAY);
+                    modified = modified_time(&path);
+                    let reload = Config::from_path(&path).and_then(|config| {
+                        let restart_required = config.apply_live(&handle, &current)?;
+                        current = config.clone();
+                        Ok(ConfigReload { config, restart_required })
+                    });
+                    on_reload(reload);
+                }
+            })
+            .map_err(|e| format!("Failed to spawn config watcher thread: {}", e))?;
+        Ok(Self { stop: Some(stop), worker: Some(worker) })
+    }
+
+    /// Stop watching
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn json_format(field_case: Option<FieldCase>, timestamps: Option<TimestampFormat>) -> JsonFormat {
+    JsonFormat::new().field_case(field_case.unwrap_or_default()).timestamps(timestamps.unwrap_or_default())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
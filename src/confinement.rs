@@ -0,0 +1,39 @@
+//! Detection of cursor clipping set with `ClipCursor`
+
+use crate::{CursorDetector, Rect};
+use windows::Win32::Foundation::RECT;
+use windows::Win32::UI::WindowsAndMessaging::GetClipCursor;
+
+/// Rectangle the cursor is currently confined to, `None` when it can move freely
+///
+/// Windows reports the whole virtual desktop when nothing clips the cursor, so
+/// that case is mapped to `None`.
+pub fn current_clip_rect() -> Option<Rect> {
+    let mut clip = RECT::default();
+    unsafe { GetClipCursor(&mut clip) }.ok()?;
+
+    let rect = Rect::new(clip.left, clip.top, clip.right, clip.bottom);
+    (rect != CursorDetector::get_virtual_screen_bounds()).then_some(rect)
+}
+
+/// Remembers the last clip rectangle so only changes are reported
+pub(crate) struct ConfinementTracker {
+    current: Option<Rect>,
+}
+
+impl ConfinementTracker {
+    /// Start unclipped so a cursor already confined at start-up is reported once
+    pub(crate) fn new() -> Self {
+        Self { current: None }
+    }
+
+    /// The new clip rectangle when it differs from the last one seen
+    pub(crate) fn update(&mut self) -> Option<Option<Rect>> {
+        let clip = current_clip_rect();
+        if clip == self.current {
+            return None;
+        }
+        self.current = clip;
+        Some(clip)
+    }
+}
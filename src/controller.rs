@@ -0,0 +1,137 @@
+//! Moving and confining the system cursor
+
+use crate::dpi::PhysicalCoordinates;
+use crate::{MouseButton, Rect};
+use std::cell::Cell;
+use windows::Win32::Foundation::{POINT, RECT};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MIDDLEDOWN,
+    MOUSEEVENTF_MIDDLEUP, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, MOUSEINPUT,
+};
+use windows::Win32::UI::WindowsAndMessaging::{ClipCursor, GetCursorPos, SetCursorPos, ShowCursor};
+
+/// Upper bound on `ShowCursor` calls in one direction, far beyond any real display count
+const MAX_DISPLAY_STEPS: i32 = 64;
+
+/// Controls the system cursor, using the same physical pixels the detector reports
+#[derive(Debug, Default)]
+pub struct CursorController {
+    /// Net `ShowCursor(false)` calls made by this controller, undone on drop
+    display_balance: Cell<i32>,
+}
+
+impl CursorController {
+    /// Create a controller
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current cursor position
+    pub fn position(&self) -> Result<(f64, f64), String> {
+        let _physical = PhysicalCoordinates::enter();
+        let mut point = POINT::default();
+        unsafe { GetCursorPos(&mut point) }.map_err(|e| format!("Failed to read cursor position: {}", e))?;
+        Ok((point.x as f64, point.y as f64))
+    }
+
+    /// Move the cursor to a virtual desktop position
+    ///
+    /// Fails on the secure desktop and when a higher-integrity window is in the foreground.
+    pub fn set_position(&self, x: f64, y: f64) -> Result<(), String> {
+        let _physical = PhysicalCoordinates::enter();
+        unsafe { SetCursorPos(x.round() as i32, y.round() as i32) }
+            .map_err(|e| format!("Failed to move cursor to ({:.0}, {:.0}): {}", x, y, e))
+    }
+
+    /// Press a mouse button at the current position, as synthetic input other applications receive
+    ///
+    /// Buttons are physical: with swapped buttons, pressing `Left` is a secondary click.
+    pub fn press(&self, button: MouseButton) -> Result<(), String> {
+        self.send_button(button, true)
+    }
+
+    /// Release a mouse button pressed with [`press`](Self::press)
+    pub fn release(&self, button: MouseButton) -> Result<(), String> {
+        self.send_button(button, false)
+    }
+
+    fn send_button(&self, button: MouseButton, down: bool) -> Result<(), String> {
+        let flags = match (button, down) {
+            (MouseButton::Left, true) => MOUSEEVENTF_LEFTDOWN,
+            (MouseButton::Left, false) => MOUSEEVENTF_LEFTUP,
+            (MouseButton::Right, true) => MOUSEEVENTF_RIGHTDOWN,
+            (MouseButton::Right, false) => MOUSEEVENTF_RIGHTUP,
+            (MouseButton::Middle, true) => MOUSEEVENTF_MIDDLEDOWN,
+            (MouseButton::Middle, false) => MOUSEEVENTF_MIDDLEUP,
+        };
+        let input = INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 { mi: MOUSEINPUT { dwFlags: flags, ..Default::default() } },
+        };
+        // Zero means the input was blocked, e.g. by UIPI or another process's BlockInput
+        if unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) } == 1 {
+            Ok(())
+        } else {
+            let action = if down { "press" } else { "release" };
+            Err(format!("Failed to {} {} button: {}", action, button, windows::core::Error::from_win32()))
+        }
+    }
+
+    /// Keep the cursor inside a rectangle until released
+    ///
+    /// Windows drops the clip when another application sets its own or the
+    /// foreground window changes, so kiosks should re-apply it on `FocusChanged`.
+    pub fn confine_to(&self, rect: Rect) -> Result<(), String> {
+        if rect.width() <= 0 || rect.height() <= 0 {
+            return Err(format!("Cannot confine cursor to an empty rectangle {:?}", rect));
+        }
+
+        let _physical = PhysicalCoordinates::enter();
+        let clip = RECT { left: rect.left, top: rect.top, right: rect.right, bottom: rect.bottom };
+        unsafe { ClipCursor(Some(&clip)) }.map_err(|e| format!("Failed to confine cursor: {}", e))
+    }
+
+    /// Let the cursor move freely again
+    pub fn release_confinement(&self) -> Result<(), String> {
+        unsafe { ClipCursor(None) }.map_err(|e| format!("Failed to release cursor confinement: {}", e))
+    }
+
+    /// Hide the cursor over windows of the calling thread
+    ///
+    /// `ShowCursor` keeps a per-thread display counter that other code may also
+    /// change, so this decrements it until the cursor is actually hidden and
+    /// remembers how far it went. Call [`show`](Self::show) from the same thread.
+    pub fn hide(&self) -> Result<(), String> {
+        self.step_display(false)
+    }
+
+    /// Show the cursor again, incrementing the display counter until it is visible
+    pub fn show(&self) -> Result<(), String> {
+        self.step_display(true)
+    }
+
+    fn step_display(&self, show: bool) -> Result<(), String> {
+        let step = if show { -1 } else { 1 };
+        for _ in 0..MAX_DISPLAY_STEPS {
+            let count = unsafe { ShowCursor(show) };
+            self.display_balance.set(self.display_balance.get() + step);
+            // The cursor is drawn while the counter is zero or above
+            if (count >= 0) == show {
+                return Ok(());
+            }
+        }
+        Err(format!("Failed to {} cursor: display counter did not settle", if show { "show" } else { "hide" }))
+    }
+}
+
+impl Drop for CursorController {
+    /// Undo this controller's `ShowCursor` calls so the thread's counter is left as found
+    fn drop(&mut self) {
+        let balance = self.display_balance.get();
+        for _ in 0..balance.abs() {
+            unsafe {
+                ShowCursor(balance > 0);
+            }
+        }
+    }
+}
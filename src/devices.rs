@@ -0,0 +1,147 @@
+//! Enumeration of connected pointing devices
+
+use serde::{Deserialize, Serialize};
+use windows::core::PCWSTR;
+use windows::Win32::Devices::HumanInterfaceDevice::{HidD_GetManufacturerString, HidD_GetProductString};
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+};
+use windows::Win32::UI::Input::{
+    GetRawInputDeviceInfoW, GetRawInputDeviceList, RAWINPUTDEVICELIST, RIDI_DEVICEINFO, RIDI_DEVICENAME,
+    RID_DEVICE_INFO, RIM_TYPEMOUSE,
+};
+
+/// A mouse, trackpad or other pointing device known to the Raw Input API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PointerDevice {
+    /// Identifier matching the `device_id` of events produced by this device
+    pub id: u64,
+    /// Device interface path
+    pub path: String,
+    /// Product string reported by the device, if it exposes one
+    pub name: Option<String>,
+    /// Manufacturer string reported by the device, if it exposes one
+    pub manufacturer: Option<String>,
+    /// USB vendor id parsed from the device path
+    pub vendor_id: Option<u16>,
+    /// USB product id parsed from the device path
+    pub product_id: Option<u16>,
+    /// Number of buttons reported by the driver
+    pub buttons: u32,
+    /// Whether the device has a horizontal wheel
+    pub horizontal_wheel: bool,
+}
+
+/// List the pointing devices currently attached
+pub fn list_pointer_devices() -> Result<Vec<PointerDevice>, String> {
+    let entry_size = std::mem::size_of::<RAWINPUTDEVICELIST>() as u32;
+    let mut count = 0u32;
+
+    unsafe {
+        if GetRawInputDeviceList(None, &mut count, entry_size) == u32::MAX {
+            return Err("Failed to count raw input devices".to_string());
+        }
+
+        let mut list = vec![RAWINPUTDEVICELIST::default(); count as usize];
+        let listed = GetRawInputDeviceList(Some(list.as_mut_ptr()), &mut count, entry_size);
+        if listed == u32::MAX {
+            return Err("Failed to list raw input devices".to_string());
+        }
+        list.truncate(listed as usize);
+
+        Ok(list
+            .iter()
+            .filter(|entry| entry.dwType == RIM_TYPEMOUSE)
+            .filter_map(|entry| describe_device(entry.hDevice))
+            .collect())
+    }
+}
+
+/// Stable numeric id for a raw input device handle
+pub(crate) fn device_id(handle: HANDLE) -> u64 {
+    handle.0 as usize as u64
+}
+
+unsafe fn describe_device(handle: HANDLE) -> Option<PointerDevice> {
+    let path = device_path(handle)?;
+
+    let mut info = RID_DEVICE_INFO { cbSize: std::mem::size_of::<RID_DEVICE_INFO>() as u32, ..Default::default() };
+    let mut size = info.cbSize;
+    let (buttons, horizontal_wheel) =
+        if GetRawInputDeviceInfoW(Some(handle), RIDI_DEVICEINFO, Some(&mut info as *mut _ as *mut _), &mut size) == u32::MAX {
+            (0, false)
+        } else {
+            let mouse = info.Anonymous.mouse;
+            (mouse.dwNumberOfButtons, mouse.fHasHorizontalWheel.as_bool())
+        };
+
+    let (name, manufacturer) = hid_strings(&path);
+
+    Some(PointerDevice {
+        id: device_id(handle),
+        vendor_id: path_hex_field(&path, "VID_"),
+        product_id: path_hex_field(&path, "PID_"),
+        path,
+        name,
+        manufacturer,
+        buttons,
+        horizontal_wheel,
+    })
+}
+
+unsafe fn device_path(handle: HANDLE) -> Option<String> {
+    let mut length = 0u32;
+    GetRawInputDeviceInfoW(Some(handle), RIDI_DEVICENAME, None, &mut length);
+    if length == 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u16; length as usize];
+    if GetRawInputDeviceInfoW(Some(handle), RIDI_DEVICENAME, Some(buffer.as_mut_ptr() as *mut _), &mut length) == u32::MAX {
+        return None;
+    }
+
+    Some(from_wide(&buffer))
+}
+
+/// Ask the HID driver for product and manufacturer strings (virtual devices have none)
+unsafe fn hid_strings(path: &str) -> (Option<String>, Option<String>) {
+    let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+
+    // Zero access rights are enough for string queries and work while the system owns the mouse
+    let file = match CreateFileW(
+        PCWSTR(wide.as_ptr()),
+        0,
+        FILE_SHARE_READ | FILE_SHARE_WRITE,
+        None,
+        OPEN_EXISTING,
+        FILE_FLAGS_AND_ATTRIBUTES(0),
+        None,
+    ) {
+        Ok(file) => file,
+        Err(_) => return (None, None),
+    };
+
+    let mut buffer = [0u16; 127];
+    let size = std::mem::size_of_val(&buffer) as u32;
+
+    let name = HidD_GetProductString(file, buffer.as_mut_ptr() as *mut _, size).then(|| from_wide(&buffer));
+    buffer.fill(0);
+    let manufacturer = HidD_GetManufacturerString(file, buffer.as_mut_ptr() as *mut _, size).then(|| from_wide(&buffer));
+
+    let _ = CloseHandle(file);
+    (name.filter(|s| !s.is_empty()), manufacturer.filter(|s| !s.is_empty()))
+}
+
+/// Parse a four digit hex field such as `VID_046D` out of a device path
+fn path_hex_field(path: &str, prefix: &str) -> Option<u16> {
+    let upper = path.to_ascii_uppercase();
+    let start = upper.find(prefix)? + prefix.len();
+    upper.get(start..start + 4).and_then(|digits| u16::from_str_radix(digits, 16).ok())
+}
+
+fn from_wide(buffer: &[u16]) -> String {
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..end])
+}
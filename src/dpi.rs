@@ -0,0 +1,67 @@
+//! Conversions between physical pixels and device-independent pixels (DIPs)
+
+#[cfg(windows)]
+use windows::Win32::UI::HiDpi::{
+    SetProcessDpiAwarenessContext, SetThreadDpiAwarenessContext, DPI_AWARENESS_CONTEXT,
+    DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+};
+
+/// DPI at which one DIP equals one physical pixel
+pub const BASE_DPI: u32 = 96;
+
+/// Scale factor for a DPI value (1.5 at 144 DPI)
+pub fn scale_for_dpi(dpi: u32) -> f64 {
+    dpi as f64 / BASE_DPI as f64
+}
+
+/// Convert a virtual desktop position from physical pixels to DIPs on the given monitor
+///
+/// The monitor's top-left corner stays fixed and distances from it are divided by
+/// the scale factor, matching how Windows virtualizes coordinates on mixed-DPI setups.
+pub fn physical_to_dips(position: (f64, f64), origin: (f64, f64), scale: f64) -> (f64, f64) {
+    (origin.0 + (position.0 - origin.0) / scale, origin.1 + (position.1 - origin.1) / scale)
+}
+
+/// Convert a virtual desktop position from DIPs back to physical pixels
+pub fn dips_to_physical(position: (f64, f64), origin: (f64, f64), scale: f64) -> (f64, f64) {
+    (origin.0 + (position.0 - origin.0) * scale, origin.1 + (position.1 - origin.1) * scale)
+}
+
+/// Make the whole process per-monitor DPI aware (v2)
+///
+/// Hook coordinates are always physical; calling this early also makes the host
+/// application's own window APIs report physical pixels so both agree.
+#[cfg(windows)]
+pub fn enable_per_monitor_awareness() -> Result<(), String> {
+    unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) }
+        .map_err(|e| format!("Failed to enable per-monitor DPI awareness: {}", e))
+}
+
+/// Switches the calling thread to per-monitor awareness until dropped
+///
+/// Monitor and window queries then return physical pixels, matching the mouse hook,
+/// regardless of how the host process declared its DPI awareness.
+#[cfg(windows)]
+pub(crate) struct PhysicalCoordinates {
+    previous: DPI_AWARENESS_CONTEXT,
+}
+
+#[cfg(windows)]
+impl PhysicalCoordinates {
+    pub(crate) fn enter() -> Self {
+        let previous = unsafe { SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+        Self { previous }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for PhysicalCoordinates {
+    fn drop(&mut self) {
+        // A null context means the switch failed, so there is nothing to restore
+        if !self.previous.is_invalid() {
+            unsafe {
+                SetThreadDpiAwarenessContext(self.previous);
+            }
+        }
+    }
+}
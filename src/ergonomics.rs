@@ -0,0 +1,166 @@
+//! End-of-session ergonomics summary for RSI awareness
+
+use crate::stats::SessionStats;
+use crate::{duration_ms, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Stretches without a break longer than this get a recommendation
+const MAX_STRETCH: Duration = Duration::from_secs(60 * 60);
+
+/// Clicks per active minute above which a recommendation is made
+const MAX_CLICK_RATE: f64 = 40.0;
+
+/// Active time needed before the click rate is judged
+const MIN_JUDGED_ACTIVITY: Duration = Duration::from_secs(10 * 60);
+
+/// How hard the mouse was worked over a session, with suggestions for easing the strain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ErgonomicsReport {
+    /// When the session started
+    pub started: Timestamp,
+    /// Active and idle time together
+    #[serde(with = "duration_ms")]
+    pub duration: Duration,
+    /// Time with activity no more than the idle threshold apart
+    #[serde(with = "duration_ms")]
+    pub active_time: Duration,
+    /// Distance traveled in physical pixels
+    pub distance_px: f64,
+    /// Distance traveled on screen in meters
+    pub distance_m: f64,
+    /// Clicks of every button
+    pub clicks: u64,
+    /// Pixels per second of active time
+    pub average_speed: f64,
+    /// Longest run of activity without a break
+    #[serde(with = "duration_ms")]
+    pub longest_stretch: Duration,
+    /// Breaks of at least [`SessionStats::BREAK_THRESHOLD`]
+    pub breaks: u32,
+    /// Suggestions for the next session, empty when nothing stood out
+    pub recommendations: Vec<String>,
+}
+
+impl ErgonomicsReport {
+    /// Summarize session statistics, e.g. those carried by `SessionEnd`
+    pub fn from_stats(stats: &SessionStats) -> Self {
+        let active_seconds = stats.active_time.as_secs_f64();
+        let clicks = stats.total_clicks();
+        let mut report = Self {
+            started: stats.started,
+            duration: stats.active_time + stats.idle_time,
+            active_time: stats.active_time,
+            distance_px: stats.distance_px,
+            distance_m: stats.distance_m,
+            clicks,
+            average_speed: if active_seconds > 0.0 { stats.distance_px / active_seconds } else { 0.0 },
+            longest_stretch: stats.longest_stretch,
+            breaks: stats.breaks,
+            recommendations: Vec::new(),
+        };
+
+        if stats.longest_stretch > MAX_STRETCH {
+            report.recommendations.push(format!(
+                "The longest stretch without a break lasted {} min; step away for a few minutes at least every {} min",
+                stats.longest_stretch.as_secs() / 60,
+                MAX_STRETCH.as_secs() / 60
+            ));
+        }
+        if stats.active_time >= MIN_JUDGED_ACTIVITY {
+            let click_rate = clicks as f64 / (active_seconds / 60.0);
+            if click_rate > MAX_CLICK_RATE {
+                report.recommendations.push(format!(
+                    "Clicking averaged {:.0} per active minute; keyboard shortcuts can take load off the clicking hand",
+                    click_rate
+                ));
+            }
+        }
+        report
+    }
+
+    /// Convert the report to JSON string
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Convert the report to pretty-formatted JSON string
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Human-readable report, one line per figure followed by the recommendations
+impl fmt::Display for ErgonomicsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Session:          {:.1} min from {}", self.duration.as_secs_f64() / 60.0, self.started)?;
+        writeln!(f, "Active time:      {:.1} min", self.active_time.as_secs_f64() / 60.0)?;
+        writeln!(f, "Distance:         {:.0} px ({:.2} m)", self.distance_px, self.distance_m)?;
+        writeln!(f, "Clicks:           {}", self.clicks)?;
+        writeln!(f, "Average speed:    {:.0} px/s", self.average_speed)?;
+        writeln!(f, "Longest stretch:  {:.1} min", self.longest_stretch.as_secs_f64() / 60.0)?;
+        write!(f, "Breaks:           {}", self.breaks)?;
+        if self.recommendations.is_empty() {
+            write!(f, "\nNo recommendations: breaks and clicking were within healthy limits")?;
+        }
+        for recommendation in &self.recommendations {
+            write!(f, "\n- {}", recommendation)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErgonomicsReport;
+    use crate::stats::SessionStats;
+    use crate::{MouseButton, Timestamp};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn stats(active_minutes: u64, clicks: u64, longest_stretch_minutes: u64) -> SessionStats {
+        SessionStats {
+            started: Timestamp::from_millis(0),
+            clicks: HashMap::from([(MouseButton::Left, clicks)]),
+            distance_px: 120_000.0,
+            distance_m: 30.0,
+            active_time: Duration::from_secs(active_minutes * 60),
+            idle_time: Duration::from_secs(15 * 60),
+            longest_stretch: Duration::from_secs(longest_stretch_minutes * 60),
+            breaks: 2,
+            cursor_type_ms: HashMap::new(),
+            applications: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn summarizes_the_session() {
+        let report = ErgonomicsReport::from_stats(&stats(20, 300, 45));
+        assert_eq!(report.duration, Duration::from_secs(35 * 60));
+        assert_eq!(report.clicks, 300);
+        assert_eq!(report.average_speed, 100.0);
+        assert_eq!(report.breaks, 2);
+        assert!(report.recommendations.is_empty());
+    }
+
+    #[test]
+    fn recommends_breaks_after_long_stretches() {
+        let report = ErgonomicsReport::from_stats(&stats(90, 100, 75));
+        assert_eq!(report.recommendations.len(), 1);
+        assert!(report.recommendations[0].contains("75 min"));
+    }
+
+    #[test]
+    fn judges_the_click_rate_only_after_enough_activity() {
+        assert!(ErgonomicsReport::from_stats(&stats(5, 500, 5)).recommendations.is_empty());
+        let report = ErgonomicsReport::from_stats(&stats(10, 500, 10));
+        assert_eq!(report.recommendations.len(), 1);
+        assert!(report.recommendations[0].contains("50 per active minute"));
+    }
+
+    #[test]
+    fn reports_no_speed_without_activity() {
+        assert_eq!(ErgonomicsReport::from_stats(&stats(0, 0, 0)).average_speed, 0.0);
+    }
+}
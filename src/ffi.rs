@@ -0,0 +1,167 @@
+//! C ABI for embedding the detector in C, C++ or Delphi applications
+//!
+//! Built into the `cdylib`; the matching declarations are in
+//! `include/luuma_cursor_helper.h`, generated with `cbindgen`. Every function
+//! takes the pointer returned by [`luuma_create`] and reports failure with a
+//! negative `LUUMA_ERR_*` code.
+
+use crate::background::BackgroundDetector;
+use crate::{CursorDetector, CursorEvent};
+use std::ffi::{c_char, c_void, CString};
+
+/// The call succeeded
+pub const LUUMA_OK: i32 = 0;
+/// The detector pointer was null
+pub const LUUMA_ERR_NULL: i32 = -1;
+/// The call needs a stopped detector but monitoring is running
+pub const LUUMA_ERR_RUNNING: i32 = -2;
+/// The call needs running monitoring but the detector is stopped
+pub const LUUMA_ERR_NOT_RUNNING: i32 = -3;
+/// Monitoring failed to start or stop; details are in the detector log
+pub const LUUMA_ERR_FAILED: i32 = -4;
+
+/// Called with each event as a NUL-terminated JSON string and the registered user data
+///
+/// Runs on the detector's processing thread. The string is only valid during the call.
+pub type LuumaEventCallback = extern "C" fn(event_json: *const c_char, user_data: *mut c_void);
+
+/// User data pointer handed back to the callback; the caller vouches for its thread safety
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+unsafe impl Send for UserData {}
+
+/// Opaque detector owned by the C caller
+pub struct LuumaDetector {
+    background: BackgroundDetector,
+    /// Installed again on every start, since starting consumes the event handler
+    callback: Option<(LuumaEventCallback, UserData)>,
+}
+
+/// Create a detector with default settings; free it with [`luuma_destroy`]
+#[no_mangle]
+pub extern "C" fn luuma_create() -> *mut LuumaDetector {
+    let background = BackgroundDetector::new(CursorDetector::new());
+    Box::into_raw(Box::new(LuumaDetector { background, callback: None }))
+}
+
+/// Stop monitoring if needed and free the detector
+///
+/// # Safety
+///
+/// `detector` must come from [`luuma_create`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn luuma_destroy(detector: *mut LuumaDetector) {
+    if detector.is_null() {
+        return;
+    }
+    // Dropping the background detector stops monitoring
+    drop(Box::from_raw(detector));
+}
+
+/// Register the event callback, or clear it with `NULL`; only while stopped
+///
+/// # Safety
+///
+/// `detector` must come from [`luuma_create`]. `user_data` is passed to the
+/// callback from another thread and must stay valid until the detector is
+/// stopped.
+#[no_mangle]
+pub unsafe extern "C" fn luuma_set_event_callback(
+    detector: *mut LuumaDetector,
+    callback: Option<LuumaEventCallback>,
+    user_data: *mut c_void,
+) -> i32 {
+    let Some(detector) = detector.as_mut() else {
+        return LUUMA_ERR_NULL;
+    };
+    if detector.background.is_running() {
+        return LUUMA_ERR_RUNNING;
+    }
+    detector.callback = callback.map(|callback| (callback, UserData(user_data)));
+    LUUMA_OK
+}
+
+/// Start monitoring on a background thread and return immediately
+///
+/// # Safety
+///
+/// `detector` must come from [`luuma_create`].
+#[no_mangle]
+pub unsafe extern "C" fn luuma_start(detector: *mut LuumaDetector) -> i32 {
+    let Some(detector) = detector.as_mut() else {
+        return LUUMA_ERR_NULL;
+    };
+    let callback = detector.callback;
+    let Some(inner) = detector.background.detector_mut() else {
+        return LUUMA_ERR_RUNNING;
+    };
+    if let Some((callback, user_data)) = callback {
+        inner.set_event_handler(move |event: CursorEvent| {
+            // Use the whole wrapper so the closure captures it rather than the bare pointer
+            let user_data = &user_data;
+            if let Ok(json) = CString::new(event.to_json()) {
+                callback(json.as_ptr(), user_data.0);
+            }
+        });
+    }
+
+    match detector.background.start() {
+        Ok(()) => LUUMA_OK,
+        Err(error) => {
+            CursorDetector::log_message(&error);
+            LUUMA_ERR_FAILED
+        }
+    }
+}
+
+/// Current cursor state as a JSON string, or `NULL` for a null detector
+///
+/// Free the string with [`luuma_free_string`].
+///
+/// # Safety
+///
+/// `detector` must come from [`luuma_create`].
+#[no_mangle]
+pub unsafe extern "C" fn luuma_get_state_json(detector: *const LuumaDetector) -> *mut c_char {
+    let Some(detector) = detector.as_ref() else {
+        return std::ptr::null_mut();
+    };
+    CString::new(detector.background.handle().get_state().to_json()).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Free a string returned by this library
+///
+/// # Safety
+///
+/// `string` must come from this library and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn luuma_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Stop monitoring and wait for the detector's threads to finish
+///
+/// The detector can be started again afterwards.
+///
+/// # Safety
+///
+/// `detector` must come from [`luuma_create`].
+#[no_mangle]
+pub unsafe extern "C" fn luuma_stop(detector: *mut LuumaDetector) -> i32 {
+    let Some(detector) = detector.as_mut() else {
+        return LUUMA_ERR_NULL;
+    };
+    if !detector.background.is_running() {
+        return LUUMA_ERR_NOT_RUNNING;
+    }
+    match detector.background.stop() {
+        Ok(()) => LUUMA_OK,
+        Err(error) => {
+            CursorDetector::log_message(&error);
+            LUUMA_ERR_FAILED
+        }
+    }
+}
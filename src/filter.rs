@@ -0,0 +1,81 @@
+//! Event kind masks and predicates that drop events early in the pipeline
+
+use crate::{CursorEvent, EventKind};
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Set of event kinds, one bit per [`EventKind`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct EventKindMask(u64);
+
+impl EventKindMask {
+    /// Every event kind
+    pub const ALL: Self = Self(u64::MAX);
+    /// No event kinds
+    pub const NONE: Self = Self(0);
+
+    /// Mask containing only the given kinds
+    pub fn of(kinds: &[EventKind]) -> Self {
+        kinds.iter().fold(Self::NONE, |mask, &kind| mask.with(kind))
+    }
+
+    /// Add a kind to the mask
+    pub fn with(self, kind: EventKind) -> Self {
+        Self(self.0 | Self::bit(kind))
+    }
+
+    /// Remove a kind from the mask
+    pub fn without(self, kind: EventKind) -> Self {
+        Self(self.0 & !Self::bit(kind))
+    }
+
+    /// Whether the mask contains a kind
+    pub fn contains(&self, kind: EventKind) -> bool {
+        self.0 & Self::bit(kind) != 0
+    }
+
+    fn bit(kind: EventKind) -> u64 {
+        1 << (kind as u64)
+    }
+}
+
+impl Default for EventKindMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl FromIterator<EventKind> for EventKindMask {
+    fn from_iter<I: IntoIterator<Item = EventKind>>(kinds: I) -> Self {
+        kinds.into_iter().fold(Self::NONE, Self::with)
+    }
+}
+
+/// Predicate deciding whether an event continues to handlers and sinks
+pub type EventFilter = Box<dyn Fn(&CursorEvent) -> bool + Send>;
+
+/// Mask shared between the detector, its handles and the producer threads
+#[cfg(windows)]
+#[derive(Debug)]
+pub(crate) struct SharedKindMask(AtomicU64);
+
+#[cfg(windows)]
+impl SharedKindMask {
+    pub(crate) fn new(mask: EventKindMask) -> Self {
+        Self(AtomicU64::new(mask.0))
+    }
+
+    pub(crate) fn load(&self) -> EventKindMask {
+        EventKindMask(self.0.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn store(&self, mask: EventKindMask) {
+        self.0.store(mask.0, Ordering::Relaxed);
+    }
+
+    /// Whether events of a kind should be created at all
+    pub(crate) fn allows(&self, kind: EventKind) -> bool {
+        self.load().contains(kind)
+    }
+}
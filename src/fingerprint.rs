@@ -0,0 +1,150 @@
+//! Stable fingerprints of cursor images for identifying custom cursors
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{OnceLock, RwLock};
+use windows::Win32::Graphics::Gdi::{
+    DeleteObject, GetDC, GetDIBits, GetObjectW, ReleaseDC, BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+    DIB_RGB_COLORS, HBITMAP, HDC, HGDIOBJ,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetIconInfo, HICON, ICONINFO};
+
+use crate::CursorType;
+
+/// Registered fingerprints plus handles already known not to match any of them
+#[derive(Default)]
+struct Registry {
+    names: HashMap<u64, CursorType>,
+    unmatched: HashSet<usize>,
+}
+
+static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+
+/// Hash the image of a cursor handle
+///
+/// The hash covers the hotspot and the mask and color pixels, so it stays the same
+/// across processes, sessions and reboots as long as the cursor artwork does not
+/// change. `handle` is the raw `HCURSOR` value.
+pub fn cursor_fingerprint(handle: usize) -> Option<u64> {
+    let mut icon = ICONINFO::default();
+    unsafe { GetIconInfo(HICON(handle as *mut _), &mut icon) }.ok()?;
+
+    let mut hash = Fnv1a::new();
+    hash.write(&icon.xHotspot.to_le_bytes());
+    hash.write(&icon.yHotspot.to_le_bytes());
+
+    let screen = unsafe { GetDC(None) };
+    let hashed = [icon.hbmMask, icon.hbmColor]
+        .into_iter()
+        .filter(|bitmap| !bitmap.is_invalid())
+        .all(|bitmap| hash_bitmap(screen, bitmap, &mut hash));
+
+    unsafe {
+        ReleaseDC(None, screen);
+        // GetIconInfo hands back copies the caller owns
+        for bitmap in [icon.hbmMask, icon.hbmColor] {
+            if !bitmap.is_invalid() {
+                let _ = DeleteObject(HGDIOBJ(bitmap.0));
+            }
+        }
+    }
+
+    hashed.then(|| hash.finish())
+}
+
+/// Fingerprint of the cursor currently shown
+pub fn current_cursor_fingerprint() -> Option<u64> {
+    let cursor_info = crate::query_cursor_info()?;
+    cursor_fingerprint(cursor_info.hCursor.0 as usize)
+}
+
+pub(crate) fn register(fingerprint: u64, name: &str) {
+    let mut registry = registry().write().unwrap_or_else(|e| e.into_inner());
+    registry.names.insert(fingerprint, CursorType::named(name));
+    // A handle that missed before may match the new entry
+    registry.unmatched.clear();
+}
+
+pub(crate) fn unregister(fingerprint: u64) {
+    let mut registry = registry().write().unwrap_or_else(|e| e.into_inner());
+    registry.names.remove(&fingerprint);
+}
+
+/// Resolve a handle missing from the handle cache through its fingerprint
+///
+/// Hashing reads the cursor bitmaps, so handles that do not match are remembered
+/// and skipped on later moves.
+pub(crate) fn lookup(handle: usize) -> Option<CursorType> {
+    {
+        let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+        if registry.names.is_empty() || registry.unmatched.contains(&handle) {
+            return None;
+        }
+    }
+
+    let matched = cursor_fingerprint(handle).and_then(|fingerprint| {
+        let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+        registry.names.get(&fingerprint).copied()
+    });
+    if matched.is_none() {
+        registry().write().unwrap_or_else(|e| e.into_inner()).unmatched.insert(handle);
+    }
+    matched
+}
+
+fn registry() -> &'static RwLock<Registry> {
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Feed a bitmap's size and pixels, read back as 32-bit top-down rows, into the hash
+fn hash_bitmap(dc: HDC, bitmap: HBITMAP, hash: &mut Fnv1a) -> bool {
+    let mut info = BITMAP::default();
+    let size = std::mem::size_of::<BITMAP>() as i32;
+    if unsafe { GetObjectW(HGDIOBJ(bitmap.0), size, Some(&mut info as *mut _ as *mut _)) } != size {
+        return false;
+    }
+
+    let (width, height) = (info.bmWidth, info.bmHeight.abs());
+    let mut header = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    let lines = unsafe {
+        GetDIBits(dc, bitmap, 0, height as u32, Some(pixels.as_mut_ptr() as *mut _), &mut header, DIB_RGB_COLORS)
+    };
+    if lines != height {
+        return false;
+    }
+
+    hash.write(&width.to_le_bytes());
+    hash.write(&height.to_le_bytes());
+    hash.write(&pixels);
+    true
+}
+
+/// 64-bit FNV-1a, chosen over `DefaultHasher` because its output never changes between builds
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
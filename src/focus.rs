@@ -0,0 +1,98 @@
+//! Foreground window tracking through WinEvent hooks
+
+use crate::clock::{Clock, SharedClock};
+use crate::queue::EventQueue;
+use crate::window::WindowContext;
+use crate::CursorEvent;
+use std::cell::RefCell;
+use std::sync::Arc;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, EVENT_SYSTEM_FOREGROUND, WINEVENT_OUTOFCONTEXT};
+
+/// Queue and clock for focus events, with the focused window's handle
+type FocusTarget = (Arc<EventQueue<CursorEvent>>, SharedClock, usize);
+
+thread_local! {
+    // WinEvent callbacks carry no user data and run on the thread that installed the hook
+    static FOCUS_QUEUE: RefCell<Option<FocusTarget>> = const { RefCell::new(None) };
+}
+
+/// Foreground change hook installed on the calling thread, removed on drop
+///
+/// Notifications are delivered through the thread's message loop, so this must be
+/// created on the hook thread before it starts pumping messages.
+pub(crate) struct FocusWatcher {
+    hook: HWINEVENTHOOK,
+}
+
+impl FocusWatcher {
+    /// Start watching and report the current foreground window right away
+    pub(crate) fn install(event_queue: Arc<EventQueue<CursorEvent>>, clock: SharedClock) -> Result<Self, String> {
+        let hook = unsafe {
+            SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                None,
+                Some(foreground_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            )
+        };
+        if hook.is_invalid() {
+            return Err("Failed to install foreground window hook".to_string());
+        }
+
+        FOCUS_QUEUE.with(|slot| *slot.borrow_mut() = Some((event_queue, clock, 0)));
+        report_focus(unsafe { GetForegroundWindow() });
+        Ok(Self { hook })
+    }
+}
+
+impl Drop for FocusWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnhookWinEvent(self.hook);
+        }
+        FOCUS_QUEUE.with(|slot| slot.borrow_mut().take());
+    }
+}
+
+unsafe extern "system" fn foreground_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    hwnd: HWND,
+    _object: i32,
+    _child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    report_focus(hwnd);
+}
+
+/// Queue a `FocusChanged` event unless the window is already the focused one
+fn report_focus(hwnd: HWND) {
+    let handle = hwnd.0 as usize;
+    FOCUS_QUEUE.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        let Some((event_queue, clock, focused)) = slot.as_mut() else {
+            return;
+        };
+        if handle == 0 || handle == *focused {
+            return;
+        }
+        *focused = handle;
+
+        if let Some(window) = WindowContext::from_hwnd(handle) {
+            let focus_event = CursorEvent::FocusChanged {
+                hwnd: window.hwnd,
+                pid: window.pid,
+                process_name: window.process_name,
+                title: window.title,
+                timestamp: clock.now(),
+            };
+            let _ = event_queue.push(focus_event);
+        }
+    });
+}
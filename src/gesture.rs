@@ -0,0 +1,269 @@
+//! Swipe, circle and shake recognition from the movement stream
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(windows)]
+use std::collections::VecDeque;
+#[cfg(windows)]
+use std::f64::consts::TAU;
+#[cfg(windows)]
+use std::time::Instant;
+
+/// Screen direction of a swipe
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Shape recognized in the cursor movement
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum GestureKind {
+    /// Fast, nearly straight stroke along one axis
+    Swipe(Direction),
+    /// Full turn around a center, in either direction
+    Circle,
+    /// Rapid back-and-forth strokes, like shaking the mouse to find the cursor
+    Shake,
+}
+
+/// Thresholds for swipes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SwipeThresholds {
+    /// Distance in pixels the stroke must cover along its axis
+    pub min_distance: f64,
+    /// Longest time the stroke may take
+    #[serde(with = "crate::duration_ms")]
+    pub max_duration: Duration,
+    /// Largest drift across the axis as a fraction of the distance along it
+    pub max_deviation: f64,
+}
+
+impl Default for SwipeThresholds {
+    fn default() -> Self {
+        Self { min_distance: 400.0, max_duration: Duration::from_millis(300), max_deviation: 0.25 }
+    }
+}
+
+/// Thresholds for circles
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CircleThresholds {
+    /// Smallest radius in pixels, so jitter around a point is not a circle
+    pub min_radius: f64,
+    /// Longest time a full turn may take
+    #[serde(with = "crate::duration_ms")]
+    pub max_duration: Duration,
+}
+
+impl Default for CircleThresholds {
+    fn default() -> Self {
+        Self { min_radius: 40.0, max_duration: Duration::from_millis(1500) }
+    }
+}
+
+/// Thresholds for shakes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ShakeThresholds {
+    /// Direction reversals needed within the window
+    pub min_reversals: usize,
+    /// Shortest stroke in pixels between reversals
+    pub min_stroke: f64,
+    /// Time in which the reversals must happen
+    #[serde(with = "crate::duration_ms")]
+    pub window: Duration,
+}
+
+impl Default for ShakeThresholds {
+    fn default() -> Self {
+        Self { min_reversals: 4, min_stroke: 40.0, window: Duration::from_millis(700) }
+    }
+}
+
+/// Which gestures are recognized and how sensitive each is
+///
+/// Set a gesture to `None` to stop recognizing it. After a gesture is reported the
+/// movement history is cleared, and nothing else is reported until `cooldown` passes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct GestureConfig {
+    pub swipe: Option<SwipeThresholds>,
+    pub circle: Option<CircleThresholds>,
+    pub shake: Option<ShakeThresholds>,
+    #[serde(with = "crate::duration_ms")]
+    pub cooldown: Duration,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            swipe: Some(SwipeThresholds::default()),
+            circle: Some(CircleThresholds::default()),
+            shake: Some(ShakeThresholds::default()),
+            cooldown: Duration::from_millis(500),
+        }
+    }
+}
+
+impl GestureConfig {
+    /// Only recognize shakes, with default thresholds
+    pub fn shake_only() -> Self {
+        Self { swipe: None, circle: None, ..Self::default() }
+    }
+
+    /// How much movement history any enabled gesture needs
+    #[cfg(windows)]
+    fn history(&self) -> Duration {
+        [
+            self.swipe.map(|swipe| swipe.max_duration),
+            self.circle.map(|circle| circle.max_duration),
+            self.shake.map(|shake| shake.window),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or_default()
+    }
+}
+
+/// Matches recent positions against the enabled gestures, owned by the hook thread
+#[cfg(windows)]
+#[derive(Debug)]
+pub(crate) struct GestureRecognizer {
+    config: GestureConfig,
+    samples: VecDeque<((f64, f64), Instant)>,
+    last_gesture: Option<Instant>,
+}
+
+#[cfg(windows)]
+impl GestureRecognizer {
+    pub(crate) fn new(config: GestureConfig) -> Self {
+        Self { config, samples: VecDeque::new(), last_gesture: None }
+    }
+
+    /// Feed a raw position and report a gesture it completes
+    pub(crate) fn update(&mut self, position: (f64, f64), now: Instant) -> Option<GestureKind> {
+        let history = self.config.history();
+        while self.samples.front().is_some_and(|(_, time)| now.duration_since(*time) > history) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((position, now));
+
+        if self.last_gesture.is_some_and(|last| now.duration_since(last) < self.config.cooldown) {
+            return None;
+        }
+
+        // Shakes are made of short strokes, so check them before a stroke can pass for a swipe
+        let gesture = self
+            .config
+            .shake
+            .and_then(|shake| self.shake(shake, now))
+            .or_else(|| self.config.circle.and_then(|circle| self.circle(circle, now)))
+            .or_else(|| self.config.swipe.and_then(|swipe| self.swipe(swipe, now)))?;
+
+        self.samples.clear();
+        self.last_gesture = Some(now);
+        Some(gesture)
+    }
+
+    /// Positions no older than `window`, oldest first
+    fn recent(&self, window: Duration, now: Instant) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.samples
+            .iter()
+            .filter(move |(_, time)| now.duration_since(*time) <= window)
+            .map(|(position, _)| *position)
+    }
+
+    fn swipe(&self, thresholds: SwipeThresholds, now: Instant) -> Option<GestureKind> {
+        let start = self.recent(thresholds.max_duration, now).next()?;
+        let end = self.samples.back()?.0;
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let (along, across) = if dx.abs() >= dy.abs() { (dx.abs(), dy.abs()) } else { (dy.abs(), dx.abs()) };
+        if along < thresholds.min_distance || across > along * thresholds.max_deviation {
+            return None;
+        }
+
+        let direction = match (dx.abs() >= dy.abs(), dx >= 0.0, dy >= 0.0) {
+            (true, true, _) => Direction::Right,
+            (true, false, _) => Direction::Left,
+            (false, _, true) => Direction::Down,
+            (false, _, false) => Direction::Up,
+        };
+        Some(GestureKind::Swipe(direction))
+    }
+
+    fn circle(&self, thresholds: CircleThresholds, now: Instant) -> Option<GestureKind> {
+        let points: Vec<(f64, f64)> = self.recent(thresholds.max_duration, now).collect();
+
+        // Sum the signed heading changes; a full turn either way is a circle
+        let mut turned: f64 = 0.0;
+        let mut last_heading = None;
+        for pair in points.windows(2) {
+            let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+            if dx == 0.0 && dy == 0.0 {
+                continue;
+            }
+            let heading = dy.atan2(dx);
+            if let Some(last) = last_heading {
+                let mut change = heading - last;
+                if change > TAU / 2.0 {
+                    change -= TAU;
+                } else if change < -TAU / 2.0 {
+                    change += TAU;
+                }
+                turned += change;
+            }
+            last_heading = Some(heading);
+        }
+        if turned.abs() < TAU {
+            return None;
+        }
+
+        let (mut min, mut max) = (points[0], points[0]);
+        for point in &points {
+            min = (min.0.min(point.0), min.1.min(point.1));
+            max = (max.0.max(point.0), max.1.max(point.1));
+        }
+        let radius = (max.0 - min.0).min(max.1 - min.1) / 2.0;
+        (radius >= thresholds.min_radius).then_some(GestureKind::Circle)
+    }
+
+    fn shake(&self, thresholds: ShakeThresholds, now: Instant) -> Option<GestureKind> {
+        let points: Vec<(f64, f64)> = self.recent(thresholds.window, now).collect();
+        let horizontal = reversals(points.iter().map(|point| point.0), thresholds.min_stroke);
+        let vertical = reversals(points.iter().map(|point| point.1), thresholds.min_stroke);
+        (horizontal.max(vertical) >= thresholds.min_reversals).then_some(GestureKind::Shake)
+    }
+}
+
+/// Count direction changes along one axis between strokes of at least `min_stroke` pixels
+#[cfg(windows)]
+fn reversals(coordinates: impl Iterator<Item = f64>, min_stroke: f64) -> usize {
+    let mut count = 0;
+    // Extreme of the current stroke and the direction it is heading
+    let mut turning_point: Option<f64> = None;
+    let mut heading: Option<bool> = None;
+
+    for coordinate in coordinates {
+        let Some(extreme) = turning_point else {
+            turning_point = Some(coordinate);
+            continue;
+        };
+
+        let forward = coordinate > extreme;
+        match heading {
+            // Still heading the same way, so the stroke extends
+            Some(current) if current == forward => turning_point = Some(coordinate),
+            // Turned back far enough to count as a new stroke
+            _ if (coordinate - extreme).abs() >= min_stroke => {
+                if heading.is_some() {
+                    count += 1;
+                }
+                heading = Some(forward);
+                turning_point = Some(coordinate);
+            }
+            _ => {}
+        }
+    }
+    count
+}
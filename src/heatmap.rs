@@ -0,0 +1,186 @@
+//! Grid heatmaps of cursor attention and clicks
+
+use crate::sink::EventSink;
+use crate::{CursorEvent, Rect, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+/// What each recorded event adds to its cell
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum HeatmapWeight {
+    /// One per Move event
+    #[default]
+    Samples,
+    /// Seconds the cursor rested in the cell before moving on
+    Dwell,
+    /// One per Click event
+    Clicks,
+}
+
+/// Accumulates positions into a fixed grid over a screen rectangle
+///
+/// Feed it events from a handler with [`record`](Self::record), or share it as a
+/// sink through `Arc<Mutex<Heatmap>>` and read it back while monitoring runs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Heatmap {
+    bounds: Rect,
+    columns: usize,
+    rows: usize,
+    weight: HeatmapWeight,
+    cells: Vec<f64>,
+    #[serde(skip)]
+    last_move: Option<((f64, f64), Timestamp)>,
+}
+
+impl Heatmap {
+    /// Create an empty heatmap splitting `bounds` into `columns` x `rows` cells
+    pub fn new(bounds: Rect, columns: usize, rows: usize) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        Self {
+            bounds,
+            columns,
+            rows,
+            weight: HeatmapWeight::Samples,
+            cells: vec![0.0; columns * rows],
+            last_move: None,
+        }
+    }
+
+    /// Cover the virtual desktop with square cells of `cell_size` pixels
+    #[cfg(windows)]
+    pub fn for_virtual_screen(cell_size: u32) -> Self {
+        let bounds = crate::CursorDetector::get_virtual_screen_bounds();
+        let cell_size = cell_size.max(1) as i32;
+        let columns = (bounds.width() + cell_size - 1) / cell_size;
+        let rows = (bounds.height() + cell_size - 1) / cell_size;
+        Self::new(bounds, columns.max(1) as usize, rows.max(1) as usize)
+    }
+
+    /// Choose what each event adds to its cell
+    pub fn weight(mut self, weight: HeatmapWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Grid size as (columns, rows)
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.columns, self.rows)
+    }
+
+    /// Screen rectangle covered by the grid
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// Fold an event into the grid according to the weight
+    pub fn record(&mut self, event: &CursorEvent) {
+        match (self.weight, event) {
+            (HeatmapWeight::Samples, CursorEvent::Move { position, .. }) => self.add(*position, 1.0),
+            (HeatmapWeight::Dwell, CursorEvent::Move { position, timestamp, .. }) => {
+                if let Some((last, since)) = self.last_move.replace((*position, *timestamp)) {
+                    self.add(last, timestamp.duration_since(since).as_secs_f64());
+                }
+            }
+            (HeatmapWeight::Clicks, CursorEvent::Click { position, .. }) => self.add(*position, 1.0),
+            _ => {}
+        }
+    }
+
+    /// Add an amount to the cell containing a position, ignoring positions outside the bounds
+    pub fn add(&mut self, position: (f64, f64), amount: f64) {
+        if let Some((column, row)) = self.cell_at(position) {
+            self.cells[row * self.columns + column] += amount;
+        }
+    }
+
+    /// Column and row of the cell containing a position
+    pub fn cell_at(&self, position: (f64, f64)) -> Option<(usize, usize)> {
+        if !self.bounds.contains(position.0, position.1) {
+            return None;
+        }
+        let column = (position.0 - self.bounds.left as f64) * self.columns as f64 / self.bounds.width() as f64;
+        let row = (position.1 - self.bounds.top as f64) * self.rows as f64 / self.bounds.height() as f64;
+        Some(((column as usize).min(self.columns - 1), (row as usize).min(self.rows - 1)))
+    }
+
+    /// Value of a cell
+    pub fn get(&self, column: usize, row: usize) -> f64 {
+        if column >= self.columns || row >= self.rows {
+            return 0.0;
+        }
+        self.cells[row * self.columns + column]
+    }
+
+    /// Largest cell value
+    pub fn max(&self) -> f64 {
+        self.cells.iter().copied().fold(0.0, f64::max)
+    }
+
+    /// Reset every cell to zero
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = 0.0);
+        self.last_move = None;
+    }
+
+    /// Cell values as rows of columns
+    pub fn to_grid(&self) -> Vec<Vec<f64>> {
+        self.cells.chunks(self.columns).map(<[f64]>::to_vec).collect()
+    }
+
+    /// Cell values as CSV, one line per row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in self.cells.chunks(self.columns) {
+            for (index, value) in row.iter().enumerate() {
+                if index > 0 {
+                    csv.push(',');
+                }
+                let _ = write!(csv, "{}", value);
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Render the grid with `cell_size` pixels per cell, from transparent through blue to red
+    #[cfg(feature = "image")]
+    pub fn to_image(&self, cell_size: u32) -> image::RgbaImage {
+        let cell_size = cell_size.max(1);
+        let max = self.max();
+        image::RgbaImage::from_fn(self.columns as u32 * cell_size, self.rows as u32 * cell_size, |x, y| {
+            let value = self.get((x / cell_size) as usize, (y / cell_size) as usize);
+            image::Rgba(heat_color(if max > 0.0 { value / max } else { 0.0 }))
+        })
+    }
+
+    /// Render the grid and save it as a PNG file
+    #[cfg(feature = "image")]
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P, cell_size: u32) -> Result<(), String> {
+        self.to_image(cell_size)
+            .save_with_format(path.as_ref(), image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to save heatmap to {}: {}", path.as_ref().display(), e))
+    }
+}
+
+/// Color for a normalized intensity, fading in from transparent blue to opaque red
+#[cfg(feature = "image")]
+fn heat_color(intensity: f64) -> [u8; 4] {
+    const STOPS: [[f64; 3]; 4] = [[0.0, 0.0, 255.0], [0.0, 255.0, 0.0], [255.0, 255.0, 0.0], [255.0, 0.0, 0.0]];
+    let intensity = intensity.clamp(0.0, 1.0);
+    if intensity == 0.0 {
+        return [0, 0, 0, 0];
+    }
+
+    let scaled = intensity * (STOPS.len() - 1) as f64;
+    let index = (scaled as usize).min(STOPS.len() - 2);
+    let fraction = scaled - index as f64;
+    let channel = |c: usize| (STOPS[index][c] + (STOPS[index + 1][c] - STOPS[index][c]) * fraction) as u8;
+    [channel(0), channel(1), channel(2), (64.0 + 191.0 * intensity) as u8]
+}
+
+impl EventSink for Arc<Mutex<Heatmap>> {
+    fn handle(&mut self, event: &CursorEvent) {
+        self.lock().unwrap_or_else(|e| e.into_inner()).record(event);
+    }
+}
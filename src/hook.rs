@@ -0,0 +1,265 @@
+//! Native low-level mouse hook driving the monitoring loop
+
+use crate::blocking::InputGuard;
+use crate::dpi::PhysicalCoordinates;
+use crate::scroll::ScrollAxis;
+use crate::source::InputSource;
+use crate::MouseButton;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{LPARAM, LRESULT, POINT, WPARAM};
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, DispatchMessageW, GetCursorPos, GetMessageW, KillTimer, SetTimer, SetWindowsHookExW,
+    TranslateMessage, UnhookWindowsHookEx, HC_ACTION, HHOOK, MSG, MSLLHOOKSTRUCT, WH_MOUSE_LL, WM_LBUTTONDOWN,
+    WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDOWN,
+    WM_RBUTTONUP, WM_TIMER,
+};
+
+/// How often the hook thread checks that its hook is still installed
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Consecutive checks that must see mouse input without a hook call before the hook counts as lost
+const WATCHDOG_STRIKES: u32 = 2;
+
+/// Wait before retrying a failed reinstall, doubled per failure up to [`MAX_RETRY_DELAY`]
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// What happened in a single hook callback
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum HookAction {
+    Move { x: f64, y: f64 },
+    ButtonPress(MouseButton),
+    ButtonRelease(MouseButton),
+    Wheel { axis: ScrollAxis, delta: i32 },
+}
+
+/// A decoded low-level mouse sample
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HookEvent {
+    pub(crate) action: HookAction,
+    pub(crate) source: InputSource,
+}
+
+/// The hook was found removed and installed again
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HookRestart {
+    /// Installs it took, more than one when reinstalling failed at first
+    pub(crate) attempts: u32,
+    /// Time since the hook last ran, during which input was missed
+    pub(crate) gap: Duration,
+}
+
+type HookCallback = Box<dyn FnMut(HookEvent)>;
+
+thread_local! {
+    // The hook procedure has no user data pointer, so the callback lives with the hook thread
+    static HOOK_CALLBACK: RefCell<Option<HookCallback>> = const { RefCell::new(None) };
+    static INPUT_GUARD: RefCell<Option<InputGuard>> = const { RefCell::new(None) };
+    static LAST_CALL: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Install a `WH_MOUSE_LL` hook on this thread and pump messages until `WM_QUIT`
+///
+/// Input the guard rejects is still reported to the callback but never reaches other applications.
+/// The guard is taken for as long as the hook runs and put back when it returns.
+/// Windows silently removes a low-level hook that takes longer than `LowLevelHooksTimeout` to
+/// return, so a watchdog reinstalls it, with backoff while that fails, and reports each restart.
+pub(crate) fn run_mouse_hook<F, R>(
+    guard: &mut Option<InputGuard>,
+    callback: F,
+    mut on_restart: R,
+) -> Result<(), String>
+where
+    F: FnMut(HookEvent) + 'static,
+    R: FnMut(HookRestart),
+{
+    // Window and monitor lookups made from the callback must agree with the hook's physical coordinates
+    let _physical = PhysicalCoordinates::enter();
+    HOOK_CALLBACK.with(|slot| *slot.borrow_mut() = Some(Box::new(callback)));
+    INPUT_GUARD.with(|slot| *slot.borrow_mut() = guard.take());
+
+    let hook = match install_hook() {
+        Ok(hook) => hook,
+        Err(error) => {
+            HOOK_CALLBACK.with(|slot| slot.borrow_mut().take());
+            *guard = INPUT_GUARD.with(|slot| slot.borrow_mut().take());
+            return Err(error);
+        }
+    };
+    LAST_CALL.with(|last| last.set(Some(Instant::now())));
+    let mut watchdog = HookWatchdog::new(hook);
+    let timer = unsafe { SetTimer(None, 0, WATCHDOG_INTERVAL.as_millis() as u32, None) };
+
+    // Low-level hooks are called from this thread's message loop
+    let mut message = MSG::default();
+    while unsafe { GetMessageW(&mut message, None, 0, 0) }.0 > 0 {
+        if message.message == WM_TIMER && message.hwnd.is_invalid() && message.wParam.0 == timer {
+            if let Some(restart) = watchdog.check() {
+                on_restart(restart);
+            }
+            continue;
+        }
+        unsafe {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+
+    unsafe {
+        let _ = KillTimer(None, timer);
+        if let Some(hook) = watchdog.hook {
+            let _ = UnhookWindowsHookEx(hook);
+        }
+    }
+    LAST_CALL.with(|last| last.set(None));
+    HOOK_CALLBACK.with(|slot| slot.borrow_mut().take());
+    *guard = INPUT_GUARD.with(|slot| slot.borrow_mut().take());
+    Ok(())
+}
+
+fn install_hook() -> Result<HHOOK, String> {
+    unsafe { SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), None, 0) }
+        .map_err(|e| format!("Failed to install mouse hook: {}", e))
+}
+
+/// Notices a hook that stopped being called while the mouse is in use, and replaces it
+///
+/// Windows gives no notice when it removes a hook. Instead, the cursor moving along with
+/// fresh user input while the hook stays silent gives it away; the input check keeps
+/// `SetCursorPos` calls from other applications from looking like a lost hook.
+struct HookWatchdog {
+    hook: Option<HHOOK>,
+    last_check: Instant,
+    last_position: POINT,
+    last_input: u32,
+    strikes: u32,
+    /// Failed reinstalls since the hook was lost, with the time of the next attempt
+    failures: u32,
+    retry_at: Option<Instant>,
+    lost_since: Option<Instant>,
+}
+
+impl HookWatchdog {
+    fn new(hook: HHOOK) -> Self {
+        Self {
+            hook: Some(hook),
+            last_check: Instant::now(),
+            last_position: cursor_position(),
+            last_input: last_input_tick(),
+            strikes: 0,
+            failures: 0,
+            retry_at: None,
+            lost_since: None,
+        }
+    }
+
+    /// Runs on each timer tick, returning a restart once the hook is back
+    fn check(&mut self) -> Option<HookRestart> {
+        let now = Instant::now();
+        let (position, input) = (cursor_position(), last_input_tick());
+        let moved = position != self.last_position && input != self.last_input;
+        let called = LAST_CALL.with(Cell::get).is_some_and(|last| last >= self.last_check);
+        (self.last_check, self.last_position, self.last_input) = (now, position, input);
+
+        if let Some(hook) = self.hook {
+            self.strikes = if moved && !called { self.strikes + 1 } else { 0 };
+            if self.strikes < WATCHDOG_STRIKES {
+                return None;
+            }
+            // Usually gone already; unhooking makes sure two hooks never run side by side
+            unsafe {
+                let _ = UnhookWindowsHookEx(hook);
+            }
+            self.hook = None;
+            self.strikes = 0;
+            self.lost_since = LAST_CALL.with(Cell::get).or(Some(now));
+        }
+
+        if self.retry_at.is_some_and(|retry_at| now < retry_at) {
+            return None;
+        }
+        match install_hook() {
+            Ok(hook) => {
+                self.hook = Some(hook);
+                let restart = HookRestart {
+                    attempts: self.failures + 1,
+                    gap: self.lost_since.take().map_or(Duration::ZERO, |since| now.duration_since(since)),
+                };
+                (self.failures, self.retry_at) = (0, None);
+                LAST_CALL.with(|last| last.set(Some(now)));
+                Some(restart)
+            }
+            Err(_) => {
+                let delay = INITIAL_RETRY_DELAY.saturating_mul(1 << self.failures.min(6)).min(MAX_RETRY_DELAY);
+                self.failures += 1;
+                self.retry_at = Some(now + delay);
+                None
+            }
+        }
+    }
+}
+
+fn cursor_position() -> POINT {
+    let mut position = POINT::default();
+    let _ = unsafe { GetCursorPos(&mut position) };
+    position
+}
+
+/// Tick count of the latest keyboard or mouse input in this session
+fn last_input_tick() -> u32 {
+    let mut info = LASTINPUTINFO { cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+    let _ = unsafe { GetLastInputInfo(&mut info) };
+    info.dwTime
+}
+
+fn wheel_delta(info: &MSLLHOOKSTRUCT) -> i32 {
+    (info.mouseData >> 16) as u16 as i16 as i32
+}
+
+unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    LAST_CALL.with(|last| last.set(Some(Instant::now())));
+    if code == HC_ACTION as i32 {
+        let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+        let action = match wparam.0 as u32 {
+            WM_MOUSEMOVE => Some(HookAction::Move { x: info.pt.x as f64, y: info.pt.y as f64 }),
+            WM_LBUTTONDOWN => Some(HookAction::ButtonPress(MouseButton::Left)),
+            WM_LBUTTONUP => Some(HookAction::ButtonRelease(MouseButton::Left)),
+            WM_RBUTTONDOWN => Some(HookAction::ButtonPress(MouseButton::Right)),
+            WM_RBUTTONUP => Some(HookAction::ButtonRelease(MouseButton::Right)),
+            WM_MBUTTONDOWN => Some(HookAction::ButtonPress(MouseButton::Middle)),
+            WM_MBUTTONUP => Some(HookAction::ButtonRelease(MouseButton::Middle)),
+            // The high word of mouseData is the signed wheel delta
+            WM_MOUSEWHEEL => Some(HookAction::Wheel { axis: ScrollAxis::Vertical, delta: wheel_delta(info) }),
+            WM_MOUSEHWHEEL => Some(HookAction::Wheel { axis: ScrollAxis::Horizontal, delta: wheel_delta(info) }),
+            _ => None,
+        };
+
+        if let Some(action) = action {
+            let event = HookEvent { action, source: InputSource::from_hook(info.flags, info.dwExtraInfo) };
+            let position = (info.pt.x as f64, info.pt.y as f64);
+            let block = INPUT_GUARD.with(|slot| match slot.try_borrow_mut() {
+                Ok(mut guard) => guard.as_mut().is_some_and(|guard| guard.should_block(action, position, event.source)),
+                Err(_) => false,
+            });
+
+            HOOK_CALLBACK.with(|slot| {
+                // Skip rather than panic if the callback re-enters the message loop
+                if let Ok(mut callback) = slot.try_borrow_mut() {
+                    if let Some(callback) = callback.as_mut() {
+                        callback(event);
+                    }
+                }
+            });
+
+            // A nonzero result stops the input from reaching the rest of the system
+            if block {
+                return LRESULT(1);
+            }
+        }
+    }
+
+    CallNextHookEx(None, code, wparam, lparam)
+}
@@ -0,0 +1,226 @@
+//! Global hotkeys that pause capture or drop markers into the event stream
+
+use crate::clock::{Clock, SharedClock};
+use crate::queue::EventQueue;
+use crate::session::CapturePause;
+use crate::CursorEvent;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::sync::Arc;
+use windows::core::w;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, RegisterClassW, HWND_MESSAGE, WINDOW_EX_STYLE, WINDOW_STYLE,
+    WM_HOTKEY, WNDCLASSW,
+};
+
+/// A key combination such as Ctrl+Alt+L
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+    /// Virtual-key code of the non-modifier key
+    pub key: u32,
+}
+
+impl Hotkey {
+    /// A key without modifiers, by virtual-key code
+    pub fn new(key: u32) -> Self {
+        Self { ctrl: false, alt: false, shift: false, win: false, key }
+    }
+
+    /// Parse a combination like `"Ctrl+Alt+L"` or `"Shift+F9"`
+    ///
+    /// Keys are letters, digits, `F1` to `F24` and a few named keys (`Space`,
+    /// `Escape`, `Pause`, `Insert`, `Delete`, `Home`, `End`, `PageUp`, `PageDown`).
+    pub fn parse(combination: &str) -> Result<Self, String> {
+        let mut hotkey = Self::new(0);
+        for part in combination.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => hotkey.ctrl = true,
+                "alt" => hotkey.alt = true,
+                "shift" => hotkey.shift = true,
+                "win" | "super" => hotkey.win = true,
+                key if hotkey.key == 0 => {
+                    hotkey.key = virtual_key(key).ok_or_else(|| format!("Unknown key '{}' in hotkey", part))?;
+                }
+                _ => return Err(format!("Hotkey '{}' has more than one key", combination)),
+            }
+        }
+        if hotkey.key == 0 {
+            return Err(format!("Hotkey '{}' has no key", combination));
+        }
+        Ok(hotkey)
+    }
+
+    /// Whether the whole combination is held down right now, from any thread
+    ///
+    /// Polling this suits loops that own no message queue for `WM_HOTKEY`, such as
+    /// an abort key checked while replaying input.
+    pub fn is_held(&self) -> bool {
+        let down = |key: i32| unsafe { GetAsyncKeyState(key) } < 0;
+        // VK_CONTROL, VK_MENU, VK_SHIFT and either Windows key
+        let modifiers = [
+            (self.ctrl, down(0x11)),
+            (self.alt, down(0x12)),
+            (self.shift, down(0x10)),
+            (self.win, down(0x5B) || down(0x5C)),
+        ];
+        down(self.key as i32) && modifiers.iter().all(|&(wanted, held)| !wanted || held)
+    }
+
+    fn modifiers(&self) -> HOT_KEY_MODIFIERS {
+        // Holding the keys down must not toggle capture on every auto-repeat
+        let mut modifiers = MOD_NOREPEAT;
+        let held = [(self.ctrl, MOD_CONTROL), (self.alt, MOD_ALT), (self.shift, MOD_SHIFT), (self.win, MOD_WIN)];
+        for (is_held, modifier) in held {
+            if is_held {
+                modifiers |= modifier;
+            }
+        }
+        modifiers
+    }
+}
+
+/// Virtual-key code for a lowercase key name
+fn virtual_key(name: &str) -> Option<u32> {
+    if let [c] = name.as_bytes() {
+        return c.is_ascii_alphanumeric().then_some(c.to_ascii_uppercase() as u32);
+    }
+    if let Some(number) = name.strip_prefix('f').and_then(|number| number.parse::<u32>().ok()) {
+        return (1..=24).contains(&number).then_some(0x70 + number - 1);
+    }
+    match name {
+        "space" => Some(0x20),
+        "escape" | "esc" => Some(0x1B),
+        "pause" => Some(0x13),
+        "insert" => Some(0x2D),
+        "delete" => Some(0x2E),
+        "home" => Some(0x24),
+        "end" => Some(0x23),
+        "pageup" => Some(0x21),
+        "pagedown" => Some(0x22),
+        _ => None,
+    }
+}
+
+/// What pressing a hotkey does
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum HotkeyAction {
+    /// Pause capture, or resume it when paused
+    TogglePause,
+    /// Emit a `Marker` event with this label
+    Marker(String),
+}
+
+/// State behind the hotkey window of the watching thread
+struct HotkeyTarget {
+    event_queue: Arc<EventQueue<CursorEvent>>,
+    pause: Arc<CapturePause>,
+    clock: SharedClock,
+    actions: Vec<HotkeyAction>,
+}
+
+thread_local! {
+    // Window procedures carry no user data and run on the thread that created the window
+    static HOTKEY_TARGET: RefCell<Option<HotkeyTarget>> = const { RefCell::new(None) };
+}
+
+/// Hotkeys registered to a message-only window on the calling thread
+pub(crate) struct HotkeyWatcher {
+    window: HWND,
+    count: usize,
+}
+
+impl HotkeyWatcher {
+    pub(crate) fn install(
+        bindings: &[(Hotkey, HotkeyAction)],
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        pause: Arc<CapturePause>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        unsafe {
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(hotkey_proc),
+                lpszClassName: w!("LuumaHotkey"),
+                ..Default::default()
+            };
+            // Registering twice fails harmlessly when a detector is restarted
+            RegisterClassW(&class);
+
+            let window = CreateWindowExW(
+                WINDOW_EX_STYLE(0),
+                w!("LuumaHotkey"),
+                w!("LuumaHotkey"),
+                WINDOW_STYLE(0),
+                0,
+                0,
+                0,
+                0,
+                Some(HWND_MESSAGE),
+                None,
+                None,
+                None,
+            )
+            .map_err(|e| format!("Failed to create hotkey window: {}", e))?;
+
+            // Hotkey ids are indexes into the action list
+            for (id, (hotkey, _)) in bindings.iter().enumerate() {
+                if let Err(error) = RegisterHotKey(Some(window), id as i32, hotkey.modifiers(), hotkey.key) {
+                    for registered in 0..id {
+                        let _ = UnregisterHotKey(Some(window), registered as i32);
+                    }
+                    let _ = DestroyWindow(window);
+                    return Err(format!("Failed to register hotkey {:?} (already in use?): {}", hotkey, error));
+                }
+            }
+
+            let actions = bindings.iter().map(|(_, action)| action.clone()).collect();
+            HOTKEY_TARGET.with(|slot| *slot.borrow_mut() = Some(HotkeyTarget { event_queue, pause, clock, actions }));
+            Ok(Self { window, count: bindings.len() })
+        }
+    }
+}
+
+impl Drop for HotkeyWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            for id in 0..self.count {
+                let _ = UnregisterHotKey(Some(self.window), id as i32);
+            }
+            let _ = DestroyWindow(self.window);
+        }
+        HOTKEY_TARGET.with(|slot| slot.borrow_mut().take());
+    }
+}
+
+unsafe extern "system" fn hotkey_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if message == WM_HOTKEY {
+        HOTKEY_TARGET.with(|slot| {
+            if let Some(target) = slot.borrow().as_ref() {
+                match target.actions.get(wparam.0) {
+                    Some(HotkeyAction::TogglePause) => {
+                        let message = if target.pause.toggle_user() { "Capture paused" } else { "Capture resumed" };
+                        crate::CursorDetector::log_message(message);
+                    }
+                    Some(HotkeyAction::Marker(label)) => {
+                        let marker = CursorEvent::Marker {
+                            label: label.clone(),
+                            data: serde_json::Value::Null,
+                            timestamp: target.clock.now(),
+                        };
+                        let _ = target.event_queue.push(marker);
+                    }
+                    None => {}
+                }
+            }
+        });
+        return LRESULT(0);
+    }
+    DefWindowProcW(window, message, wparam, lparam)
+}
@@ -0,0 +1,64 @@
+//! Cursor hotspot and rendered size
+
+use crate::Rect;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use windows::Win32::Graphics::Gdi::{DeleteObject, GetObjectW, BITMAP, HBITMAP, HGDIOBJ};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{GetIconInfo, HICON, ICONINFO};
+
+/// Where the click point sits inside the cursor image
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CursorGeometry {
+    /// Offset of the click point from the image's top-left corner in pixels
+    pub hotspot: (u32, u32),
+    /// Rendered width and height in pixels
+    pub size: (u32, u32),
+}
+
+impl CursorGeometry {
+    /// Screen rectangle covered by the cursor image when its hotspot is at `position`
+    pub fn image_rect(&self, position: (f64, f64)) -> Rect {
+        let left = position.0 as i32 - self.hotspot.0 as i32;
+        let top = position.1 as i32 - self.hotspot.1 as i32;
+        Rect::new(left, top, left + self.size.0 as i32, top + self.size.1 as i32)
+    }
+}
+
+/// Hotspot and size of a cursor handle
+///
+/// Windows reloads cursors at the accessibility cursor size, so the image size
+/// already includes that scaling. `handle` is the raw `HCURSOR` value.
+#[cfg(windows)]
+pub fn cursor_geometry(handle: usize) -> Option<CursorGeometry> {
+    let mut icon = ICONINFO::default();
+    unsafe { GetIconInfo(HICON(handle as *mut _), &mut icon) }.ok()?;
+
+    let size = if icon.hbmColor.is_invalid() {
+        // Monochrome cursors stack the AND and XOR masks in one bitmap of double height
+        bitmap_size(icon.hbmMask).map(|(width, height)| (width, height / 2))
+    } else {
+        bitmap_size(icon.hbmColor)
+    };
+
+    unsafe {
+        // GetIconInfo hands back copies the caller owns
+        for bitmap in [icon.hbmMask, icon.hbmColor] {
+            if !bitmap.is_invalid() {
+                let _ = DeleteObject(HGDIOBJ(bitmap.0));
+            }
+        }
+    }
+
+    Some(CursorGeometry { hotspot: (icon.xHotspot, icon.yHotspot), size: size? })
+}
+
+#[cfg(windows)]
+fn bitmap_size(bitmap: HBITMAP) -> Option<(u32, u32)> {
+    let mut info = BITMAP::default();
+    let size = std::mem::size_of::<BITMAP>() as i32;
+    if unsafe { GetObjectW(HGDIOBJ(bitmap.0), size, Some(&mut info as *mut _ as *mut _)) } != size {
+        return None;
+    }
+    Some((info.bmWidth.unsigned_abs(), info.bmHeight.unsigned_abs()))
+}
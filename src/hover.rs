@@ -0,0 +1,149 @@
+//! Hover-to-click conversion: how long the cursor rests on a target before it is clicked
+
+use crate::{CursorEvent, CursorType, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Application name used when the hovered process cannot be determined
+const UNKNOWN_APPLICATION: &str = "unknown";
+
+/// Hovers over one target and the clicks they led to
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HoverConversion {
+    /// Times the cursor hovered the target, counted when the hover starts for regions
+    /// and when it ends for applications
+    pub hovers: u64,
+    /// Hovers that ended in a click, each counted once
+    pub clicks: u64,
+    /// Milliseconds from the start of each converted hover until its click
+    pub hover_to_click_ms: Vec<u64>,
+}
+
+impl HoverConversion {
+    /// Share of hovers that ended in a click
+    pub fn conversion_rate(&self) -> f64 {
+        if self.hovers == 0 {
+            0.0
+        } else {
+            self.clicks as f64 / self.hovers as f64
+        }
+    }
+
+    /// Mean time from hover to click across converted hovers
+    pub fn mean_time_to_click(&self) -> Option<Duration> {
+        let count = self.hover_to_click_ms.len() as u64;
+        (count > 0).then(|| Duration::from_millis(self.hover_to_click_ms.iter().sum::<u64>() / count))
+    }
+
+    /// Hover-to-click time at a percentile (0.0..=100.0) of converted hovers
+    pub fn time_to_click_percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut times = self.hover_to_click_ms.clone();
+        times.sort_unstable();
+        let last = times.len().checked_sub(1)?;
+        let index = ((percentile.clamp(0.0, 100.0) / 100.0) * last as f64).round() as usize;
+        Some(Duration::from_millis(times[index]))
+    }
+}
+
+/// Hover-to-click conversion per region and per application
+///
+/// A region hover starts with `RegionEnter`. An application hover starts when the
+/// hand cursor appears over one of its windows, the usual sign of a link or button.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HoverReport {
+    /// Keyed by region name
+    pub regions: HashMap<String, HoverConversion>,
+    /// Keyed by the executable name of the window under the hand cursor
+    pub applications: HashMap<String, HoverConversion>,
+}
+
+impl HoverReport {
+    /// Convert the report to JSON string
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Convert the report to pretty-formatted JSON string
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Pairs hovers with the clicks that end them on the processing thread
+#[derive(Debug, Default)]
+pub(crate) struct HoverTracker {
+    report: HoverReport,
+    /// Regions the cursor is in and when it entered, while not yet clicked
+    regions: HashMap<String, Timestamp>,
+    /// Whether the hand cursor is showing
+    hand: bool,
+    /// Application under the hand cursor, once a Move names it, and when the hand appeared
+    hand_hover: Option<(Option<String>, Timestamp)>,
+}
+
+impl HoverTracker {
+    pub(crate) fn report(&self) -> HoverReport {
+        self.report.clone()
+    }
+
+    pub(crate) fn record(&mut self, event: &CursorEvent) {
+        match event {
+            CursorEvent::RegionEnter { name, timestamp, .. } => {
+                self.report.regions.entry(name.clone()).or_default().hovers += 1;
+                self.regions.insert(name.clone(), *timestamp);
+            }
+            CursorEvent::RegionExit { name, .. } => {
+                self.regions.remove(name);
+            }
+            CursorEvent::Move { cursor_type, window, timestamp, .. } => {
+                let application = window.as_ref().map(|window| window.process_name.as_str());
+                self.cursor_shown(*cursor_type, application, *timestamp);
+            }
+            CursorEvent::TypeChange { new_type, timestamp, .. } => self.cursor_shown(*new_type, None, *timestamp),
+            CursorEvent::Click { timestamp, .. } => {
+                // Only the first click of a hover converts it
+                for (name, entered) in self.regions.drain() {
+                    let conversion = self.report.regions.entry(name).or_default();
+                    conversion.clicks += 1;
+                    conversion.hover_to_click_ms.push(timestamp.duration_since(entered).as_millis() as u64);
+                }
+                if let Some((application, since)) = self.hand_hover.take() {
+                    let conversion = self.end_hand_hover(application);
+                    conversion.clicks += 1;
+                    conversion.hover_to_click_ms.push(timestamp.duration_since(since).as_millis() as u64);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn cursor_shown(&mut self, cursor_type: CursorType, application: Option<&str>, timestamp: Timestamp) {
+        let hand = cursor_type == CursorType::Hand;
+        // TypeChange comes just before the Move of the same sample, which carries the window
+        let application = application.filter(|name| !name.is_empty());
+        match (self.hand, hand) {
+            (false, true) => self.hand_hover = Some((application.map(str::to_string), timestamp)),
+            (true, true) => {
+                if let (Some((named @ None, _)), Some(application)) = (&mut self.hand_hover, application) {
+                    *named = Some(application.to_string());
+                }
+            }
+            (true, false) => {
+                if let Some((application, _)) = self.hand_hover.take() {
+                    self.end_hand_hover(application);
+                }
+            }
+            (false, false) => {}
+        }
+        self.hand = hand;
+    }
+
+    /// Count a finished hand hover, returning its application's totals
+    fn end_hand_hover(&mut self, application: Option<String>) -> &mut HoverConversion {
+        let application = application.unwrap_or_else(|| UNKNOWN_APPLICATION.to_string());
+        let conversion = self.report.applications.entry(application).or_default();
+        conversion.hovers += 1;
+        conversion
+    }
+}
@@ -0,0 +1,91 @@
+//! Ctrl+C handling that lets monitoring stop cleanly instead of killing the process
+
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::Duration;
+use windows::core::BOOL;
+use windows::Win32::System::Console::{SetConsoleCtrlHandler, CTRL_BREAK_EVENT, CTRL_C_EVENT};
+
+/// The process-wide interrupt, created with the console handler
+static INTERRUPT: OnceLock<Result<Interrupt, String>> = OnceLock::new();
+
+/// Ctrl+C and Ctrl+Break delivered to waiting threads rather than ending the process
+///
+/// Without it, the console's default handler terminates the process at once, losing
+/// events still queued for the processing thread and anything sinks have batched.
+/// After [`install`](Self::install) the program decides when to stop: typically it
+/// stops the detector, which drains the queue and flushes sinks, then exits with
+/// its own status. A second Ctrl+C falls through to the default handler, so a
+/// shutdown that hangs can still be killed.
+#[derive(Debug, Default)]
+pub struct Interrupt {
+    state: Mutex<InterruptState>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct InterruptState {
+    interrupted: bool,
+    stop_requested: bool,
+}
+
+impl Interrupt {
+    /// Install the console handler on first use and return the process-wide interrupt
+    pub fn install() -> Result<&'static Interrupt, String> {
+        INTERRUPT
+            .get_or_init(|| {
+                unsafe { SetConsoleCtrlHandler(Some(on_console_control), true) }
+                    .map_err(|e| format!("Failed to install Ctrl+C handler: {}", e))?;
+                Ok(Interrupt::default())
+            })
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    /// Whether Ctrl+C or Ctrl+Break has been pressed
+    pub fn is_interrupted(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).interrupted
+    }
+
+    /// Wake waiting threads without an interrupt, e.g. when a time or event limit is reached
+    pub fn request_stop(&self) {
+        self.update(|state| state.stop_requested = true);
+    }
+
+    /// Block until Ctrl+C or [`request_stop`](Self::request_stop), returning whether it was Ctrl+C
+    pub fn wait(&self) -> bool {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let state = self
+            .changed
+            .wait_while(state, |state| !state.interrupted && !state.stop_requested)
+            .unwrap_or_else(|e| e.into_inner());
+        state.interrupted
+    }
+
+    /// Like [`wait`](Self::wait), giving up after `timeout`
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| !state.interrupted && !state.stop_requested)
+            .unwrap_or_else(|e| e.into_inner());
+        state.interrupted
+    }
+
+    fn update(&self, change: impl FnOnce(&mut InterruptState)) {
+        change(&mut self.state.lock().unwrap_or_else(|e| e.into_inner()));
+        self.changed.notify_all();
+    }
+}
+
+/// Runs on a thread the console creates for each control event
+unsafe extern "system" fn on_console_control(control_type: u32) -> BOOL {
+    let Some(Ok(interrupt)) = INTERRUPT.get() else {
+        return BOOL(0);
+    };
+    if (control_type != CTRL_C_EVENT && control_type != CTRL_BREAK_EVENT) || interrupt.is_interrupted() {
+        // Closing the console, logoff, shutdown and a repeated Ctrl+C keep their default handling
+        return BOOL(0);
+    }
+    interrupt.update(|state| state.interrupted = true);
+    BOOL(1)
+}
@@ -0,0 +1,323 @@
+//! Stable JSON encoding of events for web consumers and log archives
+//!
+//! # Schema
+//!
+//! Every event is an object with a single key, the event kind from [`EventKind`](crate::EventKind)
+//! (`"Move"`, `"Click"`, ...), whose value holds the event's fields:
+//!
+//! ```json
+//! {"Click": {"button": "Left", "position": [640.0, 360.0], "source": {"kind": "Mouse", "injected": false},
+//!            "timestamp": "2025-01-31T12:00:00.250Z", "schema_version": 2}}
+//! ```
+//!
+//! - `schema_version` is [`SCHEMA_VERSION`] at the time the event was written; it is
+//!   left out when [`JsonFormat::schema_version`] is off.
+//! - Field names are snake_case, or camelCase with [`FieldCase::Camel`]. Kinds and
+//!   enum values such as `"Left"` are PascalCase either way.
+//! - Timestamps are UTC in RFC 3339 with milliseconds, or as chosen with
+//!   [`JsonFormat::timestamps`]: local time with its offset, or epoch milliseconds
+//!   as a number. Readers accept all of them as well as ISO 8601 without an offset.
+//! - Positions are `[x, y]` arrays in physical virtual-desktop pixels.
+//! - Durations are whole milliseconds in fields that carry a unit in their
+//!   documentation, e.g. `gap` on `ListenerRestarted`.
+//! - Optional fields are omitted rather than `null` when absent.
+//! - `data` on `Marker` is passed through untouched, whatever its field names.
+//!
+//! The fields of each kind are those of the matching [`CursorEvent`] variant.
+//!
+//! # Versioning
+//!
+//! Adding an event kind or an optional field keeps the version, so readers must
+//! ignore kinds and fields they do not know. Renaming or removing a field, or
+//! changing its type or meaning, increases [`SCHEMA_VERSION`].
+//!
+//! [`JsonFormat::from_str`] and [`CursorEvent::from_json`] read every earlier version.
+//! Version 1 wrote timestamps as UTC in `YYYY-MM-DD HH:MM:SS.mmm` form. Events
+//! without `schema_version` come from releases before versioning: their fields
+//! are those of version 1 without the optional ones added since, with the same
+//! string timestamps and cursor type names, so they load unchanged. Events
+//! from a newer schema are rejected rather than misread. Session files and
+//! collector streams are read the same way.
+
+use crate::{CursorEvent, TimestampFormat};
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{Error, Map, Value};
+
+/// Version of the event JSON schema described in this module
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Naming of the fields inside an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FieldCase {
+    /// `cursor_type`, as the Rust field names
+    #[default]
+    Snake,
+    /// `cursorType`, as JavaScript consumers usually expect
+    Camel,
+}
+
+/// How events are written as JSON by [`CursorEvent::to_json`], sinks and the CLI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonFormat {
+    pub field_case: FieldCase,
+    /// Add `schema_version` to each event
+    pub schema_version: bool,
+    pub timestamps: TimestampFormat,
+}
+
+impl Default for JsonFormat {
+    fn default() -> Self {
+        Self { field_case: FieldCase::Snake, schema_version: true, timestamps: TimestampFormat::Utc }
+    }
+}
+
+impl JsonFormat {
+    /// snake_case fields with a schema version and UTC timestamps, the format of [`CursorEvent::to_json`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name fields in snake_case or camelCase
+    pub fn field_case(mut self, field_case: FieldCase) -> Self {
+        self.field_case = field_case;
+        self
+    }
+
+    /// Include or leave out `schema_version`
+    pub fn schema_version(mut self, include: bool) -> Self {
+        self.schema_version = include;
+        self
+    }
+
+    /// Write timestamps in UTC, local time or as epoch milliseconds
+    pub fn timestamps(mut self, timestamps: TimestampFormat) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Encode an event as a JSON value
+    pub fn to_value(&self, event: &CursorEvent) -> Value {
+        let mut value = self.timestamps.scope(|| serde_json::to_value(event)).unwrap_or_default();
+        if let Some(Value::Object(fields)) = value.as_object_mut().and_then(|kind| kind.values_mut().next()) {
+            if self.schema_version {
+                fields.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+            }
+            if self.field_case == FieldCase::Camel {
+                rename_fields(fields, snake_to_camel);
+            }
+        }
+        value
+    }
+
+    /// Encode an event as a JSON string
+    pub fn to_string(&self, event: &CursorEvent) -> String {
+        self.to_value(event).to_string()
+    }
+
+    /// Decode an event written in this format, or by an earlier version of it
+    pub fn from_str(&self, json: &str) -> Result<CursorEvent, serde_json::Error> {
+        self.from_value(serde_json::from_str(json)?)
+    }
+
+    /// Decode an event value written in this format, or by an earlier version of it
+    pub fn from_value(&self, mut value: Value) -> Result<CursorEvent, serde_json::Error> {
+        if let Some(Value::Object(fields)) = value.as_object_mut().and_then(|kind| kind.values_mut().next()) {
+            if self.field_case == FieldCase::Camel {
+                rename_fields(fields, camel_to_snake);
+            }
+            let version = match fields.remove("schema_version") {
+                Some(version) => version.as_u64().ok_or_else(|| Error::custom("schema_version must be a number"))?,
+                None => 0,
+            };
+            if version > u64::from(SCHEMA_VERSION) {
+                return Err(Error::custom(format!(
+                    "event schema version {} is newer than version {} read by this build",
+                    version, SCHEMA_VERSION
+                )));
+            }
+        }
+        serde_json::from_value(value)
+    }
+}
+
+/// Serde adapter writing events with their schema version and reading any earlier version
+///
+/// For events embedded in other records, e.g. `#[serde(with = "crate::json::versioned")]`.
+#[cfg(windows)]
+pub(crate) mod versioned {
+    use super::JsonFormat;
+    use crate::CursorEvent;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(event: &CursorEvent, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonFormat::default().to_value(event).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CursorEvent, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        JsonFormat::default().from_value(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Rename the field names of an object and the objects nested in it
+fn rename_fields(fields: &mut Map<String, Value>, rename: fn(&str) -> String) {
+    let renamed = std::mem::take(fields).into_iter().map(|(name, mut value)| {
+        // Marker data belongs to the application
+        if name != "data" {
+            rename_value(&mut value, rename);
+        }
+        (rename(&name), value)
+    });
+    *fields = renamed.collect();
+}
+
+fn rename_value(value: &mut Value, rename: fn(&str) -> String) {
+    match value {
+        Value::Object(fields) => rename_fields(fields, rename),
+        Value::Array(items) => items.iter_mut().for_each(|item| rename_value(item, rename)),
+        _ => {}
+    }
+}
+
+/// `cursor_type` to `cursorType`; PascalCase kinds and map keys have no underscores and stay as they are
+fn snake_to_camel(name: &str) -> String {
+    let mut words = name.split('_');
+    let first = words.next().unwrap_or_default().to_string();
+    words.fold(first, |mut camel, word| {
+        let mut chars = word.chars();
+        if let Some(initial) = chars.next() {
+            camel.extend(initial.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+        camel
+    })
+}
+
+/// `cursorType` to `cursor_type`, leaving names that start with a capital alone
+fn camel_to_snake(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return name.to_string();
+    }
+    let mut snake = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldCase, JsonFormat, SCHEMA_VERSION};
+    use crate::{CursorEvent, CursorType, InputSource, MouseButton, PointerKind, Timestamp};
+    use std::time::Duration;
+
+    /// `2025-01-31 12:00:00.250` UTC
+    const MILLIS: u64 = 1_738_324_800_250;
+
+    #[test]
+    fn reads_move_from_before_versioning() {
+        let json = r#"{"Move":{"position":[1.0,2.0],"cursor_type":"arrow","timestamp":"2025-01-31 12:00:00.250"}}"#;
+        match CursorEvent::from_json(json).unwrap() {
+            CursorEvent::Move { position, cursor_type, smoothed, monitor, window, contact, timestamp, .. } => {
+                assert_eq!(position, (1.0, 2.0));
+                assert_eq!(cursor_type, CursorType::Arrow);
+                assert!(smoothed.is_none() && monitor.is_none() && window.is_none() && contact.is_none());
+                assert_eq!(timestamp, Timestamp::from_millis(MILLIS));
+            }
+            other => panic!("expected Move, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_release_from_before_versioning() {
+        let json = r#"{"Release":{"button":"Left","timestamp":"2025-01-31 12:00:00.250"}}"#;
+        match CursorEvent::from_json(json).unwrap() {
+            CursorEvent::Release { button, press_position, held_for, timestamp, .. } => {
+                assert_eq!(button, MouseButton::Left);
+                assert_eq!(press_position, None);
+                assert_eq!(held_for, Duration::ZERO);
+                assert_eq!(timestamp, Timestamp::from_millis(MILLIS));
+            }
+            other => panic!("expected Release, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_schema_version_1() {
+        let json = r#"{"Click":{"button":"Right","position":[640.0,360.0],"source":{"kind":"Pen","injected":true},"timestamp":"2025-01-31 12:00:00.250","schema_version":1}}"#;
+        match CursorEvent::from_json(json).unwrap() {
+            CursorEvent::Click { button, position, source, timestamp, .. } => {
+                assert_eq!(button, MouseButton::Right);
+                assert_eq!(position, (640.0, 360.0));
+                assert_eq!(source, InputSource { kind: PointerKind::Pen, injected: true, lower_integrity: false });
+                assert_eq!(timestamp, Timestamp::from_millis(MILLIS));
+            }
+            other => panic!("expected Click, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_newer_schema_version() {
+        let json = format!(
+            r#"{{"Move":{{"position":[1.0,2.0],"cursor_type":"arrow","timestamp":"2025-01-31T12:00:00.250Z","schema_version":{}}}}}"#,
+            SCHEMA_VERSION + 1
+        );
+        let error = CursorEvent::from_json(&json).unwrap_err();
+        assert!(error.to_string().contains("newer"), "{}", error);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn versioned_adapter_reads_embedded_events() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Record {
+            #[serde(with = "super::versioned")]
+            event: CursorEvent,
+        }
+
+        let json = r#"{"event":{"Release":{"button":"Left","timestamp":"2025-01-31 12:00:00.250"}}}"#;
+        let record: Record = serde_json::from_str(json).unwrap();
+        assert_eq!(record.event.timestamp(), Timestamp::from_millis(MILLIS));
+
+        let written = serde_json::to_string(&record).unwrap();
+        assert!(written.contains(&format!(r#""schema_version":{}"#, SCHEMA_VERSION)), "{}", written);
+    }
+
+    #[test]
+    fn camel_case_round_trip() {
+        let format = JsonFormat::new().field_case(FieldCase::Camel);
+        let event = CursorEvent::Release {
+            button: MouseButton::Middle,
+            press_position: Some((10.0, 20.0)),
+            held_for: Duration::from_millis(300),
+            source: Default::default(),
+            device_id: Some(7),
+            precise_time: None,
+            timestamp: Timestamp::from_millis(MILLIS),
+        };
+        let json = format.to_string(&event);
+        assert!(json.contains(r#""pressPosition":[10.0,20.0]"#), "{}", json);
+        assert!(json.contains(r#""heldFor":300"#), "{}", json);
+        assert!(json.contains(r#""schemaVersion":"#), "{}", json);
+        assert!(!json.contains("press_position"), "{}", json);
+
+        match format.from_str(&json).unwrap() {
+            CursorEvent::Release { button, press_position, held_for, device_id, timestamp, .. } => {
+                assert_eq!(button, MouseButton::Middle);
+                assert_eq!(press_position, Some((10.0, 20.0)));
+                assert_eq!(held_for, Duration::from_millis(300));
+                assert_eq!(device_id, Some(7));
+                assert_eq!(timestamp, Timestamp::from_millis(MILLIS));
+            }
+            other => panic!("expected Release, got {:?}", other),
+        }
+    }
+}
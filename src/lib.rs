@@ -13,30 +13,334 @@
 //! 
 //! ## Example
 //! 
-//! ```rust
-//! use luuma_cursor_helper::{CursorDetector, CursorState};
-//! 
+//! ```no_run
+//! #[cfg(windows)]
 //! fn main() {
+//!     use luuma_cursor_helper::CursorDetector;
+//!
 //!     let mut detector = CursorDetector::new();
 //!     detector.start_monitoring();
 //! }
+//! # #[cfg(not(windows))]
+//! # fn main() {}
 //! ```
 
-use rdev::{listen, EventType, Button};
+#[cfg(windows)]
 use device_query::{DeviceQuery, DeviceState};
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
-use windows::Win32::UI::WindowsAndMessaging::{GetCursorInfo, CURSORINFO, CURSOR_SHOWING, HCURSOR, LoadCursorW, IDC_ARROW, IDC_IBEAM, IDC_WAIT, IDC_CROSS, IDC_UPARROW, IDC_SIZE, IDC_SIZENWSE, IDC_SIZENESW, IDC_SIZEWE, IDC_SIZENS, IDC_SIZEALL, IDC_NO, IDC_HAND, IDC_APPSTARTING, IDC_HELP, IDC_PIN, IDC_PERSON};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{GetCursorInfo, CURSORINFO, CURSORINFO_FLAGS, CURSOR_SHOWING, HCURSOR, LoadCursorW, IDC_ARROW, IDC_IBEAM, IDC_WAIT, IDC_CROSS, IDC_UPARROW, IDC_SIZE, IDC_SIZENWSE, IDC_SIZENESW, IDC_SIZEWE, IDC_SIZENS, IDC_SIZEALL, IDC_NO, IDC_HAND, IDC_APPSTARTING, IDC_HELP, IDC_PIN, IDC_PERSON};
+#[cfg(windows)]
 use windows::Win32::Foundation::POINT;
-use std::sync::{Arc, OnceLock};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::time::{Instant, Duration};
-use std::sync::mpsc::{self, Sender, Receiver};
-use std::thread;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+#[cfg(windows)]
+use std::{
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering}, mpsc, RwLock},
+    thread,
+};
+#[cfg(windows)]
+use crate::{
+    blocking::InputGuard,
+    clock::SharedClock,
+    confinement::ConfinementTracker,
+    filter::SharedKindMask,
+    focus::FocusWatcher,
+    gesture::GestureRecognizer,
+    hook::{run_mouse_hook, HookAction, HookRestart},
+    hotkey::HotkeyWatcher,
+    long_press::{LongPressTracker, LongPressWatcher},
+    middleware::run_chain,
+    monitor::{MonitorTracker, VirtualScreenCache},
+    overshoot::OvershootDetector,
+    prediction::SharedPrediction,
+    privacy::{PrivacyMasker, SharedPrivacy},
+    queue::EventQueue,
+    rage_click::RageClickDetector,
+    raw_input::{RawInputShared, RawInputThread},
+    region::{RegionTracker, SharedRegions},
+    runtime::{SharedTuning, Throttle, Tuning},
+    sampling::{HeldMove, HeldMoveWatcher},
+    scroll::{ScrollTracker, ScrollWatcher},
+    session::{CapturePause, SessionWatcher},
+    shape::ShapeWatcher,
+    sink::SharedSinks,
+    snapshot::SnapshotThread,
+    source::LastContact,
+    stall::{HookActivity, StallWatchdog},
+    stats::{SharedStats, StatsCollector},
+    type_change::TypeChangeFilter,
+    viewport::ViewportThread,
+    visibility::{cursor_visible, report_visibility, VisibilityWatcher},
+    wait::StateWatch,
+    window::{WindowResolver, WindowTracker},
+};
+#[cfg(all(windows, feature = "uia"))]
+use uia::UiaWorker;
+
+#[cfg(windows)]
+pub mod activity;
+pub mod animation;
+#[cfg(windows)]
+pub mod background;
+#[cfg(windows)]
+pub mod blocking;
+#[cfg(windows)]
+pub mod builder;
+pub mod clock;
+#[cfg(windows)]
+pub mod collector;
+#[cfg(all(windows, feature = "config"))]
+pub mod config;
+#[cfg(windows)]
+pub mod confinement;
+#[cfg(windows)]
+pub mod controller;
+#[cfg(windows)]
+pub mod devices;
+pub mod dpi;
+#[cfg(windows)]
+pub mod ergonomics;
+#[cfg(windows)]
+pub mod ffi;
+pub mod filter;
+#[cfg(windows)]
+pub mod fingerprint;
+#[cfg(windows)]
+mod focus;
+pub mod gesture;
+pub mod heatmap;
+#[cfg(windows)]
+mod hook;
+#[cfg(windows)]
+pub mod hotkey;
+pub mod hotspot;
+#[cfg(windows)]
+pub mod hover;
+#[cfg(windows)]
+pub mod interrupt;
+pub mod json;
+#[cfg(windows)]
+mod long_press;
+pub mod middleware;
+#[cfg(windows)]
+pub mod mirror;
+pub mod monitor;
+#[cfg(feature = "obs")]
+pub mod obs;
+#[cfg(windows)]
+pub mod osd;
+#[cfg(windows)]
+pub mod overlay;
+pub mod overshoot;
+#[cfg(feature = "pointer")]
+pub mod pointer;
+pub mod pointing;
+pub mod prediction;
+#[cfg(windows)]
+pub mod presentation;
+#[cfg(windows)]
+pub mod privacy;
+#[cfg(any(windows, test))]
+mod queue;
+pub mod rage_click;
+#[cfg(windows)]
+mod raw_input;
+#[cfg(windows)]
+pub mod recorder;
+#[cfg(windows)]
+pub mod region;
+#[cfg(windows)]
+pub mod responsiveness;
+#[cfg(windows)]
+pub mod ripple;
+#[cfg(windows)]
+pub mod runtime;
+pub mod sampling;
+pub mod scroll;
+#[cfg(feature = "service")]
+pub mod service;
+pub mod session;
+#[cfg(windows)]
+pub mod settings;
+#[cfg(windows)]
+mod shape;
+pub mod sink;
+pub mod smoothing;
+#[cfg(windows)]
+pub mod snapshot;
+pub mod source;
+#[cfg(windows)]
+mod stall;
+#[cfg(windows)]
+pub mod stats;
+#[cfg(windows)]
+pub mod threading;
+pub mod timing;
+pub mod trail;
+pub mod transform;
+#[cfg(feature = "tray")]
+pub mod tray;
+#[cfg(windows)]
+mod type_change;
+#[cfg(feature = "uia")]
+pub mod uia;
+pub mod viewport;
+#[cfg(windows)]
+mod visibility;
+#[cfg(windows)]
+pub mod wait;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub mod web;
+#[cfg(windows)]
+pub mod webhook;
+#[cfg(windows)]
+pub mod websocket;
+pub mod window;
+
+#[cfg(windows)]
+pub use activity::AppActivity;
+pub use animation::CursorAnimation;
+#[cfg(windows)]
+pub use animation::cursor_animation;
+#[cfg(windows)]
+pub use background::BackgroundDetector;
+#[cfg(windows)]
+pub use blocking::{InputAction, InputBlocker, PendingInput};
+#[cfg(windows)]
+pub use builder::CursorDetectorBuilder;
+pub use clock::{Clock, ManualClock, SystemClock};
+#[cfg(windows)]
+pub use collector::{Collector, CollectorSink, HostEvent, HostStats};
+#[cfg(all(windows, feature = "config"))]
+pub use config::{Config, ConfigReload, ConfigWatcher, SinkConfig, SinkKind};
+#[cfg(windows)]
+pub use confinement::current_clip_rect;
+#[cfg(windows)]
+pub use controller::CursorController;
+#[cfg(windows)]
+pub use devices::{list_pointer_devices, PointerDevice};
+#[cfg(windows)]
+pub use ergonomics::ErgonomicsReport;
+pub use filter::{EventFilter, EventKindMask};
+#[cfg(windows)]
+pub use fingerprint::{current_cursor_fingerprint, cursor_fingerprint};
+pub use gesture::{Direction, GestureConfig, GestureKind};
+pub use heatmap::{Heatmap, HeatmapWeight};
+#[cfg(windows)]
+pub use hotkey::{Hotkey, HotkeyAction};
+pub use hotspot::CursorGeometry;
+#[cfg(windows)]
+pub use hotspot::cursor_geometry;
+#[cfg(windows)]
+pub use hover::{HoverConversion, HoverReport};
+#[cfg(windows)]
+pub use interrupt::Interrupt;
+pub use json::{FieldCase, JsonFormat, SCHEMA_VERSION};
+pub use middleware::{EventMiddleware, RateLimit};
+#[cfg(windows)]
+pub use mirror::{MirrorPacket, MirrorReceiver, MirrorSender, MirrorTransport, RemoteCursor};
+pub use monitor::{Monitor, MonitorInfo, MonitorPosition};
+#[cfg(windows)]
+pub use monitor::{list_monitors, monitor_at};
+#[cfg(feature = "obs")]
+pub use obs::ObsSink;
+#[cfg(windows)]
+pub use osd::{InputDisplay, OsdConfig, OsdPosition, OsdTheme};
+#[cfg(windows)]
+pub use overlay::{Overlay, OverlayCanvas, OverlayConfig, Rgba, TextMask};
+pub use overshoot::OvershootConfig;
+#[cfg(feature = "pointer")]
+pub use pointer::PointerPhase;
+pub use pointing::{PointingMetrics, PointingReport};
+pub use prediction::{PositionPredictor, PredictionModel};
+#[cfg(windows)]
+pub use presentation::{PresentationConfig, PresentationMode, PresentationStyle};
+#[cfg(windows)]
+pub use privacy::{MaskAction, PrivacyMask};
+pub use rage_click::RageClickConfig;
+#[cfg(windows)]
+pub use recorder::{Record, RecordEntry, SessionPlayer, SessionRecorder};
+#[cfg(windows)]
+pub use region::{Region, RegionArea, RegionFilter};
+#[cfg(windows)]
+pub use responsiveness::{AppResponsiveness, ResponsivenessReport};
+#[cfg(windows)]
+pub use ripple::{ClickVisualizer, RippleConfig};
+#[cfg(windows)]
+pub use runtime::RuntimeConfig;
+pub use sampling::{AdaptiveSampling, SamplingMode};
+pub use scroll::ScrollAxis;
+#[cfg(feature = "service")]
+pub use service::{install_service, run_service, stop_with_service, uninstall_service, ServiceConfig};
+pub use session::DesktopState;
+#[cfg(windows)]
+pub use session::{is_remote_session, secure_desktop_active};
+#[cfg(windows)]
+pub use settings::SystemPointerSettings;
+pub use sink::EventSink;
+#[cfg(windows)]
+pub use sink::SinkFilter;
+pub use smoothing::{PositionSmoother, SmoothingFilter};
+#[cfg(windows)]
+pub use snapshot::SnapshotCallback;
+pub use source::{ContactData, InputSource, PointerKind};
+#[cfg(windows)]
+pub use stats::SessionStats;
+#[cfg(windows)]
+pub use threading::{ThreadConfig, ThreadPriority};
+pub use timing::{FrameClock, PreciseTime};
+pub use trail::{PathPoint, PathRecorder, SvgOptions};
+pub use transform::{CoordinateTransform, Homography, Normalization};
+#[cfg(feature = "tray")]
+pub use tray::{TrayConfig, TrayIcon};
+#[cfg(feature = "uia")]
+pub use uia::{ElementTrigger, UiElement};
+pub use viewport::{Viewport, ViewportConfig};
+#[cfg(windows)]
+pub use wait::WaitFor;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub use web::PointerListener;
+#[cfg(windows)]
+pub use webhook::WebhookSink;
+#[cfg(windows)]
+pub use websocket::WebSocketSink;
+pub use window::{ClientPosition, WindowContext};
+#[cfg(windows)]
+pub use window::{client_position, window_at};
+
+/// Text of a panic caught from consumer code
+#[cfg(windows)]
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+/// Serialize durations as whole milliseconds
+pub(crate) mod duration_ms {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
 
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
 
 /// Mouse button types for better performance
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum MouseButton {
     Left,
     Right,
@@ -53,87 +357,406 @@ impl std::fmt::Display for MouseButton {
     }
 }
 
-/// Simple cursor type cache with static strings
-fn get_cursor_type_static(name: &str) -> &'static str {
-    match name {
-        "arrow" => "arrow",
-        "ibeam" => "ibeam", 
-        "wait" => "wait",
-        "cross" => "cross",
-        "up_arrow" => "up_arrow",
-        "size" => "size",
-        "size_nw_se" => "size_nw_se",
-        "size_ne_sw" => "size_ne_sw",
-        "size_we" => "size_we",
-        "size_ns" => "size_ns",
-        "size_all" => "size_all",
-        "no" => "no",
-        "hand" => "hand",
-        "app_starting" => "app_starting",
-        "help" => "help",
-        "pin" => "pin",
-        "person" => "person",
-        _ => "custom",
+/// Screen rectangle in physical pixels (right and bottom are exclusive)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Rect {
+    /// Create a rectangle from its edges
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self { left, top, right, bottom }
+    }
+
+    /// Width of the rectangle in pixels
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    /// Height of the rectangle in pixels
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    /// Check whether a point lies inside the rectangle
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.left as f64 && x < self.right as f64 && y >= self.top as f64 && y < self.bottom as f64
+    }
+
+    /// Clamp a point to the nearest pixel inside the rectangle
+    pub fn clamp(&self, x: f64, y: f64) -> (f64, f64) {
+        let max_x = (self.right - 1).max(self.left) as f64;
+        let max_y = (self.bottom - 1).max(self.top) as f64;
+        (x.clamp(self.left as f64, max_x), y.clamp(self.top as f64, max_y))
     }
 }
 
-/// Cached cursor information for performance
-#[derive(Debug, Clone)]
-struct CachedCursor {
-    handle: usize, // Store as usize for thread safety
-    name: &'static str,
+/// How positions outside the virtual desktop are handled
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SanitizeMode {
+    /// Clamp the position to the nearest on-screen pixel
+    Clamp,
+    /// Drop the sample entirely
+    Discard,
+    /// Pass the sample through unchanged and only count it
+    Flag,
+}
+
+/// Cursor shape reported by the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorType {
+    Arrow,
+    IBeam,
+    Wait,
+    Cross,
+    UpArrow,
+    Size,
+    SizeNwSe,
+    SizeNeSw,
+    SizeWe,
+    SizeNs,
+    SizeAll,
+    No,
+    Hand,
+    AppStarting,
+    Help,
+    Pin,
+    Person,
+    /// A cursor handle not found in the system cursor cache
+    Custom,
+    /// The cursor could not be queried
+    Error,
+    /// No cursor information captured yet
+    #[default]
+    Default,
+    /// An application cursor registered at runtime under a stable name
+    Named(&'static str),
+}
+
+impl CursorType {
+    /// Get the stable name of this cursor type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CursorType::Arrow => "arrow",
+            CursorType::IBeam => "ibeam",
+            CursorType::Wait => "wait",
+            CursorType::Cross => "cross",
+            CursorType::UpArrow => "up_arrow",
+            CursorType::Size => "size",
+            CursorType::SizeNwSe => "size_nw_se",
+            CursorType::SizeNeSw => "size_ne_sw",
+            CursorType::SizeWe => "size_we",
+            CursorType::SizeNs => "size_ns",
+            CursorType::SizeAll => "size_all",
+            CursorType::No => "no",
+            CursorType::Hand => "hand",
+            CursorType::AppStarting => "app_starting",
+            CursorType::Help => "help",
+            CursorType::Pin => "pin",
+            CursorType::Person => "person",
+            CursorType::Custom => "custom",
+            CursorType::Error => "error",
+            CursorType::Default => "default",
+            CursorType::Named(name) => name,
+        }
+    }
+
+    /// Create a named cursor type, interning the name so it stays `Copy`
+    pub fn named(name: &str) -> Self {
+        CursorType::Named(intern_cursor_name(name))
+    }
+
+    /// Look up a cursor type by name, treating unknown names as custom
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "arrow" => CursorType::Arrow,
+            "ibeam" => CursorType::IBeam,
+            "wait" => CursorType::Wait,
+            "cross" => CursorType::Cross,
+            "up_arrow" => CursorType::UpArrow,
+            "size" => CursorType::Size,
+            "size_nw_se" => CursorType::SizeNwSe,
+            "size_ne_sw" => CursorType::SizeNeSw,
+            "size_we" => CursorType::SizeWe,
+            "size_ns" => CursorType::SizeNs,
+            "size_all" => CursorType::SizeAll,
+            "no" => CursorType::No,
+            "hand" => CursorType::Hand,
+            "app_starting" => CursorType::AppStarting,
+            "help" => CursorType::Help,
+            "pin" => CursorType::Pin,
+            "person" => CursorType::Person,
+            "error" => CursorType::Error,
+            "default" => CursorType::Default,
+            _ => lookup_cursor_name(name).map(CursorType::Named).unwrap_or(CursorType::Custom),
+        }
+    }
+}
+
+impl Serialize for CursorType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for CursorType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = std::borrow::Cow::<str>::deserialize(deserializer)?;
+        Ok(CursorType::from_name(&name))
+    }
+}
+
+impl std::fmt::Display for CursorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Interned names of cursor types registered at runtime
+static CURSOR_NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+/// Intern a cursor name, leaking each distinct name exactly once
+fn intern_cursor_name(name: &str) -> &'static str {
+    let mut names = CURSOR_NAMES.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    match names.get(name) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+            names.insert(interned);
+            interned
+        }
+    }
 }
 
-/// Global cursor cache for performance optimization
-static CURSOR_CACHE: OnceLock<Arc<Vec<CachedCursor>>> = OnceLock::new();
+/// Find a previously interned cursor name
+fn lookup_cursor_name(name: &str) -> Option<&'static str> {
+    let names = CURSOR_NAMES.get()?.lock().unwrap_or_else(|e| e.into_inner());
+    names.get(name).copied()
+}
+
+/// Global cursor cache keyed by handle value for constant-time lookups
+#[cfg(windows)]
+static CURSOR_CACHE: OnceLock<RwLock<HashMap<usize, CursorType>>> = OnceLock::new();
 
 /// Initialize cursor cache once at startup
-fn init_cursor_cache() -> Arc<Vec<CachedCursor>> {
-    let mut cursors = Vec::new();
+#[cfg(windows)]
+fn init_cursor_cache() -> RwLock<HashMap<usize, CursorType>> {
+    let mut cursors = HashMap::new();
     
     unsafe {
         let cursor_pairs = [
-            (IDC_ARROW, "arrow"),
-            (IDC_IBEAM, "ibeam"),
-            (IDC_WAIT, "wait"),
-            (IDC_CROSS, "cross"),
-            (IDC_UPARROW, "up_arrow"),
-            (IDC_SIZE, "size"),
-            (IDC_SIZENWSE, "size_nw_se"),
-            (IDC_SIZENESW, "size_ne_sw"),
-            (IDC_SIZEWE, "size_we"),
-            (IDC_SIZENS, "size_ns"),
-            (IDC_SIZEALL, "size_all"),
-            (IDC_NO, "no"),
-            (IDC_HAND, "hand"),
-            (IDC_APPSTARTING, "app_starting"),
-            (IDC_HELP, "help"),
-            (IDC_PIN, "pin"),
-            (IDC_PERSON, "person"),
+            (IDC_ARROW, CursorType::Arrow),
+            (IDC_IBEAM, CursorType::IBeam),
+            (IDC_WAIT, CursorType::Wait),
+            (IDC_CROSS, CursorType::Cross),
+            (IDC_UPARROW, CursorType::UpArrow),
+            (IDC_SIZE, CursorType::Size),
+            (IDC_SIZENWSE, CursorType::SizeNwSe),
+            (IDC_SIZENESW, CursorType::SizeNeSw),
+            (IDC_SIZEWE, CursorType::SizeWe),
+            (IDC_SIZENS, CursorType::SizeNs),
+            (IDC_SIZEALL, CursorType::SizeAll),
+            (IDC_NO, CursorType::No),
+            (IDC_HAND, CursorType::Hand),
+            (IDC_APPSTARTING, CursorType::AppStarting),
+            (IDC_HELP, CursorType::Help),
+            (IDC_PIN, CursorType::Pin),
+            (IDC_PERSON, CursorType::Person),
         ];
 
-        for (cursor_id, name) in cursor_pairs {
+        for (cursor_id, cursor_type) in cursor_pairs {
             if let Ok(handle) = LoadCursorW(None, cursor_id) {
-                let static_name = get_cursor_type_static(name);
-                cursors.push(CachedCursor { handle: handle.0 as usize, name: static_name });
+                let handle = handle.0 as usize;
+                cursors.insert(handle, cursor_type);
+                // Animated cursors such as wait and app-starting can report any frame's handle
+                for frame in animation::frame_handles(handle) {
+                    cursors.insert(frame, cursor_type);
+                }
             }
         }
     }
     
-    Arc::new(cursors)
+    RwLock::new(cursors)
 }
 
-/// Get cached cursor type name efficiently
-fn get_cached_cursor_type(cursor_handle: HCURSOR) -> &'static str {
+/// Get cached cursor type efficiently
+#[cfg(windows)]
+fn get_cached_cursor_type(cursor_handle: HCURSOR) -> CursorType {
     let cache = CURSOR_CACHE.get_or_init(init_cursor_cache);
-    
-    for cached_cursor in cache.iter() {
-        if cursor_handle.0 as usize == cached_cursor.handle {
-            return cached_cursor.name;
+    let handle = cursor_handle.0 as usize;
+    let cached = cache.read().unwrap_or_else(|e| e.into_inner()).get(&handle).copied();
+    match cached {
+        Some(cursor_type) => cursor_type,
+        None => match fingerprint::lookup(handle) {
+            Some(cursor_type) => {
+                // Later moves over the same handle skip hashing entirely
+                cache.write().unwrap_or_else(|e| e.into_inner()).insert(handle, cursor_type);
+                cursor_type
+            }
+            None => CursorType::Custom,
+        },
+    }
+}
+
+/// Query the current cursor information with a single Windows API call
+#[cfg(windows)]
+fn query_cursor_info() -> Option<CURSORINFO> {
+    let mut cursor_info = CURSORINFO {
+        cbSize: std::mem::size_of::<CURSORINFO>() as u32,
+        flags: CURSOR_SHOWING,
+        hCursor: HCURSOR::default(),
+        ptScreenPos: POINT { x: 0, y: 0 },
+    };
+
+    unsafe { GetCursorInfo(&mut cursor_info) }.ok().map(|_| cursor_info)
+}
+
+/// Wall-clock time as milliseconds since the Unix epoch
+///
+/// Cheap to capture and copy; formatting only happens on display or serialization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    /// Format used for display, and for serialization before schema version 2
+    pub const FORMAT: &'static str = "%Y-%m-%d %H:%M:%S%.3f";
+
+    /// Capture the current time
+    pub fn now() -> Self {
+        #[cfg(all(target_arch = "wasm32", feature = "web"))]
+        let millis = js_sys::Date::now() as u64;
+        #[cfg(not(all(target_arch = "wasm32", feature = "web")))]
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self(millis)
+    }
+
+    /// Create a timestamp from milliseconds since the Unix epoch
+    pub fn from_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    /// Milliseconds since the Unix epoch
+    pub fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    /// Time elapsed between an earlier timestamp and this one
+    pub fn duration_since(&self, earlier: Timestamp) -> Duration {
+        Duration::from_millis(self.0.saturating_sub(earlier.0))
+    }
+
+    /// Convert to a chrono UTC date time
+    pub fn to_datetime(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.0 as i64).unwrap_or_default()
+    }
+
+    /// UTC in RFC 3339 with milliseconds, e.g. `2025-01-31T12:00:00.250Z`
+    pub fn to_rfc3339(&self) -> String {
+        self.to_datetime().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+
+    /// Local time in RFC 3339 with its offset, e.g. `2025-01-31T13:00:00.250+01:00`
+    pub fn to_local_rfc3339(&self) -> String {
+        self.to_datetime().with_timezone(&chrono::Local).to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+    }
+
+    /// Parse a timestamp string in `FORMAT` (with or without milliseconds), RFC 3339,
+    /// or ISO 8601 without an offset, which is read as UTC like `FORMAT`
+    pub fn parse(value: &str) -> Option<Self> {
+        let datetime = chrono::NaiveDateTime::parse_from_str(value, Self::FORMAT)
+            .or_else(|_| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+            .map(|naive| naive.and_utc())
+            .or_else(|_| DateTime::parse_from_rfc3339(value).map(|datetime| datetime.with_timezone(&Utc)))
+            .ok()?;
+        u64::try_from(datetime.timestamp_millis()).ok().map(Self)
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_datetime().format(Self::FORMAT))
+    }
+}
+
+/// How timestamps are serialized, chosen through [`JsonFormat::timestamps`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimestampFormat {
+    /// UTC in RFC 3339, e.g. `2025-01-31T12:00:00.250Z`
+    #[default]
+    Utc,
+    /// Local time in RFC 3339 with its offset, e.g. `2025-01-31T13:00:00.250+01:00`
+    Local,
+    /// Milliseconds since the Unix epoch as a number
+    EpochMillis,
+}
+
+thread_local! {
+    /// Format `Timestamp` serializes with on this thread
+    static TIMESTAMP_FORMAT: std::cell::Cell<TimestampFormat> = const { std::cell::Cell::new(TimestampFormat::Utc) };
+}
+
+impl TimestampFormat {
+    /// Serialize timestamps on this thread in this format while `serialize` runs
+    pub(crate) fn scope<R>(self, serialize: impl FnOnce() -> R) -> R {
+        let previous = TIMESTAMP_FORMAT.with(|format| format.replace(self));
+        let result = serialize();
+        TIMESTAMP_FORMAT.with(|format| format.set(previous));
+        result
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match TIMESTAMP_FORMAT.with(|format| format.get()) {
+            TimestampFormat::Utc => serializer.serialize_str(&self.to_rfc3339()),
+            TimestampFormat::Local => serializer.serialize_str(&self.to_local_rfc3339()),
+            TimestampFormat::EpochMillis => serializer.serialize_u64(self.0),
         }
     }
-    
-    "custom"
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TimestampVisitor;
+
+        impl serde::de::Visitor<'_> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a timestamp string or epoch milliseconds")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Timestamp, E> {
+                Ok(Timestamp(value))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Timestamp, E> {
+                u64::try_from(value).map(Timestamp).map_err(E::custom)
+            }
+
+            /// Fractional milliseconds, as written by JavaScript's `performance.timeOrigin + performance.now()`
+            fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Timestamp, E> {
+                if value.is_finite() && value >= 0.0 {
+                    Ok(Timestamp(value as u64))
+                } else {
+                    Err(E::custom(format!("invalid timestamp: {}", value)))
+                }
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Timestamp, E> {
+                Timestamp::parse(value).ok_or_else(|| E::custom(format!("invalid timestamp: {}", value)))
+            }
+        }
+
+        deserializer.deserialize_any(TimestampVisitor)
+    }
 }
 
 /// Represents the current state of the cursor
@@ -142,13 +765,22 @@ pub struct CursorState {
     /// Current cursor position (x, y)
     pub position: (f64, f64),
     /// Current cursor type (arrow, hand, ibeam, etc.)
-    pub cursor_type: String,
+    pub cursor_type: CursorType,
     /// Whether left mouse button is pressed
     pub left_click: bool,
     /// Whether right mouse button is pressed
     pub right_click: bool,
+    /// Whether the cursor is drawn (false when an application hides it or touch input suppresses it)
+    #[serde(default = "visible_default")]
+    pub visible: bool,
+    /// Frame timing when the cursor is animated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation: Option<CursorAnimation>,
+    /// Hotspot offset and rendered size of the cursor image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<CursorGeometry>,
     /// Timestamp when this state was captured
-    pub timestamp: String,
+    pub timestamp: Timestamp,
 }
 
 impl CursorState {
@@ -156,13 +788,38 @@ impl CursorState {
     pub fn new() -> Self {
         Self {
             position: (0.0, 0.0),
-            cursor_type: "default".to_string(),
+            cursor_type: CursorType::Default,
             left_click: false,
             right_click: false,
-            timestamp: CursorDetector::get_timestamp(),
+            visible: true,
+            animation: None,
+            geometry: None,
+            timestamp: Timestamp::now(),
+        }
+    }
+
+    /// Position in DIPs using the scale factor of the monitor under the cursor
+    ///
+    /// Falls back to the physical position when the cursor is not on any monitor.
+    #[cfg(windows)]
+    pub fn position_dips(&self) -> (f64, f64) {
+        match monitor_at(self.position) {
+            Some(monitor) => monitor.to_dips(self.position),
+            None => self.position,
         }
     }
 
+    /// Screen rectangle covered by the cursor image, when the geometry was captured
+    pub fn image_rect(&self) -> Option<Rect> {
+        self.geometry.map(|geometry| geometry.image_rect(self.position))
+    }
+
+    /// Display scaling factor of the monitor under the cursor
+    #[cfg(windows)]
+    pub fn scale_factor(&self) -> f64 {
+        monitor_at(self.position).map(|monitor| monitor.scale_factor()).unwrap_or(1.0)
+    }
+
     /// Convert cursor state to JSON string
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
@@ -179,33 +836,523 @@ impl CursorState {
     }
 }
 
-/// Different types of cursor events with interned strings
+/// Build a full cursor state from the hook's atomics and the current cursor image
+#[cfg(windows)]
+fn read_state(atomic_state: &AtomicCursorState, timestamp: Timestamp) -> CursorState {
+    let cursor_info = atomic_state.cached_cursor().or_else(query_cursor_info);
+    CursorState {
+        position: atomic_state.get_position(),
+        cursor_type: match &cursor_info {
+            Some(cursor_info) => get_cached_cursor_type(cursor_info.hCursor),
+            None => CursorType::Error,
+        },
+        left_click: atomic_state.get_left_click(),
+        right_click: atomic_state.get_right_click(),
+        visible: cursor_info.as_ref().map(cursor_visible).unwrap_or(true),
+        animation: cursor_info.as_ref().and_then(|info| cursor_animation(info.hCursor.0 as usize)),
+        geometry: cursor_info.as_ref().and_then(|info| cursor_geometry(info.hCursor.0 as usize)),
+        timestamp,
+    }
+}
+
+/// States recorded before visibility was tracked had a visible cursor
+fn visible_default() -> bool {
+    true
+}
+
+impl Default for CursorState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Different types of cursor events, cheap to construct without heap allocation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CursorEvent {
     /// Cursor moved to a new position
-    Move { position: (f64, f64), cursor_type: String, timestamp: String },
+    Move {
+        position: (f64, f64),
+        cursor_type: CursorType,
+        /// Filtered position when smoothing is enabled, alongside the raw `position`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        smoothed: Option<(f64, f64)>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        monitor: Option<MonitorPosition>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        window: Option<Arc<WindowContext>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client: Option<ClientPosition>,
+        #[serde(default)]
+        source: InputSource,
+        /// Pen or touch pressure and tilt, when the input came from a digitizer that reports them
+        /// (boxed, as most events carry none)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        contact: Option<Box<ContactData>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<u64>,
+        /// Performance counter time of the hook callback, for frame-accurate alignment
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        precise_time: Option<PreciseTime>,
+        timestamp: Timestamp,
+    },
     /// Mouse button was clicked
-    Click { button: MouseButton, position: (f64, f64), timestamp: String },
+    Click {
+        button: MouseButton,
+        position: (f64, f64),
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        monitor: Option<MonitorPosition>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        window: Option<Arc<WindowContext>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client: Option<ClientPosition>,
+        #[serde(default)]
+        source: InputSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        contact: Option<Box<ContactData>>,
+        /// How far the cursor went past this point before coming back to click it, when
+        /// [`CursorDetector::set_overshoot_detection`] is set and it did
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        overshoot_px: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        precise_time: Option<PreciseTime>,
+        timestamp: Timestamp,
+    },
     /// Mouse button was released
-    Release { button: MouseButton, timestamp: String },
+    Release {
+        button: MouseButton,
+        /// Where the button went down, `None` when the press was not seen
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        press_position: Option<(f64, f64)>,
+        /// Time since the button went down, zero when the press was not seen
+        #[serde(default, with = "duration_ms")]
+        held_for: Duration,
+        #[serde(default)]
+        source: InputSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        precise_time: Option<PreciseTime>,
+        timestamp: Timestamp,
+    },
+    /// Button held for `duration` without the cursor leaving the drag rectangle around `position`
+    ///
+    /// Raised once per press when [`CursorDetector::set_long_press`] is set, while the
+    /// button is still down; the `Release` follows whenever it comes up.
+    LongPress {
+        button: MouseButton,
+        position: (f64, f64),
+        #[serde(with = "duration_ms")]
+        duration: Duration,
+        timestamp: Timestamp,
+    },
+    /// `count` clicks of `button` on one spot within `duration`, a common frustration signal
+    ///
+    /// Raised once per burst when [`CursorDetector::set_rage_clicks`] is set; see
+    /// [`RageClickConfig`] for what counts. `position` is the center of the clicks.
+    RageClick {
+        button: MouseButton,
+        position: (f64, f64),
+        count: u32,
+        #[serde(with = "duration_ms")]
+        duration: Duration,
+        timestamp: Timestamp,
+    },
+    /// Wheel turned by `delta`, in units of 120 per notch (touchpads and high-resolution wheels send less)
+    Scroll {
+        axis: ScrollAxis,
+        delta: i32,
+        position: (f64, f64),
+        #[serde(default)]
+        source: InputSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<u64>,
+        timestamp: Timestamp,
+    },
+    /// Wheel input along `axis` without a pause longer than the idle gap, reported once it pauses
+    ///
+    /// Raised when [`CursorDetector::set_scroll_sessions`] is set. `total_delta` is the
+    /// net distance in wheel units, so scrolling back and forth nets out; `lines` is
+    /// that distance in lines (characters horizontally) at the system setting, `None`
+    /// when the wheel scrolls a screen at a time. `reversals` counts changes of
+    /// direction and `position` is where the session began.
+    ScrollSession {
+        axis: ScrollAxis,
+        total_delta: i32,
+        #[serde(with = "duration_ms")]
+        duration: Duration,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lines: Option<f64>,
+        reversals: u32,
+        position: (f64, f64),
+        timestamp: Timestamp,
+    },
+    /// Cursor was hidden or shown again
+    VisibilityChanged { visible: bool, position: (f64, f64), timestamp: Timestamp },
+    /// Another application confined the cursor to a rectangle with `ClipCursor`, or released it (`None`)
+    ConfinementChanged { rect: Option<Rect>, position: (f64, f64), timestamp: Timestamp },
     /// Cursor type changed
-    TypeChange { new_type: String, position: (f64, f64), timestamp: String },
+    TypeChange { new_type: CursorType, position: (f64, f64), timestamp: Timestamp },
+    /// Cursor left `cursor_type` after showing it for `duration`, delivered just before the `TypeChange`
+    TypeDwell {
+        cursor_type: CursorType,
+        #[serde(with = "duration_ms")]
+        duration: Duration,
+        position: (f64, f64),
+        timestamp: Timestamp,
+    },
+    /// Cursor moved over a different top-level window
+    WindowChanged { window: Arc<WindowContext>, position: (f64, f64), timestamp: Timestamp },
+    /// The workstation was locked or unlocked, or the secure desktop came up or went away
+    DesktopChanged { state: DesktopState, timestamp: Timestamp },
+    /// The session was reconnected locally or over Remote Desktop (see [`is_remote_session`])
+    RemoteSessionChanged { remote: bool, timestamp: Timestamp },
+    /// A different window came to the foreground
+    FocusChanged { hwnd: usize, pid: u32, process_name: String, title: String, timestamp: Timestamp },
+    /// Cursor entered a registered region
+    RegionEnter { name: String, position: (f64, f64), timestamp: Timestamp },
+    /// Cursor left a registered region
+    RegionExit { name: String, position: (f64, f64), timestamp: Timestamp },
+    /// Movement traced a swipe, circle or shake (see [`GestureConfig`])
+    Gesture { kind: GestureKind, position: (f64, f64), timestamp: Timestamp },
+    /// Cursor crossed from one monitor to another (ids match [`MonitorInfo::id`])
+    MonitorChanged { from: u64, to: u64, position: (f64, f64), timestamp: Timestamp },
+    /// Unaccelerated device motion from the Raw Input backend, with the current screen position
+    RawMotion {
+        delta: (f64, f64),
+        position: (f64, f64),
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<u64>,
+        timestamp: Timestamp,
+    },
+    /// Touch contact on a touch screen, `pressure` is 0.0..=1.0 when the digitizer reports it
+    #[cfg(feature = "pointer")]
+    Touch {
+        id: u32,
+        phase: PointerPhase,
+        position: (f64, f64),
+        pressure: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<u64>,
+        timestamp: Timestamp,
+    },
+    /// Pen sample with pressure 0.0..=1.0 and X/Y tilt in degrees
+    #[cfg(feature = "pointer")]
+    Pen {
+        phase: PointerPhase,
+        position: (f64, f64),
+        pressure: f64,
+        tilt: (f64, f64),
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device_id: Option<u64>,
+        timestamp: Timestamp,
+    },
+    /// Accessibility element under the cursor when it was clicked or rested on
+    #[cfg(feature = "uia")]
+    Element {
+        trigger: ElementTrigger,
+        position: (f64, f64),
+        element: UiElement,
+        timestamp: Timestamp,
+    },
+    /// Camera rectangle following the cursor moved (see [`ViewportConfig`])
+    ViewportUpdate { viewport: Viewport, timestamp: Timestamp },
+    /// Annotation placed in the stream by a hotkey or the application
+    Marker {
+        label: String,
+        #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+        data: serde_json::Value,
+        timestamp: Timestamp,
+    },
+    /// An event masked by a [`PrivacyMask`], with its position and context withheld
+    Redacted { kind: EventKind, timestamp: Timestamp },
+    /// Windows removed the mouse hook and monitoring installed it again
+    ///
+    /// Input during `gap` was not seen; buttons held then are treated as released.
+    ListenerRestarted {
+        /// Installs it took, more than one when the first attempts failed
+        attempts: u32,
+        #[serde(with = "duration_ms")]
+        gap: Duration,
+        timestamp: Timestamp,
+    },
+    /// The hook delivered nothing for `silent` while the mouse was demonstrably moving
+    ///
+    /// Raised once per stall when [`CursorDetector::set_stall_timeout`] is set.
+    FlowStalled {
+        #[serde(with = "duration_ms")]
+        silent: Duration,
+        position: (f64, f64),
+        timestamp: Timestamp,
+    },
+    /// An event handler, sink, middleware stage or snapshot callback panicked
+    ///
+    /// The panic is caught and delivery carries on; `handler` names the culprit,
+    /// e.g. `sink 'webhook'`. A panic while handling this event is only logged.
+    HandlerPanicked { handler: String, message: String, timestamp: Timestamp },
+    /// Last event of a monitoring session, delivered when it stops
+    ///
+    /// Sinks see it before they are flushed, so every log ends with a terminator
+    /// carrying the session's length and final statistics.
+    #[cfg(windows)]
+    SessionEnd {
+        #[serde(with = "duration_ms")]
+        duration: Duration,
+        stats: Box<SessionStats>,
+        timestamp: Timestamp,
+    },
+}
+
+/// Kind of a cursor event without its payload, used for filtering
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    Move,
+    Click,
+    Release,
+    LongPress,
+    RageClick,
+    Scroll,
+    ScrollSession,
+    TypeChange,
+    TypeDwell,
+    VisibilityChanged,
+    ConfinementChanged,
+    RegionEnter,
+    RegionExit,
+    Gesture,
+    MonitorChanged,
+    WindowChanged,
+    FocusChanged,
+    DesktopChanged,
+    RemoteSessionChanged,
+    RawMotion,
+    #[cfg(feature = "pointer")]
+    Touch,
+    #[cfg(feature = "pointer")]
+    Pen,
+    #[cfg(feature = "uia")]
+    Element,
+    ViewportUpdate,
+    Marker,
+    Redacted,
+    ListenerRestarted,
+    FlowStalled,
+    HandlerPanicked,
+    #[cfg(windows)]
+    SessionEnd,
 }
 
 impl CursorEvent {
-    /// Convert cursor event to JSON string
+    /// Get the kind of this event
+    pub fn kind(&self) -> EventKind {
+        match self {
+            CursorEvent::Move { .. } => EventKind::Move,
+            CursorEvent::Click { .. } => EventKind::Click,
+            CursorEvent::Release { .. } => EventKind::Release,
+            CursorEvent::LongPress { .. } => EventKind::LongPress,
+            CursorEvent::RageClick { .. } => EventKind::RageClick,
+            CursorEvent::Scroll { .. } => EventKind::Scroll,
+            CursorEvent::ScrollSession { .. } => EventKind::ScrollSession,
+            CursorEvent::TypeChange { .. } => EventKind::TypeChange,
+            CursorEvent::TypeDwell { .. } => EventKind::TypeDwell,
+            CursorEvent::VisibilityChanged { .. } => EventKind::VisibilityChanged,
+            CursorEvent::ConfinementChanged { .. } => EventKind::ConfinementChanged,
+            CursorEvent::RegionEnter { .. } => EventKind::RegionEnter,
+            CursorEvent::RegionExit { .. } => EventKind::RegionExit,
+            CursorEvent::Gesture { .. } => EventKind::Gesture,
+            CursorEvent::MonitorChanged { .. } => EventKind::MonitorChanged,
+            CursorEvent::WindowChanged { .. } => EventKind::WindowChanged,
+            CursorEvent::FocusChanged { .. } => EventKind::FocusChanged,
+            CursorEvent::DesktopChanged { .. } => EventKind::DesktopChanged,
+            CursorEvent::RemoteSessionChanged { .. } => EventKind::RemoteSessionChanged,
+            CursorEvent::RawMotion { .. } => EventKind::RawMotion,
+            #[cfg(feature = "pointer")]
+            CursorEvent::Touch { .. } => EventKind::Touch,
+            #[cfg(feature = "pointer")]
+            CursorEvent::Pen { .. } => EventKind::Pen,
+            #[cfg(feature = "uia")]
+            CursorEvent::Element { .. } => EventKind::Element,
+            CursorEvent::ViewportUpdate { .. } => EventKind::ViewportUpdate,
+            CursorEvent::Marker { .. } => EventKind::Marker,
+            CursorEvent::Redacted { .. } => EventKind::Redacted,
+            CursorEvent::ListenerRestarted { .. } => EventKind::ListenerRestarted,
+            CursorEvent::FlowStalled { .. } => EventKind::FlowStalled,
+            CursorEvent::HandlerPanicked { .. } => EventKind::HandlerPanicked,
+            #[cfg(windows)]
+            CursorEvent::SessionEnd { .. } => EventKind::SessionEnd,
+        }
+    }
+
+    /// Screen position carried by this event, if any
+    pub fn position(&self) -> Option<(f64, f64)> {
+        match self {
+            CursorEvent::Move { position, .. }
+            | CursorEvent::Click { position, .. }
+            | CursorEvent::LongPress { position, .. }
+            | CursorEvent::RageClick { position, .. }
+            | CursorEvent::Scroll { position, .. }
+            | CursorEvent::ScrollSession { position, .. }
+            | CursorEvent::VisibilityChanged { position, .. }
+            | CursorEvent::ConfinementChanged { position, .. }
+            | CursorEvent::TypeChange { position, .. }
+            | CursorEvent::TypeDwell { position, .. }
+            | CursorEvent::WindowChanged { position, .. }
+            | CursorEvent::RegionEnter { position, .. }
+            | CursorEvent::RegionExit { position, .. }
+            | CursorEvent::Gesture { position, .. }
+            | CursorEvent::MonitorChanged { position, .. }
+            | CursorEvent::RawMotion { position, .. }
+            | CursorEvent::FlowStalled { position, .. } => Some(*position),
+            #[cfg(feature = "pointer")]
+            CursorEvent::Touch { position, .. } | CursorEvent::Pen { position, .. } => Some(*position),
+            #[cfg(feature = "uia")]
+            CursorEvent::Element { position, .. } => Some(*position),
+            CursorEvent::ViewportUpdate { viewport, .. } => Some(viewport.center()),
+            CursorEvent::Release { .. }
+            | CursorEvent::FocusChanged { .. }
+            | CursorEvent::DesktopChanged { .. }
+            | CursorEvent::RemoteSessionChanged { .. }
+            | CursorEvent::Marker { .. }
+            | CursorEvent::Redacted { .. }
+            | CursorEvent::ListenerRestarted { .. }
+            | CursorEvent::HandlerPanicked { .. } => None,
+            #[cfg(windows)]
+            CursorEvent::SessionEnd { .. } => None,
+        }
+    }
+
+    /// Apply `transform` to every screen point this event carries
+    ///
+    /// Covers `position`, the smoothed position of moves, the press position of
+    /// releases and the viewport of `ViewportUpdate`, which becomes the bounding box of
+    /// its mapped corners so `position()` keeps reporting its center. Left as they are:
+    ///
+    /// - `monitor.position` and `client.position`, which are offsets inside a monitor
+    ///   or window rather than screen points
+    /// - the `rect` of `ConfinementChanged`, whose whole pixels cannot hold mapped
+    ///   coordinates such as 0..1
+    /// - relative values such as raw motion deltas
+    pub fn map_positions<F: FnMut(f64, f64) -> (f64, f64)>(&mut self, mut transform: F) {
+        let mut map = |point: &mut (f64, f64)| *point = transform(point.0, point.1);
+        match self {
+            CursorEvent::Move { position, smoothed, .. } => {
+                map(position);
+                if let Some(smoothed) = smoothed {
+                    map(smoothed);
+                }
+            }
+            CursorEvent::Release { press_position, .. } => {
+                if let Some(press_position) = press_position {
+                    map(press_position);
+                }
+            }
+            CursorEvent::Click { position, .. }
+            | CursorEvent::LongPress { position, .. }
+            | CursorEvent::RageClick { position, .. }
+            | CursorEvent::Scroll { position, .. }
+            | CursorEvent::ScrollSession { position, .. }
+            | CursorEvent::VisibilityChanged { position, .. }
+            | CursorEvent::ConfinementChanged { position, .. }
+            | CursorEvent::TypeChange { position, .. }
+            | CursorEvent::TypeDwell { position, .. }
+            | CursorEvent::WindowChanged { position, .. }
+            | CursorEvent::RegionEnter { position, .. }
+            | CursorEvent::RegionExit { position, .. }
+            | CursorEvent::Gesture { position, .. }
+            | CursorEvent::MonitorChanged { position, .. }
+            | CursorEvent::RawMotion { position, .. }
+            | CursorEvent::FlowStalled { position, .. } => map(position),
+            #[cfg(feature = "pointer")]
+            CursorEvent::Touch { position, .. } | CursorEvent::Pen { position, .. } => map(position),
+            #[cfg(feature = "uia")]
+            CursorEvent::Element { position, .. } => map(position),
+            CursorEvent::ViewportUpdate { viewport, .. } => {
+                let (right, bottom) = (viewport.left + viewport.width, viewport.top + viewport.height);
+                let (left, top) = (viewport.left, viewport.top);
+                let corners = [(left, top), (right, top), (left, bottom), (right, bottom)];
+                let corners = corners.map(|(x, y)| transform(x, y));
+                let (xs, ys) = (corners.map(|(x, _)| x), corners.map(|(_, y)| y));
+                let left = xs.into_iter().fold(f64::INFINITY, f64::min);
+                let top = ys.into_iter().fold(f64::INFINITY, f64::min);
+                viewport.width = xs.into_iter().fold(f64::NEG_INFINITY, f64::max) - left;
+                viewport.height = ys.into_iter().fold(f64::NEG_INFINITY, f64::max) - top;
+                viewport.left = left;
+                viewport.top = top;
+            }
+            CursorEvent::FocusChanged { .. }
+            | CursorEvent::DesktopChanged { .. }
+            | CursorEvent::RemoteSessionChanged { .. }
+            | CursorEvent::Marker { .. }
+            | CursorEvent::Redacted { .. }
+            | CursorEvent::ListenerRestarted { .. }
+            | CursorEvent::HandlerPanicked { .. } => {}
+            #[cfg(windows)]
+            CursorEvent::SessionEnd { .. } => {}
+        }
+    }
+
+    /// Time at which this event was captured
+    pub fn timestamp(&self) -> Timestamp {
+        match self {
+            CursorEvent::Move { timestamp, .. }
+            | CursorEvent::Click { timestamp, .. }
+            | CursorEvent::Release { timestamp, .. }
+            | CursorEvent::LongPress { timestamp, .. }
+            | CursorEvent::RageClick { timestamp, .. }
+            | CursorEvent::Scroll { timestamp, .. }
+            | CursorEvent::ScrollSession { timestamp, .. }
+            | CursorEvent::VisibilityChanged { timestamp, .. }
+            | CursorEvent::ConfinementChanged { timestamp, .. }
+            | CursorEvent::TypeChange { timestamp, .. }
+            | CursorEvent::TypeDwell { timestamp, .. }
+            | CursorEvent::WindowChanged { timestamp, .. }
+            | CursorEvent::FocusChanged { timestamp, .. }
+            | CursorEvent::DesktopChanged { timestamp, .. }
+            | CursorEvent::RemoteSessionChanged { timestamp, .. }
+            | CursorEvent::RegionEnter { timestamp, .. }
+            | CursorEvent::RegionExit { timestamp, .. }
+            | CursorEvent::Gesture { timestamp, .. }
+            | CursorEvent::MonitorChanged { timestamp, .. }
+            | CursorEvent::RawMotion { timestamp, .. }
+            | CursorEvent::ViewportUpdate { timestamp, .. }
+            | CursorEvent::Marker { timestamp, .. }
+            | CursorEvent::Redacted { timestamp, .. }
+            | CursorEvent::ListenerRestarted { timestamp, .. }
+            | CursorEvent::FlowStalled { timestamp, .. }
+            | CursorEvent::HandlerPanicked { timestamp, .. } => *timestamp,
+            #[cfg(windows)]
+            CursorEvent::SessionEnd { timestamp, .. } => *timestamp,
+            #[cfg(feature = "pointer")]
+            CursorEvent::Touch { timestamp, .. } | CursorEvent::Pen { timestamp, .. } => *timestamp,
+            #[cfg(feature = "uia")]
+            CursorEvent::Element { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Performance counter capture time, recorded for hook moves, clicks and releases
+    pub fn precise_time(&self) -> Option<PreciseTime> {
+        match self {
+            CursorEvent::Move { precise_time, .. }
+            | CursorEvent::Click { precise_time, .. }
+            | CursorEvent::Release { precise_time, .. } => *precise_time,
+            _ => None,
+        }
+    }
+
+    /// Convert cursor event to JSON string in the stable [`json`] schema
     pub fn to_json(&self) -> String {
-        serde_json::to_string(self).unwrap_or_default()
+        JsonFormat::default().to_string(self)
     }
     
-    /// Create cursor event from JSON string
+    /// Create cursor event from JSON string, with or without a schema version
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(json)
+        JsonFormat::default().from_str(json)
     }
 
     /// Convert cursor event to pretty-formatted JSON string
     pub fn to_json_pretty(&self) -> String {
-        serde_json::to_string_pretty(self).unwrap_or_default()
+        serde_json::to_string_pretty(&JsonFormat::default().to_value(self)).unwrap_or_default()
     }
 }
 
@@ -215,75 +1362,47 @@ pub type CursorCallback = Box<dyn Fn(&CursorState, &str) + Send>;
 /// Event handler function type for cursor events
 pub type CursorEventHandler = Box<dyn Fn(CursorEvent) + Send>;
 
-/// Smart event batcher with single channel
-#[derive(Debug)]
-struct SmartEventBatcher {
-    events: Vec<CursorEvent>,
-    last_flush: Instant,
-    flush_interval: Duration,
-    max_buffer_size: usize,
-    sender: Sender<Vec<CursorEvent>>,
+/// Event handler and per-event work the processing thread takes from the detector,
+/// handed back when it exits so monitoring can start again
+#[cfg(windows)]
+struct Consumers {
+    event_handler: Option<CursorEventHandler>,
+    event_filter: Option<EventFilter>,
+    middleware: Vec<Box<dyn EventMiddleware>>,
+    /// Applied to events for the handler and for sinks that do not normalize positions
+    transform: Option<CoordinateTransform>,
 }
 
-impl SmartEventBatcher {
-    fn new(flush_interval_ms: u64, max_size: usize, sender: Sender<Vec<CursorEvent>>) -> Self {
-        Self {
-            events: Vec::with_capacity(max_size),
-            last_flush: Instant::now(),
-            flush_interval: Duration::from_millis(flush_interval_ms),
-            max_buffer_size: max_size,
-            sender,
-        }
-    }
-
-    fn add_event(&mut self, event: CursorEvent) -> bool {
-        self.events.push(event);
-        
-        // Smart batching: flush when buffer is full or interval has passed
-        if self.events.len() >= self.max_buffer_size || 
-           self.last_flush.elapsed() >= self.flush_interval {
-            self.flush();
-            true
-        } else {
-            false
-        }
-    }
-
-    fn flush(&mut self) {
-        if !self.events.is_empty() {
-            let events = std::mem::take(&mut self.events);
-            let _ = self.sender.send(events); // Non-blocking send
-            self.last_flush = Instant::now();
-        }
-    }
-
-    fn force_flush(&mut self) {
-        self.flush();
-    }
+/// Processing the crate applies itself, around the caller's filter and middleware
+#[cfg(windows)]
+struct Stages {
+    /// Runs before statistics are recorded, so masked activity is never counted
+    privacy: PrivacyMasker,
+    /// Runs on events the event mask lets through
+    throttle: Throttle,
 }
 
 /// Lock-free debouncer using atomics
+#[cfg(windows)]
 #[derive(Debug)]
 struct AtomicDebouncer {
     last_check_ms: AtomicU64,
     interval_ms: u64,
-    last_cursor_handle: AtomicU64,
+    clock: SharedClock,
 }
 
+#[cfg(windows)]
 impl AtomicDebouncer {
-    fn new(interval_ms: u64) -> Self {
+    fn new(interval_ms: u64, clock: SharedClock) -> Self {
         Self {
             last_check_ms: AtomicU64::new(0),
             interval_ms,
-            last_cursor_handle: AtomicU64::new(0),
+            clock,
         }
     }
 
     fn should_check(&self) -> bool {
-        let now_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+        let now_ms = self.clock.now().as_millis();
         
         let last_check = self.last_check_ms.load(Ordering::Relaxed);
         
@@ -294,30 +1413,45 @@ impl AtomicDebouncer {
             false
         }
     }
-
-    fn has_changed(&self, cursor_handle: HCURSOR) -> bool {
-        let handle_value = cursor_handle.0 as u64;
-        let last_handle = self.last_cursor_handle.swap(handle_value, Ordering::Relaxed);
-        handle_value != last_handle
-    }
 }
 
+/// How long a cursor queried by the monitoring loop stands in for a fresh `GetCursorInfo`
+#[cfg(windows)]
+const CURSOR_CACHE_TTL_MS: u64 = 50;
+
+/// Whether [`CursorDetector::log_message`] and friends print to the console
+#[cfg(windows)]
+static LOGGING: AtomicBool = AtomicBool::new(true);
+
 /// Lock-free cursor state using atomics for performance
+#[cfg(windows)]
 #[derive(Debug)]
 struct AtomicCursorState {
     position_x: AtomicU64, // Store as bits of f64
     position_y: AtomicU64,
     left_click: AtomicBool,
     right_click: AtomicBool,
+    visible: AtomicBool,
+    cursor_handle: AtomicU64,
+    cursor_flags: AtomicU32,
+    cursor_cached_ms: AtomicU64,
+    /// Times the cursor cache, so an injected clock controls its expiry
+    clock: SharedClock,
 }
 
+#[cfg(windows)]
 impl AtomicCursorState {
-    fn new() -> Self {
+    fn new(clock: SharedClock) -> Self {
         Self {
             position_x: AtomicU64::new(0),
             position_y: AtomicU64::new(0),
             left_click: AtomicBool::new(false),
             right_click: AtomicBool::new(false),
+            visible: AtomicBool::new(true),
+            cursor_handle: AtomicU64::new(0),
+            cursor_flags: AtomicU32::new(0),
+            cursor_cached_ms: AtomicU64::new(0),
+            clock,
         }
     }
 
@@ -347,125 +1481,986 @@ impl AtomicCursorState {
     fn get_right_click(&self) -> bool {
         self.right_click.load(Ordering::Relaxed)
     }
+
+    /// Store the visibility and return the previous value
+    fn swap_visible(&self, visible: bool) -> bool {
+        self.visible.swap(visible, Ordering::Relaxed)
+    }
+
+    /// Remember a cursor just queried by the monitoring loop
+    fn cache_cursor(&self, cursor_info: &CURSORINFO) {
+        self.cursor_handle.store(cursor_info.hCursor.0 as u64, Ordering::Relaxed);
+        self.cursor_flags.store(cursor_info.flags.0, Ordering::Relaxed);
+        self.cursor_cached_ms.store(self.clock.now().as_millis(), Ordering::Relaxed);
+    }
+
+    /// Forget the cached cursor so the next read queries Windows
+    fn invalidate_cursor(&self) {
+        self.cursor_cached_ms.store(0, Ordering::Relaxed);
+    }
+
+    /// The cached cursor, unless it is older than the cache lifetime
+    fn cached_cursor(&self) -> Option<CURSORINFO> {
+        let cached_ms = self.cursor_cached_ms.load(Ordering::Relaxed);
+        if cached_ms == 0 || self.clock.now().as_millis().saturating_sub(cached_ms) > CURSOR_CACHE_TTL_MS {
+            return None;
+        }
+
+        let position = self.get_position();
+        Some(CURSORINFO {
+            cbSize: std::mem::size_of::<CURSORINFO>() as u32,
+            flags: CURSORINFO_FLAGS(self.cursor_flags.load(Ordering::Relaxed)),
+            hCursor: HCURSOR(self.cursor_handle.load(Ordering::Relaxed) as *mut _),
+            ptScreenPos: POINT { x: position.0 as i32, y: position.1 as i32 },
+        })
+    }
 }
 
-/// Main cursor detector that monitors cursor activities
-pub struct CursorDetector {
+/// Cloneable handle for pushing events into a detector from other threads
+#[cfg(windows)]
+#[derive(Debug, Clone)]
+pub struct DetectorHandle {
     atomic_state: Arc<AtomicCursorState>,
-    callback: Option<CursorCallback>,
-    event_handler: Option<CursorEventHandler>,
-    event_batcher: Option<SmartEventBatcher>,
-    _cursor_debouncer: AtomicDebouncer,
-    event_sender: Option<Sender<Vec<CursorEvent>>>,
-    processing_thread: Option<thread::JoinHandle<()>>,
-    running: Arc<AtomicBool>,
+    state_watch: Arc<StateWatch>,
+    prediction: Arc<SharedPrediction>,
+    event_queue: Arc<EventQueue<CursorEvent>>,
+    out_of_bounds: Arc<AtomicU64>,
+    tracked_window: Arc<AtomicUsize>,
+    regions: SharedRegions,
+    privacy_mask: SharedPrivacy,
+    capture_paused: Arc<CapturePause>,
+    event_mask: Arc<SharedKindMask>,
+    tuning: Arc<SharedTuning>,
+    sinks: SharedSinks,
+    stats: SharedStats,
+    clock: SharedClock,
 }
 
-impl CursorDetector {
-    /// Create a new cursor detector
-    pub fn new() -> Self {
-        Self {
-            atomic_state: Arc::new(AtomicCursorState::new()),
-            callback: None,
-            event_handler: None,
-            event_batcher: None,
-            _cursor_debouncer: AtomicDebouncer::new(16), // 60fps debouncing
-            event_sender: None,
-            processing_thread: None,
-            running: Arc::new(AtomicBool::new(false)),
-        }
+#[cfg(windows)]
+impl DetectorHandle {
+    /// Push an externally constructed event through the detector's dispatch pipeline
+    pub fn emit(&self, event: CursorEvent) -> Result<(), String> {
+        self.event_queue
+            .push(event)
+            .map_err(|_| "Event queue is full".to_string())
     }
 
-    /// Set a callback function to be called when cursor events occur
-    pub fn set_callback<F>(&mut self, callback: F)
+    /// Inject a `Marker` event into the ordered stream, e.g. when a test reaches a milestone
+    pub fn emit_marker(&self, label: &str, data: serde_json::Value) -> Result<(), String> {
+        self.emit(CursorEvent::Marker { label: label.to_string(), data, timestamp: self.clock.now() })
+    }
+
+    /// Number of events dropped because the event queue was full
+    pub fn dropped_events(&self) -> u64 {
+        self.event_queue.dropped()
+    }
+
+    /// Number of positions seen outside the virtual desktop bounds
+    pub fn out_of_bounds_count(&self) -> u64 {
+        self.out_of_bounds.load(Ordering::Relaxed)
+    }
+
+    /// Get current cursor state
+    pub fn get_state(&self) -> CursorState {
+        read_state(&self.atomic_state, self.clock.now())
+    }
+
+    /// Where the cursor is expected to be `lead_ms` from now
+    ///
+    /// Needs prediction enabled on the detector; otherwise, and before the first
+    /// move, this is the last known position.
+    pub fn predict_position(&self, lead_ms: u64) -> (f64, f64) {
+        self.prediction.predict(Duration::from_millis(lead_ms)).unwrap_or_else(|| self.atomic_state.get_position())
+    }
+
+    /// Block until `predicate` accepts the cursor state, returning `None` after `timeout`
+    pub fn wait_for<F>(&self, predicate: F, timeout: Duration) -> Option<CursorState>
     where
-        F: Fn(&CursorState, &str) + Send + 'static,
+        F: Fn(&CursorState) -> bool,
     {
-        self.callback = Some(Box::new(callback));
+        self.state_watch.wait_for(&self.atomic_state, &*self.clock, predicate, timeout)
     }
 
-    /// Set an event handler function to be called when cursor events occur
-    pub fn set_event_handler<F>(&mut self, handler: F)
+    /// Future flavor of [`wait_for`](Self::wait_for), waiting on its own thread
+    pub fn wait_for_async<F>(&self, predicate: F, timeout: Duration) -> Result<WaitFor, String>
     where
-        F: Fn(CursorEvent) + Send + 'static,
+        F: Fn(&CursorState) -> bool + Send + 'static,
     {
-        self.event_handler = Some(Box::new(handler));
+        let (watch, atomic_state, clock) =
+            (Arc::clone(&self.state_watch), Arc::clone(&self.atomic_state), Arc::clone(&self.clock));
+        WaitFor::spawn(watch, atomic_state, clock, predicate, timeout)
     }
 
-    /// Get current timestamp in formatted string
-    pub fn get_timestamp() -> String {
-        let now: DateTime<Utc> = Utc::now();
-        now.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+    /// Report client-area coordinates relative to `hwnd`, or the hovered window when `None`
+    pub fn track_window(&self, hwnd: Option<usize>) {
+        self.tracked_window.store(hwnd.unwrap_or(0), Ordering::Relaxed);
     }
 
-    /// Log a message with timestamp
-    pub fn log_message(message: &str) {
-        let timestamp = Self::get_timestamp();
-        println!("[{}] {}", timestamp, message);
+    /// Whether input is being dropped while locked, on the secure desktop or paused by the user
+    pub fn capture_paused(&self) -> bool {
+        self.capture_paused.is_paused()
     }
 
-    /// Log cursor position and type
-    pub fn log_cursor_state(position: (f64, f64), cursor_type: &str) {
-        let timestamp = Self::get_timestamp();
-        println!("[{}] Cursor Pos: ({:.0}, {:.0}) | Type: {}", timestamp, position.0, position.1, cursor_type);
+    /// Pause or resume capture, like the `TogglePause` hotkey
+    pub fn set_capture_paused(&self, paused: bool) {
+        self.capture_paused.set_user(paused);
     }
 
-    /// Get actual cursor type using Windows API with caching
-    pub fn get_cursor_type() -> String {
-        unsafe {
-            let mut cursor_info = CURSORINFO {
-                cbSize: std::mem::size_of::<CURSORINFO>() as u32,
-                flags: CURSOR_SHOWING,
-                hCursor: HCURSOR::default(),
-                ptScreenPos: POINT { x: 0, y: 0 },
-            };
-            
-            if GetCursorInfo(&mut cursor_info).is_ok() {
-                let cursor_handle = cursor_info.hCursor;
-                get_cached_cursor_type(cursor_handle).to_string()
-            } else {
-                "error".to_string()
-            }
-        }
+    /// Change which event kinds are delivered while monitoring
+    pub fn set_event_mask(&self, mask: EventKindMask) {
+        self.event_mask.store(mask);
     }
 
-    /// Get current cursor state (lock-free)
-    pub fn get_state(&self) -> CursorState {
-        let position = self.atomic_state.get_position();
-        CursorState {
-            position,
-            cursor_type: Self::get_cursor_type(),
-            left_click: self.atomic_state.get_left_click(),
-            right_click: self.atomic_state.get_right_click(),
-            timestamp: Self::get_timestamp(),
-        }
+    /// Change sampling, rate limiting, filtering and event kinds while monitoring
+    ///
+    /// `change` receives the current settings; what it leaves behind applies from
+    /// the next event on, without stopping the hook.
+    ///
+    /// ```no_run
+    /// # use luuma_cursor_helper::{CursorDetector, EventKind, RateLimit};
+    /// # let detector = CursorDetector::new();
+    /// detector.handle().configure(|config| {
+    ///     config.event_mask = config.event_mask.without(EventKind::Move);
+    ///     config.rate_limit = Some(RateLimit::per_second(30));
+    /// });
+    /// ```
+    pub fn configure<F>(&self, change: F)
+    where
+        F: FnOnce(&mut RuntimeConfig),
+    {
+        runtime::configure(&self.event_mask, &self.regions, &self.tuning, change);
     }
 
-    /// Stop monitoring and clean up resources
-    pub fn stop(&mut self) -> Result<(), String> {
-        // Signal shutdown atomically
-        self.running.store(false, Ordering::Relaxed);
+    /// Attach a named sink while monitoring, replacing any sink with the same name
+    ///
+    /// Events are created whenever a handle exists, so the sink receives them even
+    /// when monitoring started without any handler or sink.
+    pub fn add_sink<S>(&self, name: &str, sink: S, filter: SinkFilter)
+    where
+        S: EventSink + 'static,
+    {
+        self.sinks.lock().unwrap_or_else(|e| e.into_inner()).add(name, Box::new(sink), filter);
+    }
 
-        // Force flush event batcher
-        if let Some(batcher) = &mut self.event_batcher {
-            batcher.force_flush();
-        }
+    /// Flush and detach a named sink, returning whether it was attached
+    pub fn remove_sink(&self, name: &str) -> bool {
+        self.sinks.lock().unwrap_or_else(|e| e.into_inner()).remove(name)
+    }
 
-        // Wait for processing thread to finish
-        if let Some(thread) = self.processing_thread.take() {
-            thread.join().map_err(|e| format!("Failed to join thread: {:?}", e))?;
-        }
+    /// Clicks, distance, active time, cursor type durations and per-application totals so far
+    pub fn stats(&self) -> SessionStats {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).snapshot()
+    }
 
-        Ok(())
+    /// Start accumulating statistics from zero
+    pub fn reset_stats(&self) {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).reset();
     }
 
-    /// Check if event handlers are present (conditional event creation)
+    /// Busy cursor durations after clicks, per foreground application
+    pub fn responsiveness(&self) -> ResponsivenessReport {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).responsiveness()
+    }
+
+    /// Movement time, path efficiency and target re-entries between consecutive clicks
+    pub fn pointing(&self) -> PointingReport {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).pointing()
+    }
+
+    /// Time from entering a region or seeing the hand cursor until the click, per region and application
+    pub fn hover_conversion(&self) -> HoverReport {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).hover()
+    }
+
+    /// Register a region, replacing any region with the same name
+    pub fn add_region(&self, name: &str, area: RegionArea) {
+        region::add_region(&self.regions, name, area);
+    }
+
+    /// Remove a region by name, returning whether it was registered
+    pub fn remove_region(&self, name: &str) -> bool {
+        region::remove_region(&self.regions, name)
+    }
+
+    /// Only emit Move and Click events inside or outside the registered regions
+    pub fn set_region_filter(&self, filter: Option<RegionFilter>) {
+        self.regions.write().unwrap_or_else(|e| e.into_inner()).filter = filter;
+    }
+
+    /// Replace the privacy mask while monitoring, `None` to disable it
+    pub fn set_privacy_mask(&self, mask: Option<PrivacyMask>) {
+        *self.privacy_mask.write().unwrap_or_else(|e| e.into_inner()) = mask.unwrap_or_default();
+    }
+}
+
+/// Main cursor detector that monitors cursor activities
+#[cfg(windows)]
+pub struct CursorDetector {
+    atomic_state: Arc<AtomicCursorState>,
+    state_watch: Arc<StateWatch>,
+    callback: Option<CursorCallback>,
+    event_handler: Option<CursorEventHandler>,
+    sinks: SharedSinks,
+    stats: SharedStats,
+    clock: SharedClock,
+    event_filter: Option<EventFilter>,
+    coordinate_transform: Option<CoordinateTransform>,
+    input_blocker: Option<InputBlocker>,
+    middleware: Vec<Box<dyn EventMiddleware>>,
+    event_mask: Arc<SharedKindMask>,
+    _cursor_debouncer: AtomicDebouncer,
+    event_queue: Arc<EventQueue<CursorEvent>>,
+    processing_thread: Option<thread::JoinHandle<Consumers>>,
+    running: Arc<AtomicBool>,
+    capture_paused: Arc<CapturePause>,
+    sanitize_mode: Option<SanitizeMode>,
+    out_of_bounds: Arc<AtomicU64>,
+    tuning: Arc<SharedTuning>,
+    smoothing: Option<SmoothingFilter>,
+    prediction_model: Option<PredictionModel>,
+    prediction: Arc<SharedPrediction>,
+    gestures: Option<GestureConfig>,
+    rage_clicks: Option<RageClickConfig>,
+    scroll_sessions: Option<Duration>,
+    overshoot: Option<OvershootConfig>,
+    type_change_hysteresis: Duration,
+    type_change_interval: Duration,
+    listener_thread: ThreadConfig,
+    processing_thread_config: ThreadConfig,
+    raw_input: bool,
+    track_devices: bool,
+    pointer_input: bool,
+    track_monitors: bool,
+    track_windows: bool,
+    tracked_window: Arc<AtomicUsize>,
+    regions: SharedRegions,
+    track_focus: bool,
+    normalize_buttons: bool,
+    privacy_mask: SharedPrivacy,
+    hotkeys: Vec<(Hotkey, HotkeyAction)>,
+    last_device: Arc<AtomicU64>,
+    last_contact: Arc<LastContact>,
+    raw_input_thread: Option<RawInputThread>,
+    snapshots: Option<(u32, SnapshotCallback)>,
+    snapshot_thread: Option<SnapshotThread>,
+    viewport: Option<ViewportConfig>,
+    viewport_thread: Option<ViewportThread>,
+    hook_activity: Arc<HookActivity>,
+    stall_timeout: Option<Duration>,
+    long_press: Option<Duration>,
+    stall_watchdog: Option<StallWatchdog>,
+    #[cfg(feature = "uia")]
+    element_dwell: Option<Duration>,
+    #[cfg(feature = "uia")]
+    uia_worker: Option<UiaWorker>,
+}
+
+#[cfg(windows)]
+impl CursorDetector {
+    /// Default number of events the hook can queue ahead of the processing thread
+    pub const DEFAULT_QUEUE_CAPACITY: usize = 4096;
+
+    /// Create a new cursor detector
+    pub fn new() -> Self {
+        Self::from_builder(CursorDetectorBuilder::new())
+    }
+
+    /// Configure thread scheduling and other start-up settings before creating a detector
+    pub fn builder() -> CursorDetectorBuilder {
+        CursorDetectorBuilder::new()
+    }
+
+    pub(crate) fn from_builder(builder: CursorDetectorBuilder) -> Self {
+        Self {
+            atomic_state: Arc::new(AtomicCursorState::new(Arc::clone(&builder.clock))),
+            state_watch: Arc::new(StateWatch::default()),
+            callback: None,
+            event_handler: None,
+            sinks: SharedSinks::default(),
+            stats: Arc::new(Mutex::new(StatsCollector::new(Arc::clone(&builder.clock)))),
+            _cursor_debouncer: AtomicDebouncer::new(16, Arc::clone(&builder.clock)), // 60fps debouncing
+            clock: builder.clock,
+            event_filter: None,
+            coordinate_transform: None,
+            input_blocker: None,
+            middleware: Vec::new(),
+            event_mask: Arc::new(SharedKindMask::new(EventKindMask::ALL)),
+            // Queue lives as long as the detector so events emitted before start are kept
+            event_queue: Arc::new(EventQueue::with_capacity(builder.queue_capacity)),
+            processing_thread: None,
+            running: Arc::new(AtomicBool::new(false)),
+            capture_paused: Arc::new(CapturePause::default()),
+            sanitize_mode: builder.sanitize_mode,
+            out_of_bounds: Arc::new(AtomicU64::new(0)),
+            tuning: Arc::new(SharedTuning::new(builder.sampling_mode)),
+            smoothing: builder.smoothing,
+            prediction_model: builder.prediction,
+            prediction: Arc::new(SharedPrediction::default()),
+            gestures: builder.gestures,
+            rage_clicks: builder.rage_clicks,
+            scroll_sessions: builder.scroll_sessions,
+            overshoot: builder.overshoot,
+            type_change_hysteresis: builder.type_change_hysteresis,
+            type_change_interval: builder.type_change_interval,
+            listener_thread: builder.listener_thread,
+            processing_thread_config: builder.processing_thread,
+            raw_input: builder.raw_input,
+            track_devices: builder.track_devices,
+            pointer_input: builder.pointer_input,
+            track_monitors: builder.track_monitors,
+            track_windows: builder.track_windows,
+            tracked_window: Arc::new(AtomicUsize::new(0)),
+            regions: SharedRegions::default(),
+            track_focus: builder.track_focus,
+            normalize_buttons: builder.normalize_buttons,
+            privacy_mask: Arc::new(RwLock::new(builder.privacy_mask.unwrap_or_default())),
+            hotkeys: builder.hotkeys,
+            last_device: Arc::new(AtomicU64::new(0)),
+            last_contact: Arc::default(),
+            raw_input_thread: None,
+            snapshots: None,
+            snapshot_thread: None,
+            viewport: builder.viewport,
+            viewport_thread: None,
+            hook_activity: Arc::new(HookActivity::new()),
+            stall_timeout: None,
+            long_press: builder.long_press,
+            stall_watchdog: None,
+            #[cfg(feature = "uia")]
+            element_dwell: builder.resolve_elements.then_some(builder.element_dwell),
+            #[cfg(feature = "uia")]
+            uia_worker: None,
+        }
+    }
+
+    /// Set a callback function to be called when cursor events occur
+    pub fn set_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&CursorState, &str) + Send + 'static,
+    {
+        self.callback = Some(Box::new(callback));
+    }
+
+    /// Set an event handler function to be called when cursor events occur
+    pub fn set_event_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(CursorEvent) + Send + 'static,
+    {
+        self.event_handler = Some(Box::new(handler));
+    }
+
+    /// Attach a named sink receiving the events its filter accepts
+    ///
+    /// Several sinks can run side by side at different fidelities, e.g. a live
+    /// stream limited to 30 moves per second next to a full archival log. A sink
+    /// with the same name is flushed and replaced.
+    pub fn add_sink<S>(&mut self, name: &str, sink: S, filter: SinkFilter)
+    where
+        S: EventSink + 'static,
+    {
+        self.sinks.lock().unwrap_or_else(|e| e.into_inner()).add(name, Box::new(sink), filter);
+    }
+
+    /// Flush and detach a named sink, returning whether it was attached
+    pub fn remove_sink(&mut self, name: &str) -> bool {
+        self.sinks.lock().unwrap_or_else(|e| e.into_inner()).remove(name)
+    }
+
+    /// Names of the attached sinks in dispatch order
+    pub fn sink_names(&self) -> Vec<String> {
+        self.sinks.lock().unwrap_or_else(|e| e.into_inner()).names()
+    }
+
+    /// Clicks, distance, active time and cursor type durations since monitoring started
+    ///
+    /// Totals are taken from events after privacy masking but before the event
+    /// filter, rate limit and middleware. Only events that are created count:
+    /// nothing is recorded unless a handler, sink or handle exists when monitoring
+    /// starts, and Move, Click and Release events masked out by the event mask are
+    /// never created, so e.g. `distance_px` stays zero without Move events.
+    /// `applications` splits them by foreground process when `track_focus` is on,
+    /// and otherwise by the hovered window's process when `track_windows` is.
+    pub fn stats(&self) -> SessionStats {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).snapshot()
+    }
+
+    /// Start accumulating statistics and responsiveness metrics from zero
+    pub fn reset_stats(&self) {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).reset();
+    }
+
+    /// How long wait and app-starting cursors were shown after clicks, per foreground application
+    ///
+    /// Clicks are attributed to the foreground process when `track_focus` is on,
+    /// otherwise to the process of the clicked window.
+    pub fn responsiveness(&self) -> ResponsivenessReport {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).responsiveness()
+    }
+
+    /// Movement time, path efficiency and target re-entries between consecutive clicks
+    ///
+    /// Each click ends a movement that started with the first move after the
+    /// previous click. Paths are taken from Move events, so adaptive sampling
+    /// shortens them slightly.
+    pub fn pointing(&self) -> PointingReport {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).pointing()
+    }
+
+    /// How often and how quickly hovers turn into clicks, per region and per application
+    ///
+    /// A region hover runs from `RegionEnter` to the first click inside it. An
+    /// application hover runs from the hand cursor appearing to the first click
+    /// while it shows, and is named after the hovered window when `track_windows`
+    /// is on. Reset along with the statistics.
+    pub fn hover_conversion(&self) -> HoverReport {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).hover()
+    }
+
+    /// Diameter in pixels of the area around a click treated as its target
+    ///
+    /// Used for target re-entries and the index of difficulty. Clears the pointing metrics collected so far.
+    pub fn set_target_width(&mut self, width: f64) {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).set_target_width(width);
+    }
+
+    /// Gaps between activity longer than this count as idle rather than active time
+    pub fn set_idle_threshold(&mut self, threshold: Duration) {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).set_idle_threshold(threshold);
+    }
+
+    /// Raise `FlowStalled` when the hook is silent this long while the mouse moves
+    ///
+    /// A separate thread polls the cursor, so a hook Windows removed or a hook
+    /// thread that stopped pumping messages is noticed either way. Off by default;
+    /// takes effect when monitoring starts.
+    pub fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.stall_timeout = timeout;
+    }
+
+    /// Raise `LongPress` when a button is held this long without the cursor moving
+    ///
+    /// Moving beyond the system drag threshold cancels the press, so drags are
+    /// never reported. Off by default; takes effect when monitoring starts.
+    pub fn set_long_press(&mut self, threshold: Option<Duration>) {
+        self.long_press = threshold;
+    }
+
+    /// Drop events the predicate rejects before they reach the event handler and sinks
+    ///
+    /// The predicate runs on the processing thread after privacy masking. Prefer
+    /// [`set_event_mask`](Self::set_event_mask) for dropping whole kinds, which
+    /// avoids creating those events at all.
+    pub fn set_filter<F>(&mut self, filter: F)
+    where
+        F: Fn(&CursorEvent) -> bool + Send + 'static,
+    {
+        self.event_filter = Some(Box::new(filter));
+    }
+
+    /// Map every emitted position through `transform`, e.g. onto a projector surface or 0..1
+    ///
+    /// Runs on the processing thread just before delivery, so privacy zones, regions,
+    /// the filter and middleware still work in screen pixels while the event handler
+    /// and sinks see transformed positions. Sinks with
+    /// [`SinkFilter::normalized`] positions are normalized from screen pixels instead.
+    /// Session statistics, [`get_state`](Self::get_state) and state snapshots keep
+    /// screen pixels.
+    /// [`CursorEvent::map_positions`] lists the fields that are mapped. See
+    /// [`transform`] for normalizing and homography helpers; takes effect when
+    /// monitoring starts.
+    pub fn set_coordinate_transform<F>(&mut self, transform: F)
+    where
+        F: Fn(f64, f64) -> (f64, f64) + Send + 'static,
+    {
+        self.coordinate_transform = Some(Box::new(transform));
+    }
+
+    /// Swallow mouse input the predicate rejects so it never reaches other applications
+    ///
+    /// The predicate runs on the hook thread for every move, press and release, and
+    /// swallowed input is still reported as events. Releases of swallowed presses are
+    /// swallowed too. Meant for click guards and parental controls; takes effect when
+    /// monitoring starts.
+    pub fn set_input_blocker<F>(&mut self, blocker: F)
+    where
+        F: FnMut(&PendingInput) -> bool + Send + 'static,
+    {
+        self.input_blocker = Some(Box::new(blocker));
+    }
+
+    /// Append a stage to the middleware chain run before the event handler and sinks
+    ///
+    /// Stages see events after privacy masking and the [`set_filter`](Self::set_filter)
+    /// predicate, in the order they were added, so e.g. coordinate normalization
+    /// can be followed by a [`RateLimit`].
+    pub fn add_middleware<M>(&mut self, stage: M)
+    where
+        M: EventMiddleware + 'static,
+    {
+        self.middleware.push(Box::new(stage));
+    }
+
+    /// Only deliver events whose kind is in the mask
+    ///
+    /// Masked-out Move, Click and Release events are never created on the hook
+    /// thread, so e.g. a click-only consumer does not pay for the Move stream.
+    pub fn set_event_mask(&mut self, mask: EventKindMask) {
+        self.event_mask.store(mask);
+    }
+
+    /// Event kinds currently delivered
+    pub fn event_mask(&self) -> EventKindMask {
+        self.event_mask.load()
+    }
+
+    /// Get a cloneable handle that can emit events while monitoring blocks this thread
+    pub fn handle(&self) -> DetectorHandle {
+        DetectorHandle {
+            atomic_state: Arc::clone(&self.atomic_state),
+            state_watch: Arc::clone(&self.state_watch),
+            prediction: Arc::clone(&self.prediction),
+            event_queue: Arc::clone(&self.event_queue),
+            out_of_bounds: Arc::clone(&self.out_of_bounds),
+            tracked_window: Arc::clone(&self.tracked_window),
+            regions: Arc::clone(&self.regions),
+            privacy_mask: Arc::clone(&self.privacy_mask),
+            capture_paused: Arc::clone(&self.capture_paused),
+            event_mask: Arc::clone(&self.event_mask),
+            tuning: Arc::clone(&self.tuning),
+            sinks: Arc::clone(&self.sinks),
+            stats: Arc::clone(&self.stats),
+            clock: Arc::clone(&self.clock),
+        }
+    }
+
+    /// Set how many events can be queued between the hook and the processing thread
+    ///
+    /// The buffer is allocated up front (rounded up to a power of two) so the hook
+    /// never allocates; events arriving while it is full are dropped and counted.
+    /// Must be called before any handles are created.
+    pub fn set_queue_capacity(&mut self, capacity: usize) -> Result<(), String> {
+        if Arc::strong_count(&self.event_queue) > 1 {
+            return Err("Queue capacity must be set before handles are created or monitoring starts".to_string());
+        }
+
+        let queue = EventQueue::with_capacity(capacity);
+        while let Some(event) = self.event_queue.pop() {
+            let _ = queue.push(event);
+        }
+        self.event_queue = Arc::new(queue);
+        Ok(())
+    }
+
+    /// Capacity of the event queue
+    pub fn queue_capacity(&self) -> usize {
+        self.event_queue.capacity()
+    }
+
+    /// Number of events dropped because the event queue was full
+    pub fn dropped_events(&self) -> u64 {
+        self.event_queue.dropped()
+    }
+
+    /// Validate move positions against the virtual desktop bounds
+    ///
+    /// Some drivers and RDP sessions report bogus samples such as (-32768, -32768);
+    /// pass `None` to disable the check. The bounds are cached on the hook thread
+    /// and re-read once a second, so display changes apply within that time.
+    pub fn set_position_sanitizer(&mut self, mode: Option<SanitizeMode>) {
+        self.sanitize_mode = mode;
+    }
+
+    /// Number of positions seen outside the virtual desktop bounds
+    pub fn out_of_bounds_count(&self) -> u64 {
+        self.out_of_bounds.load(Ordering::Relaxed)
+    }
+
+    /// Whether input is being dropped while locked, on the secure desktop or paused by the user
+    pub fn capture_paused(&self) -> bool {
+        self.capture_paused.is_paused()
+    }
+
+    /// Pause or resume capture, like the `TogglePause` hotkey
+    pub fn set_capture_paused(&self, paused: bool) {
+        self.capture_paused.set_user(paused);
+    }
+
+    /// Choose how Move events are sampled from the raw position stream
+    ///
+    /// Adaptive sampling keeps fast motion and turns intact while thinning slow
+    /// drift, which cuts event volume heavily for analytics consumers. The
+    /// position the cursor stops on is always delivered: a thinned-out sample
+    /// is emitted after the slow interval passes without motion, or just before
+    /// the next button or wheel event.
+    pub fn set_sampling_mode(&mut self, mode: SamplingMode) {
+        self.tuning.set_sampling(mode);
+    }
+
+    /// Change sampling, rate limiting, filtering and event kinds, see [`DetectorHandle::configure`]
+    pub fn configure<F>(&mut self, change: F)
+    where
+        F: FnOnce(&mut RuntimeConfig),
+    {
+        runtime::configure(&self.event_mask, &self.regions, &self.tuning, change);
+    }
+
+    /// Recognize swipes, circles and shakes in the movement and report them as `Gesture` events
+    ///
+    /// Recognition sees every raw position, before sampling and region filtering.
+    /// Takes effect when monitoring starts; pass `None` to disable.
+    pub fn set_gestures(&mut self, config: Option<GestureConfig>) {
+        self.gestures = config;
+    }
+
+    /// Report bursts of rapid clicks on one spot as `RageClick` events
+    ///
+    /// Bursts are timed on the hook thread as the presses arrive. Takes effect when
+    /// monitoring starts; pass `None` to disable.
+    pub fn set_rage_clicks(&mut self, config: Option<RageClickConfig>) {
+        self.rage_clicks = config;
+    }
+
+    /// Group wheel input into `ScrollSession` events, ending a session once the wheel rests for `idle_gap`
+    ///
+    /// Around 300ms keeps a touchpad flick and its inertia in one session while
+    /// separating deliberate scrolls. Off by default; takes effect when monitoring starts.
+    pub fn set_scroll_sessions(&mut self, idle_gap: Option<Duration>) {
+        self.scroll_sessions = idle_gap;
+    }
+
+    /// Annotate clicks that follow an overshoot with `overshoot_px`
+    ///
+    /// The approach to each click is taken from the hook's samples over the
+    /// lookback, so adaptive sampling does not affect it. Takes effect when
+    /// monitoring starts; pass `None` to disable.
+    pub fn set_overshoot_detection(&mut self, config: Option<OvershootConfig>) {
+        self.overshoot = config;
+    }
+
+    /// Hold back `TypeChange` events until the new type persisted for `hysteresis`,
+    /// and space them at least `min_interval` apart
+    ///
+    /// Flicker between two cursors then reports nothing, or only the type it settles
+    /// on, instead of a storm of changes. Both are zero by default, reporting every
+    /// change at once. Takes effect when monitoring starts.
+    pub fn set_type_change_delays(&mut self, hysteresis: Duration, min_interval: Duration) {
+        self.type_change_hysteresis = hysteresis;
+        self.type_change_interval = min_interval;
+    }
+
+    /// Filter jitter from the position stream, reported as `smoothed` on Move events
+    ///
+    /// The raw `position` is kept, so consumers can pick either. Takes effect when
+    /// monitoring starts; pass `None` to disable.
+    pub fn set_smoothing(&mut self, filter: Option<SmoothingFilter>) {
+        self.smoothing = filter;
+    }
+
+    /// Track velocity on the hook thread so [`predict_position`](Self::predict_position) can extrapolate
+    ///
+    /// Takes effect when monitoring starts; pass `None` to disable.
+    pub fn set_prediction(&mut self, model: Option<PredictionModel>) {
+        self.prediction_model = model;
+    }
+
+    /// Where the cursor is expected to be `lead_ms` from now, e.g. the render latency of an overlay
+    ///
+    /// Needs prediction enabled; otherwise, and before the first move, this is the
+    /// last known position. Once the cursor rests it stops extrapolating.
+    pub fn predict_position(&self, lead_ms: u64) -> (f64, f64) {
+        self.prediction.predict(Duration::from_millis(lead_ms)).unwrap_or_else(|| self.atomic_state.get_position())
+    }
+
+    /// Follow the cursor with a smoothed, zoomed camera reported as `ViewportUpdate` events
+    ///
+    /// Updates arrive at `rate_hz` while the camera moves and stop once it settles.
+    /// Takes effect when monitoring starts; pass `None` to disable.
+    pub fn set_viewport(&mut self, config: Option<ViewportConfig>) {
+        self.viewport = config;
+    }
+
+    /// Redact or suppress events inside exclusion zones or over excluded processes
+    ///
+    /// The mask is applied on the processing thread before the event handler and
+    /// sinks see anything. Pass `None` to disable. The mask can also be replaced
+    /// while monitoring through [`DetectorHandle`].
+    pub fn set_privacy_mask(&mut self, mask: Option<PrivacyMask>) {
+        *self.privacy_mask.write().unwrap_or_else(|e| e.into_inner()) = mask.unwrap_or_default();
+    }
+
+    /// Report client-area coordinates relative to a fixed window
+    ///
+    /// `hwnd` is the raw `HWND` value, for example of the application under test, so
+    /// recorded coordinates survive the window being moved. Pass `None` to use the
+    /// hovered window instead (requires `track_windows`).
+    pub fn track_window(&mut self, hwnd: Option<usize>) {
+        self.tracked_window.store(hwnd.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Register a named region of interest, replacing any region with the same name
+    ///
+    /// The cursor crossing a region's edge emits `RegionEnter` and `RegionExit`.
+    /// Regions can also be changed while monitoring through [`DetectorHandle`].
+    pub fn add_region(&mut self, name: &str, area: RegionArea) {
+        region::add_region(&self.regions, name, area);
+    }
+
+    /// Remove a region by name, returning whether it was registered
+    pub fn remove_region(&mut self, name: &str) -> bool {
+        region::remove_region(&self.regions, name)
+    }
+
+    /// Registered regions of interest
+    pub fn regions(&self) -> Vec<Region> {
+        self.regions.read().unwrap_or_else(|e| e.into_inner()).regions.clone()
+    }
+
+    /// Only emit Move and Click events while the cursor is inside or outside the registered regions
+    ///
+    /// Dropping activity away from an application's window happens on the hook
+    /// thread, so filtered events never reach the queue. Pass `None` to emit everything.
+    pub fn set_region_filter(&mut self, filter: Option<RegionFilter>) {
+        self.regions.write().unwrap_or_else(|e| e.into_inner()).filter = filter;
+    }
+
+    /// Get the bounding rectangle of all monitors (the virtual desktop)
+    pub fn get_virtual_screen_bounds() -> Rect {
+        unsafe {
+            let left = GetSystemMetrics(SM_XVIRTUALSCREEN);
+            let top = GetSystemMetrics(SM_YVIRTUALSCREEN);
+            let width = GetSystemMetrics(SM_CXVIRTUALSCREEN);
+            let height = GetSystemMetrics(SM_CYVIRTUALSCREEN);
+            Rect::new(left, top, left + width, top + height)
+        }
+    }
+
+    /// Push an externally constructed event through the normal dispatch pipeline
+    ///
+    /// Events emitted before monitoring starts are queued and delivered once it does.
+    pub fn emit(&self, event: CursorEvent) -> Result<(), String> {
+        self.event_queue
+            .push(event)
+            .map_err(|_| "Event queue is full".to_string())
+    }
+
+    /// Inject a `Marker` event into the same ordered stream as cursor events
+    ///
+    /// Test harnesses can label application milestones and correlate them with the
+    /// surrounding cursor activity; `data` is any JSON payload, or `Value::Null`.
+    pub fn emit_marker(&self, label: &str, data: serde_json::Value) -> Result<(), String> {
+        self.emit(CursorEvent::Marker { label: label.to_string(), data, timestamp: self.clock.now() })
+    }
+
+    /// Get current timestamp in formatted string
+    pub fn get_timestamp() -> String {
+        Timestamp::now().to_string()
+    }
+
+    /// Turn the console log of positions, clicks and errors on or off for every detector
+    ///
+    /// Tools that print events in their own format turn it off to keep stdout clean.
+    pub fn set_logging(enabled: bool) {
+        LOGGING.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Log a message with timestamp
+    pub fn log_message(message: &str) {
+        if LOGGING.load(Ordering::Relaxed) {
+            println!("[{}] {}", Timestamp::now(), message);
+        }
+    }
+
+    /// Log cursor position and type
+    pub fn log_cursor_state(position: (f64, f64), cursor_type: CursorType) {
+        if LOGGING.load(Ordering::Relaxed) {
+            println!("[{}] Cursor Pos: ({:.0}, {:.0}) | Type: {}", Timestamp::now(), position.0, position.1, cursor_type);
+        }
+    }
+
+    /// Get actual cursor type using Windows API with caching
+    pub fn get_cursor_type() -> CursorType {
+        match query_cursor_info() {
+            Some(cursor_info) => get_cached_cursor_type(cursor_info.hCursor),
+            None => CursorType::Error,
+        }
+    }
+
+    /// Map a cursor handle (e.g. one loaded by the host application) to a cursor type
+    ///
+    /// `handle` is the raw `HCURSOR` value. Use [`CursorType::named`] to give
+    /// application cursors stable names instead of reporting them as custom.
+    pub fn register_cursor_handle(handle: usize, cursor_type: CursorType) {
+        let cache = CURSOR_CACHE.get_or_init(init_cursor_cache);
+        let mut cache = cache.write().unwrap_or_else(|e| e.into_inner());
+        cache.insert(handle, cursor_type);
+        for frame in animation::frame_handles(handle) {
+            cache.insert(frame, cursor_type);
+        }
+    }
+
+    /// Remove a cursor handle mapping added at runtime
+    pub fn unregister_cursor_handle(handle: usize) {
+        let cache = CURSOR_CACHE.get_or_init(init_cursor_cache);
+        let mut cache = cache.write().unwrap_or_else(|e| e.into_inner());
+        cache.remove(&handle);
+        for frame in animation::frame_handles(handle) {
+            cache.remove(&frame);
+        }
+    }
+
+    /// Name cursors of other applications by the fingerprint of their image
+    ///
+    /// Handles differ per process and session, but [`cursor_fingerprint`] is stable,
+    /// so themed cursors of e.g. Photoshop or games keep the same name across runs.
+    pub fn register_custom_cursor(fingerprint: u64, name: &str) {
+        fingerprint::register(fingerprint, name);
+    }
+
+    /// Remove a fingerprint registration
+    ///
+    /// Handles already resolved through it keep their name until unregistered with
+    /// [`CursorDetector::unregister_cursor_handle`].
+    pub fn unregister_custom_cursor(fingerprint: u64) {
+        fingerprint::unregister(fingerprint);
+    }
+
+    /// Get current cursor state (lock-free)
+    ///
+    /// While monitoring, the cursor seen by the hook within the last 50 ms is reused
+    /// instead of calling `GetCursorInfo`, so high-rate polling stays cheap.
+    pub fn get_state(&self) -> CursorState {
+        read_state(&self.atomic_state, self.clock.now())
+    }
+
+    /// Block until `predicate` accepts the cursor state, returning `None` after `timeout`
+    ///
+    /// E.g. wait until the cursor becomes a hand, or for a click inside a rectangle.
+    /// Monitoring blocks its thread, so wait from another thread through
+    /// [`DetectorHandle::wait_for`]; waiters wake as soon as the hook sees input.
+    pub fn wait_for<F>(&self, predicate: F, timeout: Duration) -> Option<CursorState>
+    where
+        F: Fn(&CursorState) -> bool,
+    {
+        self.state_watch.wait_for(&self.atomic_state, &*self.clock, predicate, timeout)
+    }
+
+    /// Future resolving like [`wait_for`](Self::wait_for), usable from any async executor
+    pub fn wait_for_async<F>(&self, predicate: F, timeout: Duration) -> Result<WaitFor, String>
+    where
+        F: Fn(&CursorState) -> bool + Send + 'static,
+    {
+        let (watch, atomic_state, clock) =
+            (Arc::clone(&self.state_watch), Arc::clone(&self.atomic_state), Arc::clone(&self.clock));
+        WaitFor::spawn(watch, atomic_state, clock, predicate, timeout)
+    }
+
+    /// Call `callback` with the full cursor state `rate_hz` times per second while monitoring
+    ///
+    /// Snapshots are delivered on their own thread whether or not input occurred,
+    /// for game-style consumers that want a steady stream instead of discrete events.
+    /// Takes effect when monitoring starts.
+    pub fn set_snapshot_callback<F>(&mut self, rate_hz: u32, callback: F)
+    where
+        F: Fn(&CursorState) + Send + 'static,
+    {
+        self.snapshots = Some((rate_hz, Box::new(callback)));
+    }
+
+    /// Receive the full cursor state `rate_hz` times per second while monitoring
+    ///
+    /// Channel flavor of [`set_snapshot_callback`](Self::set_snapshot_callback),
+    /// replacing any snapshot callback set before.
+    pub fn snapshot_channel(&mut self, rate_hz: u32) -> mpsc::Receiver<CursorState> {
+        let (sender, receiver) = mpsc::channel();
+        self.set_snapshot_callback(rate_hz, move |state| {
+            let _ = sender.send(state.clone());
+        });
+        receiver
+    }
+
+    /// Stop monitoring and clean up resources
+    ///
+    /// The event handler, filter, middleware, coordinate transform, input blocker and
+    /// snapshot callback stay configured, so monitoring can be started again.
+    pub fn stop(&mut self) -> Result<(), String> {
+        // Signal shutdown atomically
+        self.running.store(false, Ordering::Relaxed);
+        self.atomic_state.invalidate_cursor();
+
+        // Every step runs even when an earlier one fails, so the consumers always come back
+        let mut stopped = Ok(());
+
+        if let Some(raw_input_thread) = self.raw_input_thread.take() {
+            stopped = stopped.and(raw_input_thread.stop());
+        }
+
+        if let Some(snapshot_thread) = self.snapshot_thread.take() {
+            match snapshot_thread.stop() {
+                Ok(snapshots) => self.snapshots = self.snapshots.take().or(Some(snapshots)),
+                Err(error) => stopped = stopped.and(Err(error)),
+            }
+        }
+
+        if let Some(viewport_thread) = self.viewport_thread.take() {
+            stopped = stopped.and(viewport_thread.stop());
+        }
+
+        if let Some(stall_watchdog) = self.stall_watchdog.take() {
+            stopped = stopped.and(stall_watchdog.stop());
+        }
+
+        #[cfg(feature = "uia")]
+        if let Some(uia_worker) = self.uia_worker.take() {
+            stopped = stopped.and(uia_worker.stop());
+        }
+
+        // Wait for processing thread to finish
+        if let Some(thread) = self.processing_thread.take() {
+            match thread.join() {
+                Ok(consumers) => {
+                    // Keep them for the next start, unless replaced since monitoring stopped
+                    self.event_handler = self.event_handler.take().or(consumers.event_handler);
+                    self.event_filter = self.event_filter.take().or(consumers.event_filter);
+                    self.coordinate_transform = self.coordinate_transform.take().or(consumers.transform);
+                    let added = std::mem::replace(&mut self.middleware, consumers.middleware);
+                    self.middleware.extend(added);
+                }
+                Err(e) => stopped = stopped.and(Err(format!("Failed to join thread: {:?}", e))),
+            }
+        }
+
+        // The first failure is reported once everything has stopped
+        stopped
+    }
+
+    /// Check if event handlers are present (conditional event creation)
+    ///
+    /// A handle can attach sinks while monitoring, so one existing counts as a consumer.
     fn has_handlers(&self) -> bool {
-        self.event_handler.is_some() || self.callback.is_some()
+        let has_sinks = !self.sinks.lock().unwrap_or_else(|e| e.into_inner()).is_empty();
+        let has_handles = Arc::strong_count(&self.sinks) > 1;
+        self.event_handler.is_some() || self.callback.is_some() || has_sinks || has_handles
     }
 
-    /// Start monitoring cursor activities  
+    /// Start monitoring cursor activities
+    ///
+    /// Blocks on the mouse hook until monitoring is stopped. When starting fails
+    /// part way, everything started so far is stopped again and the handler,
+    /// filter, middleware and transform stay configured for another attempt.
     pub fn start_monitoring(&mut self) -> Result<(), String> {
+        if self.processing_thread.is_some() {
+            return Err("Monitoring already started".to_string());
+        }
+        let monitored = self.monitor();
+        if monitored.is_err() {
+            // The processing thread and helper threads would otherwise hold on to the consumers
+            let _ = self.stop();
+        }
+        monitored
+    }
+
+    /// Spawn the processing and helper threads, then run the mouse hook
+    fn monitor(&mut self) -> Result<(), String> {
         let device_state = DeviceState::new();
         
         // Initialize atomic state with current mouse position
@@ -473,260 +2468,825 @@ impl CursorDetector {
         let initial_position = (initial_mouse.coords.0 as f64, initial_mouse.coords.1 as f64);
         self.atomic_state.update_position(initial_position.0, initial_position.1);
         
-        Self::log_cursor_state(initial_position, &Self::get_cursor_type());
-
-        // Single channel setup with smart batching
-        let (tx, rx) = mpsc::channel();
-        self.event_sender = Some(tx.clone());
-        
-        // Create smart event batcher
-        self.event_batcher = Some(SmartEventBatcher::new(50, 100, tx)); // 50ms flush, max 100 events
+        Self::log_cursor_state(initial_position, Self::get_cursor_type());
 
         // Set running flag atomically
         self.running.store(true, Ordering::Relaxed);
 
-        // Move event handler to processing thread
-        let event_handler = self.event_handler.take();
+        // Check handlers before they move to the processing thread
+        let has_handlers = self.has_handlers();
+
+        // Lend the handler, filter, middleware and transform to the processing thread, which
+        // hands them back when it exits, and share the sinks with it
+        let consumers = Consumers {
+            event_handler: self.event_handler.take(),
+            event_filter: self.event_filter.take(),
+            middleware: std::mem::take(&mut self.middleware),
+            transform: self.coordinate_transform.take(),
+        };
+        let sinks = Arc::clone(&self.sinks);
+        let stats = Arc::clone(&self.stats);
+        stats.lock().unwrap_or_else(|e| e.into_inner()).reset();
         let running = Arc::clone(&self.running);
-        let processing_thread = thread::spawn(move || {
-            Self::process_events_with_timeout(rx, event_handler, running);
-        });
+        let event_queue = Arc::clone(&self.event_queue);
+        let processing_config = self.processing_thread_config;
+        let event_mask = Arc::clone(&self.event_mask);
+        let stages = Stages {
+            privacy: PrivacyMasker::new(Arc::clone(&self.privacy_mask)),
+            throttle: Throttle::new(Arc::clone(&self.tuning)),
+        };
+        let processing_thread = thread::Builder::new()
+            .name("luuma-processing".to_string())
+            .spawn(move || {
+                if let Err(error) = processing_config.apply_current() {
+                    Self::log_message(&error);
+                }
+                Self::process_events_with_timeout(
+                    event_queue,
+                    consumers,
+                    sinks,
+                    stats,
+                    event_mask,
+                    stages,
+                    running,
+                )
+            })
+            .map_err(|e| format!("Failed to spawn processing thread: {}", e))?;
         self.processing_thread = Some(processing_thread);
 
-        // Listen for mouse and keyboard events
+        // Raw deltas and device ids are only useful to consumers, so skip the extra thread without handlers
+        if (self.raw_input || self.track_devices || self.pointer_input) && has_handlers {
+            let raw_input_thread = RawInputThread::spawn(RawInputShared {
+                atomic_state: Arc::clone(&self.atomic_state),
+                event_queue: Arc::clone(&self.event_queue),
+                running: Arc::clone(&self.running),
+                pause: Arc::clone(&self.capture_paused),
+                last_device: Arc::clone(&self.last_device),
+                #[cfg(feature = "pointer")]
+                last_contact: Arc::clone(&self.last_contact),
+                emit_motion: self.raw_input,
+                pointer_input: self.pointer_input,
+                clock: Arc::clone(&self.clock),
+            })?;
+            self.raw_input_thread = Some(raw_input_thread);
+        }
+        let track_devices = self.track_devices;
+
+        if let Some((rate_hz, callback)) = self.snapshots.take() {
+            self.snapshot_thread = Some(SnapshotThread::spawn(
+                rate_hz,
+                callback,
+                Arc::clone(&self.atomic_state),
+                Arc::clone(&self.event_queue),
+                Arc::clone(&self.running),
+                Arc::clone(&self.capture_paused),
+                Arc::clone(&self.clock),
+            )?);
+        }
+
+        if let (Some(config), true) = (self.viewport, has_handlers) {
+            self.viewport_thread = Some(ViewportThread::spawn(
+                config,
+                Arc::clone(&self.atomic_state),
+                Arc::clone(&self.event_queue),
+                Arc::clone(&self.running),
+                Arc::clone(&self.capture_paused),
+                Arc::clone(&self.clock),
+            )?);
+        }
+
+        if let (Some(timeout), true) = (self.stall_timeout, has_handlers) {
+            self.stall_watchdog = Some(StallWatchdog::spawn(
+                timeout,
+                Arc::clone(&self.hook_activity),
+                Arc::clone(&self.event_queue),
+                Arc::clone(&self.event_mask),
+                Arc::clone(&self.running),
+                Arc::clone(&self.capture_paused),
+                Arc::clone(&self.clock),
+            )?);
+        }
+
+        #[cfg(feature = "uia")]
+        if let (Some(dwell), true) = (self.element_dwell, has_handlers) {
+            self.uia_worker = Some(UiaWorker::spawn(
+                dwell,
+                Arc::clone(&self.atomic_state),
+                Arc::clone(&self.event_queue),
+                Arc::clone(&self.running),
+                Arc::clone(&self.clock),
+            )?);
+        }
+        #[cfg(feature = "uia")]
+        let element_requests = self.uia_worker.as_ref().map(UiaWorker::clicks);
+
+        // Foreground notifications are dispatched by the hook thread's message loop below
+        let _focus_watcher = if self.track_focus && has_handlers {
+            Some(FocusWatcher::install(Arc::clone(&self.event_queue), Arc::clone(&self.clock))?)
+        } else {
+            None
+        };
+
+        // Cursor show/hide notifications share the same message loop
+        let _visibility_watcher = if has_handlers {
+            Some(VisibilityWatcher::install(
+                Arc::clone(&self.atomic_state),
+                Arc::clone(&self.event_queue),
+                Arc::clone(&self.clock),
+            )?)
+        } else {
+            None
+        };
+
+        // Shape changes under a resting cursor share the hook's type change filter so each is reported once
+        let cursor_debouncer = AtomicDebouncer::new(16, Arc::clone(&self.clock));
+        let type_changes = Arc::new(TypeChangeFilter::new(
+            self.type_change_hysteresis,
+            self.type_change_interval,
+            Arc::clone(&self.clock),
+        ));
+        let _shape_watcher = if has_handlers {
+            Some(ShapeWatcher::install(
+                Arc::clone(&self.atomic_state),
+                Arc::clone(&self.event_queue),
+                Arc::clone(&type_changes),
+                Arc::clone(&self.clock),
+            )?)
+        } else {
+            None
+        };
+
+        // Held buttons are checked by a timer on this thread, between hook callbacks
+        let long_press = self
+            .long_press
+            .filter(|_| has_handlers)
+            .map(|threshold| Arc::new(LongPressTracker::new(threshold, Arc::clone(&self.clock))));
+        let _long_press_watcher = match &long_press {
+            Some(tracker) => Some(LongPressWatcher::install(Arc::clone(tracker), Arc::clone(&self.event_queue))?),
+            None => None,
+        };
+        // Moves thinned out by sampling are held back, so the point the cursor stops on is still delivered
+        let held_move = has_handlers.then(|| Arc::new(HeldMove::new(Arc::clone(&self.clock))));
+        let _held_move_watcher = match &held_move {
+            Some(held) => Some(HeldMoveWatcher::install(Arc::clone(held), Arc::clone(&self.event_queue))?),
+            None => None,
+        };
+        let scroll_sessions = self
+            .scroll_sessions
+            .filter(|_| has_handlers)
+            .map(|idle_gap| Arc::new(ScrollTracker::new(idle_gap, Arc::clone(&self.clock))));
+        let _scroll_watcher = match &scroll_sessions {
+            Some(tracker) => Some(ScrollWatcher::install(Arc::clone(tracker), Arc::clone(&self.event_queue))?),
+            None => None,
+        };
+
+        // Input on the lock screen and secure desktop is never captured, even without handlers
+        let _session_watcher = SessionWatcher::install(
+            Arc::clone(&self.event_queue),
+            Arc::clone(&self.capture_paused),
+            Arc::clone(&self.clock),
+        )?;
+
+        // Hotkeys are delivered to a window on this thread, so they work without handlers too
+        let _hotkey_watcher = if self.hotkeys.is_empty() {
+            None
+        } else {
+            Some(HotkeyWatcher::install(
+                &self.hotkeys,
+                Arc::clone(&self.event_queue),
+                Arc::clone(&self.capture_paused),
+                Arc::clone(&self.clock),
+            )?)
+        };
+
+        // The hook callback runs on this thread, so schedule it before installing the hook
+        if let Err(error) = self.listener_thread.apply_current() {
+            Self::log_message(&error);
+        }
+
+        // Listen for mouse events through the low-level hook
         let atomic_state = Arc::clone(&self.atomic_state);
-        let event_sender = self.event_sender.clone();
-        let cursor_debouncer = Arc::new(AtomicDebouncer::new(16));
+        let state_watch = Arc::clone(&self.state_watch);
+        let event_queue = Arc::clone(&self.event_queue);
         let running = Arc::clone(&self.running);
-        let has_handlers = self.has_handlers();
+        let capture_paused = Arc::clone(&self.capture_paused);
+        let event_mask = Arc::clone(&self.event_mask);
+        let sanitize_mode = self.sanitize_mode;
+        let mut virtual_screen = VirtualScreenCache::new();
+        let out_of_bounds = Arc::clone(&self.out_of_bounds);
+        let last_device = Arc::clone(&self.last_device);
+        // Pen and touch promoted to mouse messages carry the pressure of the digitizer report behind them
+        let last_contact = Arc::clone(&self.last_contact);
+        // Hook events carry no device, so attribute them to the latest raw input packet
+        let current_device = move || match last_device.load(Ordering::Relaxed) {
+            0 => None,
+            device => track_devices.then_some(device),
+        };
+        let mut monitor_tracker = self.track_monitors.then(MonitorTracker::new);
+        let mut window_tracker = self.track_windows.then(WindowTracker::new);
+        let tracked_window = Arc::clone(&self.tracked_window);
+        // A caller-supplied window wins over the hovered one
+        let client_for = move |position: (f64, f64), hovered: Option<&Arc<WindowContext>>| {
+            let hwnd = match tracked_window.load(Ordering::Relaxed) {
+                0 => hovered.map(|window| window.hwnd)?,
+                hwnd => hwnd,
+            };
+            client_position(hwnd, position)
+        };
+        let normalize_buttons = self.normalize_buttons;
+        let mut confinement_tracker = ConfinementTracker::new();
+        let mut region_tracker = RegionTracker::new(Arc::clone(&self.regions));
+        let region_queue = Arc::clone(&self.event_queue);
+        let region_clock = Arc::clone(&self.clock);
+        // Report region crossings and tell whether positional events pass the region filter
+        let mut track_regions = move |position: (f64, f64)| {
+            let (crossings, passes) = region_tracker.update(position);
+            for crossing in crossings {
+                let name = crossing.name;
+                let timestamp = region_clock.now();
+                let region_event = if crossing.entered {
+                    CursorEvent::RegionEnter { name, position, timestamp }
+                } else {
+                    CursorEvent::RegionExit { name, position, timestamp }
+                };
+                let _ = region_queue.push(region_event);
+            }
+            passes
+        };
+        let mut gesture_recognizer = self.gestures.map(GestureRecognizer::new);
+        let mut overshoot = self.overshoot.filter(|_| has_handlers).map(OvershootDetector::new);
+        // Bursts of clicks on one spot are reported as a frustration signal
+        let mut rage_clicks = self.rage_clicks.filter(|_| has_handlers).map(RageClickDetector::new);
+        let rage_queue = Arc::clone(&self.event_queue);
+        let rage_clock = Arc::clone(&self.clock);
+        let mut track_rage_clicks = move |button: MouseButton, position: (f64, f64)| {
+            let Some(burst) = rage_clicks.as_mut().and_then(|rage| rage.click(button, position, rage_clock.instant()))
+            else {
+                return;
+            };
+            let _ = rage_queue.push(CursorEvent::RageClick {
+                button,
+                position: burst.position,
+                count: burst.count,
+                duration: burst.duration,
+                timestamp: rage_clock.now(),
+            });
+        };
+        let tuning = Arc::clone(&self.tuning);
+        let (mut tuning_generation, Tuning { sampling: mut sampling_mode, .. }) = tuning.snapshot();
+        let mut sampler = runtime::sampler(sampling_mode);
+        let mut smoother = self.smoothing.map(PositionSmoother::new);
+        let mut predictor = self.prediction_model.map(PositionPredictor::new);
+        let prediction = Arc::clone(&self.prediction);
         
-        if let Err(error) = listen(move |event| {
+        // Input was missed while the hook was gone, so held buttons count as released
+        let restart_state = Arc::clone(&self.atomic_state);
+        let restart_queue = Arc::clone(&self.event_queue);
+        let restart_mask = Arc::clone(&self.event_mask);
+        let restart_clock = Arc::clone(&self.clock);
+        let on_restart = move |restart: HookRestart| {
+            restart_state.set_left_click(false);
+            restart_state.set_right_click(false);
+            if has_handlers && restart_mask.allows(EventKind::ListenerRestarted) {
+                let _ = restart_queue.push(CursorEvent::ListenerRestarted {
+                    attempts: restart.attempts,
+                    gap: restart.gap,
+                    timestamp: restart_clock.now(),
+                });
+            }
+            Self::log_message(&format!("Mouse hook was removed, reinstalled after {} attempt(s)", restart.attempts));
+        };
+
+        let hook_activity = Arc::clone(&self.hook_activity);
+        let clock = Arc::clone(&self.clock);
+        // When and where each button went down, for the hold time reported on release
+        let mut left_press: Option<(std::time::Instant, (f64, f64))> = None;
+        let mut right_press: Option<(std::time::Instant, (f64, f64))> = None;
+        let mut input_guard = self.input_blocker.take().map(InputGuard::new);
+        let listened = run_mouse_hook(&mut input_guard, move |event| {
+            hook_activity.mark();
+            let source = event.source;
+            let precise_time = Some(PreciseTime::now());
+
             // Check if we should stop atomically
             if !running.load(Ordering::Relaxed) {
                 return;
             }
 
-            match event.event_type {
-                EventType::MouseMove { x, y } => {
-                    let new_position = (x, y);
+            // Compliance: nothing typed or clicked while locked, on the secure desktop or paused is recorded
+            if capture_paused.is_paused() {
+                return;
+            }
+
+            // The swap setting can change while monitoring, so read it per button event
+            let action = match event.action {
+                HookAction::ButtonPress(button) if normalize_buttons => {
+                    HookAction::ButtonPress(settings::swap_buttons(button, settings::buttons_swapped()))
+                }
+                HookAction::ButtonRelease(button) if normalize_buttons => {
+                    HookAction::ButtonRelease(settings::swap_buttons(button, settings::buttons_swapped()))
+                }
+                action => action,
+            };
+
+            // A Move held back by sampling is delivered ahead of the button or wheel event following it
+            if !matches!(action, HookAction::Move { .. }) {
+                if let Some(event) = held_move.as_ref().and_then(|held| held.take()) {
+                    let _ = event_queue.push(event);
+                }
+            }
+
+            match action {
+                HookAction::Move { x, y } => {
+                    let mut new_position = (x, y);
+
+                    // Validate against the virtual desktop before anything sees the sample
+                    if let Some(mode) = sanitize_mode {
+                        let bounds = virtual_screen.bounds();
+                        if !bounds.contains(x, y) {
+                            out_of_bounds.fetch_add(1, Ordering::Relaxed);
+                            match mode {
+                                SanitizeMode::Clamp => new_position = bounds.clamp(x, y),
+                                SanitizeMode::Discard => return,
+                                SanitizeMode::Flag => {}
+                            }
+                        }
+                    }
+
+                    if let Some(long_press) = &long_press {
+                        long_press.moved(new_position);
+                    }
+                    if let Some(overshoot) = overshoot.as_mut() {
+                        overshoot.moved(new_position, clock.instant());
+                    }
+
                     let current_position = atomic_state.get_position();
 
                     if new_position != current_position {
                         // Update position atomically
                         atomic_state.update_position(new_position.0, new_position.1);
-                        
+                        if let (Some(predictor), Some(time)) = (predictor.as_mut(), precise_time) {
+                            predictor.update(new_position, time);
+                            prediction.publish(predictor, time);
+                        }
+
+                        // Query the cursor once and share it between type check, event and log
+                        let cursor_info = query_cursor_info();
+                        if let Some(cursor_info) = &cursor_info {
+                            atomic_state.cache_cursor(cursor_info);
+                        }
+                        let cursor_handle = cursor_info.map(|info| info.hCursor).unwrap_or_default();
+                        let cursor_type = get_cached_cursor_type(cursor_handle);
+
                         // Only create events if handlers exist (conditional event creation)
                         if has_handlers {
-                            let mut events = Vec::new();
-                            
-                            // Only check cursor type with debouncing
-                            if cursor_debouncer.should_check() {
-                                unsafe {
-                                    let mut cursor_info = CURSORINFO {
-                                        cbSize: std::mem::size_of::<CURSORINFO>() as u32,
-                                        flags: CURSOR_SHOWING,
-                                        hCursor: HCURSOR::default(),
-                                        ptScreenPos: POINT { x: 0, y: 0 },
+                            let mut monitor = None;
+                            if let Some(tracker) = monitor_tracker.as_mut() {
+                                let (located, change) = tracker.update(new_position);
+                                monitor = located;
+                                if let Some(change) = change {
+                                    let monitor_event = CursorEvent::MonitorChanged {
+                                        from: change.from,
+                                        to: change.to,
+                                        position: new_position,
+                                        timestamp: clock.now(),
+                                    };
+                                    let _ = event_queue.push(monitor_event);
+                                }
+                            }
+
+                            let mut window = None;
+                            if let Some(tracker) = window_tracker.as_mut() {
+                                let (hovered, changed) = tracker.update(new_position);
+                                if let (Some(hovered), true) = (&hovered, changed) {
+                                    let window_event = CursorEvent::WindowChanged {
+                                        window: Arc::clone(hovered),
+                                        position: new_position,
+                                        timestamp: clock.now(),
                                     };
-                                    
-                                    if GetCursorInfo(&mut cursor_info).is_ok() {
-                                        if cursor_debouncer.has_changed(cursor_info.hCursor) {
-                                            let cursor_type = get_cached_cursor_type(cursor_info.hCursor);
-                                            
-                                            // Create type change event
-                                            let type_event = CursorEvent::TypeChange {
-                                                new_type: cursor_type.to_string(),
-                                                position: new_position,
-                                                timestamp: Self::get_timestamp(),
-                                            };
-                                            events.push(type_event);
-                                            
-                                            Self::log_message(&format!("Cursor type changed to: {}", cursor_type));
-                                        }
-                                    }
+                                    let _ = event_queue.push(window_event);
                                 }
+                                window = hovered;
                             }
-                            
-                            // Create move event with static cursor type
-                            let cursor_type = get_cached_cursor_type(unsafe {
-                                let mut cursor_info = CURSORINFO {
-                                    cbSize: std::mem::size_of::<CURSORINFO>() as u32,
-                                    flags: CURSOR_SHOWING,
-                                    hCursor: HCURSOR::default(),
-                                    ptScreenPos: POINT { x: 0, y: 0 },
+
+                            if let Some(rect) = confinement_tracker.update() {
+                                let confinement_event = CursorEvent::ConfinementChanged {
+                                    rect,
+                                    position: new_position,
+                                    timestamp: clock.now(),
                                 };
-                                if GetCursorInfo(&mut cursor_info).is_ok() {
-                                    cursor_info.hCursor
-                                } else {
-                                    HCURSOR::default()
+                                let _ = event_queue.push(confinement_event);
+                            }
+
+                            let in_filter = track_regions(new_position);
+
+                            if let Some(recognizer) = gesture_recognizer.as_mut() {
+                                let recognized = recognizer.update(new_position, clock.instant());
+                                if let (Some(kind), true) = (recognized, event_mask.allows(EventKind::Gesture)) {
+                                    let gesture_event = CursorEvent::Gesture {
+                                        kind,
+                                        position: new_position,
+                                        timestamp: clock.now(),
+                                    };
+                                    let _ = event_queue.push(gesture_event);
                                 }
-                            });
-                            
-                            let move_event = CursorEvent::Move {
-                                position: new_position,
-                                cursor_type: cursor_type.to_string(),
-                                timestamp: Self::get_timestamp(),
-                            };
-                            events.push(move_event);
-                            
-                            // Send events in batch (non-blocking)
-                            if let Some(sender) = &event_sender {
-                                let _ = sender.send(events);
                             }
-                        }
-                        
-                        Self::log_cursor_state(new_position, get_cached_cursor_type(unsafe {
-                            let mut cursor_info = CURSORINFO {
-                                cbSize: std::mem::size_of::<CURSORINFO>() as u32,
-                                flags: CURSOR_SHOWING,
-                                hCursor: HCURSOR::default(),
-                                ptScreenPos: POINT { x: 0, y: 0 },
-                            };
-                            if GetCursorInfo(&mut cursor_info).is_ok() {
-                                cursor_info.hCursor
-                            } else {
-                                HCURSOR::default()
+
+                            if let Some(cursor_info) = &cursor_info {
+                                report_visibility(&atomic_state, &event_queue, &*clock, cursor_visible(cursor_info));
                             }
-                        }));
-                    }
-                }
-                EventType::ButtonPress(Button::Left) => {
-                    if !atomic_state.get_left_click() {
-                        atomic_state.set_left_click(true);
-                        
-                        // Only create event if handlers exist (conditional event creation)
-                        if has_handlers {
-                            let position = atomic_state.get_position();
-                            let click_event = CursorEvent::Click {
-                                button: MouseButton::Left,
-                                position,
-                                timestamp: Self::get_timestamp(),
+
+                            // Only check cursor type with debouncing
+                            let transition = (cursor_info.is_some() && cursor_debouncer.should_check())
+                                .then(|| type_changes.observe(cursor_type))
+                                .flatten();
+                            if let Some(transition) = transition {
+                                // Dwell on the old type, then the type change itself
+                                for type_event in transition.into_events(new_position, clock.now()) {
+                                    let _ = event_queue.push(type_event);
+                                }
+
+                                Self::log_message(&format!("Cursor type changed to: {}", cursor_type));
+                            }
+
+                            // Smoothing sees every raw sample, so sampled-out ones still shape the path
+                            let now = clock.instant();
+                            let smoothed = smoother.as_mut().map(|smoother| smoother.update(new_position, now));
+
+                            // A sampler changed through `configure` starts fresh from this sample
+                            if let Some(settings) = tuning.changed(&mut tuning_generation) {
+                                if settings.sampling != sampling_mode {
+                                    sampling_mode = settings.sampling;
+                                    sampler = runtime::sampler(sampling_mode);
+                                }
+                            }
+
+                            // Adaptive sampling may skip this sample (the position is still tracked)
+                            let keep_sample = match &mut sampler {
+                                Some(sampler) => sampler.should_emit(new_position, now),
+                                None => true,
                             };
-                            
-                            // Send event asynchronously (non-blocking)
-                            if let Some(sender) = &event_sender {
-                                let _ = sender.send(vec![click_event]);
+                            if let (true, Some(held)) = (keep_sample, &held_move) {
+                                held.clear();
+                            }
+
+                            if in_filter && event_mask.allows(EventKind::Move) {
+                                let move_event = CursorEvent::Move {
+                                    position: new_position,
+                                    cursor_type,
+                                    smoothed,
+                                    monitor,
+                                    client: client_for(new_position, window.as_ref()),
+                                    window,
+                                    source,
+                                    contact: last_contact.get(source.kind),
+                                    device_id: current_device(),
+                                    precise_time,
+                                    timestamp: clock.now(),
+                                };
+
+                                if keep_sample {
+                                    // Send event without batching allocations (non-blocking)
+                                    let _ = event_queue.push(move_event);
+                                } else if let (Some(sampler), Some(held)) = (&sampler, &held_move) {
+                                    // A skipped sample is held in case the cursor stops on it
+                                    held.hold(move_event, sampler.slow_interval());
+                                }
                             }
                         }
-                        
-                        let position = atomic_state.get_position();
-                        Self::log_message(&format!("Left click at position ({:.0}, {:.0})", 
-                            position.0, position.1));
+
+                        Self::log_cursor_state(new_position, cursor_type);
                     }
                 }
-                EventType::ButtonRelease(Button::Left) => {
-                    if atomic_state.get_left_click() {
-                        atomic_state.set_left_click(false);
-                        
-                        // Only create event if handlers exist (conditional event creation)
-                        if has_handlers {
-                            let release_event = CursorEvent::Release {
-                                button: MouseButton::Left,
-                                timestamp: Self::get_timestamp(),
-                            };
-                            
-                            // Send event asynchronously (non-blocking)
-                            if let Some(sender) = &event_sender {
-                                let _ = sender.send(vec![release_event]);
-                            }
+                HookAction::ButtonPress(MouseButton::Left) if !atomic_state.get_left_click() => {
+                    atomic_state.set_left_click(true);
+                    left_press = Some((clock.instant(), atomic_state.get_position()));
+                    if let Some(long_press) = &long_press {
+                        long_press.press(MouseButton::Left, atomic_state.get_position());
+                    }
+                    track_rage_clicks(MouseButton::Left, atomic_state.get_position());
+                    let overshoot_px = overshoot
+                        .as_mut()
+                        .and_then(|detector| detector.click(atomic_state.get_position(), clock.instant()));
+
+                    // Only create event if handlers exist (conditional event creation)
+                    if has_handlers && event_mask.allows(EventKind::Click) && track_regions(atomic_state.get_position()) {
+                        let position = atomic_state.get_position();
+                        let monitor = monitor_tracker.as_mut().and_then(|tracker| tracker.update(position).0);
+                        let window = window_tracker.as_mut().and_then(|tracker| tracker.update(position).0);
+                        let client = client_for(position, window.as_ref());
+                        let click_event = CursorEvent::Click {
+                            button: MouseButton::Left,
+                            position,
+                            monitor,
+                            window,
+                            client,
+                            source,
+                            contact: last_contact.get(source.kind),
+                            overshoot_px,
+                            device_id: current_device(),
+                            precise_time,
+                            timestamp: clock.now(),
+                        };
+
+                        // Send event asynchronously (non-blocking)
+                        let _ = event_queue.push(click_event);
+
+                        #[cfg(feature = "uia")]
+                        if let Some(element_requests) = &element_requests {
+                            let _ = element_requests.send(position);
                         }
-                        
-                        Self::log_message("Left click released");
                     }
+
+                    let position = atomic_state.get_position();
+                    Self::log_message(&format!("Left click at position ({:.0}, {:.0})",
+                        position.0, position.1));
                 }
-                EventType::ButtonPress(Button::Right) => {
-                    if !atomic_state.get_right_click() {
-                        atomic_state.set_right_click(true);
-                        
-                        // Only create event if handlers exist (conditional event creation)
-                        if has_handlers {
-                            let position = atomic_state.get_position();
-                            let click_event = CursorEvent::Click {
-                                button: MouseButton::Right,
-                                position,
-                                timestamp: Self::get_timestamp(),
-                            };
-                            
-                            // Send event asynchronously (non-blocking)
-                            if let Some(sender) = &event_sender {
-                                let _ = sender.send(vec![click_event]);
-                            }
-                        }
-                        
+                HookAction::ButtonRelease(MouseButton::Left) if atomic_state.get_left_click() => {
+                    atomic_state.set_left_click(false);
+                    let press = left_press.take();
+                    if let Some(long_press) = &long_press {
+                        long_press.release(MouseButton::Left);
+                    }
+
+                    // Only create event if handlers exist (conditional event creation)
+                    if has_handlers && event_mask.allows(EventKind::Release) {
+                        let held_for = press.map(|(at, _)| clock.instant().saturating_duration_since(at));
+                        let release_event = CursorEvent::Release {
+                            button: MouseButton::Left,
+                            press_position: press.map(|(_, position)| position),
+                            held_for: held_for.unwrap_or_default(),
+                            source,
+                            device_id: current_device(),
+                            precise_time,
+                            timestamp: clock.now(),
+                        };
+
+                        // Send event asynchronously (non-blocking)
+                        let _ = event_queue.push(release_event);
+                    }
+
+                    Self::log_message("Left click released");
+                }
+                HookAction::ButtonPress(MouseButton::Right) if !atomic_state.get_right_click() => {
+                    atomic_state.set_right_click(true);
+                    right_press = Some((clock.instant(), atomic_state.get_position()));
+                    if let Some(long_press) = &long_press {
+                        long_press.press(MouseButton::Right, atomic_state.get_position());
+                    }
+                    track_rage_clicks(MouseButton::Right, atomic_state.get_position());
+                    let overshoot_px = overshoot
+                        .as_mut()
+                        .and_then(|detector| detector.click(atomic_state.get_position(), clock.instant()));
+
+                    // Only create event if handlers exist (conditional event creation)
+                    if has_handlers && event_mask.allows(EventKind::Click) && track_regions(atomic_state.get_position()) {
                         let position = atomic_state.get_position();
-                        Self::log_message(&format!("Right click at position ({:.0}, {:.0})", 
-                            position.0, position.1));
+                        let monitor = monitor_tracker.as_mut().and_then(|tracker| tracker.update(position).0);
+                        let window = window_tracker.as_mut().and_then(|tracker| tracker.update(position).0);
+                        let client = client_for(position, window.as_ref());
+                        let click_event = CursorEvent::Click {
+                            button: MouseButton::Right,
+                            position,
+                            monitor,
+                            window,
+                            client,
+                            source,
+                            contact: last_contact.get(source.kind),
+                            overshoot_px,
+                            device_id: current_device(),
+                            precise_time,
+                            timestamp: clock.now(),
+                        };
+
+                        // Send event asynchronously (non-blocking)
+                        let _ = event_queue.push(click_event);
+
+                        #[cfg(feature = "uia")]
+                        if let Some(element_requests) = &element_requests {
+                            let _ = element_requests.send(position);
+                        }
                     }
+
+                    let position = atomic_state.get_position();
+                    Self::log_message(&format!("Right click at position ({:.0}, {:.0})",
+                        position.0, position.1));
                 }
-                EventType::ButtonRelease(Button::Right) => {
-                    if atomic_state.get_right_click() {
-                        atomic_state.set_right_click(false);
-                        
-                        // Only create event if handlers exist (conditional event creation)
-                        if has_handlers {
-                            let release_event = CursorEvent::Release {
-                                button: MouseButton::Right,
-                                timestamp: Self::get_timestamp(),
-                            };
-                            
-                            // Send event asynchronously (non-blocking)
-                            if let Some(sender) = &event_sender {
-                                let _ = sender.send(vec![release_event]);
-                            }
+                HookAction::ButtonRelease(MouseButton::Right) if atomic_state.get_right_click() => {
+                    atomic_state.set_right_click(false);
+                    let press = right_press.take();
+                    if let Some(long_press) = &long_press {
+                        long_press.release(MouseButton::Right);
+                    }
+
+                    // Only create event if handlers exist (conditional event creation)
+                    if has_handlers && event_mask.allows(EventKind::Release) {
+                        let held_for = press.map(|(at, _)| clock.instant().saturating_duration_since(at));
+                        let release_event = CursorEvent::Release {
+                            button: MouseButton::Right,
+                            press_position: press.map(|(_, position)| position),
+                            held_for: held_for.unwrap_or_default(),
+                            source,
+                            device_id: current_device(),
+                            precise_time,
+                            timestamp: clock.now(),
+                        };
+
+                        // Send event asynchronously (non-blocking)
+                        let _ = event_queue.push(release_event);
+                    }
+
+                    Self::log_message("Right click released");
+                }
+                HookAction::Wheel { axis, delta } => {
+                    let position = atomic_state.get_position();
+                    // Input after a pause or on the other axis ends the previous session first
+                    if let Some(tracker) = &scroll_sessions {
+                        if let Some(ended) = tracker.wheel(axis, delta, position) {
+                            let _ = event_queue.push(ended);
                         }
-                        
-                        Self::log_message("Right click released");
                     }
+
+                    // Only create event if handlers exist (conditional event creation)
+                    if has_handlers && event_mask.allows(EventKind::Scroll) && track_regions(position) {
+                        let scroll_event = CursorEvent::Scroll {
+                            axis,
+                            delta,
+                            position,
+                            source,
+                            device_id: current_device(),
+                            timestamp: clock.now(),
+                        };
+
+                        // Send event asynchronously (non-blocking)
+                        let _ = event_queue.push(scroll_event);
+                    }
+
+                    Self::log_message(&format!("Scrolled {} by {}", axis, delta));
                 }
                 _ => {}
             }
-        }) {
-            return Err(format!("Failed to start listening: {:?}", error));
-        }
 
-        Ok(())
+            // Let state waiters re-check now rather than on their next timeout
+            state_watch.notify();
+        }, on_restart);
+        // The blocker stays set for the next start, unless replaced in the meantime
+        self.input_blocker = self.input_blocker.take().or(input_guard.map(InputGuard::into_blocker));
+        listened.map_err(|error| format!("Failed to start listening: {}", error))
     }
 
     /// Process events with proper blocking and timeout (no busy waiting)
     fn process_events_with_timeout(
-        receiver: Receiver<Vec<CursorEvent>>,
-        event_handler: Option<CursorEventHandler>,
-        running: Arc<AtomicBool>
-    ) {
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        consumers: Consumers,
+        sinks: SharedSinks,
+        stats: SharedStats,
+        event_mask: Arc<SharedKindMask>,
+        stages: Stages,
+        running: Arc<AtomicBool>,
+    ) -> Consumers {
+        let Consumers { event_handler, event_filter, mut middleware, transform } = consumers;
+        let Stages { mut privacy, mut throttle } = stages;
         let timeout = Duration::from_millis(100); // 100ms timeout
-        
-        while running.load(Ordering::Relaxed) {
-            // Use blocking receive with timeout to avoid busy waiting
-            match receiver.recv_timeout(timeout) {
-                Ok(events) => {
-                    // Batch process events efficiently
-                    if let Some(handler) = &event_handler {
-                        for event in events {
-                            handler(event);
-                        }
-                    }
-                }
-                Err(mpsc::RecvTimeoutError::Timeout) => {
-                    // Timeout is expected, continue loop
-                    continue;
+        let clock = stats.lock().unwrap_or_else(|e| e.into_inner()).clock();
+        let started = clock.instant();
+
+        let mut windows = WindowResolver::new();
+
+        let mut deliver = |mut event: CursorEvent| {
+            // The hook only attaches window handles, so describe the window before anything sees it
+            windows.resolve(&mut event);
+
+            // Kinds are checked before masking, so a redacted event never revives a masked-out kind
+            let allowed = event_mask.allows(event.kind());
+            // Statistics reach sinks through `SessionEnd`, so they only count what survives masking
+            let Some(event) = privacy.apply(event) else {
+                return;
+            };
+            stats.lock().unwrap_or_else(|e| e.into_inner()).record(&event);
+            if !allowed {
+                return;
+            }
+
+            // Consumer panics are caught so one bad handler never ends delivery for everyone
+            let reporting_panic = event.kind() == EventKind::HandlerPanicked;
+            let mut on_panic = |handler: &str, payload: Box<dyn std::any::Any + Send>| {
+                let message = panic_message(&*payload);
+                Self::log_message(&format!("{} panicked: {}", handler, message));
+                // Reporting a panic raised by the report itself would loop
+                if !reporting_panic {
+                    let handler = handler.to_string();
+                    let _ = event_queue.push(CursorEvent::HandlerPanicked { handler, message, timestamp: clock.now() });
                 }
-                Err(mpsc::RecvTimeoutError::Disconnected) => {
-                    // Channel disconnected, exit gracefully
-                    break;
+            };
+
+            let processed = catch_unwind(AssertUnwindSafe(|| {
+                throttle
+                    .process(event)
+                    .filter(|event| event_filter.as_ref().is_none_or(|filter| filter(event)))
+                    .and_then(|event| run_chain(&mut middleware, event))
+            }));
+            let event = match processed {
+                Ok(Some(event)) => event,
+                Ok(None) => return,
+                Err(payload) => return on_panic("middleware", payload),
+            };
+            // Sinks normalizing positions start from the screen pixels rather than the transformed ones
+            let transformed = transform.as_ref().map(|transform| {
+                let mut transformed = event.clone();
+                transformed.map_positions(transform);
+                transformed
+            });
+            sinks.lock().unwrap_or_else(|e| e.into_inner()).dispatch(&event, transformed.as_ref(), &mut on_panic);
+            if let Some(handler) = &event_handler {
+                let event = transformed.unwrap_or(event);
+                if let Err(payload) = catch_unwind(AssertUnwindSafe(|| handler(event))) {
+                    on_panic("event handler", payload);
                 }
             }
+        };
+
+        while running.load(Ordering::Relaxed) {
+            // Park until an event arrives or the timeout passes (no busy waiting)
+            if let Some(event) = event_queue.pop_timeout(timeout) {
+                deliver(event);
+            }
         }
+
+        // Events queued just before stopping still reach handlers and sinks
+        while let Some(event) = event_queue.pop() {
+            deliver(event);
+        }
+
+        // Terminator record, so downstream logs can tell a finished session from a cut-off one
+        let final_stats = stats.lock().unwrap_or_else(|e| e.into_inner()).snapshot();
+        deliver(CursorEvent::SessionEnd {
+            duration: clock.instant().saturating_duration_since(started),
+            stats: Box::new(final_stats),
+            timestamp: clock.now(),
+        });
+        // Panics raised by consumers of the terminator are reported through the queue
+        while let Some(event) = event_queue.pop() {
+            deliver(event);
+        }
+
+        // Give sinks a chance to deliver buffered events before shutdown
+        sinks.lock().unwrap_or_else(|e| e.into_inner()).flush();
+        Consumers { event_handler, event_filter, middleware, transform }
     }
 }
 
+#[cfg(windows)]
 impl Default for CursorDetector {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(windows)]
 impl Drop for CursorDetector {
     fn drop(&mut self) {
         let _ = self.stop();
     }
 }
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::{AtomicCursorState, AtomicDebouncer, CURSOR_CACHE_TTL_MS};
+    use crate::clock::ManualClock;
+    use crate::Timestamp;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use windows::Win32::UI::WindowsAndMessaging::CURSORINFO;
+
+    fn clock() -> Arc<ManualClock> {
+        Arc::new(ManualClock::new(Timestamp::from_millis(1_738_324_800_000)))
+    }
+
+    #[test]
+    fn debouncer_follows_the_injected_clock() {
+        let clock = clock();
+        let debouncer = AtomicDebouncer::new(16, clock.clone());
+        assert!(debouncer.should_check());
+        assert!(!debouncer.should_check());
+        clock.advance(Duration::from_millis(15));
+        assert!(!debouncer.should_check());
+        clock.advance(Duration::from_millis(1));
+        assert!(debouncer.should_check());
+    }
+
+    #[test]
+    fn cached_cursor_expires_on_the_injected_clock() {
+        let clock = clock();
+        let state = AtomicCursorState::new(clock.clone());
+        state.cache_cursor(&CURSORINFO::default());
+        clock.advance(Duration::from_millis(CURSOR_CACHE_TTL_MS));
+        assert!(state.cached_cursor().is_some());
+        clock.advance(Duration::from_millis(1));
+        assert!(state.cached_cursor().is_none());
+
+        state.cache_cursor(&CURSORINFO::default());
+        state.invalidate_cursor();
+        assert!(state.cached_cursor().is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! Long-press detection for buttons held down without moving
+
+use crate::clock::{Clock, SharedClock};
+use crate::queue::EventQueue;
+use crate::settings::SystemPointerSettings;
+use crate::{CursorEvent, MouseButton};
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::WindowsAndMessaging::{KillTimer, SetTimer};
+
+/// How often held buttons are checked against the threshold
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Drift allowed while a press still counts as stationary, when the drag threshold cannot be read
+const DEFAULT_TOLERANCE: (f64, f64) = (4.0, 4.0);
+
+#[derive(Debug, Clone, Copy)]
+struct HeldButton {
+    button: MouseButton,
+    position: (f64, f64),
+    since: Instant,
+    reported: bool,
+}
+
+/// Buttons currently held, updated by the mouse hook and checked by a timer on the same thread
+///
+/// A press stops counting once the cursor leaves the system drag rectangle around
+/// where it went down, so drags never become long presses.
+#[derive(Debug)]
+pub(crate) struct LongPressTracker {
+    threshold: Duration,
+    tolerance: (f64, f64),
+    clock: SharedClock,
+    held: Mutex<Vec<HeldButton>>,
+}
+
+impl LongPressTracker {
+    pub(crate) fn new(threshold: Duration, clock: SharedClock) -> Self {
+        // The drag rectangle is centered on the press, so half of it either way
+        let tolerance = SystemPointerSettings::query()
+            .map(|settings| (settings.drag_threshold.0 as f64 / 2.0, settings.drag_threshold.1 as f64 / 2.0))
+            .unwrap_or(DEFAULT_TOLERANCE);
+        Self { threshold, tolerance, clock, held: Mutex::default() }
+    }
+
+    pub(crate) fn press(&self, button: MouseButton, position: (f64, f64)) {
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        held.retain(|press| press.button != button);
+        held.push(HeldButton { button, position, since: self.clock.instant(), reported: false });
+    }
+
+    pub(crate) fn release(&self, button: MouseButton) {
+        self.held.lock().unwrap_or_else(|e| e.into_inner()).retain(|press| press.button != button);
+    }
+
+    /// Forget presses the cursor has moved away from
+    pub(crate) fn moved(&self, position: (f64, f64)) {
+        let (tolerance_x, tolerance_y) = self.tolerance;
+        self.held.lock().unwrap_or_else(|e| e.into_inner()).retain(|press| {
+            (position.0 - press.position.0).abs() <= tolerance_x && (position.1 - press.position.1).abs() <= tolerance_y
+        });
+    }
+
+    /// `LongPress` events for presses that just passed the threshold, each reported once
+    fn poll(&self) -> Vec<CursorEvent> {
+        let now = self.clock.instant();
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        held.iter_mut()
+            .filter(|press| !press.reported && now.saturating_duration_since(press.since) >= self.threshold)
+            .map(|press| {
+                press.reported = true;
+                CursorEvent::LongPress {
+                    button: press.button,
+                    position: press.position,
+                    duration: now.saturating_duration_since(press.since),
+                    timestamp: self.clock.now(),
+                }
+            })
+            .collect()
+    }
+}
+
+type LongPressTarget = (Arc<LongPressTracker>, Arc<EventQueue<CursorEvent>>);
+
+thread_local! {
+    // Timer procedures carry no user data and run on the thread that set the timer
+    static LONG_PRESS_TARGET: RefCell<Option<LongPressTarget>> = const { RefCell::new(None) };
+}
+
+/// Timer on the hook thread raising `LongPress` while a button is held still, removed on drop
+pub(crate) struct LongPressWatcher {
+    timer: usize,
+}
+
+impl LongPressWatcher {
+    pub(crate) fn install(
+        tracker: Arc<LongPressTracker>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+    ) -> Result<Self, String> {
+        // A thread timer with a procedure is dispatched by the hook thread's message loop
+        let timer = unsafe { SetTimer(None, 0, POLL_INTERVAL.as_millis() as u32, Some(long_press_proc)) };
+        if timer == 0 {
+            return Err("Failed to start long-press timer".to_string());
+        }
+        LONG_PRESS_TARGET.with(|slot| *slot.borrow_mut() = Some((tracker, event_queue)));
+        Ok(Self { timer })
+    }
+}
+
+impl Drop for LongPressWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = KillTimer(None, self.timer);
+        }
+        LONG_PRESS_TARGET.with(|slot| slot.borrow_mut().take());
+    }
+}
+
+unsafe extern "system" fn long_press_proc(_hwnd: HWND, _message: u32, _id: usize, _time: u32) {
+    LONG_PRESS_TARGET.with(|slot| {
+        if let Some((tracker, event_queue)) = slot.borrow().as_ref() {
+            for event in tracker.poll() {
+                let _ = event_queue.push(event);
+            }
+        }
+    });
+}
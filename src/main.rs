@@ -1,13 +1,1127 @@
-use luuma_cursor_helper::CursorDetector;
+//! `luuma`: watch, record, replay and serve cursor events from the command line
 
+#[cfg(windows)]
+use clap::{Args, Parser, Subcommand, ValueEnum};
+#[cfg(windows)]
+use luuma_cursor_helper::recorder::load_session;
+#[cfg(windows)]
+use luuma_cursor_helper::{
+    BackgroundDetector, Config, ConfigReload, ConfigWatcher, CursorController, CursorDetector, CursorEvent,
+    DetectorHandle, EventKind, EventKindMask, Heatmap, HeatmapWeight, Hotkey, Interrupt, JsonFormat, MouseButton,
+    Record, SessionPlayer, SessionRecorder, SessionStats, SinkFilter, TimestampFormat, WebSocketSink,
+};
+#[cfg(all(windows, feature = "parquet"))]
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+#[cfg(all(windows, feature = "parquet"))]
+use parquet::arrow::ArrowWriter;
+#[cfg(all(windows, feature = "parquet"))]
+use parquet::errors::ParquetError;
+#[cfg(windows)]
+use std::fs::File;
+#[cfg(windows)]
+use std::io::{self, BufWriter, Write};
+#[cfg(windows)]
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(windows)]
+use std::process::ExitCode;
+#[cfg(windows)]
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(windows)]
+use std::sync::{Arc, Mutex};
+#[cfg(windows)]
+use std::thread;
+#[cfg(windows)]
+use std::time::Duration;
+#[cfg(all(windows, feature = "service"))]
+use luuma_cursor_helper::{install_service, run_service, stop_with_service, uninstall_service, ServiceConfig};
+#[cfg(all(windows, feature = "tray"))]
+use luuma_cursor_helper::{TrayConfig, TrayIcon};
+#[cfg(all(windows, feature = "tui"))]
+use luuma_cursor_helper::CursorState;
+#[cfg(all(windows, feature = "tui"))]
+use ratatui::crossterm::event::{self as terminal_event, Event, KeyCode, KeyEventKind, KeyModifiers};
+#[cfg(all(windows, feature = "tui"))]
+use ratatui::layout::{Constraint, Layout};
+#[cfg(all(windows, feature = "tui"))]
+use ratatui::text::Line;
+#[cfg(all(windows, feature = "tui"))]
+use ratatui::widgets::{Block, List, Paragraph, Sparkline};
+#[cfg(all(windows, feature = "tui"))]
+use ratatui::{DefaultTerminal, Frame};
+#[cfg(all(windows, feature = "tui"))]
+use std::collections::{HashMap, VecDeque};
+#[cfg(all(windows, feature = "tui"))]
+use std::time::Instant;
+
+#[cfg(windows)]
+#[derive(Parser)]
+#[command(name = "luuma", version, about = "Cursor position, type and click events from the command line")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// How events and statistics are printed
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// How JSON output writes timestamps
+    #[arg(long, global = true, value_enum, default_value_t = Timestamps::Utc)]
+    timestamps: Timestamps,
+    /// Only these event kinds, e.g. `clicks,moves` (all kinds by default)
+    #[arg(long, global = true, value_delimiter = ',', value_parser = parse_kind)]
+    only: Vec<EventKind>,
+    /// TOML settings file for the live commands, reloaded when it changes
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+}
+
+#[cfg(windows)]
+#[derive(Subcommand)]
+enum Command {
+    /// Print cursor events until Ctrl+C
+    Watch {
+        #[command(flatten)]
+        limits: Limits,
+    },
+    /// Record cursor events to a session file until Ctrl+C
+    Record {
+        /// Session file to create
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Replace pauses longer than this, e.g. `5s`, with a single idle gap
+        #[arg(long, value_parser = parse_duration)]
+        idle_gap: Option<Duration>,
+        #[command(flatten)]
+        limits: Limits,
+    },
+    /// Print the events of a session file with their recorded timing
+    Replay {
+        session: PathBuf,
+        /// Playback speed multiplier
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+        /// Skip idle gaps instead of waiting them out
+        #[arg(long)]
+        skip_idle: bool,
+        /// Replay moves and clicks as real input instead of printing them
+        #[arg(long)]
+        inject: bool,
+        /// With --inject, print what would be injected without touching the cursor
+        #[arg(long, requires = "inject")]
+        dry_run: bool,
+        /// Seconds to wait before injecting, to bring the target window forward
+        #[arg(long, default_value_t = 3)]
+        countdown: u64,
+        /// Key combination that stops an injected replay, e.g. `Escape` or `Ctrl+Shift+Q`
+        #[arg(long, default_value = "Escape")]
+        abort_key: String,
+    },
+    /// Summarize a session file
+    Stats { session: PathBuf },
+    /// Distance, clicks, speed and activity stretches of a session file, with break recommendations
+    Ergonomics { session: PathBuf },
+    /// Convert a session file to CSV, JSON Lines or Parquet, chosen by the output extension
+    Convert {
+        session: PathBuf,
+        /// File to write: `.csv`, `.jsonl` or `.parquet`
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Render a session file as a heatmap image over the virtual desktop
+    Heatmap {
+        session: PathBuf,
+        /// PNG file to write
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Columns and rows of the grid
+        #[arg(long, value_name = "COLUMNSxROWS", default_value = "64x36", value_parser = parse_grid)]
+        grid: (usize, usize),
+        /// What each cell measures
+        #[arg(long, value_enum, default_value_t = Metric::Dwell)]
+        metric: Metric,
+        /// Pixels per cell in the image
+        #[arg(long, default_value_t = 20)]
+        cell_size: u32,
+    },
+    /// Broadcast events as JSON to WebSocket clients until Ctrl+C
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 9001)]
+        ws: u16,
+        /// Address to listen on; use 0.0.0.0 to accept other machines
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[command(flatten)]
+        limits: Limits,
+    },
+    /// Live dashboard of position, cursor type, clicks and recent events
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Recent events kept for scrolling
+        #[arg(long, default_value_t = 500)]
+        history: usize,
+    },
+    /// Keep a live command running as a Windows service, for kiosks and lab machines
+    #[cfg(feature = "service")]
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+#[cfg(all(windows, feature = "service"))]
+#[derive(Subcommand)]
+enum ServiceAction {
+    /// Register and start the service, which then starts at boot and restarts after failures
+    Install {
+        #[command(flatten)]
+        options: ServiceOptions,
+        /// The luuma command to keep running in the signed-in user's session, e.g. `-- record -o lab.jsonl`
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        helper: Vec<String>,
+    },
+    /// Stop and remove the service
+    Uninstall {
+        /// Name the service was installed under
+        #[arg(long, default_value = "luuma")]
+        name: String,
+    },
+    /// Run as the service; started by the service manager, not by hand
+    #[command(hide = true)]
+    Run {
+        #[command(flatten)]
+        options: ServiceOptions,
+        #[arg(last = true, required = true)]
+        helper: Vec<String>,
+    },
+}
+
+#[cfg(all(windows, feature = "service"))]
+#[derive(Debug, Clone, Args)]
+struct ServiceOptions {
+    /// Name to install the service under
+    #[arg(long, default_value = "luuma")]
+    name: String,
+    /// Directory for service.log and helper.log and the command's working directory (default: %ProgramData%\luuma)
+    #[arg(long, value_name = "DIR")]
+    log_dir: Option<PathBuf>,
+}
+
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Metric {
+    /// Clicks in each cell
+    Clicks,
+    /// Seconds the cursor rested in each cell
+    Dwell,
+    /// Move events in each cell
+    Samples,
+}
+
+/// When a live command stops without Ctrl+C, and how it can be stopped without a console
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy, Args)]
+struct Limits {
+    /// Stop after this long, e.g. `60s`, `5m` or `500ms`
+    #[arg(long = "for", value_name = "DURATION", value_parser = parse_duration)]
+    duration: Option<Duration>,
+    /// Stop after this many events
+    #[arg(long, value_name = "N")]
+    max_events: Option<u64>,
+    /// Show a notification area icon to pause, resume and quit, hiding a console window opened just for luuma
+    #[cfg(feature = "tray")]
+    #[arg(long)]
+    tray: bool,
+}
+
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// One readable line per event
+    Text,
+    /// One JSON object per line
+    Json,
+    /// Exactly one compact JSON event per line, flushed as it happens and with no banner, for pipelines
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Timestamps {
+    /// UTC in RFC 3339, e.g. `2025-01-31T12:00:00.250Z`
+    Utc,
+    /// Local time in RFC 3339 with its offset
+    Local,
+    /// Milliseconds since the Unix epoch
+    Epoch,
+}
+
+/// How often an injected replay checks the abort key
+#[cfg(windows)]
+const ABORT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Exit status after Ctrl+C or an aborted replay, as shells report an interrupted command
+#[cfg(windows)]
+const INTERRUPTED_EXIT_CODE: u8 = 130;
+
+#[cfg(windows)]
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mask = if cli.only.is_empty() { EventKindMask::ALL } else { EventKindMask::of(&cli.only) };
+    let json = JsonFormat::new().timestamps(match cli.timestamps {
+        Timestamps::Utc => TimestampFormat::Utc,
+        Timestamps::Local => TimestampFormat::Local,
+        Timestamps::Epoch => TimestampFormat::EpochMillis,
+    });
+    let config = match cli.config.map(ConfigFile::load).transpose() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match cli.command {
+        Command::Watch { limits } => watch(cli.format, json, mask, config, limits),
+        Command::Record { output, idle_gap, limits } => record(output, idle_gap, mask, config, limits),
+        Command::Serve { ws, host, limits } => serve(&host, ws, json, mask, config, limits),
+        Command::Replay { session, speed, skip_idle, inject: false, .. } => {
+            replay(session, speed, skip_idle, cli.format, json, mask).map(|()| ExitCode::SUCCESS)
+        }
+        Command::Replay { session, speed, skip_idle, inject: true, dry_run, countdown, abort_key } => {
+            let player = SessionPlayer::open(&session).map(|player| player.speed(speed).skip_idle_gaps(skip_idle));
+            player
+                .and_then(|player| replay_input(&player, cli.format, json, mask, countdown, &abort_key, dry_run))
+                .map(|()| ExitCode::SUCCESS)
+        }
+        Command::Stats { session } => stats(session, cli.format, mask).map(|()| ExitCode::SUCCESS),
+        Command::Ergonomics { session } => ergonomics(session, cli.format, mask).map(|()| ExitCode::SUCCESS),
+        Command::Convert { session, output } => convert(session, output, json, mask).map(|()| ExitCode::SUCCESS),
+        Command::Heatmap { session, output, grid, metric, cell_size } => {
+            heatmap(session, output, grid, metric, cell_size, mask).map(|()| ExitCode::SUCCESS)
+        }
+        #[cfg(feature = "tui")]
+        Command::Tui { history } => tui(history, mask, config).map(|()| ExitCode::SUCCESS),
+        #[cfg(feature = "service")]
+        Command::Service { action } => service(action).map(|()| ExitCode::SUCCESS),
+    };
+    result.unwrap_or_else(|error| {
+        eprintln!("Error: {}", error);
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(not(windows))]
 fn main() {
-    println!("Cursor Detection Program Started");
-    println!("Monitoring cursor position, type, and mouse clicks...");
-    println!("Press Ctrl+C to exit\n");
+    eprintln!("luuma is only available on Windows");
+}
+
+#[cfg(windows)]
+fn watch(
+    format: Format,
+    json: JsonFormat,
+    mask: EventKindMask,
+    config: Option<ConfigFile>,
+    limits: Limits,
+) -> Result<ExitCode, String> {
+    let mut detector = live_detector(mask, config.as_ref())?;
+    print_header(format);
+    detector.set_event_handler(move |event| print_event(format, json, &event));
+    if format != Format::Ndjson {
+        eprintln!("Watching cursor events, press Ctrl+C to stop");
+    }
+    run_live(detector, limits, None, config)
+}
+
+#[cfg(windows)]
+fn record(
+    output: PathBuf,
+    idle_gap: Option<Duration>,
+    mask: EventKindMask,
+    config: Option<ConfigFile>,
+    limits: Limits,
+) -> Result<ExitCode, String> {
+    let mut recorder = SessionRecorder::create(&output)?;
+    if let Some(threshold) = idle_gap {
+        recorder = recorder.with_idle_gap_compression(threshold);
+    }
+    let mut detector = live_detector(mask, config.as_ref())?;
+    detector.add_sink("record", recorder, SinkFilter::all());
+    eprintln!("Recording to {}, press Ctrl+C to stop", output.display());
+    run_live(detector, limits, Some(&output), config)
+}
+
+#[cfg(windows)]
+fn replay(
+    session: PathBuf,
+    speed: f64,
+    skip_idle: bool,
+    format: Format,
+    json: JsonFormat,
+    mask: EventKindMask,
+) -> Result<(), String> {
+    let player = SessionPlayer::open(&session)?.speed(speed).skip_idle_gaps(skip_idle);
+    print_header(format);
+    player.play(|event| {
+        if mask.contains(event.kind()) {
+            print_event(format, json, event);
+        }
+    });
+    Ok(())
+}
+
+/// Replay moves and clicks as real input, after a countdown and until the abort key
+#[cfg(windows)]
+fn replay_input(
+    player: &SessionPlayer,
+    format: Format,
+    json: JsonFormat,
+    mask: EventKindMask,
+    countdown: u64,
+    abort_key: &str,
+    dry_run: bool,
+) -> Result<(), String> {
+    let abort = Hotkey::parse(abort_key)?;
+    let inputs = player
+        .entries()
+        .iter()
+        .filter(|entry| matches!(&entry.record, Record::Event(event) if is_input(event) && mask.contains(event.kind())))
+        .count();
+
+    if dry_run {
+        eprintln!("Dry run: {} moves and clicks would be injected", inputs);
+        print_header(format);
+        return player.try_play(|event| {
+            if is_input(event) && mask.contains(event.kind()) {
+                print_event(format, json, event);
+            }
+            Ok(())
+        });
+    }
+
+    eprintln!("Replaying {} moves and clicks as real input, hold {} to abort", inputs, abort_key);
+    for remaining in (1..=countdown).rev() {
+        eprint!("{}... ", remaining);
+        thread::sleep(Duration::from_secs(1));
+        if abort.is_held() {
+            eprintln!("aborted");
+            return Ok(());
+        }
+    }
+    eprintln!("go");
+
+    // Buttons pressed by the replay, released again however it ends
+    let held: Arc<Mutex<Vec<MouseButton>>> = Arc::new(Mutex::new(Vec::new()));
+    let (done, finished) = mpsc::channel::<()>();
+    let watcher_held = Arc::clone(&held);
+    // Ctrl+C aborts like the abort key instead of killing the process with a button down
+    let interrupt = Interrupt::install()?;
+    // The player sleeps between events, so a separate thread watches for the abort key
+    let watcher = thread::spawn(move || loop {
+        if abort.is_held() || interrupt.is_interrupted() {
+            release_all(&watcher_held);
+            eprintln!("Aborted");
+            std::process::exit(INTERRUPTED_EXIT_CODE.into());
+        }
+        if finished.recv_timeout(ABORT_POLL_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+            return;
+        }
+    });
+
+    let controller = CursorController::new();
+    let result = player.try_play(|event| {
+        if !mask.contains(event.kind()) {
+            return Ok(());
+        }
+        match event {
+            CursorEvent::Move { position, .. } => controller.set_position(position.0, position.1),
+            CursorEvent::Click { button, position, .. } => {
+                controller.set_position(position.0, position.1)?;
+                controller.press(*button)?;
+                held.lock().unwrap_or_else(|e| e.into_inner()).push(*button);
+                Ok(())
+            }
+            CursorEvent::Release { button, .. } => {
+                held.lock().unwrap_or_else(|e| e.into_inner()).retain(|pressed| pressed != button);
+                controller.release(*button)
+            }
+            _ => Ok(()),
+        }
+    });
+    let _ = done.send(());
+    let _ = watcher.join();
+    // A session recorded up to the middle of a drag must not leave the button down
+    release_all(&held);
+    result
+}
+
+/// Moves and clicks, the events that replay as real input
+#[cfg(windows)]
+fn is_input(event: &CursorEvent) -> bool {
+    matches!(event, CursorEvent::Move { .. } | CursorEvent::Click { .. } | CursorEvent::Release { .. })
+}
+
+#[cfg(windows)]
+fn release_all(held: &Mutex<Vec<MouseButton>>) {
+    let controller = CursorController::new();
+    for button in held.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+        let _ = controller.release(button);
+    }
+}
+
+#[cfg(windows)]
+fn convert(session: PathBuf, output: PathBuf, json: JsonFormat, mask: EventKindMask) -> Result<(), String> {
+    let entries = load_session(&session)?;
+    let events: Vec<&CursorEvent> = entries
+        .iter()
+        .filter_map(|entry| match &entry.record {
+            Record::Event(event) if mask.contains(event.kind()) => Some(event),
+            _ => None,
+        })
+        .collect();
+
+    let extension = output.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "csv" => write_lines(&output, Some(CSV_HEADER), events.iter().map(|event| EventRow::new(event).to_csv())),
+        "jsonl" | "ndjson" => write_lines(&output, None, events.iter().map(|event| json.to_string(event))),
+        #[cfg(feature = "parquet")]
+        "parquet" => write_parquet(&output, &events),
+        #[cfg(not(feature = "parquet"))]
+        "parquet" => Err("Parquet output needs luuma built with the parquet feature".to_string()),
+        _ => Err(format!("Unknown output format for {}, use .csv, .jsonl or .parquet", output.display())),
+    }?;
+    eprintln!("Converted {} events to {}", events.len(), output.display());
+    Ok(())
+}
+
+/// Write an optional header and one line per item to a new file
+#[cfg(windows)]
+fn write_lines<I>(path: &Path, header: Option<&str>, lines: I) -> Result<(), String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let failed = |e: io::Error| format!("Failed to write {}: {}", path.display(), e);
+    let mut writer = BufWriter::new(File::create(path).map_err(failed)?);
+    for line in header.map(str::to_string).into_iter().chain(lines) {
+        writeln!(writer, "{}", line).map_err(failed)?;
+    }
+    writer.flush().map_err(failed)
+}
+
+/// Columnar export with the same columns as the CSV format
+#[cfg(all(windows, feature = "parquet"))]
+fn write_parquet(path: &Path, events: &[&CursorEvent]) -> Result<(), String> {
+    let rows: Vec<EventRow> = events.iter().map(|event| EventRow::new(event)).collect();
+    let columns = [
+        ("timestamp_ms", Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.timestamp_ms))) as ArrayRef),
+        ("kind", Arc::new(StringArray::from_iter_values(rows.iter().map(|row| row.kind.as_str()))) as ArrayRef),
+        ("x", Arc::new(Float64Array::from_iter(rows.iter().map(|row| row.position.map(|(x, _)| x)))) as ArrayRef),
+        ("y", Arc::new(Float64Array::from_iter(rows.iter().map(|row| row.position.map(|(_, y)| y)))) as ArrayRef),
+        ("button", Arc::new(StringArray::from_iter(rows.iter().map(|row| row.button.as_deref()))) as ArrayRef),
+        ("cursor_type", Arc::new(StringArray::from_iter(rows.iter().map(|row| row.cursor_type.as_deref()))) as ArrayRef),
+    ];
+    let batch = RecordBatch::try_from_iter(columns).map_err(|e| format!("Failed to build Parquet batch: {}", e))?;
+
+    let failed = |e: ParquetError| format!("Failed to write {}: {}", path.display(), e);
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).map_err(failed)?;
+    writer.write(&batch).map_err(failed)?;
+    writer.close().map_err(failed)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn stats(session: PathBuf, format: Format, mask: EventKindMask) -> Result<(), String> {
+    let entries = load_session(&session)?;
+    let events = entries.iter().filter_map(|entry| match &entry.record {
+        Record::Event(event) if mask.contains(event.kind()) => Some(event),
+        _ => None,
+    });
+    let stats = SessionStats::from_events(events);
+
+    match format {
+        Format::Json => println!("{}", stats.to_json_pretty()),
+        Format::Ndjson => write_line(&stats.to_json()),
+        Format::Csv => {
+            println!("metric,value");
+            println!("started,{}", stats.started.as_millis());
+            println!("clicks,{}", stats.total_clicks());
+            for (button, count) in &stats.clicks {
+                println!("clicks_{},{}", button.to_string().to_lowercase(), count);
+            }
+            println!("distance_px,{:.1}", stats.distance_px);
+            println!("distance_m,{:.3}", stats.distance_m);
+            println!("active_ms,{}", stats.active_time.as_millis());
+            println!("idle_ms,{}", stats.idle_time.as_millis());
+            println!("longest_stretch_ms,{}", stats.longest_stretch.as_millis());
+            println!("breaks,{}", stats.breaks);
+            for (cursor_type, ms) in &stats.cursor_type_ms {
+                println!("cursor_{}_ms,{}", cursor_type.to_string().to_lowercase(), ms);
+            }
+            for (application, activity) in &stats.applications {
+                println!("app_{}_active_ms,{}", application, activity.active_time.as_millis());
+                println!("app_{}_clicks,{}", application, activity.clicks);
+            }
+        }
+        Format::Text => println!("{}", stats_text(&stats)),
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn ergonomics(session: PathBuf, format: Format, mask: EventKindMask) -> Result<(), String> {
+    let entries = load_session(&session)?;
+    let events = entries.iter().filter_map(|entry| match &entry.record {
+        Record::Event(event) if mask.contains(event.kind()) => Some(event),
+        _ => None,
+    });
+    let report = SessionStats::from_events(events).ergonomics();
+
+    match format {
+        Format::Json => println!("{}", report.to_json_pretty()),
+        Format::Ndjson => write_line(&report.to_json()),
+        Format::Csv => {
+            println!("metric,value");
+            println!("duration_ms,{}", report.duration.as_millis());
+            println!("active_ms,{}", report.active_time.as_millis());
+            println!("distance_px,{:.1}", report.distance_px);
+            println!("distance_m,{:.3}", report.distance_m);
+            println!("clicks,{}", report.clicks);
+            println!("average_speed,{:.1}", report.average_speed);
+            println!("longest_stretch_ms,{}", report.longest_stretch.as_millis());
+            println!("breaks,{}", report.breaks);
+        }
+        Format::Text => println!("{}", report),
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn heatmap(
+    session: PathBuf,
+    output: PathBuf,
+    (columns, rows): (usize, usize),
+    metric: Metric,
+    cell_size: u32,
+    mask: EventKindMask,
+) -> Result<(), String> {
+    let weight = match metric {
+        Metric::Clicks => HeatmapWeight::Clicks,
+        Metric::Dwell => HeatmapWeight::Dwell,
+        Metric::Samples => HeatmapWeight::Samples,
+    };
+    // Sessions do not store the screen layout, so the grid covers this machine's desktop
+    let mut heatmap = Heatmap::new(CursorDetector::get_virtual_screen_bounds(), columns, rows).weight(weight);
+    for entry in load_session(&session)? {
+        if let Record::Event(event) = &entry.record {
+            if mask.contains(event.kind()) {
+                heatmap.record(event);
+            }
+        }
+    }
+    heatmap.save_png(&output, cell_size)?;
+    eprintln!("Wrote {}x{} heatmap to {}", columns, rows, output.display());
+    Ok(())
+}
+
+#[cfg(windows)]
+fn serve(
+    host: &str,
+    port: u16,
+    json: JsonFormat,
+    mask: EventKindMask,
+    config: Option<ConfigFile>,
+    limits: Limits,
+) -> Result<ExitCode, String> {
+    let sink = WebSocketSink::bind(&format!("{}:{}", host, port))?.with_json_format(json);
+    eprintln!("Serving cursor events on ws://{}, press Ctrl+C to stop", sink.local_address());
+    let mut detector = live_detector(mask, config.as_ref())?;
+    detector.add_sink("websocket", sink, SinkFilter::all());
+    run_live(detector, limits, None, config)
+}
+
+/// Seconds of event rate history behind the sparkline
+#[cfg(all(windows, feature = "tui"))]
+const RATE_HISTORY: usize = 300;
+
+/// How often the dashboard redraws and checks for keys
+#[cfg(all(windows, feature = "tui"))]
+const TUI_TICK: Duration = Duration::from_millis(100);
+
+/// Counters and recent events for the dashboard, filled in on the processing thread
+#[cfg(all(windows, feature = "tui"))]
+#[derive(Default)]
+struct Dashboard {
+    clicks: HashMap<MouseButton, u64>,
+    total: u64,
+    /// Events since the last rate sample
+    pending: u64,
+    /// Events per second, oldest first
+    rate: VecDeque<u64>,
+    /// Event summaries, oldest first
+    recent: VecDeque<String>,
+}
 
+#[cfg(all(windows, feature = "tui"))]
+impl Dashboard {
+    fn record(&mut self, event: &CursorEvent, history: usize) {
+        if let CursorEvent::Click { button, .. } = event {
+            *self.clicks.entry(*button).or_default() += 1;
+        }
+        self.total += 1;
+        self.pending += 1;
+        self.note(format!("[{}] {}", event.timestamp(), describe(event)), history);
+    }
+
+    /// Add a line to the event list
+    fn note(&mut self, line: String, history: usize) {
+        self.recent.push_back(line);
+        while self.recent.len() > history {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Close the current one-second rate bucket
+    fn sample_rate(&mut self) {
+        self.rate.push_back(std::mem::take(&mut self.pending));
+        while self.rate.len() > RATE_HISTORY {
+            self.rate.pop_front();
+        }
+    }
+}
+
+#[cfg(all(windows, feature = "tui"))]
+fn tui(history: usize, mask: EventKindMask, config: Option<ConfigFile>) -> Result<(), String> {
+    let dashboard = Arc::new(Mutex::new(Dashboard::default()));
+    let mut detector = live_detector(mask, config.as_ref())?;
+    let shared = Arc::clone(&dashboard);
+    detector.set_event_handler(move |event| {
+        shared.lock().unwrap_or_else(|e| e.into_inner()).record(&event, history.max(1));
+    });
+
+    let mut background = BackgroundDetector::new(detector);
+    // Reloads are listed with the events, since anything printed would garble the dashboard
+    let shared = Arc::clone(&dashboard);
+    let _watcher = config
+        .map(|config| {
+            config.watch(background.handle().clone(), move |message| {
+                shared.lock().unwrap_or_else(|e| e.into_inner()).note(message, history.max(1));
+            })
+        })
+        .transpose()?;
+    background.start()?;
+    // Restores the terminal on panic as well
+    let mut terminal = ratatui::init();
+    let result = run_dashboard(&mut terminal, &background, &dashboard);
+    ratatui::restore();
+    background.stop()?;
+    result
+}
+
+/// Draw until `q`, Esc or Ctrl+C; arrows, Page Up/Down, Home and End scroll the event list
+#[cfg(all(windows, feature = "tui"))]
+fn run_dashboard(
+    terminal: &mut DefaultTerminal,
+    background: &BackgroundDetector,
+    dashboard: &Mutex<Dashboard>,
+) -> Result<(), String> {
+    // Newest events scrolled out of view above the top of the list
+    let mut scroll = 0usize;
+    let mut last_sample = Instant::now();
+    loop {
+        if last_sample.elapsed() >= Duration::from_secs(1) {
+            last_sample += Duration::from_secs(1);
+            dashboard.lock().unwrap_or_else(|e| e.into_inner()).sample_rate();
+        }
+
+        let state = background.handle().get_state();
+        {
+            let dashboard = dashboard.lock().unwrap_or_else(|e| e.into_inner());
+            scroll = scroll.min(dashboard.recent.len().saturating_sub(1));
+            terminal
+                .draw(|frame| draw_dashboard(frame, &state, &dashboard, scroll))
+                .map_err(|e| format!("Failed to draw dashboard: {}", e))?;
+        }
+
+        if !terminal_event::poll(TUI_TICK).map_err(|e| format!("Failed to read terminal input: {}", e))? {
+            continue;
+        }
+        let event = terminal_event::read().map_err(|e| format!("Failed to read terminal input: {}", e))?;
+        let Event::Key(key) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Down => scroll = scroll.saturating_add(1),
+            KeyCode::Up => scroll = scroll.saturating_sub(1),
+            KeyCode::PageDown => scroll = scroll.saturating_add(10),
+            KeyCode::PageUp => scroll = scroll.saturating_sub(10),
+            KeyCode::Home => scroll = 0,
+            KeyCode::End => scroll = usize::MAX,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(all(windows, feature = "tui"))]
+fn draw_dashboard(frame: &mut Frame, state: &CursorState, dashboard: &Dashboard, scroll: usize) {
+    let [status_area, rate_area, events_area] =
+        Layout::vertical([Constraint::Length(4), Constraint::Length(6), Constraint::Min(3)]).areas(frame.area());
+
+    let clicks = |button: MouseButton| dashboard.clicks.get(&button).copied().unwrap_or(0);
+    let status = Paragraph::new(vec![
+        Line::from(format!(
+            "Position ({:.0}, {:.0})    Cursor {}    Events {}",
+            state.position.0, state.position.1, state.cursor_type, dashboard.total
+        )),
+        Line::from(format!(
+            "Clicks: left {}  right {}  middle {}",
+            clicks(MouseButton::Left),
+            clicks(MouseButton::Right),
+            clicks(MouseButton::Middle)
+        )),
+    ])
+    .block(Block::bordered().title(" luuma "));
+    frame.render_widget(status, status_area);
+
+    // Only the newest seconds that fit inside the border
+    let visible = rate_area.width.saturating_sub(2) as usize;
+    let rate: Vec<u64> = dashboard.rate.iter().skip(dashboard.rate.len().saturating_sub(visible)).copied().collect();
+    let current = rate.last().copied().unwrap_or(0);
+    let sparkline = Sparkline::default().data(&rate).block(Block::bordered().title(format!(" Events/s: {} ", current)));
+    frame.render_widget(sparkline, rate_area);
+
+    let events = List::new(dashboard.recent.iter().rev().skip(scroll).map(String::as_str)).block(
+        Block::bordered().title(format!(" Recent events ({}/{}), q to quit ", scroll, dashboard.recent.len())),
+    );
+    frame.render_widget(events, events_area);
+}
+
+/// Readable summary of session statistics
+#[cfg(all(windows, feature = "service"))]
+fn service(action: ServiceAction) -> Result<(), String> {
+    match action {
+        ServiceAction::Install { options, helper } => {
+            // A typo would otherwise only show up in helper.log, once per restart
+            Cli::try_parse_from(std::iter::once("luuma".to_string()).chain(helper.iter().cloned()))
+                .map_err(|e| format!("Invalid service command: {}", e))?;
+            let config = service_config(options, helper)?;
+            let mut arguments = vec!["service".to_string(), "run".to_string(), "--name".to_string(), config.name.clone()];
+            arguments.extend(["--log-dir".to_string(), config.log_dir.to_string_lossy().into_owned(), "--".to_string()]);
+            arguments.extend(config.helper_args.iter().cloned());
+            install_service(&config, &arguments)?;
+            eprintln!("Installed and started service {}, logging to {}", config.name, config.log_dir.display());
+            Ok(())
+        }
+        ServiceAction::Uninstall { name } => {
+            uninstall_service(&name)?;
+            eprintln!("Removed service {}", name);
+            Ok(())
+        }
+        ServiceAction::Run { options, helper } => run_service(service_config(options, helper)?),
+    }
+}
+
+#[cfg(all(windows, feature = "service"))]
+fn service_config(options: ServiceOptions, helper: Vec<String>) -> Result<ServiceConfig, String> {
+    let defaults = ServiceConfig::default();
+    // The service starts in System32, so a relative directory would end up there
+    let log_dir = std::path::absolute(options.log_dir.unwrap_or(defaults.log_dir))
+        .map_err(|e| format!("Invalid log directory: {}", e))?;
+    Ok(ServiceConfig { name: options.name, log_dir, helper_args: helper, ..defaults })
+}
+
+#[cfg(windows)]
+fn stats_text(stats: &SessionStats) -> String {
+    let per_button: Vec<String> = stats.clicks.iter().map(|(button, count)| format!("{} {}", button, count)).collect();
+    let mut cursor_types: Vec<_> = stats.cursor_type_ms.iter().collect();
+    cursor_types.sort_by(|a, b| b.1.cmp(a.1));
+
+    let mut text = format!("Started:      {}\n", stats.started);
+    text += &format!("Clicks:       {} ({})\n", stats.total_clicks(), per_button.join(", "));
+    text += &format!("Distance:     {:.0} px ({:.2} m)\n", stats.distance_px, stats.distance_m);
+    text += &format!("Active time:  {:.1} s\n", stats.active_time.as_secs_f64());
+    text += &format!("Idle time:    {:.1} s\n", stats.idle_time.as_secs_f64());
+    let longest_stretch = stats.longest_stretch.as_secs_f64() / 60.0;
+    text += &format!("Breaks:       {} (longest stretch {:.1} min)\n", stats.breaks, longest_stretch);
+    text += "Cursor types:";
+    for (cursor_type, ms) in cursor_types {
+        text += &format!("\n  {:<12}{:.1} s", cursor_type.to_string(), *ms as f64 / 1000.0);
+    }
+    let mut applications: Vec<_> = stats.applications.iter().collect();
+    applications.sort_by_key(|(_, activity)| std::cmp::Reverse(activity.active_time));
+    if !applications.is_empty() {
+        text += "\nApplications:";
+    }
+    for (application, activity) in applications {
+        text += &format!(
+            "\n  {:<24}{:.1} s, {} clicks, {:.0} px",
+            application,
+            activity.active_time.as_secs_f64(),
+            activity.clicks,
+            activity.distance_px
+        );
+    }
+    text
+}
+
+/// Settings file given with `--config`, as read at startup
+#[cfg(windows)]
+struct ConfigFile {
+    path: PathBuf,
+    config: Config,
+}
+
+#[cfg(windows)]
+impl ConfigFile {
+    fn load(path: PathBuf) -> Result<Self, String> {
+        let config = Config::from_path(&path)?;
+        Ok(Self { path, config })
+    }
+
+    /// Apply later edits to the detector behind `handle`, describing each reload to `report`
+    fn watch(
+        self,
+        handle: DetectorHandle,
+        mut report: impl FnMut(String) + Send + 'static,
+    ) -> Result<ConfigWatcher, String> {
+        let name = self.path.display().to_string();
+        ConfigWatcher::spawn(&self.path, self.config, handle, move |reload| {
+            report(match reload {
+                Ok(ConfigReload { restart_required, .. }) if restart_required.is_empty() => format!("Reloaded {}", name),
+                Ok(ConfigReload { restart_required, .. }) => {
+                    format!("Reloaded {}, restart to apply {}", name, restart_required.join(", "))
+                }
+                Err(error) => format!("Kept previous settings: {}", error),
+            })
+        })
+    }
+}
+
+/// Detector for the live commands, quiet so its log does not mix with the output
+///
+/// `--only` takes precedence over the events listed in the settings file.
+#[cfg(windows)]
+fn live_detector(mask: EventKindMask, config: Option<&ConfigFile>) -> Result<CursorDetector, String> {
+    CursorDetector::set_logging(false);
     let mut detector = CursorDetector::new();
-    
-    if let Err(error) = detector.start_monitoring() {
-        eprintln!("Error: {:?}", error);
+    if let Some(config) = config {
+        config.config.apply(&mut detector)?;
+    }
+    if mask != EventKindMask::ALL {
+        detector.set_event_mask(mask);
+    }
+    Ok(detector)
+}
+
+/// Monitor until Ctrl+C or a limit, then stop the detector so sinks flush and print a summary
+///
+/// `output` is the file the command writes, whose folder the tray icon opens. Edits to
+/// the settings file are applied while monitoring and reported on stderr.
+#[cfg(windows)]
+#[cfg_attr(not(feature = "tray"), allow(unused_variables))]
+fn run_live(
+    mut detector: CursorDetector,
+    limits: Limits,
+    output: Option<&Path>,
+    config: Option<ConfigFile>,
+) -> Result<ExitCode, String> {
+    let interrupt = Interrupt::install()?;
+    // A helper started by `luuma service` stops like this when the service does
+    #[cfg(feature = "service")]
+    stop_with_service(interrupt)?;
+
+    if let Some(max_events) = limits.max_events {
+        // Filtering rather than counting in the handler keeps events past the limit out of sinks too
+        let seen = AtomicU64::new(0);
+        detector.set_filter(move |_| {
+            let count = seen.fetch_add(1, Ordering::Relaxed) + 1;
+            if count == max_events {
+                interrupt.request_stop();
+            }
+            count <= max_events
+        });
+    }
+
+    let mut background = BackgroundDetector::new(detector);
+    let _watcher = config
+        .map(|config| config.watch(background.handle().clone(), |message| eprintln!("{}", message)))
+        .transpose()?;
+    #[cfg(feature = "tray")]
+    let _tray = limits
+        .tray
+        .then(|| {
+            // The output's folder, or the working directory, which is the log directory under `luuma service`
+            let folder = output
+                .and_then(|output| std::path::absolute(output).ok())
+                .and_then(|output| output.parent().map(Path::to_path_buf))
+                .or_else(|| std::env::current_dir().ok());
+            let config = TrayConfig { title: "luuma".to_string(), folder, hide_console: true };
+            TrayIcon::spawn(background.handle().clone(), config, move || interrupt.request_stop())
+        })
+        .transpose()?;
+    let interrupted = match limits.duration {
+        Some(duration) => {
+            background.start()?;
+            let interrupted = interrupt.wait_timeout(duration);
+            background.stop()?;
+            interrupted
+        }
+        None => background.run_until_interrupt()?,
+    };
+    // On stderr, so the summary never mixes with events on stdout
+    eprintln!("\n{}", stats_text(&background.handle().stats()));
+    Ok(if interrupted { ExitCode::from(INTERRUPTED_EXIT_CODE) } else { ExitCode::SUCCESS })
+}
+
+/// Column names of the CSV and Parquet formats
+#[cfg(windows)]
+const CSV_HEADER: &str = "timestamp_ms,kind,x,y,button,cursor_type";
+
+/// The flat columns of an event shared by the CSV and Parquet formats
+#[cfg(windows)]
+struct EventRow {
+    timestamp_ms: u64,
+    kind: String,
+    position: Option<(f64, f64)>,
+    button: Option<String>,
+    cursor_type: Option<String>,
+}
+
+#[cfg(windows)]
+impl EventRow {
+    fn new(event: &CursorEvent) -> Self {
+        Self {
+            timestamp_ms: event.timestamp().as_millis(),
+            kind: format!("{:?}", event.kind()),
+            position: event.position(),
+            button: match event {
+                CursorEvent::Click { button, .. } | CursorEvent::Release { button, .. } => Some(button.to_string()),
+                _ => None,
+            },
+            cursor_type: match event {
+                CursorEvent::Move { cursor_type, .. } => Some(cursor_type.to_string()),
+                CursorEvent::TypeChange { new_type, .. } => Some(new_type.to_string()),
+                CursorEvent::TypeDwell { cursor_type, .. } => Some(cursor_type.to_string()),
+                _ => None,
+            },
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let (x, y) = self.position.map_or((String::new(), String::new()), |(x, y)| (x.to_string(), y.to_string()));
+        format!(
+            "{},{},{},{},{},{}",
+            self.timestamp_ms,
+            self.kind,
+            x,
+            y,
+            self.button.as_deref().unwrap_or_default(),
+            self.cursor_type.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+#[cfg(windows)]
+fn print_header(format: Format) {
+    if format == Format::Csv {
+        println!("{}", CSV_HEADER);
+    }
+}
+
+#[cfg(windows)]
+fn print_event(format: Format, json: JsonFormat, event: &CursorEvent) {
+    match format {
+        Format::Text => println!("[{}] {}", event.timestamp(), describe(event)),
+        Format::Json => println!("{}", json.to_string(event)),
+        Format::Ndjson => write_line(&json.to_string(event)),
+        Format::Csv => println!("{}", EventRow::new(event).to_csv()),
+    }
+}
+
+/// Write and flush a single line, so a pipeline sees each event as it happens
+#[cfg(windows)]
+fn write_line(line: &str) {
+    let mut stdout = io::stdout().lock();
+    // A reader that went away (`| head`) ends the output quietly instead of panicking like println!
+    let _ = writeln!(stdout, "{}", line).and_then(|()| stdout.flush());
+}
+
+/// One-line summary of an event for the text format
+#[cfg(windows)]
+fn describe(event: &CursorEvent) -> String {
+    match event {
+        CursorEvent::Move { position, cursor_type, .. } => {
+            format!("Move ({:.0}, {:.0}) {}", position.0, position.1, cursor_type)
+        }
+        CursorEvent::Click { button, position, .. } => {
+            format!("Click {} ({:.0}, {:.0})", button, position.0, position.1)
+        }
+        CursorEvent::Release { button, .. } => format!("Release {}", button),
+        CursorEvent::Scroll { axis, delta, .. } => format!("Scroll {} {:+}", axis, delta),
+        CursorEvent::ScrollSession { axis, total_delta, duration, reversals, .. } => {
+            format!("ScrollSession {} {:+} {:.1}s {} reversals", axis, total_delta, duration.as_secs_f64(), reversals)
+        }
+        CursorEvent::TypeChange { new_type, .. } => format!("TypeChange {}", new_type),
+        CursorEvent::TypeDwell { cursor_type, duration, .. } => {
+            format!("TypeDwell {} {:.1}s", cursor_type, duration.as_secs_f64())
+        }
+        other => match other.position() {
+            Some((x, y)) => format!("{:?} ({:.0}, {:.0})", other.kind(), x, y),
+            None => format!("{:?}", other.kind()),
+        },
+    }
+}
+
+/// Duration from a command-line value such as `60s`, `1.5m`, `500ms` or `2h`, in seconds without a unit
+#[cfg(windows)]
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.trim().parse().map_err(|_| format!("invalid duration '{}'", value))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(format!("unknown unit '{}' in '{}', use ms, s, m or h", unit, value)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("invalid duration '{}': {}", value, e))
+}
+
+/// Grid size from a command-line value such as `64x36`
+#[cfg(windows)]
+fn parse_grid(value: &str) -> Result<(usize, usize), String> {
+    let (columns, rows) =
+        value.split_once(['x', 'X']).ok_or_else(|| format!("expected COLUMNSxROWS, got '{}'", value))?;
+    let parse = |part: &str| part.trim().parse::<usize>().ok().filter(|&count| count > 0);
+    parse(columns).zip(parse(rows)).ok_or_else(|| format!("invalid grid '{}'", value))
+}
+
+/// Event kind from a command-line name such as `clicks`, `move` or `type-change`
+#[cfg(windows)]
+fn parse_kind(name: &str) -> Result<EventKind, String> {
+    let pascal: String = name
+        .split(['-', '_'])
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_uppercase().to_string() + &chars.as_str().to_ascii_lowercase()
+            })
+        })
+        .collect();
+    // Accept plurals like `clicks` and `moves`
+    let singular = pascal.strip_suffix('s').unwrap_or(&pascal).to_string();
+    for candidate in [pascal, singular] {
+        if let Ok(kind) = serde_json::from_value(serde_json::Value::String(candidate)) {
+            return Ok(kind);
+        }
     }
+    Err(format!("unknown event kind '{}'", name))
 }
@@ -0,0 +1,88 @@
+//! Composable stages that events pass through before handlers and sinks
+
+use crate::filter::EventKindMask;
+#[cfg(windows)]
+use crate::privacy::PrivacyMasker;
+use crate::{CursorEvent, EventKind};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A stage of the processing pipeline that can drop, transform or enrich events
+///
+/// Stages run in the order they were added on the detector's processing thread,
+/// after privacy masking and before the event handler and sinks. Closures taking
+/// an event and returning `Option<CursorEvent>` are stages too.
+pub trait EventMiddleware: Send {
+    /// Process one event, returning `None` to drop it
+    fn process(&mut self, event: CursorEvent) -> Option<CursorEvent>;
+}
+
+impl<F> EventMiddleware for F
+where
+    F: FnMut(CursorEvent) -> Option<CursorEvent> + Send,
+{
+    fn process(&mut self, event: CursorEvent) -> Option<CursorEvent> {
+        self(event)
+    }
+}
+
+#[cfg(windows)]
+impl EventMiddleware for PrivacyMasker {
+    fn process(&mut self, event: CursorEvent) -> Option<CursorEvent> {
+        self.apply(event)
+    }
+}
+
+/// Drops events of a kind that arrive sooner than `interval` after the last one kept
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    interval: Duration,
+    kinds: EventKindMask,
+    last_kept: HashMap<EventKind, Instant>,
+}
+
+impl RateLimit {
+    /// Keep at most one event per kind every `interval`
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, kinds: EventKindMask::ALL, last_kept: HashMap::new() }
+    }
+
+    /// Keep at most `count` events per kind each second
+    pub fn per_second(count: u32) -> Self {
+        Self::new(Duration::from_secs(1) / count.max(1))
+    }
+
+    /// Only limit these kinds, passing every other event through
+    pub fn kinds(mut self, kinds: EventKindMask) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Whether an event of a kind may pass now, recording it when it does
+    pub(crate) fn allow(&mut self, kind: EventKind) -> bool {
+        if !self.kinds.contains(kind) {
+            return true;
+        }
+
+        let now = Instant::now();
+        match self.last_kept.get(&kind) {
+            Some(last) if now.duration_since(*last) < self.interval => false,
+            _ => {
+                self.last_kept.insert(kind, now);
+                true
+            }
+        }
+    }
+}
+
+impl EventMiddleware for RateLimit {
+    fn process(&mut self, event: CursorEvent) -> Option<CursorEvent> {
+        self.allow(event.kind()).then_some(event)
+    }
+}
+
+/// Run an event through each stage in turn, stopping at the first that drops it
+#[cfg(windows)]
+pub(crate) fn run_chain(stages: &mut [Box<dyn EventMiddleware>], event: CursorEvent) -> Option<CursorEvent> {
+    stages.iter_mut().try_fold(event, |event, stage| stage.process(event))
+}
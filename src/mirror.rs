@@ -0,0 +1,317 @@
+//! Streaming the cursor to another machine and rebuilding it there
+
+use crate::sink::EventSink;
+use crate::timing::PreciseTime;
+use crate::{CursorDetector, CursorEvent, MouseButton};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Packets waiting to be sent before new ones are dropped
+const QUEUE_CAPACITY: usize = 1024;
+
+/// How long the receiver keeps extrapolating from the last packet's velocity
+const MAX_EXTRAPOLATION: Duration = Duration::from_millis(100);
+
+/// How often blocked network calls wake up to check for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Transport between a [`MirrorSender`] and a [`MirrorReceiver`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum MirrorTransport {
+    /// One JSON datagram per packet; lowest latency, late packets are dropped
+    #[default]
+    Udp,
+    /// Newline-delimited JSON over one connection; every packet arrives, in order
+    Tcp,
+}
+
+/// What the sender transmits on every cursor event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MirrorPacket {
+    /// Increases by one per packet, so receivers can drop stale and count lost ones
+    pub sequence: u64,
+    pub position: (f64, f64),
+    /// Recent velocity in pixels per second, for extrapolating between packets
+    pub velocity: (f64, f64),
+    pub left_click: bool,
+    pub right_click: bool,
+    pub middle_click: bool,
+    /// Button pressed by the event that produced this packet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click: Option<MouseButton>,
+}
+
+/// Sink streaming moves and clicks to a [`MirrorReceiver`]
+///
+/// Packets go out from a background thread, so a slow or absent receiver never
+/// holds up the detector. Over TCP the sender reconnects after the receiver
+/// restarts.
+pub struct MirrorSender {
+    packet: MirrorPacket,
+    last_move: Option<((f64, f64), PreciseTime)>,
+    sender: SyncSender<MirrorPacket>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl MirrorSender {
+    /// Stream to a receiver at `address`, e.g. `"192.168.1.20:7878"`
+    pub fn connect(address: &str, transport: MirrorTransport) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::sync_channel::<MirrorPacket>(QUEUE_CAPACITY);
+        let address = address.to_string();
+        let worker = match transport {
+            MirrorTransport::Udp => {
+                let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("Failed to open UDP socket: {}", e))?;
+                socket.connect(&address).map_err(|e| format!("Failed to resolve {}: {}", address, e))?;
+                thread::spawn(move || {
+                    for packet in receiver {
+                        if let Ok(datagram) = serde_json::to_vec(&packet) {
+                            let _ = socket.send(&datagram);
+                        }
+                    }
+                })
+            }
+            MirrorTransport::Tcp => thread::spawn(move || {
+                let mut stream: Option<TcpStream> = None;
+                for packet in receiver {
+                    if stream.is_none() {
+                        stream = TcpStream::connect(&address)
+                            .inspect(|stream| {
+                                let _ = stream.set_nodelay(true);
+                            })
+                            .ok();
+                    }
+                    let Some(connection) = stream.as_mut() else {
+                        continue;
+                    };
+                    let Ok(mut line) = serde_json::to_vec(&packet) else {
+                        continue;
+                    };
+                    line.push(b'\n');
+                    if let Err(error) = connection.write_all(&line) {
+                        CursorDetector::log_message(&format!("Mirror connection lost: {}", error));
+                        stream = None;
+                    }
+                }
+            }),
+        };
+
+        Ok(Self {
+            packet: MirrorPacket {
+                sequence: 0,
+                position: (0.0, 0.0),
+                velocity: (0.0, 0.0),
+                left_click: false,
+                right_click: false,
+                middle_click: false,
+                click: None,
+            },
+            last_move: None,
+            sender,
+            worker: Some(worker),
+        })
+    }
+
+    fn set_button(&mut self, button: MouseButton, pressed: bool) {
+        match button {
+            MouseButton::Left => self.packet.left_click = pressed,
+            MouseButton::Right => self.packet.right_click = pressed,
+            MouseButton::Middle => self.packet.middle_click = pressed,
+        }
+    }
+}
+
+impl EventSink for MirrorSender {
+    fn handle(&mut self, event: &CursorEvent) {
+        self.packet.click = None;
+        match event {
+            CursorEvent::Move { position, .. } => {
+                let now = PreciseTime::of_event(event);
+                if let Some((last_position, last_time)) = self.last_move {
+                    let elapsed = now.duration_since(last_time).as_secs_f64();
+                    if elapsed > 0.0 {
+                        let velocity = ((position.0 - last_position.0) / elapsed, (position.1 - last_position.1) / elapsed);
+                        // Light smoothing keeps single jittery samples from throwing the receiver off
+                        self.packet.velocity = (
+                            self.packet.velocity.0 * 0.5 + velocity.0 * 0.5,
+                            self.packet.velocity.1 * 0.5 + velocity.1 * 0.5,
+                        );
+                    }
+                }
+                self.last_move = Some((*position, now));
+                self.packet.position = *position;
+            }
+            CursorEvent::Click { button, position, .. } => {
+                self.set_button(*button, true);
+                self.packet.click = Some(*button);
+                self.packet.position = *position;
+                self.packet.velocity = (0.0, 0.0);
+            }
+            CursorEvent::Release { button, .. } => self.set_button(*button, false),
+            _ => return,
+        }
+
+        self.packet.sequence += 1;
+        let _ = self.sender.try_send(self.packet);
+    }
+}
+
+impl Drop for MirrorSender {
+    fn drop(&mut self) {
+        // Replacing the sender closes the channel, which ends the worker
+        let (closed, _) = mpsc::sync_channel(0);
+        drop(std::mem::replace(&mut self.sender, closed));
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// The remote cursor as reconstructed by a [`MirrorReceiver`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RemoteCursor {
+    /// Last received position, extrapolated to now from the sender's velocity
+    pub position: (f64, f64),
+    pub left_click: bool,
+    pub right_click: bool,
+    pub middle_click: bool,
+    /// Sequence number of the newest packet applied
+    pub sequence: u64,
+    /// Packets that never arrived or arrived too late to use
+    pub lost_packets: u64,
+    /// Time since the newest packet arrived
+    #[serde(with = "crate::duration_ms")]
+    pub age: Duration,
+}
+
+/// Newest packet and receive bookkeeping shared with the listening thread
+#[derive(Debug, Default)]
+struct ReceiverState {
+    latest: Option<(MirrorPacket, Instant)>,
+    lost_packets: u64,
+    clicks: Vec<(MouseButton, (f64, f64))>,
+}
+
+impl ReceiverState {
+    fn apply(&mut self, packet: MirrorPacket) {
+        let last_sequence = self.latest.map_or(0, |(latest, _)| latest.sequence);
+        // A restarted sender counts from the beginning again
+        if packet.sequence <= last_sequence && packet.sequence != 1 {
+            self.lost_packets += 1;
+            return;
+        }
+        if packet.sequence > last_sequence + 1 && last_sequence != 0 {
+            self.lost_packets += packet.sequence - last_sequence - 1;
+        }
+        if let Some(button) = packet.click {
+            self.clicks.push((button, packet.position));
+        }
+        self.latest = Some((packet, Instant::now()));
+    }
+}
+
+/// Listens for a [`MirrorSender`] and keeps the remote cursor's state
+pub struct MirrorReceiver {
+    state: Arc<Mutex<ReceiverState>>,
+    running: Arc<AtomicBool>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl MirrorReceiver {
+    /// Listen on `address`, e.g. `"0.0.0.0:7878"`, using the sender's transport
+    pub fn bind(address: &str, transport: MirrorTransport) -> Result<Self, String> {
+        let state = Arc::new(Mutex::new(ReceiverState::default()));
+        let running = Arc::new(AtomicBool::new(true));
+        let (worker_state, worker_running) = (Arc::clone(&state), Arc::clone(&running));
+
+        let worker = match transport {
+            MirrorTransport::Udp => {
+                let socket = UdpSocket::bind(address).map_err(|e| format!("Failed to bind {}: {}", address, e))?;
+                let _ = socket.set_read_timeout(Some(POLL_INTERVAL));
+                thread::spawn(move || {
+                    let mut datagram = [0u8; 2048];
+                    while worker_running.load(Ordering::Relaxed) {
+                        let Ok(length) = socket.recv(&mut datagram) else {
+                            continue;
+                        };
+                        if let Ok(packet) = serde_json::from_slice(&datagram[..length]) {
+                            worker_state.lock().unwrap_or_else(|e| e.into_inner()).apply(packet);
+                        }
+                    }
+                })
+            }
+            MirrorTransport::Tcp => {
+                let listener = TcpListener::bind(address).map_err(|e| format!("Failed to bind {}: {}", address, e))?;
+                let _ = listener.set_nonblocking(true);
+                thread::spawn(move || {
+                    while worker_running.load(Ordering::Relaxed) {
+                        let stream = match listener.accept() {
+                            Ok((stream, _)) => stream,
+                            Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                                thread::sleep(POLL_INTERVAL);
+                                continue;
+                            }
+                            Err(_) => continue,
+                        };
+                        let _ = stream.set_nonblocking(false);
+                        let _ = stream.set_read_timeout(Some(POLL_INTERVAL));
+                        let mut reader = BufReader::new(stream);
+                        let mut line = String::new();
+                        while worker_running.load(Ordering::Relaxed) {
+                            match reader.read_line(&mut line) {
+                                Ok(0) => break,
+                                Ok(_) => {
+                                    if let Ok(packet) = serde_json::from_str(&line) {
+                                        worker_state.lock().unwrap_or_else(|e| e.into_inner()).apply(packet);
+                                    }
+                                    line.clear();
+                                }
+                                // A timeout keeps the partial line and reads on
+                                Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                                Err(_) => break,
+                            }
+                        }
+                    }
+                })
+            }
+        };
+
+        Ok(Self { state, running, worker: Some(worker) })
+    }
+
+    /// Remote cursor as of now, `None` until the first packet arrives
+    pub fn cursor(&self) -> Option<RemoteCursor> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (packet, received) = state.latest?;
+        let age = received.elapsed();
+        let ahead = age.min(MAX_EXTRAPOLATION).as_secs_f64();
+        Some(RemoteCursor {
+            position: (packet.position.0 + packet.velocity.0 * ahead, packet.position.1 + packet.velocity.1 * ahead),
+            left_click: packet.left_click,
+            right_click: packet.right_click,
+            middle_click: packet.middle_click,
+            sequence: packet.sequence,
+            lost_packets: state.lost_packets,
+            age,
+        })
+    }
+
+    /// Clicks received since the last call, oldest first
+    pub fn take_clicks(&self) -> Vec<(MouseButton, (f64, f64))> {
+        std::mem::take(&mut self.state.lock().unwrap_or_else(|e| e.into_inner()).clicks)
+    }
+}
+
+impl Drop for MirrorReceiver {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Display enumeration and per-monitor cursor tracking
+
+use crate::dpi;
+#[cfg(windows)]
+use crate::dpi::PhysicalCoordinates;
+#[cfg(windows)]
+use crate::CursorDetector;
+use crate::Rect;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+#[cfg(windows)]
+use windows::core::BOOL;
+#[cfg(windows)]
+use windows::Win32::Foundation::{LPARAM, POINT, RECT};
+#[cfg(windows)]
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplayMonitors, GetMonitorInfoW, MonitorFromPoint, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+    MONITOR_DEFAULTTONULL,
+};
+#[cfg(windows)]
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::MONITORINFOF_PRIMARY;
+
+/// A display attached to the virtual desktop
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MonitorInfo {
+    /// Identifier used as `monitor_id` in events (valid until the display configuration changes)
+    pub id: u64,
+    /// Device name such as `\\.\DISPLAY1`
+    pub name: String,
+    /// Monitor rectangle in virtual desktop coordinates
+    pub bounds: Rect,
+    /// Area not covered by the taskbar and docked toolbars
+    pub work_area: Rect,
+    /// Whether this is the primary monitor
+    pub primary: bool,
+    /// Effective DPI (96 at 100% scaling)
+    pub dpi: u32,
+}
+
+/// Shorthand for [`MonitorInfo`]
+pub type Monitor = MonitorInfo;
+
+impl MonitorInfo {
+    /// Convert a virtual desktop position into coordinates relative to this monitor
+    pub fn to_local(&self, position: (f64, f64)) -> (f64, f64) {
+        (position.0 - self.bounds.left as f64, position.1 - self.bounds.top as f64)
+    }
+
+    /// Display scaling factor (1.5 on a 150% display)
+    pub fn scale_factor(&self) -> f64 {
+        dpi::scale_for_dpi(self.dpi)
+    }
+
+    /// Convert a physical virtual desktop position on this monitor to DIPs
+    pub fn to_dips(&self, position: (f64, f64)) -> (f64, f64) {
+        dpi::physical_to_dips(position, self.origin(), self.scale_factor())
+    }
+
+    /// Convert a DIP position on this monitor back to physical pixels
+    pub fn from_dips(&self, position: (f64, f64)) -> (f64, f64) {
+        dpi::dips_to_physical(position, self.origin(), self.scale_factor())
+    }
+
+    fn origin(&self) -> (f64, f64) {
+        (self.bounds.left as f64, self.bounds.top as f64)
+    }
+
+    #[cfg(windows)]
+    unsafe fn query(handle: HMONITOR) -> Option<Self> {
+        let mut info = MONITORINFOEXW::default();
+        info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        if !GetMonitorInfoW(handle, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO).as_bool() {
+            return None;
+        }
+
+        let name_end = info.szDevice.iter().position(|&c| c == 0).unwrap_or(info.szDevice.len());
+        let (mut dpi_x, mut dpi_y) = (dpi::BASE_DPI, dpi::BASE_DPI);
+        let _ = GetDpiForMonitor(handle, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y);
+        Some(Self {
+            id: handle.0 as usize as u64,
+            name: String::from_utf16_lossy(&info.szDevice[..name_end]),
+            bounds: to_rect(info.monitorInfo.rcMonitor),
+            work_area: to_rect(info.monitorInfo.rcWork),
+            primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+            dpi: dpi_x,
+        })
+    }
+}
+
+/// Position of a point relative to the monitor that contains it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MonitorPosition {
+    pub monitor_id: u64,
+    /// Coordinates relative to the monitor's top-left corner
+    pub position: (f64, f64),
+}
+
+/// List every monitor in enumeration order
+#[cfg(windows)]
+pub fn list_monitors() -> Vec<MonitorInfo> {
+    unsafe extern "system" fn collect(handle: HMONITOR, _: HDC, _: *mut RECT, data: LPARAM) -> BOOL {
+        let monitors = &mut *(data.0 as *mut Vec<MonitorInfo>);
+        if let Some(monitor) = MonitorInfo::query(handle) {
+            monitors.push(monitor);
+        }
+        BOOL(1)
+    }
+
+    let _physical = PhysicalCoordinates::enter();
+    let mut monitors: Vec<MonitorInfo> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(None, None, Some(collect), LPARAM(&mut monitors as *mut _ as isize));
+    }
+    monitors
+}
+
+/// Find the monitor containing a virtual desktop point
+#[cfg(windows)]
+pub fn monitor_at(position: (f64, f64)) -> Option<MonitorInfo> {
+    let _physical = PhysicalCoordinates::enter();
+    unsafe {
+        let handle = MonitorFromPoint(POINT { x: position.0 as i32, y: position.1 as i32 }, MONITOR_DEFAULTTONULL);
+        if handle.is_invalid() {
+            return None;
+        }
+        MonitorInfo::query(handle)
+    }
+}
+
+#[cfg(windows)]
+fn to_rect(rect: RECT) -> Rect {
+    Rect::new(rect.left, rect.top, rect.right, rect.bottom)
+}
+
+/// Caches the virtual desktop bounds so validating a sample needs no system call
+#[cfg(windows)]
+pub(crate) struct VirtualScreenCache {
+    bounds: Option<(Rect, Instant)>,
+}
+
+#[cfg(windows)]
+impl VirtualScreenCache {
+    /// Re-query at least this often so display configuration changes are noticed
+    const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub(crate) fn new() -> Self {
+        Self { bounds: None }
+    }
+
+    pub(crate) fn bounds(&mut self) -> Rect {
+        match self.bounds {
+            Some((bounds, checked)) if checked.elapsed() < Self::REFRESH_INTERVAL => bounds,
+            _ => {
+                let bounds = CursorDetector::get_virtual_screen_bounds();
+                self.bounds = Some((bounds, Instant::now()));
+                bounds
+            }
+        }
+    }
+}
+
+/// Caches the monitor under the cursor so most moves need no system call
+#[cfg(windows)]
+pub(crate) struct MonitorTracker {
+    current: Option<(MonitorInfo, Instant)>,
+    last_id: Option<u64>,
+}
+
+#[cfg(windows)]
+impl MonitorTracker {
+    /// Re-query at least this often so display configuration changes are noticed
+    const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub(crate) fn new() -> Self {
+        Self { current: None, last_id: None }
+    }
+
+    /// Resolve the monitor for a position, also reporting a crossing from another monitor
+    pub(crate) fn update(&mut self, position: (f64, f64)) -> (Option<MonitorPosition>, Option<MonitorChange>) {
+        let cached = self.current.as_ref().filter(|(monitor, checked)| {
+            monitor.bounds.contains(position.0, position.1) && checked.elapsed() < Self::REFRESH_INTERVAL
+        });
+        if let Some((monitor, _)) = cached {
+            return (Some(MonitorPosition { monitor_id: monitor.id, position: monitor.to_local(position) }), None);
+        }
+
+        self.current = None;
+        let Some(monitor) = monitor_at(position) else {
+            return (None, None);
+        };
+
+        let located = MonitorPosition { monitor_id: monitor.id, position: monitor.to_local(position) };
+        // The first sample only establishes the starting monitor
+        let change = match self.last_id {
+            Some(previous) if previous != monitor.id => Some(MonitorChange { from: previous, to: monitor.id }),
+            _ => None,
+        };
+        self.last_id = Some(monitor.id);
+        self.current = Some((monitor, Instant::now()));
+        (Some(located), change)
+    }
+}
+
+/// Transition between monitors detected by the tracker
+#[cfg(windows)]
+pub(crate) struct MonitorChange {
+    pub(crate) from: u64,
+    pub(crate) to: u64,
+}
@@ -0,0 +1,347 @@
+//! Sink broadcasting events to OBS Studio through obs-websocket
+
+use crate::sink::EventSink;
+use crate::websocket::base64;
+use crate::{CursorDetector, CursorEvent, EventKind};
+use serde_json::{json, Value};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Networking::WinHttp::{
+    WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest, WinHttpReceiveResponse, WinHttpSendRequest,
+    WinHttpSetOption, WinHttpSetTimeouts, WinHttpWebSocketClose, WinHttpWebSocketCompleteUpgrade,
+    WinHttpWebSocketReceive, WinHttpWebSocketSend, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY, WINHTTP_FLAG_SECURE,
+    WINHTTP_OPEN_REQUEST_FLAGS, WINHTTP_OPTION_UPGRADE_TO_WEB_SOCKET, WINHTTP_WEB_SOCKET_BUFFER_TYPE,
+    WINHTTP_WEB_SOCKET_CLOSE_BUFFER_TYPE, WINHTTP_WEB_SOCKET_SUCCESS_CLOSE_STATUS,
+    WINHTTP_WEB_SOCKET_UTF8_FRAGMENT_BUFFER_TYPE, WINHTTP_WEB_SOCKET_UTF8_MESSAGE_BUFFER_TYPE,
+};
+use windows::Win32::Security::Cryptography::{BCryptHash, BCRYPT_SHA256_ALG_HANDLE};
+
+/// Events waiting for the connection before new ones are dropped
+const QUEUE_CAPACITY: usize = 256;
+
+/// Parsed obs-websocket address
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ObsUrl {
+    secure: bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl ObsUrl {
+    /// Split a `ws://` or `wss://` URL into its parts
+    fn parse(url: &str) -> Result<Self, String> {
+        let (secure, rest) = if let Some(rest) = url.strip_prefix("wss://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("ws://") {
+            (false, rest)
+        } else {
+            return Err(format!("Unsupported OBS WebSocket URL scheme: {}", url));
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+
+        // obs-websocket listens on 4455 unless configured otherwise
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse::<u16>().map_err(|_| format!("Invalid OBS WebSocket port: {}", port))?;
+                (host, port)
+            }
+            None => (authority, 4455),
+        };
+
+        if host.is_empty() {
+            return Err(format!("Missing OBS WebSocket host: {}", url));
+        }
+
+        Ok(Self { secure, host: host.to_string(), port, path: path.to_string() })
+    }
+}
+
+/// Sink that sends events to OBS as `BroadcastCustomEvent` requests
+///
+/// Every connected obs-websocket client, such as a browser source overlay
+/// listening for `CustomEvent`, receives `{"source": "luuma_cursor_helper",
+/// "event": ...}` as it happens, so overlays stay in step with what OBS is
+/// recording. Events are sent from a background thread that reconnects after
+/// OBS restarts; events arriving while OBS is unreachable are dropped. Limit
+/// moves with a [`SinkFilter`](crate::SinkFilter) rate limit, since OBS relays
+/// each one to every client.
+pub struct ObsSink {
+    url: ObsUrl,
+    password: Option<String>,
+    kinds: Option<Vec<EventKind>>,
+    reconnect_delay: Duration,
+    sender: Option<SyncSender<CursorEvent>>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl ObsSink {
+    /// Create a sink for an obs-websocket server, e.g. `ws://localhost:4455`
+    pub fn new(url: &str) -> Result<Self, String> {
+        Ok(Self {
+            url: ObsUrl::parse(url)?,
+            password: None,
+            kinds: None,
+            reconnect_delay: Duration::from_secs(5),
+            sender: None,
+            worker: None,
+        })
+    }
+
+    /// Server password set in OBS under WebSocket Server Settings
+    pub fn with_password(mut self, password: &str) -> Self {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// Only forward events of the given kinds (all kinds by default)
+    pub fn with_kinds(mut self, kinds: &[EventKind]) -> Self {
+        self.kinds = Some(kinds.to_vec());
+        self
+    }
+
+    /// How long to wait before connecting again after a failure
+    pub fn with_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Start the sending thread on first use
+    fn sender(&mut self) -> &SyncSender<CursorEvent> {
+        if self.sender.is_none() {
+            let (tx, rx) = mpsc::sync_channel::<CursorEvent>(QUEUE_CAPACITY);
+            let url = self.url.clone();
+            let password = self.password.clone();
+            let reconnect_delay = self.reconnect_delay;
+            self.worker = Some(thread::spawn(move || {
+                let mut connection: Option<ObsConnection> = None;
+                let mut next_attempt = Instant::now();
+                for (request_id, event) in rx.into_iter().enumerate() {
+                    if connection.is_none() {
+                        if Instant::now() < next_attempt {
+                            continue;
+                        }
+                        match ObsConnection::open(&url, password.as_deref()) {
+                            Ok(opened) => connection = Some(opened),
+                            Err(error) => {
+                                CursorDetector::log_message(&format!("OBS connection failed: {}", error));
+                                next_attempt = Instant::now() + reconnect_delay;
+                                continue;
+                            }
+                        }
+                    }
+
+                    if let Some(open) = &connection {
+                        if let Err(error) = open.broadcast(&event, request_id) {
+                            CursorDetector::log_message(&format!("OBS connection lost: {}", error));
+                            connection = None;
+                            next_attempt = Instant::now() + reconnect_delay;
+                        }
+                    }
+                }
+            }));
+            self.sender = Some(tx);
+        }
+        self.sender.as_ref().expect("OBS worker started")
+    }
+}
+
+impl EventSink for ObsSink {
+    fn handle(&mut self, event: &CursorEvent) {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&event.kind()) {
+                return;
+            }
+        }
+        // Never block the processing thread on a slow or absent OBS
+        let _ = self.sender().try_send(event.clone());
+    }
+}
+
+impl Drop for ObsSink {
+    fn drop(&mut self) {
+        // Closing the channel makes the worker close the socket and exit
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// An identified obs-websocket session
+struct ObsConnection {
+    session: *mut std::ffi::c_void,
+    connection: *mut std::ffi::c_void,
+    socket: *mut std::ffi::c_void,
+}
+
+impl ObsConnection {
+    /// Connect, upgrade to a WebSocket and complete the Hello/Identify handshake
+    fn open(url: &ObsUrl, password: Option<&str>) -> Result<Self, String> {
+        let mut opened = Self {
+            session: std::ptr::null_mut(),
+            connection: std::ptr::null_mut(),
+            socket: std::ptr::null_mut(),
+        };
+        unsafe {
+            opened.session = WinHttpOpen(
+                &HSTRING::from("luuma_cursor_helper"),
+                WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                0,
+            );
+            if opened.session.is_null() {
+                return Err(format!("WinHttpOpen failed: {}", windows::core::Error::from_win32()));
+            }
+            let _ = WinHttpSetTimeouts(opened.session, 5000, 5000, 5000, 5000);
+
+            opened.connection = WinHttpConnect(opened.session, &HSTRING::from(url.host.as_str()), url.port, 0);
+            if opened.connection.is_null() {
+                return Err(format!("WinHttpConnect failed: {}", windows::core::Error::from_win32()));
+            }
+
+            let flags = if url.secure { WINHTTP_FLAG_SECURE } else { WINHTTP_OPEN_REQUEST_FLAGS(0) };
+            let request = WinHttpOpenRequest(
+                opened.connection,
+                &HSTRING::from("GET"),
+                &HSTRING::from(url.path.as_str()),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                std::ptr::null(),
+                flags,
+            );
+            if request.is_null() {
+                return Err(format!("WinHttpOpenRequest failed: {}", windows::core::Error::from_win32()));
+            }
+
+            let upgraded = WinHttpSetOption(Some(request as *const _), WINHTTP_OPTION_UPGRADE_TO_WEB_SOCKET, None)
+                .and_then(|_| WinHttpSendRequest(request, None, None, 0, 0, 0))
+                .and_then(|_| WinHttpReceiveResponse(request, std::ptr::null_mut()));
+            if upgraded.is_ok() {
+                opened.socket = WinHttpWebSocketCompleteUpgrade(request, None);
+            }
+            let _ = WinHttpCloseHandle(request);
+            if let Err(error) = upgraded {
+                return Err(format!("WebSocket upgrade failed: {}", error));
+            }
+            if opened.socket.is_null() {
+                return Err(format!("WebSocket upgrade failed: {}", windows::core::Error::from_win32()));
+            }
+        }
+
+        // Hello carries the challenge when the server requires a password
+        let hello = opened.receive()?;
+        if hello["op"] != 0 {
+            return Err(format!("Expected Hello from OBS, got {}", hello));
+        }
+        let mut identify = json!({ "rpcVersion": 1, "eventSubscriptions": 0 });
+        if let Some(auth) = hello["d"]["authentication"].as_object() {
+            let password = password.ok_or("OBS requires a password")?;
+            let challenge = auth.get("challenge").and_then(Value::as_str).unwrap_or_default();
+            let salt = auth.get("salt").and_then(Value::as_str).unwrap_or_default();
+            let secret = base64(&sha256(format!("{}{}", password, salt).as_bytes())?);
+            identify["authentication"] = json!(base64(&sha256(format!("{}{}", secret, challenge).as_bytes())?));
+        }
+        opened.send(&json!({ "op": 1, "d": identify }))?;
+
+        let identified = opened.receive()?;
+        if identified["op"] != 2 {
+            return Err("OBS rejected the identification, check the password".to_string());
+        }
+        Ok(opened)
+    }
+
+    /// Broadcast an event and wait for OBS to acknowledge the request
+    fn broadcast(&self, event: &CursorEvent, request_id: usize) -> Result<(), String> {
+        let request = json!({
+            "op": 6,
+            "d": {
+                "requestType": "BroadcastCustomEvent",
+                "requestId": request_id.to_string(),
+                "requestData": { "eventData": { "source": "luuma_cursor_helper", "event": event } },
+            },
+        });
+        self.send(&request)?;
+
+        // Reading every response keeps the socket from backing up
+        let response = self.receive()?;
+        let status = &response["d"]["requestStatus"];
+        if status["result"] == false {
+            CursorDetector::log_message(&format!("OBS refused custom event: {}", status["comment"]));
+        }
+        Ok(())
+    }
+
+    fn send(&self, message: &Value) -> Result<(), String> {
+        let text = message.to_string();
+        let error = unsafe {
+            WinHttpWebSocketSend(self.socket, WINHTTP_WEB_SOCKET_UTF8_MESSAGE_BUFFER_TYPE, Some(text.as_bytes()))
+        };
+        if error != 0 {
+            return Err(format!("WebSocket send failed with error {}", error));
+        }
+        Ok(())
+    }
+
+    /// Read one complete text message and parse it
+    fn receive(&self) -> Result<Value, String> {
+        let mut message = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let mut read = 0;
+            let mut buffer_type = WINHTTP_WEB_SOCKET_BUFFER_TYPE::default();
+            let error = unsafe {
+                WinHttpWebSocketReceive(
+                    self.socket,
+                    chunk.as_mut_ptr() as *mut _,
+                    chunk.len() as u32,
+                    &mut read,
+                    &mut buffer_type,
+                )
+            };
+            if error != 0 {
+                return Err(format!("WebSocket receive failed with error {}", error));
+            }
+            message.extend_from_slice(&chunk[..read as usize]);
+            match buffer_type {
+                WINHTTP_WEB_SOCKET_UTF8_MESSAGE_BUFFER_TYPE => break,
+                WINHTTP_WEB_SOCKET_UTF8_FRAGMENT_BUFFER_TYPE => {}
+                WINHTTP_WEB_SOCKET_CLOSE_BUFFER_TYPE => return Err("OBS closed the connection".to_string()),
+                _ => return Err("Unexpected binary message from OBS".to_string()),
+            }
+        }
+        serde_json::from_slice(&message).map_err(|e| format!("Invalid message from OBS: {}", e))
+    }
+}
+
+impl Drop for ObsConnection {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.socket.is_null() {
+                let _ = WinHttpWebSocketClose(self.socket, WINHTTP_WEB_SOCKET_SUCCESS_CLOSE_STATUS.0 as u16, None, 0);
+                let _ = WinHttpCloseHandle(self.socket);
+            }
+            if !self.connection.is_null() {
+                let _ = WinHttpCloseHandle(self.connection);
+            }
+            if !self.session.is_null() {
+                let _ = WinHttpCloseHandle(self.session);
+            }
+        }
+    }
+}
+
+fn sha256(data: &[u8]) -> Result<[u8; 32], String> {
+    let mut digest = [0u8; 32];
+    unsafe { BCryptHash(BCRYPT_SHA256_ALG_HANDLE, None, data, &mut digest) }
+        .ok()
+        .map_err(|e| format!("SHA-256 failed: {}", e))?;
+    Ok(digest)
+}
@@ -0,0 +1,260 @@
+//! On-screen display of recent input, for streams and screencasts
+
+use crate::monitor::list_monitors;
+use crate::overlay::{Overlay, OverlayCanvas, OverlayConfig, Rgba, TextMask};
+use crate::sink::EventSink;
+use crate::{CursorEvent, MouseButton, Rect};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Corner of the primary monitor's work area the display sits in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum OsdPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Colors and sizes of the display entries
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OsdTheme {
+    pub background: Rgba,
+    pub foreground: Rgba,
+    /// Glyph height in pixels
+    pub font_height: i32,
+    /// Space between the text and the edge of its box
+    pub padding: i32,
+}
+
+impl Default for OsdTheme {
+    fn default() -> Self {
+        Self {
+            background: Rgba::new(20, 20, 20, 200),
+            foreground: Rgba::new(255, 255, 255, 255),
+            font_height: 28,
+            padding: 12,
+        }
+    }
+}
+
+/// Placement, look and timing of an [`InputDisplay`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct OsdConfig {
+    pub position: OsdPosition,
+    pub theme: OsdTheme,
+    /// Distance in pixels from the corner of the work area
+    pub margin: i32,
+    /// Entries shown at once, older ones are dropped first
+    pub max_entries: usize,
+    /// How long an entry stays fully visible
+    #[serde(with = "crate::duration_ms")]
+    pub display_time: Duration,
+    /// How long an entry takes to fade out afterwards
+    #[serde(with = "crate::duration_ms")]
+    pub fade: Duration,
+    /// Frames drawn per second while entries fade
+    pub frame_rate: u32,
+}
+
+impl Default for OsdConfig {
+    fn default() -> Self {
+        Self {
+            position: OsdPosition::default(),
+            theme: OsdTheme::default(),
+            margin: 24,
+            max_entries: 5,
+            display_time: Duration::from_millis(2000),
+            fade: Duration::from_millis(500),
+            frame_rate: 30,
+        }
+    }
+}
+
+/// A line of the display
+struct Entry {
+    label: String,
+    /// Times the label was repeated while still visible
+    count: u32,
+    shown: Instant,
+    /// Rendered text, created on the overlay thread when first drawn
+    mask: Option<TextMask>,
+}
+
+impl Entry {
+    fn text(&self) -> String {
+        if self.count > 1 {
+            format!("{} \u{00D7}{}", self.label, self.count)
+        } else {
+            self.label.clone()
+        }
+    }
+}
+
+/// What the sink side shares with the overlay's draw callback
+struct OsdState {
+    config: OsdConfig,
+    enabled: bool,
+    entries: VecDeque<Entry>,
+    /// Area painted in the previous frame, cleared before the next one
+    painted: Option<Rect>,
+    /// Whether entries changed since the last frame
+    dirty: bool,
+}
+
+impl OsdState {
+    fn push(&mut self, label: String) {
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        let display_time = self.config.display_time;
+        match self.entries.back_mut() {
+            // Repeats of a still visible entry count up instead of stacking
+            Some(last) if last.label == label && now.duration_since(last.shown) < display_time => {
+                last.count += 1;
+                last.shown = now;
+                last.mask = None;
+            }
+            _ => {
+                self.entries.push_back(Entry { label, count: 1, shown: now, mask: None });
+                while self.entries.len() > self.config.max_entries.max(1) {
+                    self.entries.pop_front();
+                }
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Repaint the entries, returning whether the frame differs from the last one
+    fn draw(&mut self, canvas: &mut OverlayCanvas) -> bool {
+        let now = Instant::now();
+        let visible_for = self.config.display_time + self.config.fade;
+        let count = self.entries.len();
+        self.entries.retain(|entry| now.duration_since(entry.shown) < visible_for);
+        if !self.enabled {
+            self.entries.clear();
+        }
+        let fading = self.entries.iter().any(|entry| now.duration_since(entry.shown) >= self.config.display_time);
+        if !self.dirty && !fading && self.entries.len() == count {
+            return false;
+        }
+        self.dirty = false;
+
+        if let Some(area) = self.painted.take() {
+            canvas.clear_rect(area);
+        }
+
+        let OsdConfig { position, theme, margin, .. } = self.config;
+        let bounds = canvas.bounds();
+        let from_top = matches!(position, OsdPosition::TopLeft | OsdPosition::TopRight);
+        let from_left = matches!(position, OsdPosition::TopLeft | OsdPosition::BottomLeft);
+        let mut edge = if from_top { bounds.top + margin } else { bounds.bottom - margin };
+
+        // The newest entry sits in the corner and older ones stack away from it
+        for entry in self.entries.iter_mut().rev() {
+            if entry.mask.is_none() {
+                match TextMask::render(&entry.text(), theme.font_height) {
+                    Ok(mask) => entry.mask = Some(mask),
+                    Err(error) => {
+                        crate::CursorDetector::log_message(&error);
+                        continue;
+                    }
+                }
+            }
+            let Some(mask) = entry.mask.as_ref() else {
+                continue;
+            };
+
+            let (width, height) = (mask.width() + 2 * theme.padding, mask.height() + 2 * theme.padding);
+            let left = if from_left { bounds.left + margin } else { bounds.right - margin - width };
+            let top = if from_top { edge } else { edge - height };
+            edge = if from_top { top + height + theme.padding } else { top - theme.padding };
+
+            let age = now.duration_since(entry.shown).saturating_sub(self.config.display_time);
+            let opacity = 1.0 - age.as_secs_f64() / self.config.fade.as_secs_f64().max(0.001);
+            let area = Rect::new(left, top, left + width, top + height);
+            canvas.fill_rect(area, theme.background.with_opacity(opacity));
+            canvas.draw_text(left + theme.padding, top + theme.padding, mask, theme.foreground.with_opacity(opacity));
+
+            self.painted = Some(match self.painted {
+                Some(painted) => Rect::new(
+                    painted.left.min(area.left),
+                    painted.top.min(area.top),
+                    painted.right.max(area.right),
+                    painted.bottom.max(area.bottom),
+                ),
+                None => area,
+            });
+        }
+        true
+    }
+}
+
+/// Shows recent clicks as labels that fade out, in a corner of the primary monitor
+///
+/// Attach it as a sink like [`ClickVisualizer`](crate::ClickVisualizer) and push
+/// any other input, e.g. key strokes read by the application, with
+/// [`show`](Self::show). The overlay closes when the last clone is dropped.
+#[derive(Clone)]
+pub struct InputDisplay {
+    state: Arc<Mutex<OsdState>>,
+    overlay: Arc<Overlay>,
+}
+
+impl InputDisplay {
+    /// Create the overlay over the primary monitor's work area
+    pub fn spawn(config: OsdConfig) -> Result<Self, String> {
+        let state = Arc::new(Mutex::new(OsdState {
+            config,
+            enabled: true,
+            entries: VecDeque::new(),
+            painted: None,
+            dirty: false,
+        }));
+
+        let bounds = list_monitors().into_iter().find(|monitor| monitor.primary).map(|monitor| monitor.work_area);
+        let draw_state = Arc::clone(&state);
+        let overlay = Overlay::spawn(OverlayConfig { bounds, frame_rate: config.frame_rate }, move |canvas| {
+            draw_state.lock().unwrap_or_else(|e| e.into_inner()).draw(canvas)
+        })?;
+
+        Ok(Self { state, overlay: Arc::new(overlay) })
+    }
+
+    /// Add an entry, counting it up when it repeats the newest visible one
+    pub fn show(&self, label: impl Into<String>) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).push(label.into());
+    }
+
+    /// Turn the display on or off, dropping the entries shown
+    pub fn set_enabled(&self, enabled: bool) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).enabled = enabled;
+    }
+
+    /// Whether entries are shown
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    /// Raw `HWND` of the overlay, e.g. to exclude it from a screen capture
+    pub fn hwnd(&self) -> usize {
+        self.overlay.hwnd()
+    }
+}
+
+impl EventSink for InputDisplay {
+    fn handle(&mut self, event: &CursorEvent) {
+        let label = match event {
+            CursorEvent::Click { button: MouseButton::Left, .. } => "Left click",
+            CursorEvent::Click { button: MouseButton::Right, .. } => "Right click",
+            CursorEvent::Click { button: MouseButton::Middle, .. } => "Middle click",
+            CursorEvent::Marker { label, .. } => label.as_str(),
+            _ => return,
+        };
+        self.show(label);
+    }
+}
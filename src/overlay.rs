@@ -0,0 +1,495 @@
+//! Click-through topmost overlay windows for drawing over the desktop
+
+use crate::dpi::PhysicalCoordinates;
+use crate::Rect;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc;
+use std::thread;
+use windows::core::w;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, SIZE, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    CreateCompatibleDC, CreateDIBSection, CreateFontW, DeleteDC, DeleteObject, GdiFlush, GetTextExtentPoint32W,
+    SelectObject, SetBkMode, SetTextColor, TextOutW, AC_SRC_ALPHA, AC_SRC_OVER, ANTIALIASED_QUALITY, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+    BLENDFUNCTION, CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET, DIB_RGB_COLORS, FW_SEMIBOLD, HBITMAP, HDC,
+    OUT_DEFAULT_PRECIS, TRANSPARENT,
+};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, KillTimer, PostThreadMessageW,
+    RegisterClassW, SetTimer, ShowWindowAsync, UpdateLayeredWindow, MSG, SW_HIDE,
+    SW_SHOWNOACTIVATE, ULW_ALPHA, WM_QUIT, WM_TIMER, WNDCLASSW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW,
+    WS_EX_TOPMOST, WS_EX_TRANSPARENT, WS_POPUP,
+};
+
+/// Timer id driving the overlay's frames
+const FRAME_TIMER: usize = 1;
+
+/// A straight (not premultiplied) color with alpha
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// The same color with its alpha scaled by `opacity` (0.0..=1.0)
+    pub fn with_opacity(self, opacity: f64) -> Self {
+        Self { a: (self.a as f64 * opacity.clamp(0.0, 1.0)).round() as u8, ..self }
+    }
+}
+
+/// Where an overlay is placed and how often it redraws
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct OverlayConfig {
+    /// Screen area covered in physical pixels, the whole virtual desktop when `None`
+    pub bounds: Option<Rect>,
+    /// Frames drawn per second
+    pub frame_rate: u32,
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        Self { bounds: None, frame_rate: 60 }
+    }
+}
+
+/// Pixel buffer of an overlay frame, addressed in screen coordinates
+///
+/// Pixels are premultiplied BGRA, one `u32` each, in top-down rows. The buffer
+/// keeps its contents between frames.
+pub struct OverlayCanvas<'a> {
+    pixels: &'a mut [u32],
+    bounds: Rect,
+}
+
+impl OverlayCanvas<'_> {
+    /// Screen area the canvas covers
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// Raw premultiplied BGRA pixels, `bounds().width()` per row
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        self.pixels
+    }
+
+    /// Make every pixel fully transparent
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    /// Blend a color over the pixel at a screen position, ignoring positions off the canvas
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Rgba) {
+        if !self.bounds.contains(x as f64, y as f64) || color.a == 0 {
+            return;
+        }
+        let index = (y - self.bounds.top) as usize * self.bounds.width() as usize + (x - self.bounds.left) as usize;
+        let Some(pixel) = self.pixels.get_mut(index) else {
+            return;
+        };
+
+        // Source-over in premultiplied space
+        let destination = *pixel;
+        let alpha = color.a as u32;
+        let inverse = 255 - alpha;
+        let channel = |shift: u32, source: u8| {
+            ((source as u32 * alpha + ((destination >> shift) & 0xFF) * inverse) / 255) << shift
+        };
+        *pixel = channel(24, 255) | channel(16, color.r) | channel(8, color.g) | channel(0, color.b);
+    }
+
+    /// Blend a color over a screen rectangle
+    pub fn fill_rect(&mut self, rect: Rect, color: Rgba) {
+        for y in rect.top.max(self.bounds.top)..rect.bottom.min(self.bounds.bottom) {
+            for x in rect.left.max(self.bounds.left)..rect.right.min(self.bounds.right) {
+                self.blend_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Make a screen rectangle fully transparent again
+    pub fn clear_rect(&mut self, rect: Rect) {
+        self.set_rect(rect, Rgba::new(0, 0, 0, 0));
+    }
+
+    /// Replace the pixel at a screen position with a color, without blending
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Rgba) {
+        if !self.bounds.contains(x as f64, y as f64) {
+            return;
+        }
+        let index = (y - self.bounds.top) as usize * self.bounds.width() as usize + (x - self.bounds.left) as usize;
+        if let Some(pixel) = self.pixels.get_mut(index) {
+            *pixel = premultiply(color);
+        }
+    }
+
+    /// Replace every pixel of a screen rectangle with a color, without blending
+    pub fn set_rect(&mut self, rect: Rect, color: Rgba) {
+        let value = premultiply(color);
+        let width = self.bounds.width() as usize;
+        let (left, right) = (rect.left.max(self.bounds.left), rect.right.min(self.bounds.right));
+        if left >= right {
+            return;
+        }
+        for y in rect.top.max(self.bounds.top)..rect.bottom.min(self.bounds.bottom) {
+            let row = (y - self.bounds.top) as usize * width;
+            let start = row + (left - self.bounds.left) as usize;
+            let end = row + (right - self.bounds.left) as usize;
+            self.pixels[start..end].fill(value);
+        }
+    }
+
+    /// Blend an anti-aliased disc centered on a screen position
+    pub fn fill_circle(&mut self, center: (f64, f64), radius: f64, color: Rgba) {
+        self.draw_circle(center, radius + 0.5, color, |distance| radius + 0.5 - distance);
+    }
+
+    /// Blend an anti-aliased ring of `width` pixels centered on a screen position
+    pub fn stroke_circle(&mut self, center: (f64, f64), radius: f64, width: f64, color: Rgba) {
+        let half = width / 2.0;
+        self.draw_circle(center, radius + half + 0.5, color, |distance| half + 0.5 - (distance - radius).abs());
+    }
+
+    /// Blend `color` through a text mask with its top-left corner at a screen position
+    pub fn draw_text(&mut self, left: i32, top: i32, text: &TextMask, color: Rgba) {
+        for row in 0..text.height {
+            for column in 0..text.width {
+                let covered = text.coverage[(row * text.width + column) as usize];
+                if covered > 0 {
+                    self.blend_pixel(left + column, top + row, color.with_opacity(covered as f64 / 255.0));
+                }
+            }
+        }
+    }
+
+    /// Blend `color` over the pixels within `extent` of `center`, weighted by the clamped coverage of each
+    fn draw_circle<F>(&mut self, center: (f64, f64), extent: f64, color: Rgba, coverage: F)
+    where
+        F: Fn(f64) -> f64,
+    {
+        let area = circle_bounds(center, extent);
+        for y in area.top.max(self.bounds.top)..area.bottom.min(self.bounds.bottom) {
+            for x in area.left.max(self.bounds.left)..area.right.min(self.bounds.right) {
+                let distance = (x as f64 + 0.5 - center.0).hypot(y as f64 + 0.5 - center.1);
+                let covered = coverage(distance).clamp(0.0, 1.0);
+                if covered > 0.0 {
+                    self.blend_pixel(x, y, color.with_opacity(covered));
+                }
+            }
+        }
+    }
+}
+
+/// A line of anti-aliased text rasterized once and drawn in any color
+///
+/// Rendering goes through GDI, so create masks when the text changes rather
+/// than on every frame.
+#[derive(Debug, Clone)]
+pub struct TextMask {
+    width: i32,
+    height: i32,
+    /// Glyph coverage per pixel, 0 to 255, in top-down rows
+    coverage: Vec<u8>,
+}
+
+impl TextMask {
+    /// Rasterize `text` in the system UI font with glyphs `height` pixels tall
+    pub fn render(text: &str, height: i32) -> Result<Self, String> {
+        let wide: Vec<u16> = text.encode_utf16().collect();
+        unsafe {
+            let memory_dc = CreateCompatibleDC(None);
+            // Grayscale anti-aliasing, since ClearType fringes need an opaque background
+            let font = CreateFontW(
+                -height.max(1),
+                0,
+                0,
+                0,
+                FW_SEMIBOLD.0 as i32,
+                0,
+                0,
+                0,
+                DEFAULT_CHARSET,
+                OUT_DEFAULT_PRECIS,
+                CLIP_DEFAULT_PRECIS,
+                ANTIALIASED_QUALITY,
+                0,
+                w!("Segoe UI"),
+            );
+            if font.is_invalid() {
+                let _ = DeleteDC(memory_dc);
+                return Err("Failed to create overlay font".to_string());
+            }
+            let previous_font = SelectObject(memory_dc, font.into());
+
+            let mut size = SIZE::default();
+            let _ = GetTextExtentPoint32W(memory_dc, &wide, &mut size);
+            let (width, rows) = (size.cx.max(1), size.cy.max(1));
+
+            let result = match create_bitmap(memory_dc, width, rows) {
+                Some((bitmap, bits)) => {
+                    let previous_bitmap = SelectObject(memory_dc, bitmap.into());
+                    SetTextColor(memory_dc, COLORREF(0x00FF_FFFF));
+                    SetBkMode(memory_dc, TRANSPARENT);
+                    let _ = TextOutW(memory_dc, 0, 0, &wide);
+                    let _ = GdiFlush();
+
+                    // White text on black, so any channel is the coverage
+                    let pixels = std::slice::from_raw_parts(bits as *const u32, width as usize * rows as usize);
+                    let coverage = pixels.iter().map(|pixel| ((pixel >> 8) & 0xFF) as u8).collect();
+
+                    SelectObject(memory_dc, previous_bitmap);
+                    let _ = DeleteObject(bitmap.into());
+                    Ok(Self { width, height: rows, coverage })
+                }
+                None => Err("Failed to allocate text pixels".to_string()),
+            };
+
+            SelectObject(memory_dc, previous_font);
+            let _ = DeleteObject(font.into());
+            let _ = DeleteDC(memory_dc);
+            result
+        }
+    }
+
+    /// Width of the rendered text in pixels
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Height of the rendered line in pixels
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+}
+
+/// Premultiplied BGRA pixel value of a color
+fn premultiply(color: Rgba) -> u32 {
+    let alpha = color.a as u32;
+    let channel = |shift: u32, value: u8| (value as u32 * alpha / 255) << shift;
+    (alpha << 24) | channel(16, color.r) | channel(8, color.g) | channel(0, color.b)
+}
+
+/// Smallest pixel rectangle containing a circle
+pub(crate) fn circle_bounds(center: (f64, f64), radius: f64) -> Rect {
+    Rect::new(
+        (center.0 - radius).floor() as i32,
+        (center.1 - radius).floor() as i32,
+        (center.0 + radius).ceil() as i32 + 1,
+        (center.1 + radius).ceil() as i32 + 1,
+    )
+}
+
+/// Layered, click-through, topmost window redrawn by a closure on its own thread
+///
+/// The window never takes focus or input, so the cursor and clicks pass through
+/// to whatever is underneath. It is closed when dropped.
+pub struct Overlay {
+    window: usize,
+    thread_id: u32,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Overlay {
+    /// Create the overlay and call `draw` once per frame
+    ///
+    /// `draw` returns whether it changed the canvas; unchanged frames are not
+    /// presented, so an idle overlay costs next to nothing.
+    pub fn spawn<F>(config: OverlayConfig, draw: F) -> Result<Self, String>
+    where
+        F: FnMut(&mut OverlayCanvas) -> bool + Send + 'static,
+    {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("luuma-overlay".to_string())
+            .spawn(move || {
+                // Overlay pixels must line up with the hook's physical coordinates
+                let _physical = PhysicalCoordinates::enter();
+                let bounds = config.bounds.unwrap_or_else(crate::CursorDetector::get_virtual_screen_bounds);
+                let window = match create_window(bounds) {
+                    Ok(window) => window,
+                    Err(error) => {
+                        let _ = ready_tx.send(Err(error));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok((window.0 as usize, unsafe { GetCurrentThreadId() })));
+
+                if let Err(error) = run(window, bounds, config.frame_rate, draw) {
+                    crate::CursorDetector::log_message(&error);
+                }
+                unsafe {
+                    let _ = DestroyWindow(window);
+                }
+            })
+            .map_err(|e| format!("Failed to spawn overlay thread: {}", e))?;
+
+        match ready_rx.recv() {
+            Ok(Ok((window, thread_id))) => Ok(Self { window, thread_id, handle: Some(handle) }),
+            Ok(Err(error)) => {
+                let _ = handle.join();
+                Err(error)
+            }
+            Err(_) => Err("Overlay thread exited during start-up".to_string()),
+        }
+    }
+
+    /// Raw `HWND` value of the overlay window
+    pub fn hwnd(&self) -> usize {
+        self.window
+    }
+
+    /// Show or hide the overlay without destroying it
+    pub fn set_visible(&self, visible: bool) {
+        let command = if visible { SW_SHOWNOACTIVATE } else { SW_HIDE };
+        unsafe {
+            let _ = ShowWindowAsync(HWND(self.window as *mut _), command);
+        }
+    }
+
+    /// Close the window and wait for its thread to exit
+    pub fn close(mut self) -> Result<(), String> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<(), String> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        handle.join().map_err(|e| format!("Failed to join overlay thread: {:?}", e))
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+fn create_window(bounds: Rect) -> Result<HWND, String> {
+    unsafe {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(overlay_proc),
+            lpszClassName: w!("LuumaOverlay"),
+            ..Default::default()
+        };
+        // Registering twice fails harmlessly when several overlays are created
+        RegisterClassW(&class);
+
+        // Transparent plus layered lets every click fall through to the windows below
+        let window = CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+            w!("LuumaOverlay"),
+            w!("LuumaOverlay"),
+            WS_POPUP,
+            bounds.left,
+            bounds.top,
+            bounds.width(),
+            bounds.height(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| format!("Failed to create overlay window: {}", e))?;
+
+        let _ = ShowWindowAsync(window, SW_SHOWNOACTIVATE);
+        Ok(window)
+    }
+}
+
+unsafe extern "system" fn overlay_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    DefWindowProcW(window, message, wparam, lparam)
+}
+
+/// Draw frames into a DIB section and present them until `WM_QUIT`
+fn run<F>(window: HWND, bounds: Rect, frame_rate: u32, mut draw: F) -> Result<(), String>
+where
+    F: FnMut(&mut OverlayCanvas) -> bool,
+{
+    let (width, height) = (bounds.width().max(1), bounds.height().max(1));
+    unsafe {
+        let memory_dc = CreateCompatibleDC(None);
+        let Some((bitmap, bits)) = create_bitmap(memory_dc, width, height) else {
+            let _ = DeleteDC(memory_dc);
+            return Err("Failed to allocate overlay pixels".to_string());
+        };
+        let previous = SelectObject(memory_dc, bitmap.into());
+        let pixels = std::slice::from_raw_parts_mut(bits as *mut u32, width as usize * height as usize);
+
+        SetTimer(Some(window), FRAME_TIMER, 1000 / frame_rate.clamp(1, 1000), None);
+        let mut message = MSG::default();
+        while GetMessageW(&mut message, None, 0, 0).0 > 0 {
+            if message.message == WM_TIMER && message.hwnd == window {
+                let mut canvas = OverlayCanvas { pixels: &mut *pixels, bounds };
+                if draw(&mut canvas) {
+                    present(window, memory_dc, bounds);
+                }
+                continue;
+            }
+            DispatchMessageW(&message);
+        }
+        let _ = KillTimer(Some(window), FRAME_TIMER);
+
+        SelectObject(memory_dc, previous);
+        let _ = DeleteObject(bitmap.into());
+        let _ = DeleteDC(memory_dc);
+    }
+    Ok(())
+}
+
+/// Allocate a zeroed 32-bit top-down DIB section and return its pixel pointer
+fn create_bitmap(memory_dc: HDC, width: i32, height: i32) -> Option<(HBITMAP, *mut std::ffi::c_void)> {
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative height makes rows top-down
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bits = std::ptr::null_mut();
+    match unsafe { CreateDIBSection(Some(memory_dc), &info, DIB_RGB_COLORS, &mut bits, None, 0) } {
+        Ok(bitmap) if !bits.is_null() => Some((bitmap, bits)),
+        _ => None,
+    }
+}
+
+/// Copy the frame to the layered window with per-pixel alpha
+fn present(window: HWND, memory_dc: HDC, bounds: Rect) {
+    let destination = POINT { x: bounds.left, y: bounds.top };
+    let size = SIZE { cx: bounds.width(), cy: bounds.height() };
+    let source = POINT { x: 0, y: 0 };
+    let blend = BLENDFUNCTION {
+        BlendOp: AC_SRC_OVER as u8,
+        BlendFlags: 0,
+        SourceConstantAlpha: 255,
+        AlphaFormat: AC_SRC_ALPHA as u8,
+    };
+    unsafe {
+        let _ = UpdateLayeredWindow(
+            window,
+            None,
+            Some(&destination),
+            Some(&size),
+            Some(memory_dc),
+            Some(&source),
+            COLORREF(0),
+            Some(&blend),
+            ULW_ALPHA,
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Overshoot detection: the cursor passing a target, coming back and clicking it
+
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::collections::VecDeque;
+use std::time::Duration;
+#[cfg(windows)]
+use std::time::Instant;
+
+/// What counts as an overshoot before a click
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct OvershootConfig {
+    /// How far back before the click the approach is looked at
+    #[serde(with = "crate::duration_ms")]
+    pub lookback: Duration,
+    /// Shortest approach, in pixels, worth checking; shorter ones are adjustments
+    pub min_distance: f64,
+    /// Least distance past the click point, in pixels, reported as an overshoot
+    pub min_overshoot: f64,
+}
+
+impl Default for OvershootConfig {
+    fn default() -> Self {
+        Self { lookback: Duration::from_millis(1500), min_distance: 40.0, min_overshoot: 3.0 }
+    }
+}
+
+/// Recent cursor path, owned by the hook thread
+#[cfg(windows)]
+#[derive(Debug)]
+pub(crate) struct OvershootDetector {
+    config: OvershootConfig,
+    /// Positions since the last click, no older than the lookback
+    path: VecDeque<((f64, f64), Instant)>,
+}
+
+#[cfg(windows)]
+impl OvershootDetector {
+    pub(crate) fn new(config: OvershootConfig) -> Self {
+        Self { config, path: VecDeque::new() }
+    }
+
+    pub(crate) fn moved(&mut self, position: (f64, f64), now: Instant) {
+        self.forget_before(now);
+        self.path.push_back((position, now));
+    }
+
+    /// How far the cursor went past `position` before coming back to click it
+    pub(crate) fn click(&mut self, position: (f64, f64), now: Instant) -> Option<f64> {
+        self.forget_before(now);
+        let overshoot = self.measure(position);
+        // The next approach starts from this click
+        self.path.clear();
+        overshoot.filter(|&overshoot| overshoot >= self.config.min_overshoot)
+    }
+
+    /// Farthest any position got beyond the click point along the approach direction
+    ///
+    /// The approach runs from the oldest position within the lookback to the click.
+    fn measure(&self, position: (f64, f64)) -> Option<f64> {
+        let (start, _) = *self.path.front()?;
+        let approach = (position.0 - start.0, position.1 - start.1);
+        let distance = approach.0.hypot(approach.1);
+        if distance < self.config.min_distance {
+            return None;
+        }
+
+        let direction = (approach.0 / distance, approach.1 / distance);
+        let beyond = |(point, _): &((f64, f64), Instant)| {
+            (point.0 - position.0) * direction.0 + (point.1 - position.1) * direction.1
+        };
+        Some(self.path.iter().map(beyond).fold(0.0, f64::max))
+    }
+
+    fn forget_before(&mut self, now: Instant) {
+        while self.path.front().is_some_and(|(_, at)| now.saturating_duration_since(*at) > self.config.lookback) {
+            self.path.pop_front();
+        }
+    }
+}
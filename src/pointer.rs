@@ -0,0 +1,387 @@
+//! Touch and pen input decoded from HID digitizer reports
+
+use crate::raw_input::{
+    HID_USAGE_DIGITIZER_PEN as USAGE_PEN, HID_USAGE_DIGITIZER_TOUCH_SCREEN as USAGE_TOUCH_SCREEN,
+    HID_USAGE_PAGE_DIGITIZER as USAGE_PAGE_DIGITIZER,
+};
+use crate::source::{ContactData, LastContact, PointerKind};
+use crate::{CursorEvent, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use windows::Win32::Devices::HumanInterfaceDevice::{
+    HidP_GetCaps, HidP_GetUsageValue, HidP_GetUsages, HidP_GetValueCaps, HidP_Input, HIDP_CAPS,
+    HIDP_STATUS_SUCCESS, HIDP_VALUE_CAPS, PHIDP_PREPARSED_DATA,
+};
+use windows::Win32::Foundation::{HANDLE, RECT};
+use windows::Win32::UI::Input::Pointer::GetPointerDeviceRects;
+use windows::Win32::UI::Input::{GetRawInputDeviceInfoW, RIDI_PREPARSEDDATA};
+use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
+
+/// HID usages read from digitizer reports
+const USAGE_PAGE_GENERIC: u16 = 0x01;
+const USAGE_X: u16 = 0x30;
+const USAGE_Y: u16 = 0x31;
+const USAGE_TIP_PRESSURE: u16 = 0x30;
+const USAGE_IN_RANGE: u16 = 0x32;
+const USAGE_X_TILT: u16 = 0x3D;
+const USAGE_Y_TILT: u16 = 0x3E;
+const USAGE_TIP_SWITCH: u16 = 0x42;
+const USAGE_CONTACT_ID: u16 = 0x51;
+const USAGE_CONTACT_COUNT: u16 = 0x54;
+
+/// Stage of a touch contact or pen stroke
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PointerPhase {
+    /// Contact started (tip touched the surface)
+    Down,
+    /// Contact moved or changed pressure
+    Update,
+    /// Contact ended
+    Up,
+    /// Pen is in range above the surface without touching it
+    Hover,
+}
+
+#[derive(Clone, Copy)]
+enum DigitizerKind {
+    Touch,
+    Pen,
+}
+
+/// Report layout of one digitizer, cached on first use
+struct Digitizer {
+    kind: DigitizerKind,
+    preparsed: Vec<u8>,
+    values: Vec<HIDP_VALUE_CAPS>,
+    /// Link collections holding one contact each (identified by their X usage)
+    contacts: Vec<u16>,
+    /// Screen rectangle the digitizer surface maps onto, refreshed for every packet
+    display: RECT,
+}
+
+impl Digitizer {
+    unsafe fn load(device: HANDLE) -> Option<Self> {
+        let mut size = 0u32;
+        GetRawInputDeviceInfoW(Some(device), RIDI_PREPARSEDDATA, None, &mut size);
+        if size == 0 {
+            return None;
+        }
+        let mut preparsed = vec![0u8; size as usize];
+        if GetRawInputDeviceInfoW(Some(device), RIDI_PREPARSEDDATA, Some(preparsed.as_mut_ptr() as *mut _), &mut size)
+            == u32::MAX
+        {
+            return None;
+        }
+
+        let data = PHIDP_PREPARSED_DATA(preparsed.as_ptr() as isize);
+        let mut caps = HIDP_CAPS::default();
+        if HidP_GetCaps(data, &mut caps) != HIDP_STATUS_SUCCESS || caps.UsagePage != USAGE_PAGE_DIGITIZER {
+            return None;
+        }
+        let kind = match caps.Usage {
+            USAGE_PEN => DigitizerKind::Pen,
+            USAGE_TOUCH_SCREEN => DigitizerKind::Touch,
+            _ => return None,
+        };
+
+        let mut count = caps.NumberInputValueCaps;
+        let mut values = vec![HIDP_VALUE_CAPS::default(); count as usize];
+        if HidP_GetValueCaps(HidP_Input, values.as_mut_ptr(), &mut count, data) != HIDP_STATUS_SUCCESS {
+            return None;
+        }
+        values.truncate(count as usize);
+
+        let contacts = values
+            .iter()
+            .filter(|cap| cap.UsagePage == USAGE_PAGE_GENERIC && !cap.IsRange && cap.Anonymous.NotRange.Usage == USAGE_X)
+            .map(|cap| cap.LinkCollection)
+            .collect();
+
+        Some(Self { kind, preparsed, values, contacts, display: display_rect(device) })
+    }
+
+    fn data(&self) -> PHIDP_PREPARSED_DATA {
+        PHIDP_PREPARSED_DATA(self.preparsed.as_ptr() as isize)
+    }
+
+    fn cap(&self, page: u16, usage: u16, collection: u16) -> Option<&HIDP_VALUE_CAPS> {
+        self.values.iter().find(|cap| {
+            cap.UsagePage == page
+                && cap.LinkCollection == collection
+                && !cap.IsRange
+                && unsafe { cap.Anonymous.NotRange.Usage } == usage
+        })
+    }
+
+    /// Read a value usage, sign-extending fields with a negative logical minimum
+    fn raw_value(&self, page: u16, usage: u16, collection: u16, report: &[u8]) -> Option<(i64, &HIDP_VALUE_CAPS)> {
+        let cap = self.cap(page, usage, collection)?;
+        let mut value = 0u32;
+        let status =
+            unsafe { HidP_GetUsageValue(HidP_Input, page, Some(collection), usage, &mut value, self.data(), report) };
+        if status != HIDP_STATUS_SUCCESS {
+            return None;
+        }
+
+        let bits = cap.BitSize.clamp(1, 32) as u32;
+        let value = if cap.LogicalMin < 0 && bits < 32 && value & (1 << (bits - 1)) != 0 {
+            value as i64 - (1i64 << bits)
+        } else {
+            value as i64
+        };
+        Some((value, cap))
+    }
+
+    /// Value as a fraction of its logical range
+    fn normalized(&self, page: u16, usage: u16, collection: u16, report: &[u8]) -> Option<f64> {
+        let (value, cap) = self.raw_value(page, usage, collection, report)?;
+        let span = (cap.LogicalMax - cap.LogicalMin) as f64;
+        (span > 0.0).then(|| ((value - cap.LogicalMin as i64) as f64 / span).clamp(0.0, 1.0))
+    }
+
+    /// Value in physical units (degrees for tilt)
+    fn physical(&self, page: u16, usage: u16, collection: u16, report: &[u8]) -> Option<f64> {
+        let (value, cap) = self.raw_value(page, usage, collection, report)?;
+        let logical_span = (cap.LogicalMax - cap.LogicalMin) as f64;
+        let physical_span = (cap.PhysicalMax - cap.PhysicalMin) as f64;
+        if logical_span <= 0.0 || physical_span <= 0.0 {
+            return Some(value as f64);
+        }
+
+        let physical = cap.PhysicalMin as f64 + (value - cap.LogicalMin as i64) as f64 * physical_span / logical_span;
+        Some(physical * 10f64.powi(unit_exponent(cap.UnitsExp)))
+    }
+
+    fn usages(&self, collection: u16, report: &mut [u8]) -> Vec<u16> {
+        let mut usages = [0u16; 16];
+        let mut length = usages.len() as u32;
+        let status = unsafe {
+            HidP_GetUsages(
+                HidP_Input,
+                USAGE_PAGE_DIGITIZER,
+                Some(collection),
+                usages.as_mut_ptr(),
+                &mut length,
+                self.data(),
+                report,
+            )
+        };
+        if status != HIDP_STATUS_SUCCESS {
+            return Vec::new();
+        }
+        usages[..length as usize].to_vec()
+    }
+}
+
+/// Decode the HID report unit exponent nibble (0x0E means -2)
+fn unit_exponent(raw: u32) -> i32 {
+    let nibble = (raw & 0x0F) as i32;
+    if nibble > 7 {
+        nibble - 16
+    } else {
+        nibble
+    }
+}
+
+/// Turns digitizer reports into Touch and Pen events
+pub(crate) struct PointerDecoder {
+    digitizers: HashMap<u64, Option<Digitizer>>,
+    touching: HashSet<(u64, u32)>,
+    pen_down: HashSet<u64>,
+    /// Where contacts are left for the mouse messages Windows promotes from them
+    last_contact: Arc<LastContact>,
+}
+
+impl PointerDecoder {
+    pub(crate) fn new(last_contact: Arc<LastContact>) -> Self {
+        Self { digitizers: HashMap::new(), touching: HashSet::new(), pen_down: HashSet::new(), last_contact }
+    }
+
+    /// Decode every report in a `WM_INPUT` HID packet, stamping its events with `timestamp`
+    pub(crate) fn decode<F>(
+        &mut self,
+        device: HANDLE,
+        device_id: u64,
+        reports: &[u8],
+        report_size: usize,
+        timestamp: Timestamp,
+        mut emit: F,
+    ) where
+        F: FnMut(CursorEvent),
+    {
+        let digitizer = self
+            .digitizers
+            .entry(device_id)
+            .or_insert_with(|| unsafe { Digitizer::load(device) });
+        let Some(digitizer) = digitizer.as_mut() else {
+            return;
+        };
+        if report_size == 0 {
+            return;
+        }
+        // The display a digitizer is mapped to can be changed in the pen and touch settings
+        digitizer.display = display_rect(device);
+        let digitizer = &*digitizer;
+
+        let last_contact = &*self.last_contact;
+        for report in reports.chunks_exact(report_size) {
+            let mut report = report.to_vec();
+            match digitizer.kind {
+                DigitizerKind::Touch => Self::decode_touch(
+                    digitizer,
+                    &mut self.touching,
+                    last_contact,
+                    device_id,
+                    &mut report,
+                    timestamp,
+                    &mut emit,
+                ),
+                DigitizerKind::Pen => Self::decode_pen(
+                    digitizer,
+                    &mut self.pen_down,
+                    last_contact,
+                    device_id,
+                    &mut report,
+                    timestamp,
+                    &mut emit,
+                ),
+            }
+        }
+    }
+
+    fn decode_touch<F>(
+        digitizer: &Digitizer,
+        touching: &mut HashSet<(u64, u32)>,
+        last_contact: &LastContact,
+        device_id: u64,
+        report: &mut [u8],
+        timestamp: Timestamp,
+        emit: &mut F,
+    ) where
+        F: FnMut(CursorEvent),
+    {
+        // Hybrid-mode devices spread contacts over several reports; a zero count marks a continuation
+        let contact_count = digitizer
+            .raw_value(USAGE_PAGE_DIGITIZER, USAGE_CONTACT_COUNT, 0, report)
+            .map(|(count, _)| count as usize)
+            .filter(|&count| count > 0)
+            .unwrap_or(digitizer.contacts.len());
+
+        for &collection in digitizer.contacts.iter().take(contact_count) {
+            let Some(position) = screen_position(digitizer, collection, report) else {
+                continue;
+            };
+            let id = digitizer
+                .raw_value(USAGE_PAGE_DIGITIZER, USAGE_CONTACT_ID, collection, report)
+                .map(|(id, _)| id as u32)
+                .unwrap_or(collection as u32);
+            let tip = digitizer.usages(collection, report).contains(&USAGE_TIP_SWITCH);
+            let pressure = digitizer.normalized(USAGE_PAGE_DIGITIZER, USAGE_TIP_PRESSURE, collection, report);
+
+            let key = (device_id, id);
+            let phase = match (tip, touching.contains(&key)) {
+                (true, false) => {
+                    touching.insert(key);
+                    PointerPhase::Down
+                }
+                (true, true) => PointerPhase::Update,
+                (false, true) => {
+                    touching.remove(&key);
+                    PointerPhase::Up
+                }
+                (false, false) => continue,
+            };
+            if phase != PointerPhase::Up {
+                last_contact.record(PointerKind::Touch, ContactData { pressure, tilt: None });
+            }
+
+            emit(CursorEvent::Touch {
+                id,
+                phase,
+                position,
+                pressure,
+                device_id: Some(device_id),
+                timestamp,
+            });
+        }
+    }
+
+    fn decode_pen<F>(
+        digitizer: &Digitizer,
+        pen_down: &mut HashSet<u64>,
+        last_contact: &LastContact,
+        device_id: u64,
+        report: &mut [u8],
+        timestamp: Timestamp,
+        emit: &mut F,
+    ) where
+        F: FnMut(CursorEvent),
+    {
+        let collection = digitizer.contacts.first().copied().unwrap_or(0);
+        let Some(position) = screen_position(digitizer, collection, report) else {
+            return;
+        };
+        let usages = digitizer.usages(collection, report);
+        let tip = usages.contains(&USAGE_TIP_SWITCH);
+        let in_range = usages.contains(&USAGE_IN_RANGE);
+
+        let phase = match (tip, pen_down.contains(&device_id)) {
+            (true, false) => {
+                pen_down.insert(device_id);
+                PointerPhase::Down
+            }
+            (true, true) => PointerPhase::Update,
+            (false, true) => {
+                pen_down.remove(&device_id);
+                PointerPhase::Up
+            }
+            (false, false) if in_range => PointerPhase::Hover,
+            (false, false) => return,
+        };
+
+        let reported_pressure = digitizer.normalized(USAGE_PAGE_DIGITIZER, USAGE_TIP_PRESSURE, collection, report);
+        let tilt_x = digitizer.physical(USAGE_PAGE_DIGITIZER, USAGE_X_TILT, collection, report);
+        let tilt_y = digitizer.physical(USAGE_PAGE_DIGITIZER, USAGE_Y_TILT, collection, report);
+        if phase != PointerPhase::Up {
+            let reported_tilt = (tilt_x.is_some() || tilt_y.is_some())
+                .then(|| (tilt_x.unwrap_or(0.0), tilt_y.unwrap_or(0.0)));
+            last_contact.record(PointerKind::Pen, ContactData { pressure: reported_pressure, tilt: reported_tilt });
+        }
+        let pressure = reported_pressure.unwrap_or(if tip { 1.0 } else { 0.0 });
+        let tilt = (tilt_x.unwrap_or(0.0), tilt_y.unwrap_or(0.0));
+
+        emit(CursorEvent::Pen {
+            phase,
+            position,
+            pressure,
+            tilt,
+            device_id: Some(device_id),
+            timestamp,
+        });
+    }
+}
+
+/// Map digitizer coordinates onto the display the digitizer is mapped to
+fn screen_position(digitizer: &Digitizer, collection: u16, report: &[u8]) -> Option<(f64, f64)> {
+    let x = digitizer.normalized(USAGE_PAGE_GENERIC, USAGE_X, collection, report)?;
+    let y = digitizer.normalized(USAGE_PAGE_GENERIC, USAGE_Y, collection, report)?;
+    let RECT { left, top, right, bottom } = digitizer.display;
+    Some((left as f64 + x * (right - left) as f64, top as f64 + y * (bottom - top) as f64))
+}
+
+/// Screen rectangle of the display a digitizer maps onto, in physical pixels
+///
+/// Windows assigns every digitizer one display, which need not be the primary one
+/// nor share its size. Devices the pointer stack does not know are assumed to
+/// cover the primary monitor, where integrated digitizers live.
+fn display_rect(device: HANDLE) -> RECT {
+    let mut device_rect = RECT::default();
+    let mut display = RECT::default();
+    let mapped = unsafe { GetPointerDeviceRects(device, &mut device_rect, &mut display) }.is_ok();
+    if mapped && display.right > display.left && display.bottom > display.top {
+        return display;
+    }
+    let (width, height) = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    RECT { left: 0, top: 0, right: width, bottom: height }
+}
@@ -0,0 +1,180 @@
+//! Pointing efficiency between consecutive clicks, for Fitts' law style analysis
+
+use crate::{duration_ms, MouseButton, Timestamp};
+#[cfg(windows)]
+use crate::CursorEvent;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Movement leading from one click to the next
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PointingMetrics {
+    /// Button of the click ending the movement
+    pub button: MouseButton,
+    /// Position of the previous click
+    pub from: (f64, f64),
+    /// Position of this click
+    pub to: (f64, f64),
+    /// Time from the first move after the previous click until this click
+    #[serde(with = "duration_ms")]
+    pub movement_time: Duration,
+    /// Straight-line distance between the clicks in pixels
+    pub distance: f64,
+    /// Length of the path actually traveled in pixels
+    pub path_length: f64,
+    /// Times the cursor entered the target area again after leaving it
+    pub target_reentries: u32,
+    /// When this click happened
+    pub timestamp: Timestamp,
+}
+
+impl PointingMetrics {
+    /// Straight-line distance over traveled distance, 1.0 for a perfectly direct movement
+    pub fn efficiency(&self) -> f64 {
+        if self.path_length > 0.0 {
+            (self.distance / self.path_length).min(1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Shannon index of difficulty in bits for a target `target_width` pixels wide
+    pub fn index_of_difficulty(&self, target_width: f64) -> f64 {
+        (self.distance / target_width.max(1.0) + 1.0).log2()
+    }
+
+    /// Index of difficulty per second of movement, in bits per second
+    pub fn throughput(&self, target_width: f64) -> Option<f64> {
+        let seconds = self.movement_time.as_secs_f64();
+        (seconds > 0.0).then(|| self.index_of_difficulty(target_width) / seconds)
+    }
+}
+
+/// Pointing metrics of every click since monitoring started or the last reset
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PointingReport {
+    /// Diameter in pixels of the area around each click counted as its target
+    pub target_width: f64,
+    pub movements: Vec<PointingMetrics>,
+}
+
+impl PointingReport {
+    /// Target width used when none is configured, about the size of a toolbar button
+    pub const DEFAULT_TARGET_WIDTH: f64 = 32.0;
+
+    /// Mean path efficiency over all movements
+    pub fn mean_efficiency(&self) -> Option<f64> {
+        mean(self.movements.iter().map(PointingMetrics::efficiency))
+    }
+
+    /// Mean throughput in bits per second over all movements
+    pub fn mean_throughput(&self) -> Option<f64> {
+        mean(self.movements.iter().filter_map(|movement| movement.throughput(self.target_width)))
+    }
+
+    /// Convert the report to JSON string
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Convert the report to pretty-formatted JSON string
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Collects the path between clicks on the processing thread
+#[cfg(windows)]
+pub(crate) struct PointingTracker {
+    report: PointingReport,
+    last_click: Option<(f64, f64)>,
+    movement_start: Option<Timestamp>,
+    path: Vec<(f64, f64)>,
+}
+
+#[cfg(windows)]
+impl PointingTracker {
+    pub(crate) fn new(target_width: f64) -> Self {
+        Self {
+            report: PointingReport { target_width, movements: Vec::new() },
+            last_click: None,
+            movement_start: None,
+            path: Vec::new(),
+        }
+    }
+
+    pub(crate) fn target_width(&self) -> f64 {
+        self.report.target_width
+    }
+
+    pub(crate) fn report(&self) -> PointingReport {
+        self.report.clone()
+    }
+
+    pub(crate) fn record(&mut self, event: &CursorEvent) {
+        match event {
+            CursorEvent::Move { position, timestamp, .. } if self.last_click.is_some() => {
+                self.movement_start.get_or_insert(*timestamp);
+                self.path.push(*position);
+            }
+            CursorEvent::Click { button, position, timestamp, .. } => {
+                if let (Some(from), Some(start)) = (self.last_click, self.movement_start) {
+                    let movement = self.measure(*button, from, *position, start, *timestamp);
+                    self.report.movements.push(movement);
+                }
+                self.last_click = Some(*position);
+                self.movement_start = None;
+                self.path.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn measure(
+        &self,
+        button: MouseButton,
+        from: (f64, f64),
+        to: (f64, f64),
+        start: Timestamp,
+        timestamp: Timestamp,
+    ) -> PointingMetrics {
+        let radius = self.report.target_width / 2.0;
+        let inside = |point: (f64, f64)| distance(point, to) <= radius;
+
+        let mut path_length = 0.0;
+        let mut entries = 0u32;
+        let mut previous = from;
+        let mut was_inside = inside(from);
+        for &point in self.path.iter().chain(std::iter::once(&to)) {
+            path_length += distance(previous, point);
+            let is_inside = inside(point);
+            if is_inside && !was_inside {
+                entries += 1;
+            }
+            was_inside = is_inside;
+            previous = point;
+        }
+
+        PointingMetrics {
+            button,
+            from,
+            to,
+            movement_time: timestamp.duration_since(start),
+            distance: distance(from, to),
+            path_length,
+            // The first entry is the approach itself
+            target_reentries: entries.saturating_sub(1),
+            timestamp,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
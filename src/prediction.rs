@@ -0,0 +1,131 @@
+//! Extrapolating the cursor ahead in time to hide render latency
+
+use crate::timing::PreciseTime;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Samples older than this mean the cursor has stopped, so nothing is extrapolated
+const STALE_AFTER: Duration = Duration::from_millis(100);
+
+/// How the cursor's velocity is estimated
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PredictionModel {
+    /// Velocity between the last two samples; reacts instantly but passes on jitter
+    ConstantVelocity,
+    /// Alpha-beta filter; `alpha` and `beta` in 0.0..=1.0 weight new samples for position and velocity
+    AlphaBeta { alpha: f64, beta: f64 },
+}
+
+impl Default for PredictionModel {
+    fn default() -> Self {
+        Self::AlphaBeta { alpha: 0.85, beta: 0.005 }
+    }
+}
+
+/// Tracks position and velocity from samples and extrapolates them
+///
+/// The detector runs one on the hook thread when prediction is enabled and
+/// answers [`predict_position`](crate::CursorDetector::predict_position) from it.
+#[derive(Debug, Clone)]
+pub struct PositionPredictor {
+    model: PredictionModel,
+    position: (f64, f64),
+    velocity: (f64, f64),
+    last_time: Option<PreciseTime>,
+}
+
+impl PositionPredictor {
+    /// Predictor with no samples yet
+    pub fn new(model: PredictionModel) -> Self {
+        Self { model, position: (0.0, 0.0), velocity: (0.0, 0.0), last_time: None }
+    }
+
+    /// Feed a sample captured at `time`
+    pub fn update(&mut self, position: (f64, f64), time: PreciseTime) {
+        let Some(last_time) = self.last_time.replace(time) else {
+            self.position = position;
+            return;
+        };
+        let dt = time.duration_since(last_time).as_secs_f64();
+        if dt <= 0.0 {
+            self.position = position;
+            return;
+        }
+        // A long pause means the previous velocity no longer applies
+        if dt > STALE_AFTER.as_secs_f64() {
+            self.position = position;
+            self.velocity = (0.0, 0.0);
+            return;
+        }
+
+        match self.model {
+            PredictionModel::ConstantVelocity => {
+                self.velocity = ((position.0 - self.position.0) / dt, (position.1 - self.position.1) / dt);
+                self.position = position;
+            }
+            PredictionModel::AlphaBeta { alpha, beta } => {
+                let predicted = (self.position.0 + self.velocity.0 * dt, self.position.1 + self.velocity.1 * dt);
+                let residual = (position.0 - predicted.0, position.1 - predicted.1);
+                self.position = (predicted.0 + alpha * residual.0, predicted.1 + alpha * residual.1);
+                self.velocity = (self.velocity.0 + beta * residual.0 / dt, self.velocity.1 + beta * residual.1 / dt);
+            }
+        }
+    }
+
+    /// Estimated velocity in pixels per second
+    pub fn velocity(&self) -> (f64, f64) {
+        self.velocity
+    }
+
+    /// Where the cursor will be `lead` after `now`, `None` before the first sample
+    pub fn predict(&self, now: PreciseTime, lead: Duration) -> Option<(f64, f64)> {
+        let last_time = self.last_time?;
+        Some(extrapolate(self.position, self.velocity, now.duration_since(last_time), lead))
+    }
+}
+
+fn extrapolate(position: (f64, f64), velocity: (f64, f64), age: Duration, lead: Duration) -> (f64, f64) {
+    if age > STALE_AFTER {
+        return position;
+    }
+    let ahead = (age + lead).as_secs_f64();
+    (position.0 + velocity.0 * ahead, position.1 + velocity.1 * ahead)
+}
+
+/// Latest predictor estimate, published by the hook thread without locking
+#[cfg(windows)]
+#[derive(Debug, Default)]
+pub(crate) struct SharedPrediction {
+    published: AtomicBool,
+    position_x: AtomicU64,
+    position_y: AtomicU64,
+    velocity_x: AtomicU64,
+    velocity_y: AtomicU64,
+    sampled_nanos: AtomicU64,
+}
+
+#[cfg(windows)]
+impl SharedPrediction {
+    pub(crate) fn publish(&self, predictor: &PositionPredictor, time: PreciseTime) {
+        self.position_x.store(predictor.position.0.to_bits(), Ordering::Relaxed);
+        self.position_y.store(predictor.position.1.to_bits(), Ordering::Relaxed);
+        self.velocity_x.store(predictor.velocity.0.to_bits(), Ordering::Relaxed);
+        self.velocity_y.store(predictor.velocity.1.to_bits(), Ordering::Relaxed);
+        self.sampled_nanos.store(time.as_nanos(), Ordering::Relaxed);
+        self.published.store(true, Ordering::Release);
+    }
+
+    /// Extrapolated position, `None` until the hook has published a sample
+    pub(crate) fn predict(&self, lead: Duration) -> Option<(f64, f64)> {
+        if !self.published.load(Ordering::Acquire) {
+            return None;
+        }
+        let load = |value: &AtomicU64| f64::from_bits(value.load(Ordering::Relaxed));
+        let position = (load(&self.position_x), load(&self.position_y));
+        let velocity = (load(&self.velocity_x), load(&self.velocity_y));
+        let sampled = PreciseTime::from_nanos(self.sampled_nanos.load(Ordering::Relaxed));
+        Some(extrapolate(position, velocity, PreciseTime::now().duration_since(sampled), lead))
+    }
+}
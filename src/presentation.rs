@@ -0,0 +1,176 @@
+//! Presentation aids that keep the audience's eyes on the cursor
+
+use crate::overlay::{circle_bounds, Overlay, OverlayCanvas, OverlayConfig, Rgba};
+use crate::sink::EventSink;
+use crate::{CursorEvent, Rect};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// How the cursor is emphasized
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PresentationStyle {
+    /// Dim the whole desktop except a circle of `radius` pixels around the cursor
+    Spotlight { radius: f64, dim: Rgba },
+    /// Horizontal and vertical lines across the desktop through the cursor
+    Crosshair { color: Rgba, thickness: i32 },
+}
+
+impl Default for PresentationStyle {
+    fn default() -> Self {
+        Self::Spotlight { radius: 120.0, dim: Rgba::new(0, 0, 0, 160) }
+    }
+}
+
+/// Style and redraw rate of a [`PresentationMode`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PresentationConfig {
+    pub style: PresentationStyle,
+    /// Frames drawn per second while the cursor moves
+    pub frame_rate: u32,
+}
+
+impl Default for PresentationConfig {
+    fn default() -> Self {
+        Self { style: PresentationStyle::default(), frame_rate: 60 }
+    }
+}
+
+/// What the sink side shares with the overlay's draw callback
+struct PresentationState {
+    style: PresentationStyle,
+    enabled: bool,
+    cursor: Option<(f64, f64)>,
+    /// Style and cursor position currently on the canvas
+    drawn: Option<(PresentationStyle, (f64, f64))>,
+}
+
+impl PresentationState {
+    /// Move the effect to the cursor, returning whether the frame differs from the last one
+    fn draw(&mut self, canvas: &mut OverlayCanvas) -> bool {
+        let target = self.cursor.filter(|_| self.enabled).map(|cursor| (self.style, cursor));
+        if target == self.drawn {
+            return false;
+        }
+
+        // Only the area around the previous position is undone while the style stays the same
+        match (self.drawn, target) {
+            (Some((style, cursor)), Some((target_style, _))) if style == target_style => erase(canvas, style, cursor),
+            _ => {
+                canvas.clear();
+                if let Some((PresentationStyle::Spotlight { dim, .. }, _)) = target {
+                    canvas.set_rect(canvas.bounds(), dim);
+                }
+            }
+        }
+        if let Some((style, cursor)) = target {
+            paint(canvas, style, cursor);
+        }
+        self.drawn = target;
+        true
+    }
+}
+
+/// Rows and columns covered by the crosshair lines through a position
+fn crosshair_lines(bounds: Rect, cursor: (f64, f64), thickness: i32) -> (Rect, Rect) {
+    let (x, y) = (cursor.0 as i32 - thickness / 2, cursor.1 as i32 - thickness / 2);
+    let horizontal = Rect::new(bounds.left, y, bounds.right, y + thickness.max(1));
+    let vertical = Rect::new(x, bounds.top, x + thickness.max(1), bounds.bottom);
+    (horizontal, vertical)
+}
+
+fn paint(canvas: &mut OverlayCanvas, style: PresentationStyle, cursor: (f64, f64)) {
+    match style {
+        PresentationStyle::Spotlight { radius, dim } => {
+            // Cut the hole with an anti-aliased edge so the circle does not look jagged
+            let area = circle_bounds(cursor, radius + 1.0);
+            for y in area.top..area.bottom {
+                for x in area.left..area.right {
+                    let distance = (x as f64 + 0.5 - cursor.0).hypot(y as f64 + 0.5 - cursor.1);
+                    let inside = (radius + 0.5 - distance).clamp(0.0, 1.0);
+                    if inside > 0.0 {
+                        canvas.set_pixel(x, y, dim.with_opacity(1.0 - inside));
+                    }
+                }
+            }
+        }
+        PresentationStyle::Crosshair { color, thickness } => {
+            let (horizontal, vertical) = crosshair_lines(canvas.bounds(), cursor, thickness);
+            canvas.set_rect(horizontal, color);
+            canvas.set_rect(vertical, color);
+        }
+    }
+}
+
+fn erase(canvas: &mut OverlayCanvas, style: PresentationStyle, cursor: (f64, f64)) {
+    match style {
+        PresentationStyle::Spotlight { radius, dim } => canvas.set_rect(circle_bounds(cursor, radius + 1.0), dim),
+        PresentationStyle::Crosshair { thickness, .. } => {
+            let (horizontal, vertical) = crosshair_lines(canvas.bounds(), cursor, thickness);
+            canvas.clear_rect(horizontal);
+            canvas.clear_rect(vertical);
+        }
+    }
+}
+
+/// Spotlight or crosshair following the cursor across the virtual desktop
+///
+/// Attach it as a sink so it follows the detector's move events; filtering the
+/// sink to `EventKind::Move` keeps other events off its lock. The overlay
+/// closes when the last clone is dropped.
+#[derive(Clone)]
+pub struct PresentationMode {
+    state: Arc<Mutex<PresentationState>>,
+    overlay: Arc<Overlay>,
+}
+
+impl PresentationMode {
+    /// Create the overlay covering the virtual desktop
+    pub fn spawn(config: PresentationConfig) -> Result<Self, String> {
+        let state = Arc::new(Mutex::new(PresentationState {
+            style: config.style,
+            enabled: true,
+            cursor: None,
+            drawn: None,
+        }));
+
+        let draw_state = Arc::clone(&state);
+        let overlay = Overlay::spawn(OverlayConfig { bounds: None, frame_rate: config.frame_rate }, move |canvas| {
+            draw_state.lock().unwrap_or_else(|e| e.into_inner()).draw(canvas)
+        })?;
+
+        Ok(Self { state, overlay: Arc::new(overlay) })
+    }
+
+    /// Switch between spotlight and crosshair, or change their look
+    pub fn set_style(&self, style: PresentationStyle) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).style = style;
+    }
+
+    /// Current style
+    pub fn style(&self) -> PresentationStyle {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).style
+    }
+
+    /// Turn the effect on or off
+    pub fn set_enabled(&self, enabled: bool) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).enabled = enabled;
+    }
+
+    /// Whether the effect is drawn
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    /// Raw `HWND` of the overlay, e.g. to exclude it from a screen capture
+    pub fn hwnd(&self) -> usize {
+        self.overlay.hwnd()
+    }
+}
+
+impl EventSink for PresentationMode {
+    fn handle(&mut self, event: &CursorEvent) {
+        if let CursorEvent::Move { position, .. } = event {
+            self.state.lock().unwrap_or_else(|e| e.into_inner()).cursor = Some(*position);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Privacy masking applied before events reach handlers and sinks
+
+use crate::region::{self, MonitorCache, RegionArea};
+use crate::window::WindowResolver;
+use crate::CursorEvent;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// What happens to an event that falls under a masking rule
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MaskAction {
+    /// Replace the event with `Redacted`, keeping only its kind and timestamp
+    Redact,
+    /// Drop the event entirely
+    Suppress,
+}
+
+/// Area of the screen where activity is masked
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PrivacyZone {
+    pub name: String,
+    pub area: RegionArea,
+    pub action: MaskAction,
+}
+
+/// Process whose windows mask activity over them and focus changes to them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProcessRule {
+    /// Executable file name such as `keepass.exe`, matched case-insensitively
+    pub process_name: String,
+    pub action: MaskAction,
+}
+
+/// Exclusion zones and process rules applied on the processing thread
+///
+/// Masking runs before statistics are recorded and before the event handler and
+/// every sink, including events pushed through [`crate::DetectorHandle::emit`], so
+/// masked positions never leave the crate, not even as totals in `SessionEnd`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PrivacyMask {
+    pub zones: Vec<PrivacyZone>,
+    pub processes: Vec<ProcessRule>,
+}
+
+impl PrivacyMask {
+    /// Create a mask without any rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mask activity inside an area
+    pub fn exclude_area(mut self, name: &str, area: RegionArea, action: MaskAction) -> Self {
+        self.zones.push(PrivacyZone { name: name.to_string(), area, action });
+        self
+    }
+
+    /// Mask activity over windows of a process
+    pub fn exclude_process(mut self, process_name: &str, action: MaskAction) -> Self {
+        self.processes.push(ProcessRule { process_name: process_name.to_string(), action });
+        self
+    }
+
+    /// Whether the mask has no rules
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty() && self.processes.is_empty()
+    }
+}
+
+/// Mask shared between the detector, its handles and the processing thread, empty when disabled
+pub(crate) type SharedPrivacy = Arc<RwLock<PrivacyMask>>;
+
+/// Applies a [`PrivacyMask`] to the event stream
+pub(crate) struct PrivacyMasker {
+    mask: SharedPrivacy,
+    monitors: MonitorCache,
+    windows: WindowResolver,
+    /// Action taken on the last positional event, applied to releases which carry no position
+    last_action: Option<MaskAction>,
+}
+
+impl PrivacyMasker {
+    pub(crate) fn new(mask: SharedPrivacy) -> Self {
+        Self { mask, monitors: MonitorCache::default(), windows: WindowResolver::new(), last_action: None }
+    }
+
+    /// Mask an event, returning `None` when it is suppressed
+    pub(crate) fn apply(&mut self, event: CursorEvent) -> Option<CursorEvent> {
+        // The mask can be replaced while monitoring, so it is read per event
+        let shared = Arc::clone(&self.mask);
+        let mask = shared.read().unwrap_or_else(|e| e.into_inner());
+        if mask.is_empty() {
+            return Some(event);
+        }
+        let action = match (&event, event.position()) {
+            (CursorEvent::FocusChanged { process_name, .. }, _) => process_action(&mask, process_name),
+            (_, Some(position)) => {
+                let action = self.zone_action(&mask, position).max(self.window_action(&mask, &event, position));
+                self.last_action = action;
+                action
+            }
+            // A drag can leave the zone the button went down in, so the press position counts too
+            (CursorEvent::Release { press_position, .. }, None) => {
+                let at_press = press_position.and_then(|position| {
+                    self.zone_action(&mask, position).max(self.window_action(&mask, &event, position))
+                });
+                self.last_action.max(at_press)
+            }
+            _ => None,
+        };
+
+        match action {
+            None => Some(event),
+            Some(MaskAction::Redact) => {
+                Some(CursorEvent::Redacted { kind: event.kind(), timestamp: event.timestamp() })
+            }
+            Some(MaskAction::Suppress) => None,
+        }
+    }
+
+    /// Strictest action of the zones containing a position
+    fn zone_action(&mut self, mask: &PrivacyMask, position: (f64, f64)) -> Option<MaskAction> {
+        let needs_monitors = mask.zones.iter().any(|zone| zone.area.is_monitor_relative());
+        let monitors = if needs_monitors { self.monitors.monitors() } else { &[] };
+        mask.zones
+            .iter()
+            .filter(|zone| {
+                region::resolve(&zone.area, monitors).is_some_and(|rect| rect.contains(position.0, position.1))
+            })
+            .map(|zone| zone.action)
+            .max()
+    }
+
+    /// Action for the process owning the window at a position
+    fn window_action(&mut self, mask: &PrivacyMask, event: &CursorEvent, position: (f64, f64)) -> Option<MaskAction> {
+        if mask.processes.is_empty() {
+            return None;
+        }
+
+        // Reuse the window already attached by `track_windows` before resolving one
+        let attached = match event {
+            CursorEvent::Move { window, .. } | CursorEvent::Click { window, .. } => window.clone(),
+            _ => None,
+        };
+        let window = attached.or_else(|| self.windows.at(position))?;
+        process_action(mask, &window.process_name)
+    }
+}
+
+/// Strictest action of the rules matching a process name
+fn process_action(mask: &PrivacyMask, process_name: &str) -> Option<MaskAction> {
+    mask.processes
+        .iter()
+        .filter(|rule| rule.process_name.eq_ignore_ascii_case(process_name))
+        .map(|rule| rule.action)
+        .max()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{MaskAction, PrivacyMask, PrivacyMasker};
+    use crate::clock::ManualClock;
+    use crate::filter::SharedKindMask;
+    use crate::queue::EventQueue;
+    use crate::region::RegionArea;
+    use crate::runtime::{SharedTuning, Throttle};
+    use crate::stats::{SessionStats, StatsCollector};
+    use crate::{
+        Consumers, CursorDetector, CursorEvent, CursorType, EventKindMask, MouseButton, Rect, SamplingMode, Stages,
+        Timestamp,
+    };
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex, RwLock};
+    use std::time::Duration;
+
+    const START: u64 = 1_738_324_800_000;
+
+    fn click(x: f64, y: f64, millis: u64) -> CursorEvent {
+        CursorEvent::Click {
+            button: MouseButton::Left,
+            position: (x, y),
+            monitor: None,
+            window: None,
+            client: None,
+            source: Default::default(),
+            contact: None,
+            overshoot_px: None,
+            device_id: None,
+            precise_time: None,
+            timestamp: Timestamp::from_millis(START + millis),
+        }
+    }
+
+    fn moved(x: f64, y: f64, millis: u64) -> CursorEvent {
+        CursorEvent::Move {
+            position: (x, y),
+            cursor_type: CursorType::Arrow,
+            smoothed: None,
+            monitor: None,
+            window: None,
+            client: None,
+            source: Default::default(),
+            contact: None,
+            device_id: None,
+            precise_time: None,
+            timestamp: Timestamp::from_millis(START + millis),
+        }
+    }
+
+    fn release(press_position: Option<(f64, f64)>, millis: u64) -> CursorEvent {
+        CursorEvent::Release {
+            button: MouseButton::Left,
+            press_position,
+            held_for: Duration::ZERO,
+            source: Default::default(),
+            device_id: None,
+            precise_time: None,
+            timestamp: Timestamp::from_millis(START + millis),
+        }
+    }
+
+    fn focus(process_name: &str, millis: u64) -> CursorEvent {
+        CursorEvent::FocusChanged {
+            hwnd: 1,
+            pid: 1,
+            process_name: process_name.to_string(),
+            title: String::new(),
+            timestamp: Timestamp::from_millis(START + millis),
+        }
+    }
+
+    /// Statistics carried by `SessionEnd` after processing `events` under `mask`
+    fn session_end(mask: PrivacyMask, events: Vec<CursorEvent>) -> SessionStats {
+        let queue = Arc::new(EventQueue::with_capacity(64));
+        for event in events {
+            let _ = queue.push(event);
+        }
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&delivered);
+        let consumers = Consumers {
+            event_handler: Some(Box::new(move |event| sink.lock().unwrap().push(event))),
+            event_filter: None,
+            middleware: Vec::new(),
+            transform: None,
+        };
+        let clock = Arc::new(ManualClock::new(Timestamp::from_millis(START)));
+        let stages = Stages {
+            privacy: PrivacyMasker::new(Arc::new(RwLock::new(mask))),
+            throttle: Throttle::new(Arc::new(SharedTuning::new(SamplingMode::All))),
+        };
+        CursorDetector::process_events_with_timeout(
+            queue,
+            consumers,
+            Default::default(),
+            Arc::new(Mutex::new(StatsCollector::new(clock))),
+            Arc::new(SharedKindMask::new(EventKindMask::ALL)),
+            stages,
+            Arc::new(AtomicBool::new(false)),
+        );
+        let delivered = delivered.lock().unwrap();
+        match delivered.last() {
+            Some(CursorEvent::SessionEnd { stats, .. }) => (**stats).clone(),
+            other => panic!("expected SessionEnd, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn masked_zones_contribute_nothing_to_session_end() {
+        let zone = RegionArea::Screen(Rect::new(0, 0, 100, 100));
+        for action in [MaskAction::Redact, MaskAction::Suppress] {
+            let mask = PrivacyMask::new().exclude_area("vault", zone, action);
+            // Clicked outside, then inside the zone and released there
+            let stats = session_end(mask, vec![click(500.0, 500.0, 1000), click(50.0, 50.0, 1100), release(None, 1200)]);
+            assert_eq!(stats.total_clicks(), 1);
+            assert_eq!(stats.distance_px, 0.0);
+            assert_eq!(stats.active_time, Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn excluded_processes_stay_out_of_the_application_breakdown() {
+        for action in [MaskAction::Redact, MaskAction::Suppress] {
+            let mask = PrivacyMask::new().exclude_process("keepass.exe", action);
+            let events = vec![
+                focus("notepad.exe", 1000),
+                release(None, 1100),
+                focus("KeePass.exe", 1200),
+                release(None, 1300),
+                release(None, 1400),
+            ];
+            let stats = session_end(mask, events);
+            let mut applications: Vec<_> = stats.applications.keys().cloned().collect();
+            applications.sort();
+            assert_eq!(applications, ["notepad.exe"]);
+        }
+    }
+
+    #[test]
+    fn releases_are_masked_by_where_the_press_was() {
+        let zone = RegionArea::Screen(Rect::new(0, 0, 100, 100));
+        let mask = PrivacyMask::new().exclude_area("vault", zone, MaskAction::Redact);
+        let mut masker = PrivacyMasker::new(Arc::new(RwLock::new(mask)));
+
+        // Pressed inside the zone, dragged out and released outside
+        assert!(matches!(masker.apply(click(50.0, 50.0, 0)), Some(CursorEvent::Redacted { .. })));
+        assert!(matches!(masker.apply(moved(400.0, 400.0, 100)), Some(CursorEvent::Move { .. })));
+        assert!(matches!(masker.apply(release(Some((50.0, 50.0)), 200)), Some(CursorEvent::Redacted { .. })));
+
+        // Pressed and released outside
+        assert!(matches!(masker.apply(release(Some((400.0, 400.0)), 300)), Some(CursorEvent::Release { .. })));
+    }
+}
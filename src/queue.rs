@@ -0,0 +1,301 @@
+//! Pre-allocated lock-free queue between the hook thread and the processing thread
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+/// Single slot of the ring buffer with its sequence number
+struct Slot<T> {
+    sequence: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Bounded multi-producer queue backed by a fixed ring buffer
+///
+/// Producers (the hook callback and detector handles) never allocate, lock or block;
+/// when the buffer is full the event is counted as dropped instead. The consumer
+/// parks while the queue is empty and is woken by the next push.
+pub(crate) struct EventQueue<T> {
+    buffer: Box<[Slot<T>]>,
+    mask: usize,
+    enqueue_pos: AtomicUsize,
+    dequeue_pos: AtomicUsize,
+    consumer_waiting: AtomicBool,
+    /// Thread parking in `pop_timeout`, null until one has
+    consumer: AtomicPtr<Thread>,
+    /// Owns every handle `consumer` has pointed to, so producers never unpark a freed
+    /// one; grows only when the consuming thread changes, e.g. on restart
+    consumers: Mutex<Vec<Arc<Thread>>>,
+    dropped: AtomicU64,
+}
+
+unsafe impl<T: Send> Send for EventQueue<T> {}
+unsafe impl<T: Send> Sync for EventQueue<T> {}
+
+impl<T> EventQueue<T> {
+    /// Create a queue holding at least `capacity` items (rounded up to a power of two)
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+        let buffer = (0..capacity)
+            .map(|index| Slot { sequence: AtomicUsize::new(index), value: UnsafeCell::new(MaybeUninit::uninit()) })
+            .collect();
+
+        Self {
+            buffer,
+            mask: capacity - 1,
+            enqueue_pos: AtomicUsize::new(0),
+            dequeue_pos: AtomicUsize::new(0),
+            consumer_waiting: AtomicBool::new(false),
+            consumer: AtomicPtr::new(std::ptr::null_mut()),
+            consumers: Mutex::new(Vec::new()),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of slots in the ring buffer
+    pub(crate) fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Number of items rejected because the queue was full
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Push an item, handing it back if the queue is full
+    pub(crate) fn push(&self, value: T) -> Result<(), T> {
+        let mut pos = self.enqueue_pos.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let diff = sequence as isize - pos as isize;
+
+            if diff == 0 {
+                match self.enqueue_pos.compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.sequence.store(pos + 1, Ordering::Release);
+                        self.wake_consumer();
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return Err(value);
+            } else {
+                pos = self.enqueue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pop the oldest item without waiting
+    pub(crate) fn pop(&self) -> Option<T> {
+        let mut pos = self.dequeue_pos.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let diff = sequence as isize - (pos + 1) as isize;
+
+            if diff == 0 {
+                match self.dequeue_pos.compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.sequence.store(pos + self.mask + 1, Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                return None;
+            } else {
+                pos = self.dequeue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pop an item, parking the calling thread for up to `timeout` while the queue is empty
+    pub(crate) fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        if let Some(value) = self.pop() {
+            return Some(value);
+        }
+
+        self.register_consumer();
+        self.consumer_waiting.store(true, Ordering::Release);
+        // Pairs with the fence in `wake_consumer`: either the re-check below sees the
+        // pushed item or the producer sees us waiting
+        fence(Ordering::SeqCst);
+
+        let value = self.pop().or_else(|| {
+            thread::park_timeout(timeout);
+            self.pop()
+        });
+
+        self.consumer_waiting.store(false, Ordering::Relaxed);
+        value
+    }
+
+    /// Point producers at the calling thread unless it is the registered consumer already
+    fn register_consumer(&self) {
+        let current = thread::current();
+        // Safety: registered handles live as long as the queue
+        let registered = unsafe { self.consumer.load(Ordering::Acquire).as_ref() };
+        if registered.is_some_and(|consumer| consumer.id() == current.id()) {
+            return;
+        }
+        let consumer = Arc::new(current);
+        self.consumer.store(Arc::as_ptr(&consumer).cast_mut(), Ordering::Release);
+        self.consumers.lock().unwrap_or_else(|e| e.into_inner()).push(consumer);
+    }
+
+    /// Unpark the consumer if it is waiting for items
+    fn wake_consumer(&self) {
+        fence(Ordering::SeqCst);
+        if self.consumer_waiting.load(Ordering::Acquire) {
+            // Safety: registered handles live as long as the queue
+            if let Some(consumer) = unsafe { self.consumer.load(Ordering::Acquire).as_ref() } {
+                consumer.unpark();
+            }
+        }
+    }
+}
+
+impl<T> Drop for EventQueue<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T> std::fmt::Debug for EventQueue<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventQueue")
+            .field("capacity", &self.capacity())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventQueue;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn rounds_capacity_up_to_a_power_of_two() {
+        assert_eq!(EventQueue::<u32>::with_capacity(0).capacity(), 2);
+        assert_eq!(EventQueue::<u32>::with_capacity(5).capacity(), 8);
+        assert_eq!(EventQueue::<u32>::with_capacity(8).capacity(), 8);
+    }
+
+    #[test]
+    fn keeps_order_across_wraparound() {
+        let queue = EventQueue::with_capacity(4);
+        let mut next = 0;
+        for round in 0..50 {
+            // Vary the fill level so the positions wrap at every offset
+            let batch = round % 4 + 1;
+            for value in next..next + batch {
+                queue.push(value).unwrap();
+            }
+            for value in next..next + batch {
+                assert_eq!(queue.pop(), Some(value));
+            }
+            assert_eq!(queue.pop(), None);
+            next += batch;
+        }
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[test]
+    fn counts_items_dropped_while_full() {
+        let queue = EventQueue::with_capacity(4);
+        for value in 0..4 {
+            queue.push(value).unwrap();
+        }
+        assert_eq!(queue.push(4), Err(4));
+        assert_eq!(queue.push(5), Err(5));
+        assert_eq!(queue.dropped(), 2);
+
+        // Room frees up as the consumer catches up
+        assert_eq!(queue.pop(), Some(0));
+        queue.push(6).unwrap();
+        assert_eq!(queue.push(7), Err(7));
+        assert_eq!(queue.dropped(), 3);
+        let drained: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(drained, vec![1, 2, 3, 6]);
+    }
+
+    #[test]
+    fn keeps_each_producers_order() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 10_000;
+        let queue = Arc::new(EventQueue::with_capacity(64));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for sequence in 0..PER_PRODUCER {
+                        let mut item = (producer, sequence);
+                        while let Err(rejected) = queue.push(item) {
+                            item = rejected;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut next = [0; PRODUCERS];
+        let mut received = 0;
+        while received < PRODUCERS * PER_PRODUCER {
+            if let Some((producer, sequence)) = queue.pop_timeout(Duration::from_millis(100)) {
+                assert_eq!(sequence, next[producer], "producer {} out of order", producer);
+                next[producer] += 1;
+                received += 1;
+            }
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(next, [PER_PRODUCER; PRODUCERS]);
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn push_wakes_a_parked_consumer() {
+        let queue = Arc::new(EventQueue::with_capacity(8));
+        // A later consumer thread must be woken too, as after a restart
+        for value in 0..3 {
+            let consumer = {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    let started = Instant::now();
+                    // Parking may end spuriously, which is not what is tested here
+                    let popped = loop {
+                        if let Some(value) = queue.pop_timeout(Duration::from_secs(10)) {
+                            break value;
+                        }
+                    };
+                    (popped, started.elapsed())
+                })
+            };
+            thread::sleep(Duration::from_millis(50));
+            queue.push(value).unwrap();
+            let (popped, waited) = consumer.join().unwrap();
+            assert_eq!(popped, value);
+            assert!(waited < Duration::from_secs(5), "woken only by the timeout after {:?}", waited);
+        }
+    }
+
+    #[test]
+    fn pop_timeout_gives_up_when_empty() {
+        let queue = EventQueue::<u32>::with_capacity(2);
+        assert_eq!(queue.pop_timeout(Duration::from_millis(20)), None);
+    }
+}
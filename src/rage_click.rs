@@ -0,0 +1,92 @@
+//! Rage-click detection: bursts of rapid clicks on one spot
+
+#[cfg(windows)]
+use crate::MouseButton;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(windows)]
+use std::time::Instant;
+
+/// What counts as a rage click
+///
+/// A burst is a run of clicks of the same button, each within `interval` of the
+/// previous one and within `radius` pixels of the first. It is reported once,
+/// when it reaches `count` clicks.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RageClickConfig {
+    /// Clicks in a burst before it is reported
+    pub count: u32,
+    /// Longest time between two clicks of a burst
+    #[serde(with = "crate::duration_ms")]
+    pub interval: Duration,
+    /// Farthest a click may land from the first one of its burst, in pixels
+    pub radius: f64,
+}
+
+impl Default for RageClickConfig {
+    fn default() -> Self {
+        Self { count: 3, interval: Duration::from_millis(500), radius: 30.0 }
+    }
+}
+
+/// A burst that reached the configured count
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RageClick {
+    /// Center of the burst's clicks
+    pub(crate) position: (f64, f64),
+    pub(crate) count: u32,
+    pub(crate) duration: Duration,
+}
+
+/// Current burst of clicks, owned by the hook thread
+#[cfg(windows)]
+#[derive(Debug)]
+pub(crate) struct RageClickDetector {
+    config: RageClickConfig,
+    button: Option<MouseButton>,
+    first: ((f64, f64), Instant),
+    last: Option<Instant>,
+    count: u32,
+    sum: (f64, f64),
+    reported: bool,
+}
+
+#[cfg(windows)]
+impl RageClickDetector {
+    pub(crate) fn new(config: RageClickConfig) -> Self {
+        let first = ((0.0, 0.0), Instant::now());
+        Self { config, button: None, first, last: None, count: 0, sum: (0.0, 0.0), reported: false }
+    }
+
+    /// Feed a button press and report the burst it completes
+    pub(crate) fn click(&mut self, button: MouseButton, position: (f64, f64), now: Instant) -> Option<RageClick> {
+        let origin = self.first.0;
+        let continues = self.button == Some(button)
+            && self.last.is_some_and(|last| now.saturating_duration_since(last) <= self.config.interval)
+            && (position.0 - origin.0).hypot(position.1 - origin.1) <= self.config.radius;
+        if !continues {
+            self.button = Some(button);
+            self.first = (position, now);
+            self.count = 0;
+            self.sum = (0.0, 0.0);
+            self.reported = false;
+        }
+
+        self.last = Some(now);
+        self.count += 1;
+        self.sum = (self.sum.0 + position.0, self.sum.1 + position.1);
+        if self.reported || self.count < self.config.count {
+            return None;
+        }
+
+        // Later clicks of the same burst are part of this report
+        self.reported = true;
+        let count = self.count as f64;
+        Some(RageClick {
+            position: (self.sum.0 / count, self.sum.1 / count),
+            count: self.count,
+            duration: now.saturating_duration_since(self.first.1),
+        })
+    }
+}
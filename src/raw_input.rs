@@ -0,0 +1,235 @@
+//! Raw Input backend reporting unaccelerated device deltas
+
+use crate::clock::{Clock, SharedClock};
+use crate::devices::device_id;
+#[cfg(feature = "pointer")]
+use crate::pointer::PointerDecoder;
+#[cfg(feature = "pointer")]
+use crate::source::LastContact;
+use crate::queue::EventQueue;
+use crate::session::CapturePause;
+use crate::{AtomicCursorState, CursorEvent};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use windows::core::w;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Input::{
+    GetRawInputData, RegisterRawInputDevices, HRAWINPUT, MOUSE_MOVE_ABSOLUTE, RAWINPUT, RAWINPUTDEVICE,
+    RAWINPUTHEADER, RAWMOUSE, RIDEV_INPUTSINK, RID_INPUT, RIM_TYPEMOUSE,
+};
+#[cfg(feature = "pointer")]
+use windows::Win32::UI::Input::RIM_TYPEHID;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DestroyWindow, DispatchMessageW, GetMessageW, PostThreadMessageW, HWND_MESSAGE, MSG,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WM_INPUT, WM_QUIT,
+};
+
+/// HID usage page and usage for generic mice
+const HID_USAGE_PAGE_GENERIC: u16 = 0x01;
+const HID_USAGE_GENERIC_MOUSE: u16 = 0x02;
+pub(crate) const HID_USAGE_PAGE_DIGITIZER: u16 = 0x0D;
+pub(crate) const HID_USAGE_DIGITIZER_PEN: u16 = 0x02;
+pub(crate) const HID_USAGE_DIGITIZER_TOUCH_SCREEN: u16 = 0x04;
+
+/// What the raw input thread shares with the rest of the detector
+pub(crate) struct RawInputShared {
+    pub(crate) atomic_state: Arc<AtomicCursorState>,
+    pub(crate) event_queue: Arc<EventQueue<CursorEvent>>,
+    pub(crate) running: Arc<AtomicBool>,
+    /// Paused while the workstation is locked, the secure desktop is active or the user paused capture
+    pub(crate) pause: Arc<CapturePause>,
+    /// Id of the device that produced the latest packet, 0 when unknown
+    pub(crate) last_device: Arc<AtomicU64>,
+    /// Source of event timestamps
+    pub(crate) clock: SharedClock,
+    /// Emit `RawMotion` events rather than only tracking devices
+    pub(crate) emit_motion: bool,
+    /// Also register touch screens and pens
+    pub(crate) pointer_input: bool,
+    /// Latest digitizer contact, for the mouse messages promoted from it
+    #[cfg(feature = "pointer")]
+    pub(crate) last_contact: Arc<LastContact>,
+}
+
+/// Thread receiving `WM_INPUT` on a message-only window
+pub(crate) struct RawInputThread {
+    handle: thread::JoinHandle<()>,
+    thread_id: u32,
+}
+
+impl RawInputThread {
+    /// Register for raw mouse input and start pumping messages
+    pub(crate) fn spawn(shared: RawInputShared) -> Result<Self, String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        let handle = thread::Builder::new()
+            .name("luuma-raw-input".to_string())
+            .spawn(move || {
+                let window = match register_window(shared.pointer_input) {
+                    Ok(window) => window,
+                    Err(error) => {
+                        let _ = ready_tx.send(Err(error));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(unsafe { GetCurrentThreadId() }));
+
+                pump_messages(&shared);
+
+                unsafe {
+                    let _ = DestroyWindow(window);
+                }
+            })
+            .map_err(|e| format!("Failed to spawn raw input thread: {}", e))?;
+
+        match ready_rx.recv() {
+            Ok(Ok(thread_id)) => Ok(Self { handle, thread_id }),
+            Ok(Err(error)) => {
+                let _ = handle.join();
+                Err(error)
+            }
+            Err(_) => Err("Raw input thread exited during start-up".to_string()),
+        }
+    }
+
+    /// Quit the message loop and wait for the thread to exit
+    pub(crate) fn stop(self) -> Result<(), String> {
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        self.handle.join().map_err(|e| format!("Failed to join raw input thread: {:?}", e))
+    }
+}
+
+/// Create a message-only window and route raw mouse input to it, even in the background
+fn register_window(pointer_input: bool) -> Result<HWND, String> {
+    unsafe {
+        let window = CreateWindowExW(
+            WINDOW_EX_STYLE(0),
+            w!("STATIC"),
+            w!("LuumaRawInput"),
+            WINDOW_STYLE(0),
+            0,
+            0,
+            0,
+            0,
+            Some(HWND_MESSAGE),
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| format!("Failed to create raw input window: {}", e))?;
+
+        let device = |usage_page, usage| RAWINPUTDEVICE {
+            usUsagePage: usage_page,
+            usUsage: usage,
+            dwFlags: RIDEV_INPUTSINK,
+            hwndTarget: window,
+        };
+
+        let mut devices = vec![device(HID_USAGE_PAGE_GENERIC, HID_USAGE_GENERIC_MOUSE)];
+        if pointer_input {
+            devices.push(device(HID_USAGE_PAGE_DIGITIZER, HID_USAGE_DIGITIZER_TOUCH_SCREEN));
+            devices.push(device(HID_USAGE_PAGE_DIGITIZER, HID_USAGE_DIGITIZER_PEN));
+        }
+
+        if let Err(error) = RegisterRawInputDevices(&devices, std::mem::size_of::<RAWINPUTDEVICE>() as u32) {
+            let _ = DestroyWindow(window);
+            return Err(format!("Failed to register raw input devices: {}", error));
+        }
+
+        Ok(window)
+    }
+}
+
+fn pump_messages(shared: &RawInputShared) {
+    let mut message = MSG::default();
+    // Reused packet buffer; u64 elements keep RAWINPUT aligned
+    let mut buffer: Vec<u64> = Vec::new();
+    #[cfg(feature = "pointer")]
+    let mut pointer_decoder = PointerDecoder::new(Arc::clone(&shared.last_contact));
+
+    // GetMessageW returns 0 on WM_QUIT and -1 on failure
+    while unsafe { GetMessageW(&mut message, None, 0, 0) }.0 > 0 {
+        let capturing = shared.running.load(Ordering::Relaxed) && !shared.pause.is_paused();
+        if message.message == WM_INPUT && capturing {
+            if let Some(input) = read_input(HRAWINPUT(message.lParam.0 as _), &mut buffer) {
+                let device = device_id(input.header.hDevice);
+
+                if input.header.dwType == RIM_TYPEMOUSE.0 {
+                    shared.last_device.store(device, Ordering::Relaxed);
+
+                    let mouse = unsafe { input.data.mouse };
+                    if let Some(delta) = mouse_delta(&mouse).filter(|_| shared.emit_motion) {
+                        let raw_event = CursorEvent::RawMotion {
+                            delta,
+                            position: shared.atomic_state.get_position(),
+                            device_id: Some(device),
+                            timestamp: shared.clock.now(),
+                        };
+                        let _ = shared.event_queue.push(raw_event);
+                    }
+                }
+
+                #[cfg(feature = "pointer")]
+                if input.header.dwType == RIM_TYPEHID.0 {
+                    let (reports, report_size) = unsafe { hid_reports(input) };
+                    let timestamp = shared.clock.now();
+                    pointer_decoder.decode(input.header.hDevice, device, reports, report_size, timestamp, |event| {
+                        let _ = shared.event_queue.push(event);
+                    });
+                }
+            }
+        }
+
+        // Default processing releases the raw input buffer
+        unsafe {
+            DispatchMessageW(&message);
+        }
+    }
+}
+
+/// Copy a `WM_INPUT` packet into `buffer`, growing it for large HID packets
+fn read_input(handle: HRAWINPUT, buffer: &mut Vec<u64>) -> Option<&RAWINPUT> {
+    let header_size = std::mem::size_of::<RAWINPUTHEADER>() as u32;
+    let mut size = 0u32;
+
+    unsafe {
+        if GetRawInputData(handle, RID_INPUT, None, &mut size, header_size) == u32::MAX {
+            return None;
+        }
+
+        let words = (size as usize).max(std::mem::size_of::<RAWINPUT>()).div_ceil(8);
+        if buffer.len() < words {
+            buffer.resize(words, 0);
+        }
+
+        let read = GetRawInputData(handle, RID_INPUT, Some(buffer.as_mut_ptr() as *mut _), &mut size, header_size);
+        if read == u32::MAX {
+            return None;
+        }
+
+        Some(&*(buffer.as_ptr() as *const RAWINPUT))
+    }
+}
+
+/// HID reports following the header of a HID packet, with the size of each report
+#[cfg(feature = "pointer")]
+unsafe fn hid_reports(input: &RAWINPUT) -> (&[u8], usize) {
+    let hid = &input.data.hid;
+    let report_size = hid.dwSizeHid as usize;
+    let total = report_size * hid.dwCount as usize;
+    (std::slice::from_raw_parts(hid.bRawData.as_ptr(), total), report_size)
+}
+
+/// Relative motion carried by a mouse packet
+fn mouse_delta(mouse: &RAWMOUSE) -> Option<(f64, f64)> {
+    // Tablets and remote sessions report absolute coordinates, which carry no device delta
+    if mouse.usFlags.0 & MOUSE_MOVE_ABSOLUTE.0 != 0 || (mouse.lLastX == 0 && mouse.lLastY == 0) {
+        return None;
+    }
+
+    Some((mouse.lLastX as f64, mouse.lLastY as f64))
+}
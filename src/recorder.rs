@@ -0,0 +1,224 @@
+//! Session recording to newline-delimited JSON and timed replay
+
+use crate::clock::{Clock, SharedClock, SystemClock};
+use crate::sink::EventSink;
+use crate::{duration_ms, CursorDetector, CursorEvent};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A single record in a session file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Record {
+    /// A recorded cursor event
+    Event(#[serde(with = "crate::json::versioned")] CursorEvent),
+    /// An idle period that was elided from the recording timeline
+    IdleGap {
+        #[serde(with = "duration_ms")]
+        duration: Duration,
+    },
+}
+
+/// One line of a session file: a record and its offset from the session start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordEntry {
+    /// Milliseconds since recording started, excluding elided idle time
+    pub offset_ms: u64,
+    pub record: Record,
+}
+
+/// Sink that writes every event to a session file, one JSON entry per line
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    clock: SharedClock,
+    started: Instant,
+    last_event: Option<Instant>,
+    idle_threshold: Option<Duration>,
+    elided: Duration,
+}
+
+impl SessionRecorder {
+    /// Create (or truncate) a session file
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let file = File::create(path.as_ref())
+            .map_err(|e| format!("Failed to create session file {:?}: {}", path.as_ref(), e))?;
+        let clock: SharedClock = Arc::new(SystemClock);
+        Ok(Self {
+            writer: BufWriter::new(file),
+            started: clock.instant(),
+            clock,
+            last_event: None,
+            idle_threshold: None,
+            elided: Duration::ZERO,
+        })
+    }
+
+    /// Replace idle periods longer than `threshold` with a single `IdleGap` record
+    ///
+    /// The gap is removed from the offsets of later entries, so a player can either
+    /// wait it out or skip straight to the next event.
+    pub fn with_idle_gap_compression(mut self, threshold: Duration) -> Self {
+        self.idle_threshold = Some(threshold);
+        self
+    }
+
+    /// Take entry offsets from `clock` instead of the real time, starting from its current time
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self.started = self.clock.instant();
+        self
+    }
+
+    /// Append an event to the session file
+    pub fn record(&mut self, event: &CursorEvent) -> Result<(), String> {
+        let now = self.clock.instant();
+
+        if let (Some(threshold), Some(last_event)) = (self.idle_threshold, self.last_event) {
+            let gap = now.duration_since(last_event);
+            if gap > threshold {
+                let offset_ms = self.offset_ms(last_event);
+                self.elided += gap;
+                self.write_entry(&RecordEntry { offset_ms, record: Record::IdleGap { duration: gap } })?;
+            }
+        }
+
+        self.last_event = Some(now);
+        let offset_ms = self.offset_ms(now);
+        self.write_entry(&RecordEntry { offset_ms, record: Record::Event(event.clone()) })
+    }
+
+    /// Offset of an instant on the compressed timeline
+    fn offset_ms(&self, at: Instant) -> u64 {
+        at.duration_since(self.started).saturating_sub(self.elided).as_millis() as u64
+    }
+
+    fn write_entry(&mut self, entry: &RecordEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry).map_err(|e| format!("Failed to serialize record: {}", e))?;
+        writeln!(self.writer, "{}", line).map_err(|e| format!("Failed to write record: {}", e))
+    }
+}
+
+impl EventSink for SessionRecorder {
+    fn handle(&mut self, event: &CursorEvent) {
+        if let Err(error) = self.record(event) {
+            CursorDetector::log_message(&error);
+        }
+    }
+
+    fn flush(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Read every entry from a session file
+pub fn load_session<P: AsRef<Path>>(path: P) -> Result<Vec<RecordEntry>, String> {
+    let file = File::open(path.as_ref())
+        .map_err(|e| format!("Failed to open session file {:?}: {}", path.as_ref(), e))?;
+
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read session file: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid record on line {}: {}", index + 1, e))?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Replays a recorded session with its original timing
+pub struct SessionPlayer {
+    entries: Vec<RecordEntry>,
+    skip_idle_gaps: bool,
+    speed: f64,
+    clock: SharedClock,
+}
+
+impl SessionPlayer {
+    /// Load a session file for replay
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        Ok(Self::from_entries(load_session(path)?))
+    }
+
+    /// Create a player from already loaded entries
+    pub fn from_entries(entries: Vec<RecordEntry>) -> Self {
+        Self { entries, skip_idle_gaps: false, speed: 1.0, clock: Arc::new(SystemClock) }
+    }
+
+    /// Skip elided idle periods instead of waiting them out
+    pub fn skip_idle_gaps(mut self, skip: bool) -> Self {
+        self.skip_idle_gaps = skip;
+        self
+    }
+
+    /// Playback speed multiplier (2.0 plays twice as fast)
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = if speed > 0.0 { speed } else { 1.0 };
+        self
+    }
+
+    /// Wait between events on `clock` instead of the real time
+    ///
+    /// With a [`ManualClock`](crate::ManualClock) the session plays back instantly
+    /// and the clock ends up advanced by the session's duration.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Recorded entries in file order
+    pub fn entries(&self) -> &[RecordEntry] {
+        &self.entries
+    }
+
+    /// Play the session, blocking while each event is delivered at its recorded time
+    pub fn play<F>(&self, mut handler: F)
+    where
+        F: FnMut(&CursorEvent),
+    {
+        let _ = self.try_play(|event| {
+            handler(event);
+            Ok::<(), std::convert::Infallible>(())
+        });
+    }
+
+    /// Play the session until the handler fails, returning its error
+    pub fn try_play<F, E>(&self, mut handler: F) -> Result<(), E>
+    where
+        F: FnMut(&CursorEvent) -> Result<(), E>,
+    {
+        let started = self.clock.instant();
+        let mut waited = Duration::ZERO;
+
+        for entry in &self.entries {
+            match &entry.record {
+                Record::IdleGap { duration } => {
+                    if !self.skip_idle_gaps {
+                        waited += duration.div_f64(self.speed);
+                    }
+                }
+                Record::Event(event) => {
+                    let due = Duration::from_millis(entry.offset_ms).div_f64(self.speed) + waited;
+                    let elapsed = self.clock.instant().saturating_duration_since(started);
+                    if let Some(remaining) = due.checked_sub(elapsed) {
+                        self.clock.sleep(remaining);
+                    }
+                    handler(event)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
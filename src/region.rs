@@ -0,0 +1,162 @@
+//! Regions of interest for filtering events and reporting enter/exit
+
+use crate::monitor::{list_monitors, MonitorInfo};
+use crate::Rect;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Area covered by a region
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RegionArea {
+    /// Rectangle in virtual desktop coordinates
+    Screen(Rect),
+    /// Rectangle relative to a monitor's top-left corner (ids match [`MonitorInfo::id`])
+    Monitor { monitor_id: u64, rect: Rect },
+    /// A whole monitor
+    WholeMonitor(u64),
+}
+
+/// Named region of interest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Region {
+    pub name: String,
+    pub area: RegionArea,
+}
+
+/// Which positional events pass when regions are registered
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RegionFilter {
+    /// Only while the cursor is inside at least one region
+    Inside,
+    /// Only while the cursor is outside every region
+    Outside,
+}
+
+/// Regions and filter shared between the detector, its handles and the hook
+#[derive(Debug, Default)]
+pub(crate) struct RegionSet {
+    pub(crate) regions: Vec<Region>,
+    pub(crate) filter: Option<RegionFilter>,
+}
+
+pub(crate) type SharedRegions = Arc<RwLock<RegionSet>>;
+
+/// Add a region or replace the one with the same name
+pub(crate) fn add_region(shared: &SharedRegions, name: &str, area: RegionArea) {
+    let mut set = shared.write().unwrap_or_else(|e| e.into_inner());
+    set.regions.retain(|region| region.name != name);
+    set.regions.push(Region { name: name.to_string(), area });
+}
+
+/// Remove a region by name, reporting whether it existed
+pub(crate) fn remove_region(shared: &SharedRegions, name: &str) -> bool {
+    let mut set = shared.write().unwrap_or_else(|e| e.into_inner());
+    let before = set.regions.len();
+    set.regions.retain(|region| region.name != name);
+    set.regions.len() != before
+}
+
+/// Monitor layouts cached for resolving monitor-relative areas
+#[derive(Default)]
+pub(crate) struct MonitorCache {
+    monitors: Option<(Vec<MonitorInfo>, Instant)>,
+}
+
+impl MonitorCache {
+    /// Monitor layouts are re-read at least this often
+    const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Current monitors, re-enumerated once the cached layout is stale
+    pub(crate) fn monitors(&mut self) -> &[MonitorInfo] {
+        let stale = self.monitors.as_ref().is_none_or(|(_, read)| read.elapsed() >= Self::REFRESH_INTERVAL);
+        if stale {
+            self.monitors = Some((list_monitors(), Instant::now()));
+        }
+        self.monitors.as_ref().map(|(monitors, _)| monitors.as_slice()).unwrap_or_default()
+    }
+}
+
+/// Tracks which regions the cursor is in on the hook thread
+pub(crate) struct RegionTracker {
+    shared: SharedRegions,
+    inside: Vec<String>,
+    monitors: MonitorCache,
+}
+
+/// Region boundary crossing detected by the tracker
+pub(crate) struct RegionCrossing {
+    pub(crate) name: String,
+    pub(crate) entered: bool,
+}
+
+impl RegionTracker {
+    pub(crate) fn new(shared: SharedRegions) -> Self {
+        Self { shared, inside: Vec::new(), monitors: MonitorCache::default() }
+    }
+
+    /// Update membership for a position, returning the crossings and whether events pass the filter
+    pub(crate) fn update(&mut self, position: (f64, f64)) -> (Vec<RegionCrossing>, bool) {
+        let set = self.shared.read().unwrap_or_else(|e| e.into_inner());
+        if set.regions.is_empty() && self.inside.is_empty() {
+            return (Vec::new(), true);
+        }
+
+        let needs_monitors = set.regions.iter().any(|region| region.area.is_monitor_relative());
+        let monitors = if needs_monitors { self.monitors.monitors() } else { &[] };
+
+        let now_inside: Vec<String> = set
+            .regions
+            .iter()
+            .filter(|region| resolve(&region.area, monitors).is_some_and(|rect| rect.contains(position.0, position.1)))
+            .map(|region| region.name.clone())
+            .collect();
+
+        let mut crossings: Vec<RegionCrossing> = self
+            .inside
+            .iter()
+            .filter(|name| !now_inside.contains(name))
+            .map(|name| RegionCrossing { name: name.clone(), entered: false })
+            .collect();
+        crossings.extend(
+            now_inside
+                .iter()
+                .filter(|name| !self.inside.contains(name))
+                .map(|name| RegionCrossing { name: name.clone(), entered: true }),
+        );
+
+        let passes = match set.filter {
+            Some(RegionFilter::Inside) if !set.regions.is_empty() => !now_inside.is_empty(),
+            Some(RegionFilter::Outside) => now_inside.is_empty(),
+            _ => true,
+        };
+        drop(set);
+
+        self.inside = now_inside;
+        (crossings, passes)
+    }
+}
+
+impl RegionArea {
+    /// Whether resolving the area needs the monitor layout
+    pub(crate) fn is_monitor_relative(&self) -> bool {
+        !matches!(self, RegionArea::Screen(_))
+    }
+}
+
+/// Screen rectangle of a region, `None` when its monitor is not connected
+pub(crate) fn resolve(area: &RegionArea, monitors: &[MonitorInfo]) -> Option<Rect> {
+    let monitor = |id: u64| monitors.iter().find(|monitor| monitor.id == id);
+    match *area {
+        RegionArea::Screen(rect) => Some(rect),
+        RegionArea::Monitor { monitor_id, rect } => monitor(monitor_id).map(|monitor| {
+            Rect::new(
+                monitor.bounds.left + rect.left,
+                monitor.bounds.top + rect.top,
+                monitor.bounds.left + rect.right,
+                monitor.bounds.top + rect.bottom,
+            )
+        }),
+        RegionArea::WholeMonitor(monitor_id) => monitor(monitor_id).map(|monitor| monitor.bounds),
+    }
+}
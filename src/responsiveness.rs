@@ -0,0 +1,151 @@
+//! Perceived responsiveness from busy cursors shown after clicks
+
+use crate::window::window_at;
+use crate::{duration_ms, CursorEvent, CursorType, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A busy cursor appearing later than this after a click is not attributed to it
+const BUSY_ONSET_WINDOW: Duration = Duration::from_millis(500);
+
+/// Application name used when the clicked process cannot be determined
+const UNKNOWN_APPLICATION: &str = "unknown";
+
+/// Wait and app-starting cursor statistics for one application
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AppResponsiveness {
+    /// Clicks while the application was in the foreground
+    pub clicks: u64,
+    /// Clicks followed by a wait or app-starting cursor
+    pub busy_clicks: u64,
+    /// Total time busy cursors were shown after clicks
+    #[serde(with = "duration_ms")]
+    pub total_busy: Duration,
+    /// Longest single busy period
+    #[serde(with = "duration_ms")]
+    pub max_busy: Duration,
+    /// Milliseconds from each busy click until the normal cursor returned
+    pub click_latencies_ms: Vec<u64>,
+}
+
+impl AppResponsiveness {
+    /// Share of clicks that made the user wait
+    pub fn busy_ratio(&self) -> f64 {
+        if self.clicks == 0 {
+            0.0
+        } else {
+            self.busy_clicks as f64 / self.clicks as f64
+        }
+    }
+
+    /// Mean busy period across clicks that showed a busy cursor
+    pub fn mean_busy(&self) -> Duration {
+        match self.busy_clicks {
+            0 => Duration::ZERO,
+            count => self.total_busy / count as u32,
+        }
+    }
+
+    /// Click-to-ready latency at a percentile (0.0..=100.0) of busy clicks
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut latencies = self.click_latencies_ms.clone();
+        latencies.sort_unstable();
+        let last = latencies.len().checked_sub(1)?;
+        let index = ((percentile.clamp(0.0, 100.0) / 100.0) * last as f64).round() as usize;
+        Some(Duration::from_millis(latencies[index]))
+    }
+}
+
+/// Responsiveness per foreground application, keyed by executable name
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResponsivenessReport {
+    pub applications: HashMap<String, AppResponsiveness>,
+}
+
+impl ResponsivenessReport {
+    /// Convert the report to JSON string
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Convert the report to pretty-formatted JSON string
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// A click waiting to see whether a busy cursor follows
+struct PendingClick {
+    application: String,
+    clicked: Timestamp,
+    busy_since: Option<Timestamp>,
+}
+
+/// Pairs clicks with the busy cursors that follow them on the processing thread
+#[derive(Default)]
+pub(crate) struct ResponsivenessTracker {
+    report: ResponsivenessReport,
+    foreground: Option<String>,
+    pending: Option<PendingClick>,
+}
+
+impl ResponsivenessTracker {
+    pub(crate) fn report(&self) -> ResponsivenessReport {
+        self.report.clone()
+    }
+
+    pub(crate) fn record(&mut self, event: &CursorEvent) {
+        match event {
+            CursorEvent::FocusChanged { process_name, .. } => self.foreground = Some(process_name.clone()),
+            CursorEvent::Click { position, window, timestamp, .. } => {
+                // A newer click ends whatever the previous one was waiting for
+                self.finish(*timestamp);
+
+                let application = self
+                    .foreground
+                    .clone()
+                    .or_else(|| window.as_ref().map(|window| window.process_name.clone()))
+                    .or_else(|| window_at(*position).map(|window| window.process_name))
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| UNKNOWN_APPLICATION.to_string());
+                self.report.applications.entry(application.clone()).or_default().clicks += 1;
+                self.pending = Some(PendingClick { application, clicked: *timestamp, busy_since: None });
+            }
+            CursorEvent::Move { cursor_type, timestamp, .. } => self.cursor_shown(*cursor_type, *timestamp),
+            CursorEvent::TypeChange { new_type, timestamp, .. } => self.cursor_shown(*new_type, *timestamp),
+            _ => {}
+        }
+    }
+
+    fn cursor_shown(&mut self, cursor_type: CursorType, timestamp: Timestamp) {
+        let busy = matches!(cursor_type, CursorType::Wait | CursorType::AppStarting);
+        let Some(pending) = self.pending.as_mut() else {
+            return;
+        };
+
+        match (busy, pending.busy_since) {
+            (true, None) if timestamp.duration_since(pending.clicked) <= BUSY_ONSET_WINDOW => {
+                pending.busy_since = Some(timestamp);
+            }
+            // No busy cursor soon after the click, so it was handled instantly
+            (false, None) if timestamp.duration_since(pending.clicked) > BUSY_ONSET_WINDOW => self.pending = None,
+            (false, Some(_)) => self.finish(timestamp),
+            _ => {}
+        }
+    }
+
+    /// Close the pending click, recording its busy period if it had one
+    fn finish(&mut self, timestamp: Timestamp) {
+        let Some(PendingClick { application, clicked, busy_since: Some(busy_since) }) = self.pending.take() else {
+            return;
+        };
+
+        let busy = timestamp.duration_since(busy_since);
+        let app = self.report.applications.entry(application).or_default();
+        app.busy_clicks += 1;
+        app.total_busy += busy;
+        app.max_busy = app.max_busy.max(busy);
+        app.click_latencies_ms.push(timestamp.duration_since(clicked).as_millis() as u64);
+    }
+}
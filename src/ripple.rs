@@ -0,0 +1,179 @@
+//! Click ripples and a cursor highlight drawn on an overlay, for screencasts
+
+use crate::overlay::{circle_bounds, Overlay, OverlayCanvas, OverlayConfig, Rgba};
+use crate::sink::EventSink;
+use crate::{CursorEvent, MouseButton, Rect};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Look of the click ripples and the cursor highlight
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RippleConfig {
+    /// Ring color for left clicks
+    pub left_color: Rgba,
+    /// Ring color for right and middle clicks
+    pub right_color: Rgba,
+    /// Radius in pixels a ripple grows to
+    pub radius: f64,
+    /// Ring thickness in pixels
+    pub stroke_width: f64,
+    /// How long a ripple takes to expand and fade out
+    #[serde(with = "crate::duration_ms")]
+    pub duration: Duration,
+    /// Disc drawn under the cursor, `None` for no highlight
+    pub highlight: Option<Rgba>,
+    /// Radius of the highlight disc in pixels
+    pub highlight_radius: f64,
+    /// Frames drawn per second while something animates
+    pub frame_rate: u32,
+}
+
+impl Default for RippleConfig {
+    fn default() -> Self {
+        Self {
+            left_color: Rgba::new(255, 196, 0, 230),
+            right_color: Rgba::new(0, 160, 255, 230),
+            radius: 36.0,
+            stroke_width: 4.0,
+            duration: Duration::from_millis(450),
+            highlight: Some(Rgba::new(255, 255, 0, 70)),
+            highlight_radius: 28.0,
+            frame_rate: 60,
+        }
+    }
+}
+
+/// A click still animating
+struct Ripple {
+    center: (f64, f64),
+    color: Rgba,
+    started: Instant,
+}
+
+/// What the sink side shares with the overlay's draw callback
+struct RippleState {
+    config: RippleConfig,
+    enabled: bool,
+    show_highlight: bool,
+    cursor: Option<(f64, f64)>,
+    ripples: Vec<Ripple>,
+    /// Areas painted in the previous frame, cleared before the next one
+    painted: Vec<Rect>,
+    /// Cursor position the highlight was last drawn at
+    drawn_cursor: Option<(f64, f64)>,
+    /// Whether the previous frame had ripples that now need clearing
+    drew_ripples: bool,
+}
+
+impl RippleState {
+    /// Repaint what changed, returning whether the frame differs from the last one
+    fn draw(&mut self, canvas: &mut OverlayCanvas) -> bool {
+        let now = Instant::now();
+        let duration = self.config.duration;
+        self.ripples.retain(|ripple| now.duration_since(ripple.started) < duration);
+
+        let highlight = self.config.highlight.filter(|_| self.enabled && self.show_highlight);
+        let cursor = highlight.and(self.cursor);
+        if !self.enabled {
+            self.ripples.clear();
+        }
+        if self.ripples.is_empty() && !self.drew_ripples && cursor == self.drawn_cursor {
+            return false;
+        }
+
+        for area in self.painted.drain(..) {
+            canvas.clear_rect(area);
+        }
+
+        if let (Some(color), Some(center)) = (highlight, cursor) {
+            canvas.fill_circle(center, self.config.highlight_radius, color);
+            self.painted.push(circle_bounds(center, self.config.highlight_radius + 1.0));
+        }
+        self.drawn_cursor = cursor;
+
+        for ripple in &self.ripples {
+            // Ease out so the ring pops quickly and settles while fading
+            let progress = now.duration_since(ripple.started).as_secs_f64() / duration.as_secs_f64().max(0.001);
+            let radius = self.config.radius * (1.0 - (1.0 - progress).powi(3));
+            let color = ripple.color.with_opacity(1.0 - progress);
+            canvas.stroke_circle(ripple.center, radius, self.config.stroke_width, color);
+            self.painted.push(circle_bounds(ripple.center, self.config.radius + self.config.stroke_width));
+        }
+        self.drew_ripples = !self.ripples.is_empty();
+        true
+    }
+}
+
+/// Draws an expanding ring at every click and a highlight around the cursor
+///
+/// Attach it as a sink and keep a clone to toggle it while monitoring:
+/// `detector.add_sink("ripples", visualizer.clone(), SinkFilter::all())`. The
+/// overlay closes when the last clone is dropped.
+#[derive(Clone)]
+pub struct ClickVisualizer {
+    state: Arc<Mutex<RippleState>>,
+    overlay: Arc<Overlay>,
+}
+
+impl ClickVisualizer {
+    /// Create the overlay covering the virtual desktop
+    pub fn spawn(config: RippleConfig) -> Result<Self, String> {
+        let state = Arc::new(Mutex::new(RippleState {
+            config,
+            enabled: true,
+            show_highlight: true,
+            cursor: None,
+            ripples: Vec::new(),
+            painted: Vec::new(),
+            drawn_cursor: None,
+            drew_ripples: false,
+        }));
+
+        let draw_state = Arc::clone(&state);
+        let overlay_config = OverlayConfig { bounds: None, frame_rate: config.frame_rate };
+        let overlay = Overlay::spawn(overlay_config, move |canvas| {
+            draw_state.lock().unwrap_or_else(|e| e.into_inner()).draw(canvas)
+        })?;
+
+        Ok(Self { state, overlay: Arc::new(overlay) })
+    }
+
+    /// Turn the ripples and highlight on or off
+    pub fn set_enabled(&self, enabled: bool) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).enabled = enabled;
+    }
+
+    /// Whether anything is drawn
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    /// Show or hide the highlight around the cursor, keeping the click ripples
+    pub fn set_highlight(&self, visible: bool) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).show_highlight = visible;
+    }
+
+    /// Raw `HWND` of the overlay, e.g. to exclude it from a screen capture
+    pub fn hwnd(&self) -> usize {
+        self.overlay.hwnd()
+    }
+}
+
+impl EventSink for ClickVisualizer {
+    fn handle(&mut self, event: &CursorEvent) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            CursorEvent::Move { position, .. } => state.cursor = Some(*position),
+            CursorEvent::Click { button, position, .. } if state.enabled => {
+                let color = match button {
+                    MouseButton::Left => state.config.left_color,
+                    MouseButton::Right | MouseButton::Middle => state.config.right_color,
+                };
+                state.cursor = Some(*position);
+                state.ripples.push(Ripple { center: *position, color, started: Instant::now() });
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Settings changed while monitoring without restarting the hook
+
+use crate::filter::{EventKindMask, SharedKindMask};
+use crate::middleware::{EventMiddleware, RateLimit};
+use crate::region::{RegionFilter, SharedRegions};
+use crate::sampling::{AdaptiveSampler, SamplingMode};
+use crate::CursorEvent;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Throttling and filtering a running detector picks up on its next event
+///
+/// Passed to [`DetectorHandle::configure`](crate::DetectorHandle::configure) filled
+/// with the current settings. The hook keeps running throughout, so unlike a stop
+/// and start no input, button state or region membership is lost.
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeConfig {
+    /// Event kinds created and delivered
+    pub event_mask: EventKindMask,
+    /// How Move events are sampled from the hook stream
+    pub sampling: SamplingMode,
+    /// Thinning of every delivered event, after privacy masking and before other stages
+    pub rate_limit: Option<RateLimit>,
+    /// Only emit Move and Click events inside or outside the registered regions
+    pub region_filter: Option<RegionFilter>,
+}
+
+/// The settings owned by the hook and processing threads, with a generation they poll
+#[derive(Debug, Default)]
+pub(crate) struct SharedTuning {
+    generation: AtomicU64,
+    settings: Mutex<Tuning>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tuning {
+    pub(crate) sampling: SamplingMode,
+    pub(crate) rate_limit: Option<RateLimit>,
+}
+
+impl SharedTuning {
+    pub(crate) fn new(sampling: SamplingMode) -> Self {
+        Self { generation: AtomicU64::new(0), settings: Mutex::new(Tuning { sampling, rate_limit: None }) }
+    }
+
+    /// Current settings with their generation
+    pub(crate) fn snapshot(&self) -> (u64, Tuning) {
+        let settings = self.settings.lock().unwrap_or_else(|e| e.into_inner());
+        (self.generation.load(Ordering::Acquire), settings.clone())
+    }
+
+    /// Settings changed since `seen`, updating it; a single atomic load when nothing changed
+    pub(crate) fn changed(&self, seen: &mut u64) -> Option<Tuning> {
+        if self.generation.load(Ordering::Acquire) == *seen {
+            return None;
+        }
+        let (generation, settings) = self.snapshot();
+        *seen = generation;
+        Some(settings)
+    }
+
+    pub(crate) fn set_sampling(&self, sampling: SamplingMode) {
+        self.update(|settings| settings.sampling = sampling);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Tuning)) {
+        let mut settings = self.settings.lock().unwrap_or_else(|e| e.into_inner());
+        change(&mut settings);
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+}
+
+/// Let `change` edit the runtime settings, then publish them to the running threads
+///
+/// Holding the tuning lock throughout keeps concurrent calls from undoing each other.
+pub(crate) fn configure(
+    event_mask: &SharedKindMask,
+    regions: &SharedRegions,
+    tuning: &SharedTuning,
+    change: impl FnOnce(&mut RuntimeConfig),
+) {
+    tuning.update(|settings| {
+        let mut config = RuntimeConfig {
+            event_mask: event_mask.load(),
+            sampling: settings.sampling,
+            rate_limit: settings.rate_limit.clone(),
+            region_filter: regions.read().unwrap_or_else(|e| e.into_inner()).filter,
+        };
+        change(&mut config);
+        event_mask.store(config.event_mask);
+        regions.write().unwrap_or_else(|e| e.into_inner()).filter = config.region_filter;
+        settings.sampling = config.sampling;
+        settings.rate_limit = config.rate_limit;
+    });
+}
+
+/// Sampler for a mode, `None` when every sample is kept
+pub(crate) fn sampler(mode: SamplingMode) -> Option<AdaptiveSampler> {
+    match mode {
+        SamplingMode::All => None,
+        SamplingMode::Adaptive(config) => Some(AdaptiveSampler::new(config)),
+    }
+}
+
+/// Processing stage applying the runtime rate limit
+pub(crate) struct Throttle {
+    tuning: Arc<SharedTuning>,
+    generation: u64,
+    rate_limit: Option<RateLimit>,
+}
+
+impl Throttle {
+    pub(crate) fn new(tuning: Arc<SharedTuning>) -> Self {
+        let (generation, settings) = tuning.snapshot();
+        Self { tuning, generation, rate_limit: settings.rate_limit }
+    }
+}
+
+impl EventMiddleware for Throttle {
+    fn process(&mut self, event: CursorEvent) -> Option<CursorEvent> {
+        if let Some(settings) = self.tuning.changed(&mut self.generation) {
+            self.rate_limit = settings.rate_limit;
+        }
+        match &mut self.rate_limit {
+            Some(rate_limit) => rate_limit.allow(event.kind()).then_some(event),
+            None => Some(event),
+        }
+    }
+}
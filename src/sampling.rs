@@ -0,0 +1,300 @@
+//! Velocity-based sampling of Move events
+
+#[cfg(windows)]
+use crate::clock::{Clock, SharedClock};
+#[cfg(windows)]
+use crate::queue::EventQueue;
+#[cfg(windows)]
+use crate::CursorEvent;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::cell::RefCell;
+#[cfg(windows)]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(windows)]
+use std::time::Instant;
+#[cfg(windows)]
+use windows::Win32::Foundation::HWND;
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{KillTimer, SetTimer};
+
+/// How often a held-back Move is checked for the cursor having come to rest
+#[cfg(windows)]
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How Move events are sampled from the raw hook stream
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SamplingMode {
+    /// Emit a Move event for every position change
+    #[default]
+    All,
+    /// Emit densely during fast motion and sparsely when nearly stationary
+    Adaptive(AdaptiveSampling),
+}
+
+/// Tuning for adaptive Move sampling
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveSampling {
+    /// Speed in pixels per second at and above which every sample is kept
+    pub fast_speed: f64,
+    /// Longest gap between samples while the cursor drifts slowly
+    #[serde(with = "crate::duration_ms")]
+    pub slow_interval: Duration,
+    /// Minimum distance in pixels from the previous sample while moving slowly
+    pub min_distance: f64,
+    /// Heading change in degrees that always keeps a sample, preserving corners
+    pub turn_angle: f64,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        Self {
+            fast_speed: 1500.0,
+            slow_interval: Duration::from_millis(100),
+            min_distance: 3.0,
+            turn_angle: 30.0,
+        }
+    }
+}
+
+/// Decides which raw samples become Move events, owned by the hook thread
+#[cfg(windows)]
+#[derive(Debug)]
+pub(crate) struct AdaptiveSampler {
+    config: AdaptiveSampling,
+    last_raw: Option<((f64, f64), Instant)>,
+    last_emitted: Option<((f64, f64), Instant)>,
+    last_heading: Option<f64>,
+    speed: f64,
+}
+
+#[cfg(windows)]
+impl AdaptiveSampler {
+    pub(crate) fn new(config: AdaptiveSampling) -> Self {
+        Self { config, last_raw: None, last_emitted: None, last_heading: None, speed: 0.0 }
+    }
+
+    /// Silence after which a thinned-out sample is delivered as the point the cursor stopped on
+    pub(crate) fn slow_interval(&self) -> Duration {
+        self.config.slow_interval
+    }
+
+    /// Feed a raw position and decide whether it should be emitted
+    pub(crate) fn should_emit(&mut self, position: (f64, f64), now: Instant) -> bool {
+        // Smoothed instantaneous speed from consecutive raw samples
+        if let Some((last_position, last_time)) = self.last_raw {
+            let elapsed = now.duration_since(last_time).as_secs_f64();
+            if elapsed > 0.0 {
+                let instant_speed = distance(position, last_position) / elapsed;
+                self.speed = self.speed * 0.5 + instant_speed * 0.5;
+            }
+        }
+        self.last_raw = Some((position, now));
+
+        let (emitted_position, emitted_time) = match self.last_emitted {
+            Some(last_emitted) => last_emitted,
+            None => return self.accept(position, now),
+        };
+
+        let moved = distance(position, emitted_position);
+        let heading = (position.1 - emitted_position.1).atan2(position.0 - emitted_position.0);
+
+        // Fast motion keeps every sample
+        if self.speed >= self.config.fast_speed {
+            return self.accept(position, now);
+        }
+
+        // Sharp turns keep the corner so the trajectory shape survives thinning
+        if let Some(last_heading) = self.last_heading {
+            let mut turn = (heading - last_heading).abs().to_degrees();
+            if turn > 180.0 {
+                turn = 360.0 - turn;
+            }
+            if moved >= self.config.min_distance && turn >= self.config.turn_angle {
+                return self.accept(position, now);
+            }
+        }
+
+        // Slower motion waits longer between samples, scaling down to the slow interval
+        let slowness = 1.0 - (self.speed / self.config.fast_speed).clamp(0.0, 1.0);
+        let required = self.config.slow_interval.mul_f64(slowness);
+        if moved >= self.config.min_distance && now.duration_since(emitted_time) >= required {
+            return self.accept(position, now);
+        }
+
+        false
+    }
+
+    fn accept(&mut self, position: (f64, f64), now: Instant) -> bool {
+        if let Some((emitted_position, _)) = self.last_emitted {
+            if position != emitted_position {
+                self.last_heading = Some((position.1 - emitted_position.1).atan2(position.0 - emitted_position.0));
+            }
+        }
+        self.last_emitted = Some((position, now));
+        true
+    }
+}
+
+/// Latest Move the sampler thinned out, shared between the hook and a timer on its thread
+///
+/// Without it the last slow samples before the cursor stops could all be dropped,
+/// leaving trajectories short of their endpoint. The held event is delivered ahead
+/// of the next button or wheel event, or once the cursor has been still for the
+/// sampler's slow interval, and is discarded when a later sample is kept.
+#[cfg(windows)]
+#[derive(Debug)]
+pub(crate) struct HeldMove {
+    clock: SharedClock,
+    held: Mutex<Option<(CursorEvent, Instant, Duration)>>,
+}
+
+#[cfg(windows)]
+impl HeldMove {
+    pub(crate) fn new(clock: SharedClock) -> Self {
+        Self { clock, held: Mutex::default() }
+    }
+
+    /// Hold back `event`, replacing any older one, until `silence` passes without another sample
+    pub(crate) fn hold(&self, event: CursorEvent, silence: Duration) {
+        *self.held.lock().unwrap_or_else(|e| e.into_inner()) = Some((event, self.clock.instant(), silence));
+    }
+
+    /// Forget the held event, superseded by a sample that was kept
+    pub(crate) fn clear(&self) {
+        self.held.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    /// The held event, to be delivered now
+    pub(crate) fn take(&self) -> Option<CursorEvent> {
+        self.held.lock().unwrap_or_else(|e| e.into_inner()).take().map(|(event, _, _)| event)
+    }
+
+    /// The held event once the cursor has been still for its silence
+    fn take_settled(&self) -> Option<CursorEvent> {
+        let now = self.clock.instant();
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        match held.as_ref() {
+            Some((_, since, silence)) if now.saturating_duration_since(*since) >= *silence => {
+                held.take().map(|(event, _, _)| event)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(windows)]
+type HeldMoveTarget = (Arc<HeldMove>, Arc<EventQueue<CursorEvent>>);
+
+#[cfg(windows)]
+thread_local! {
+    // Timer procedures carry no user data and run on the thread that set the timer
+    static HELD_MOVE_TARGET: RefCell<Option<HeldMoveTarget>> = const { RefCell::new(None) };
+}
+
+/// Timer on the hook thread delivering a held-back Move once the cursor rests, removed on drop
+///
+/// Dropping it delivers the Move still held, so stopping never loses the endpoint.
+#[cfg(windows)]
+pub(crate) struct HeldMoveWatcher {
+    timer: usize,
+}
+
+#[cfg(windows)]
+impl HeldMoveWatcher {
+    pub(crate) fn install(held: Arc<HeldMove>, event_queue: Arc<EventQueue<CursorEvent>>) -> Result<Self, String> {
+        // A thread timer with a procedure is dispatched by the hook thread's message loop
+        let timer = unsafe { SetTimer(None, 0, POLL_INTERVAL.as_millis() as u32, Some(held_move_proc)) };
+        if timer == 0 {
+            return Err("Failed to start sampling timer".to_string());
+        }
+        HELD_MOVE_TARGET.with(|slot| *slot.borrow_mut() = Some((held, event_queue)));
+        Ok(Self { timer })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for HeldMoveWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = KillTimer(None, self.timer);
+        }
+        if let Some((held, event_queue)) = HELD_MOVE_TARGET.with(|slot| slot.borrow_mut().take()) {
+            if let Some(event) = held.take() {
+                let _ = event_queue.push(event);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn held_move_proc(_hwnd: HWND, _message: u32, _id: usize, _time: u32) {
+    HELD_MOVE_TARGET.with(|slot| {
+        if let Some((held, event_queue)) = slot.borrow().as_ref() {
+            if let Some(event) = held.take_settled() {
+                let _ = event_queue.push(event);
+            }
+        }
+    });
+}
+
+#[cfg(windows)]
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::{AdaptiveSampler, AdaptiveSampling, HeldMove};
+    use crate::clock::ManualClock;
+    use crate::{CursorEvent, MouseButton, Timestamp};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    fn event(millis: u64) -> CursorEvent {
+        CursorEvent::Release {
+            button: MouseButton::Left,
+            press_position: None,
+            held_for: Duration::ZERO,
+            source: Default::default(),
+            device_id: None,
+            precise_time: None,
+            timestamp: Timestamp::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn thins_slow_drift() {
+        let mut sampler = AdaptiveSampler::new(AdaptiveSampling::default());
+        let start = Instant::now();
+        assert!(sampler.should_emit((0.0, 0.0), start));
+        assert!(!sampler.should_emit((1.0, 0.0), start + Duration::from_millis(50)));
+        assert!(sampler.should_emit((5.0, 0.0), start + Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn delivers_a_held_move_after_silence() {
+        let clock = Arc::new(ManualClock::new(Timestamp::from_millis(0)));
+        let held = HeldMove::new(clock.clone());
+        held.hold(event(1), Duration::from_millis(100));
+        clock.advance(Duration::from_millis(60));
+        assert!(held.take_settled().is_none());
+
+        // A newer skipped sample restarts the silence
+        held.hold(event(2), Duration::from_millis(100));
+        clock.advance(Duration::from_millis(60));
+        assert!(held.take_settled().is_none());
+        clock.advance(Duration::from_millis(40));
+        assert_eq!(held.take_settled().map(|event| event.timestamp()), Some(Timestamp::from_millis(2)));
+        assert!(held.take().is_none());
+    }
+
+    #[test]
+    fn a_kept_sample_discards_the_held_move() {
+        let held = HeldMove::new(Arc::new(ManualClock::new(Timestamp::from_millis(0))));
+        held.hold(event(1), Duration::ZERO);
+        held.clear();
+        assert!(held.take().is_none());
+    }
+}
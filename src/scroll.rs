@@ -0,0 +1,206 @@
+//! Wheel scrolling, and the grouping of wheel input into scroll sessions
+
+#[cfg(windows)]
+use crate::clock::{Clock, SharedClock};
+#[cfg(windows)]
+use crate::queue::EventQueue;
+#[cfg(windows)]
+use crate::settings::system_parameter;
+#[cfg(windows)]
+use crate::CursorEvent;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::cell::RefCell;
+#[cfg(windows)]
+use std::sync::{Arc, Mutex};
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+#[cfg(windows)]
+use windows::Win32::Foundation::HWND;
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    KillTimer, SetTimer, SPI_GETWHEELSCROLLCHARS, SPI_GETWHEELSCROLLLINES, WHEEL_DELTA,
+};
+
+/// How often an open session is checked for having gone idle
+#[cfg(windows)]
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Wheel a scroll came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ScrollAxis {
+    /// Regular wheel; positive deltas scroll up, away from the user
+    Vertical,
+    /// Tilt wheel or sideways touchpad scrolling; positive deltas scroll right
+    Horizontal,
+}
+
+impl std::fmt::Display for ScrollAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrollAxis::Vertical => write!(f, "vertical"),
+            ScrollAxis::Horizontal => write!(f, "horizontal"),
+        }
+    }
+}
+
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    axis: ScrollAxis,
+    position: (f64, f64),
+    started: Instant,
+    last: Instant,
+    total_delta: i32,
+    /// Sign of the latest nonzero delta
+    direction: i32,
+    reversals: u32,
+}
+
+#[cfg(windows)]
+impl Session {
+    fn new(axis: ScrollAxis, position: (f64, f64), now: Instant) -> Self {
+        Self { axis, position, started: now, last: now, total_delta: 0, direction: 0, reversals: 0 }
+    }
+
+    fn add(&mut self, delta: i32, now: Instant) {
+        let direction = delta.signum();
+        if direction != 0 && self.direction != 0 && direction != self.direction {
+            self.reversals += 1;
+        }
+        if direction != 0 {
+            self.direction = direction;
+        }
+        self.total_delta = self.total_delta.saturating_add(delta);
+        self.last = now;
+    }
+}
+
+/// Open scroll session, fed by the mouse hook and closed by a timer on the same thread
+///
+/// Wheel input belongs to one session until it pauses for longer than the idle gap
+/// or switches axis. Touchpad inertia keeps arriving in quick small steps after the
+/// fingers lift, so a flick and its glide end up in the same session.
+#[cfg(windows)]
+#[derive(Debug)]
+pub(crate) struct ScrollTracker {
+    idle_gap: Duration,
+    clock: SharedClock,
+    session: Mutex<Option<Session>>,
+}
+
+#[cfg(windows)]
+impl ScrollTracker {
+    pub(crate) fn new(idle_gap: Duration, clock: SharedClock) -> Self {
+        Self { idle_gap, clock, session: Mutex::default() }
+    }
+
+    /// Add wheel input, returning the session it ended by switching axis or following a pause
+    pub(crate) fn wheel(&self, axis: ScrollAxis, delta: i32, position: (f64, f64)) -> Option<CursorEvent> {
+        let now = self.clock.instant();
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let ended = session
+            .take_if(|open| open.axis != axis || now.saturating_duration_since(open.last) > self.idle_gap)
+            .map(|open| self.report(open));
+        session.get_or_insert_with(|| Session::new(axis, position, now)).add(delta, now);
+        ended
+    }
+
+    /// `ScrollSession` for a session that has gone idle
+    fn poll(&self) -> Option<CursorEvent> {
+        let now = self.clock.instant();
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        session.take_if(|open| now.saturating_duration_since(open.last) > self.idle_gap).map(|open| self.report(open))
+    }
+
+    /// `ScrollSession` for the open session however recent, when monitoring stops
+    fn flush(&self) -> Option<CursorEvent> {
+        let open = self.session.lock().unwrap_or_else(|e| e.into_inner()).take();
+        open.map(|open| self.report(open))
+    }
+
+    fn report(&self, session: Session) -> CursorEvent {
+        CursorEvent::ScrollSession {
+            axis: session.axis,
+            total_delta: session.total_delta,
+            duration: session.last.saturating_duration_since(session.started),
+            lines: lines(session.axis, session.total_delta),
+            reversals: session.reversals,
+            position: session.position,
+            timestamp: self.clock.now(),
+        }
+    }
+}
+
+/// Lines (characters for the horizontal wheel) `delta` scrolls with the current system setting
+///
+/// `None` when the wheel is set to scroll a screen at a time, or the setting cannot be read.
+#[cfg(windows)]
+fn lines(axis: ScrollAxis, delta: i32) -> Option<f64> {
+    let action = match axis {
+        ScrollAxis::Vertical => SPI_GETWHEELSCROLLLINES,
+        ScrollAxis::Horizontal => SPI_GETWHEELSCROLLCHARS,
+    };
+    let mut per_notch = 0u32;
+    system_parameter(action, &mut per_notch, "wheel scroll setting").ok()?;
+    // u32::MAX is WHEEL_PAGESCROLL, a screen per notch
+    (per_notch != u32::MAX).then(|| delta as f64 / WHEEL_DELTA as f64 * per_notch as f64)
+}
+
+#[cfg(windows)]
+type ScrollTarget = (Arc<ScrollTracker>, Arc<EventQueue<CursorEvent>>);
+
+#[cfg(windows)]
+thread_local! {
+    // Timer procedures carry no user data and run on the thread that set the timer
+    static SCROLL_TARGET: RefCell<Option<ScrollTarget>> = const { RefCell::new(None) };
+}
+
+/// Timer on the hook thread ending scroll sessions that went idle, removed on drop
+///
+/// Dropping it reports the session still open, so stopping never loses one.
+#[cfg(windows)]
+pub(crate) struct ScrollWatcher {
+    timer: usize,
+}
+
+#[cfg(windows)]
+impl ScrollWatcher {
+    pub(crate) fn install(
+        tracker: Arc<ScrollTracker>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+    ) -> Result<Self, String> {
+        // A thread timer with a procedure is dispatched by the hook thread's message loop
+        let timer = unsafe { SetTimer(None, 0, POLL_INTERVAL.as_millis() as u32, Some(scroll_proc)) };
+        if timer == 0 {
+            return Err("Failed to start scroll session timer".to_string());
+        }
+        SCROLL_TARGET.with(|slot| *slot.borrow_mut() = Some((tracker, event_queue)));
+        Ok(Self { timer })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for ScrollWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = KillTimer(None, self.timer);
+        }
+        if let Some((tracker, event_queue)) = SCROLL_TARGET.with(|slot| slot.borrow_mut().take()) {
+            if let Some(session) = tracker.flush() {
+                let _ = event_queue.push(session);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn scroll_proc(_hwnd: HWND, _message: u32, _id: usize, _time: u32) {
+    SCROLL_TARGET.with(|slot| {
+        if let Some((tracker, event_queue)) = slot.borrow().as_ref() {
+            if let Some(session) = tracker.poll() {
+                let _ = event_queue.push(session);
+            }
+        }
+    });
+}
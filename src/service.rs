@@ -0,0 +1,642 @@
+//! Running a live command as a Windows service, for kiosks and lab machines
+//!
+//! Services run in session 0, which has no desktop of its own: hooks and cursor
+//! queries there never see what the signed-in user does. The service therefore
+//! only supervises. It starts a helper process in the active console session,
+//! under that user's token, and starts it again when it exits or the console
+//! changes hands (fast user switching, a new sign-in, a remote session taking over).
+
+use crate::{Interrupt, Timestamp};
+use std::ffi::c_void;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_CALL_NOT_IMPLEMENTED, ERROR_FAILED_SERVICE_CONTROLLER_CONNECT, ERROR_SERVICE_DOES_NOT_EXIST,
+    ERROR_SERVICE_EXISTS, ERROR_SERVICE_SPECIFIC_ERROR, HANDLE, NO_ERROR, WAIT_OBJECT_0,
+};
+use windows::Win32::Security::SECURITY_ATTRIBUTES;
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, DELETE, FILE_APPEND_DATA, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_ALWAYS,
+};
+use windows::Win32::System::Environment::{CreateEnvironmentBlock, DestroyEnvironmentBlock};
+use windows::Win32::System::RemoteDesktop::{WTSGetActiveConsoleSessionId, WTSQueryUserToken};
+use windows::Win32::System::Services::{
+    ChangeServiceConfig2W, CloseServiceHandle, ControlService, CreateServiceW, DeleteService, OpenSCManagerW,
+    OpenServiceW, RegisterServiceCtrlHandlerExW, SetServiceStatus, StartServiceCtrlDispatcherW, StartServiceW,
+    SC_ACTION, SC_ACTION_RESTART, SC_HANDLE, SC_MANAGER_CONNECT, SC_MANAGER_CREATE_SERVICE,
+    SERVICE_ACCEPT_SESSIONCHANGE, SERVICE_ACCEPT_SHUTDOWN, SERVICE_ACCEPT_STOP, SERVICE_AUTO_START,
+    SERVICE_CHANGE_CONFIG, SERVICE_CONFIG_DESCRIPTION, SERVICE_CONFIG_FAILURE_ACTIONS,
+    SERVICE_CONFIG_FAILURE_ACTIONS_FLAG, SERVICE_CONTROL_INTERROGATE, SERVICE_CONTROL_SESSIONCHANGE,
+    SERVICE_CONTROL_SHUTDOWN, SERVICE_CONTROL_STOP, SERVICE_DESCRIPTIONW, SERVICE_ERROR_NORMAL,
+    SERVICE_FAILURE_ACTIONSW, SERVICE_FAILURE_ACTIONS_FLAG, SERVICE_RUNNING, SERVICE_START, SERVICE_STATUS,
+    SERVICE_STATUS_CURRENT_STATE, SERVICE_STATUS_HANDLE, SERVICE_STOP, SERVICE_STOPPED, SERVICE_STOP_PENDING,
+    SERVICE_TABLE_ENTRYW, SERVICE_WIN32_OWN_PROCESS,
+};
+use windows::Win32::System::Threading::{
+    CreateEventW, CreateProcessAsUserW, GetExitCodeProcess, ResetEvent, SetEvent, TerminateProcess,
+    WaitForSingleObject, CREATE_NO_WINDOW, CREATE_UNICODE_ENVIRONMENT, INFINITE, PROCESS_INFORMATION,
+    STARTF_USESTDHANDLES, STARTUPINFOW,
+};
+
+/// Environment variable carrying the handle of the event that asks a helper to stop
+const STOP_EVENT_VARIABLE: &str = "LUUMA_SERVICE_STOP_EVENT";
+
+/// The service's own log in the log directory
+const SERVICE_LOG: &str = "service.log";
+
+/// Standard output and error of every helper, appended across restarts
+const HELPER_LOG: &str = "helper.log";
+
+/// How often the supervisor checks whether the helper is still running
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a helper gets to drain its queue and flush sinks before it is terminated
+const HELPER_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often to look for a signed-in user while nobody is at the console
+const NO_USER_RETRY: Duration = Duration::from_secs(5);
+
+/// First wait before restarting a helper that exited, doubling on every quick exit
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait before restarting a helper
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// A helper that ran this long restarts without backoff
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+/// Restarts the service manager makes after the service itself fails, and how long it waits before each
+const SERVICE_RESTART_DELAYS: [Duration; 3] =
+    [Duration::from_secs(5), Duration::from_secs(30), Duration::from_secs(60)];
+
+/// Failure-free period after which the service manager starts counting restarts again
+const FAILURE_RESET_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What `WTSGetActiveConsoleSessionId` returns while the console is being attached or detached
+const NO_SESSION: u32 = 0xFFFF_FFFF;
+
+/// Desktop the helper's hooks and cursor queries need to see
+const INTERACTIVE_DESKTOP: &str = "winsta0\\default";
+
+/// The configuration [`run_service`] hands to the service thread
+static SERVICE: OnceLock<ServiceConfig> = OnceLock::new();
+
+/// Stop and session change requests from the service manager
+static CONTROL: Control = Control {
+    state: Mutex::new(ControlState { stopping: false, session_changed: false }),
+    changed: Condvar::new(),
+};
+
+/// How a service is registered and what it keeps running
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+    /// Name the service is registered under, as used by `sc` and `net start`
+    pub name: String,
+    /// Name shown in the Services console
+    pub display_name: String,
+    /// Description shown in the Services console
+    pub description: String,
+    /// Directory for `service.log` and `helper.log`, also the helper's working directory
+    pub log_dir: PathBuf,
+    /// Arguments the helper is started with; the helper runs the service's own executable
+    pub helper_args: Vec<String>,
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        let program_data = std::env::var_os("ProgramData").map(PathBuf::from).unwrap_or_else(|| "C:\\ProgramData".into());
+        Self {
+            name: "luuma".to_string(),
+            display_name: "Luuma cursor collector".to_string(),
+            description: "Collects cursor events from the signed-in user's session".to_string(),
+            log_dir: program_data.join("luuma"),
+            helper_args: Vec::new(),
+        }
+    }
+}
+
+/// Register the current executable as an automatically starting service and start it
+///
+/// The service manager starts the executable with `arguments`, which must lead it to
+/// [`run_service`] with the same configuration. If the service process fails, the
+/// service manager restarts it a few times before giving up for the day. Needs an
+/// elevated process.
+pub fn install_service(config: &ServiceConfig, arguments: &[String]) -> Result<(), String> {
+    let executable = std::env::current_exe().map_err(|e| format!("Failed to locate the executable: {}", e))?;
+    let command = std::iter::once(executable.to_string_lossy().into_owned())
+        .chain(arguments.iter().cloned())
+        .map(|argument| quote_argument(&argument))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let manager = open_manager(SC_MANAGER_CREATE_SERVICE)?;
+    let service = unsafe {
+        CreateServiceW(
+            manager,
+            &HSTRING::from(config.name.as_str()),
+            &HSTRING::from(config.display_name.as_str()),
+            SERVICE_CHANGE_CONFIG | SERVICE_START,
+            SERVICE_WIN32_OWN_PROCESS,
+            SERVICE_AUTO_START,
+            SERVICE_ERROR_NORMAL,
+            &HSTRING::from(command),
+            PCWSTR::null(),
+            None,
+            PCWSTR::null(),
+            // LocalSystem, which may ask for the signed-in user's token
+            PCWSTR::null(),
+            PCWSTR::null(),
+        )
+    };
+    let result = match service {
+        Ok(service) => {
+            let result = configure(service, config).and_then(|()| {
+                unsafe { StartServiceW(service, None) }.map_err(|e| format!("Failed to start {}: {}", config.name, e))
+            });
+            unsafe {
+                let _ = CloseServiceHandle(service);
+            }
+            result
+        }
+        Err(error) if error.code() == ERROR_SERVICE_EXISTS.to_hresult() => {
+            Err(format!("A service named {} is already installed", config.name))
+        }
+        Err(error) => Err(format!("Failed to create service {}: {}", config.name, error)),
+    };
+    unsafe {
+        let _ = CloseServiceHandle(manager);
+    }
+    result
+}
+
+/// Stop the service if it is running and remove it
+pub fn uninstall_service(name: &str) -> Result<(), String> {
+    let manager = open_manager(SC_MANAGER_CONNECT)?;
+    let result = match unsafe { OpenServiceW(manager, &HSTRING::from(name), SERVICE_STOP | DELETE.0) } {
+        Ok(service) => {
+            let mut status = SERVICE_STATUS::default();
+            // Not running is fine; the service is removed once it has stopped either way
+            let _ = unsafe { ControlService(service, SERVICE_CONTROL_STOP, &mut status) };
+            let result = unsafe { DeleteService(service) }.map_err(|e| format!("Failed to remove {}: {}", name, e));
+            unsafe {
+                let _ = CloseServiceHandle(service);
+            }
+            result
+        }
+        Err(error) if error.code() == ERROR_SERVICE_DOES_NOT_EXIST.to_hresult() => {
+            Err(format!("No service named {} is installed", name))
+        }
+        Err(error) => Err(format!("Failed to open service {}: {}", name, error)),
+    };
+    unsafe {
+        let _ = CloseServiceHandle(manager);
+    }
+    result
+}
+
+/// Run as the service the service manager started, returning once it has been stopped
+///
+/// Keeps a helper running in the active console session with `config.helper_args`,
+/// restarting it with growing delays when it exits and moving it when another
+/// session takes over the console. On stop, the helper gets a few seconds to shut
+/// down cleanly (see [`stop_with_service`]) before it is terminated. Fails when the
+/// process was not started by the service manager.
+pub fn run_service(config: ServiceConfig) -> Result<(), String> {
+    let mut name: Vec<u16> = config.name.encode_utf16().chain(std::iter::once(0)).collect();
+    SERVICE.set(config).map_err(|_| "The service is already running in this process".to_string())?;
+    let table = [
+        SERVICE_TABLE_ENTRYW { lpServiceName: PWSTR(name.as_mut_ptr()), lpServiceProc: Some(service_main) },
+        SERVICE_TABLE_ENTRYW::default(),
+    ];
+    unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) }.map_err(|e| {
+        if e.code() == ERROR_FAILED_SERVICE_CONTROLLER_CONNECT.to_hresult() {
+            "Not started by the service manager; install the service and start it from there".to_string()
+        } else {
+            format!("Failed to connect to the service manager: {}", e)
+        }
+    })
+}
+
+/// Have `interrupt` request a stop when the supervising service stops this helper
+///
+/// Does nothing in a process that was not started by [`run_service`], so live
+/// commands can call it unconditionally.
+pub fn stop_with_service(interrupt: &'static Interrupt) -> Result<(), String> {
+    let Ok(value) = std::env::var(STOP_EVENT_VARIABLE) else {
+        return Ok(());
+    };
+    let handle: usize = value.parse().map_err(|_| format!("Invalid {}: {}", STOP_EVENT_VARIABLE, value))?;
+    thread::Builder::new()
+        .name("luuma-service-stop".to_string())
+        .spawn(move || {
+            if unsafe { WaitForSingleObject(HANDLE(handle as *mut c_void), INFINITE) } == WAIT_OBJECT_0 {
+                interrupt.request_stop();
+            }
+        })
+        .map(drop)
+        .map_err(|e| format!("Failed to spawn service stop thread: {}", e))
+}
+
+fn open_manager(access: u32) -> Result<SC_HANDLE, String> {
+    unsafe { OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), access) }
+        .map_err(|e| format!("Failed to open the service manager (run as administrator): {}", e))
+}
+
+/// Description and restart-on-failure settings, which `CreateServiceW` cannot set
+fn configure(service: SC_HANDLE, config: &ServiceConfig) -> Result<(), String> {
+    let mut description: Vec<u16> = config.description.encode_utf16().chain(std::iter::once(0)).collect();
+    let description = SERVICE_DESCRIPTIONW { lpDescription: PWSTR(description.as_mut_ptr()) };
+
+    let mut actions = SERVICE_RESTART_DELAYS
+        .map(|delay| SC_ACTION { Type: SC_ACTION_RESTART, Delay: delay.as_millis() as u32 });
+    let failure_actions = SERVICE_FAILURE_ACTIONSW {
+        dwResetPeriod: FAILURE_RESET_PERIOD.as_secs() as u32,
+        lpRebootMsg: PWSTR::null(),
+        lpCommand: PWSTR::null(),
+        cActions: actions.len() as u32,
+        lpsaActions: actions.as_mut_ptr(),
+    };
+    // Also restart after the supervisor reports an error, not only after a crash
+    let failure_flag = SERVICE_FAILURE_ACTIONS_FLAG { fFailureActionsOnNonCrashFailures: true.into() };
+
+    unsafe {
+        ChangeServiceConfig2W(service, SERVICE_CONFIG_DESCRIPTION, Some(&description as *const _ as *const c_void))
+            .and_then(|()| {
+                ChangeServiceConfig2W(
+                    service,
+                    SERVICE_CONFIG_FAILURE_ACTIONS,
+                    Some(&failure_actions as *const _ as *const c_void),
+                )
+            })
+            .and_then(|()| {
+                ChangeServiceConfig2W(
+                    service,
+                    SERVICE_CONFIG_FAILURE_ACTIONS_FLAG,
+                    Some(&failure_flag as *const _ as *const c_void),
+                )
+            })
+    }
+    .map_err(|e| format!("Failed to configure service {}: {}", config.name, e))
+}
+
+/// Quote an argument so the C runtime splits the command line back into the same argument
+fn quote_argument(argument: &str) -> String {
+    if !argument.is_empty() && !argument.contains([' ', '\t', '"']) {
+        return argument.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for character in argument.chars() {
+        if character == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        // Backslashes only escape when a quote follows them
+        let escapes = if character == '"' { backslashes * 2 + 1 } else { backslashes };
+        quoted.push_str(&"\\".repeat(escapes));
+        quoted.push(character);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Entry point the dispatcher calls on a thread of its own
+unsafe extern "system" fn service_main(_argc: u32, _argv: *mut PWSTR) {
+    let Some(config) = SERVICE.get() else {
+        return;
+    };
+    let Ok(status) = RegisterServiceCtrlHandlerExW(&HSTRING::from(config.name.as_str()), Some(on_control), None)
+    else {
+        return;
+    };
+
+    let mut log = ServiceLog::open(&config.log_dir);
+    report(status, SERVICE_RUNNING, false, Duration::ZERO);
+    log.write(&format!("Service started, supervising: {}", config.helper_args.join(" ")));
+    let failed = match supervise(config, &mut log, status) {
+        Ok(()) => false,
+        Err(error) => {
+            log.write(&error);
+            true
+        }
+    };
+    log.write("Service stopped");
+    report(status, SERVICE_STOPPED, failed, Duration::ZERO);
+}
+
+/// Runs on the dispatcher thread, so it only records the request for the service thread
+unsafe extern "system" fn on_control(control: u32, _event_type: u32, _data: *mut c_void, _context: *mut c_void) -> u32 {
+    match control {
+        SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => CONTROL.update(|state| state.stopping = true),
+        SERVICE_CONTROL_SESSIONCHANGE => CONTROL.update(|state| state.session_changed = true),
+        SERVICE_CONTROL_INTERROGATE => {}
+        _ => return ERROR_CALL_NOT_IMPLEMENTED.0,
+    }
+    NO_ERROR.0
+}
+
+fn report(handle: SERVICE_STATUS_HANDLE, state: SERVICE_STATUS_CURRENT_STATE, failed: bool, wait_hint: Duration) {
+    let status = SERVICE_STATUS {
+        dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+        dwCurrentState: state,
+        dwControlsAccepted: if state == SERVICE_RUNNING {
+            SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN | SERVICE_ACCEPT_SESSIONCHANGE
+        } else {
+            0
+        },
+        dwWin32ExitCode: if failed { ERROR_SERVICE_SPECIFIC_ERROR.0 } else { NO_ERROR.0 },
+        dwServiceSpecificExitCode: failed as u32,
+        dwCheckPoint: 0,
+        dwWaitHint: wait_hint.as_millis() as u32,
+    };
+    unsafe {
+        let _ = SetServiceStatus(handle, &status);
+    }
+}
+
+/// Open the helper log and the stop event, both inherited by every helper, and run helpers until stopped
+fn supervise(config: &ServiceConfig, log: &mut ServiceLog, status: SERVICE_STATUS_HANDLE) -> Result<(), String> {
+    let inheritable = SECURITY_ATTRIBUTES {
+        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: std::ptr::null_mut(),
+        bInheritHandle: true.into(),
+    };
+    let helper_log = config.log_dir.join(HELPER_LOG);
+    let output = unsafe {
+        CreateFileW(
+            &HSTRING::from(helper_log.as_path()),
+            FILE_APPEND_DATA.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            Some(&inheritable),
+            OPEN_ALWAYS,
+            FILE_ATTRIBUTE_NORMAL,
+            None,
+        )
+    }
+    .map_err(|e| format!("Failed to open {}: {}", helper_log.display(), e))?;
+    let stop_event = match unsafe { CreateEventW(Some(&inheritable), true, false, PCWSTR::null()) } {
+        Ok(event) => event,
+        Err(error) => {
+            unsafe {
+                let _ = CloseHandle(output);
+            }
+            return Err(format!("Failed to create helper stop event: {}", error));
+        }
+    };
+
+    let result = run_helpers(config, log, status, output, stop_event);
+    unsafe {
+        let _ = CloseHandle(stop_event);
+        let _ = CloseHandle(output);
+    }
+    result
+}
+
+fn run_helpers(
+    config: &ServiceConfig,
+    log: &mut ServiceLog,
+    status: SERVICE_STATUS_HANDLE,
+    output: HANDLE,
+    stop_event: HANDLE,
+) -> Result<(), String> {
+    let mut restart_delay = MIN_RESTART_DELAY;
+    let mut last_launch_error = None;
+    loop {
+        let session = unsafe { WTSGetActiveConsoleSessionId() };
+        let helper = match Helper::launch(config, session, output, stop_event) {
+            Ok(helper) => helper,
+            Err(error) => {
+                // Normal at the sign-in screen, so only logged when the reason changes
+                if last_launch_error.as_ref() != Some(&error) {
+                    log.write(&format!("Waiting for a signed-in user: {}", error));
+                    last_launch_error = Some(error);
+                }
+                if CONTROL.wait(NO_USER_RETRY).stopping {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        last_launch_error = None;
+        log.write(&format!("Started helper process {} in session {}", helper.process_id, session));
+
+        let exit_code = loop {
+            let control = CONTROL.wait(POLL_INTERVAL);
+            if control.stopping {
+                report(status, SERVICE_STOP_PENDING, false, HELPER_STOP_TIMEOUT + POLL_INTERVAL);
+                log.write(&helper.stop(stop_event));
+                return Ok(());
+            }
+            if control.session_changed && unsafe { WTSGetActiveConsoleSessionId() } != helper.session {
+                log.write(&format!("Console session changed, moving helper: {}", helper.stop(stop_event)));
+                restart_delay = MIN_RESTART_DELAY;
+                break None;
+            }
+            if let Some(exit_code) = helper.exit_code() {
+                break Some(exit_code);
+            }
+        };
+
+        let Some(exit_code) = exit_code else {
+            continue;
+        };
+        if helper.started.elapsed() >= HEALTHY_RUN {
+            restart_delay = MIN_RESTART_DELAY;
+        }
+        log.write(&format!(
+            "Helper process {} exited with code {}, restarting in {}s",
+            helper.process_id,
+            exit_code,
+            restart_delay.as_secs()
+        ));
+        drop(helper);
+        if CONTROL.wait(restart_delay).stopping {
+            return Ok(());
+        }
+        restart_delay = (restart_delay * 2).min(MAX_RESTART_DELAY);
+    }
+}
+
+/// A helper process running in a user's session
+struct Helper {
+    process: HANDLE,
+    process_id: u32,
+    session: u32,
+    started: Instant,
+}
+
+impl Helper {
+    /// Start the executable with the helper arguments as the user signed in to `session`
+    fn launch(config: &ServiceConfig, session: u32, output: HANDLE, stop_event: HANDLE) -> Result<Self, String> {
+        if session == NO_SESSION {
+            return Err("No session is attached to the console".to_string());
+        }
+        let mut token = HANDLE::default();
+        unsafe { WTSQueryUserToken(session, &mut token) }
+            .map_err(|e| format!("No user signed in to session {}: {}", session, e))?;
+        let result = Self::launch_as(config, token, output, stop_event);
+        unsafe {
+            let _ = CloseHandle(token);
+        }
+        result.map(|(process, process_id)| Self { process, process_id, session, started: Instant::now() })
+    }
+
+    fn launch_as(config: &ServiceConfig, token: HANDLE, output: HANDLE, stop_event: HANDLE) -> Result<(HANDLE, u32), String> {
+        let executable = std::env::current_exe().map_err(|e| format!("Failed to locate the executable: {}", e))?;
+        let mut command_line: Vec<u16> = std::iter::once(executable.to_string_lossy().into_owned())
+            .chain(config.helper_args.iter().cloned())
+            .map(|argument| quote_argument(&argument))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let environment = user_environment(token, stop_event)?;
+        let mut desktop: Vec<u16> = INTERACTIVE_DESKTOP.encode_utf16().chain(std::iter::once(0)).collect();
+        let startup = STARTUPINFOW {
+            cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+            lpDesktop: PWSTR(desktop.as_mut_ptr()),
+            dwFlags: STARTF_USESTDHANDLES,
+            hStdOutput: output,
+            hStdError: output,
+            ..Default::default()
+        };
+        let mut information = PROCESS_INFORMATION::default();
+        unsafe {
+            CreateProcessAsUserW(
+                Some(token),
+                PCWSTR::null(),
+                Some(PWSTR(command_line.as_mut_ptr())),
+                None,
+                None,
+                // Passes on the log file and the stop event, the only inheritable handles
+                true,
+                CREATE_UNICODE_ENVIRONMENT | CREATE_NO_WINDOW,
+                Some(environment.as_ptr() as *const c_void),
+                &HSTRING::from(config.log_dir.as_path()),
+                &startup,
+                &mut information,
+            )
+        }
+        .map_err(|e| format!("Failed to start helper: {}", e))?;
+        unsafe {
+            let _ = CloseHandle(information.hThread);
+        }
+        Ok((information.hProcess, information.dwProcessId))
+    }
+
+    /// Exit code once the process has exited
+    fn exit_code(&self) -> Option<u32> {
+        if unsafe { WaitForSingleObject(self.process, 0) } != WAIT_OBJECT_0 {
+            return None;
+        }
+        let mut exit_code = 0;
+        unsafe { GetExitCodeProcess(self.process, &mut exit_code) }.ok()?;
+        Some(exit_code)
+    }
+
+    /// Ask the helper to stop, terminating it if it does not, and describe how it ended
+    fn stop(&self, stop_event: HANDLE) -> String {
+        unsafe {
+            let _ = SetEvent(stop_event);
+            let stopped = WaitForSingleObject(self.process, HELPER_STOP_TIMEOUT.as_millis() as u32) == WAIT_OBJECT_0;
+            // Manual reset, so the next helper must not start out signalled
+            let _ = ResetEvent(stop_event);
+            if stopped {
+                format!("Helper process {} stopped", self.process_id)
+            } else {
+                let _ = TerminateProcess(self.process, 1);
+                format!("Helper process {} did not stop in time and was terminated", self.process_id)
+            }
+        }
+    }
+}
+
+impl Drop for Helper {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.process);
+        }
+    }
+}
+
+/// The user's environment block, plus the variable that names the stop event
+fn user_environment(token: HANDLE, stop_event: HANDLE) -> Result<Vec<u16>, String> {
+    let mut block: *mut c_void = std::ptr::null_mut();
+    unsafe { CreateEnvironmentBlock(&mut block, Some(token), false) }
+        .map_err(|e| format!("Failed to read the user's environment: {}", e))?;
+
+    // Variables are null-terminated strings, ended by an empty one
+    let mut environment = Vec::new();
+    unsafe {
+        let mut cursor = block as *const u16;
+        loop {
+            let length = (0..).take_while(|&index| *cursor.add(index) != 0).count();
+            if length == 0 {
+                break;
+            }
+            environment.extend_from_slice(std::slice::from_raw_parts(cursor, length + 1));
+            cursor = cursor.add(length + 1);
+        }
+        let _ = DestroyEnvironmentBlock(block);
+    }
+    environment.extend(format!("{}={}", STOP_EVENT_VARIABLE, stop_event.0 as usize).encode_utf16());
+    environment.extend([0, 0]);
+    Ok(environment)
+}
+
+/// Timestamped lines appended to `service.log`, dropped if the file cannot be opened
+struct ServiceLog {
+    file: Option<File>,
+}
+
+impl ServiceLog {
+    fn open(log_dir: &Path) -> Self {
+        let _ = fs::create_dir_all(log_dir);
+        let file = OpenOptions::new().create(true).append(true).open(log_dir.join(SERVICE_LOG)).ok();
+        Self { file }
+    }
+
+    fn write(&mut self, message: &str) {
+        if let Some(file) = &mut self.file {
+            let _ = writeln!(file, "[{}] {}", Timestamp::now(), message);
+        }
+    }
+}
+
+struct Control {
+    state: Mutex<ControlState>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ControlState {
+    stopping: bool,
+    session_changed: bool,
+}
+
+impl Control {
+    fn update(&self, change: impl FnOnce(&mut ControlState)) {
+        change(&mut self.state.lock().unwrap_or_else(|e| e.into_inner()));
+        self.changed.notify_all();
+    }
+
+    /// Wait up to `timeout` for a request, consuming a session change
+    fn wait(&self, timeout: Duration) -> ControlState {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| !state.stopping && !state.session_changed)
+            .unwrap_or_else(|e| e.into_inner());
+        let seen = *state;
+        state.session_changed = false;
+        seen
+    }
+}
@@ -0,0 +1,306 @@
+//! Workstation lock, secure desktop and remote session tracking
+
+#[cfg(windows)]
+use crate::clock::{Clock, SharedClock};
+#[cfg(windows)]
+use crate::queue::EventQueue;
+#[cfg(windows)]
+use crate::CursorEvent;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::cell::RefCell;
+#[cfg(windows)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(windows)]
+use std::sync::Arc;
+#[cfg(windows)]
+use windows::core::w;
+#[cfg(windows)]
+use windows::Win32::Foundation::{HANDLE, HWND, LPARAM, LRESULT, WPARAM};
+#[cfg(windows)]
+use windows::Win32::System::RemoteDesktop::{
+    WTSRegisterSessionNotification, WTSUnRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+};
+#[cfg(windows)]
+use windows::Win32::System::StationsAndDesktops::{
+    CloseDesktop, GetUserObjectInformationW, OpenInputDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_READOBJECTS, UOI_NAME,
+};
+#[cfg(windows)]
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, GetSystemMetrics, RegisterClassW, EVENT_SYSTEM_DESKTOPSWITCH,
+    HWND_MESSAGE, SM_REMOTESESSION, WINDOW_EX_STYLE, WINDOW_STYLE, WINEVENT_OUTOFCONTEXT, WM_WTSSESSION_CHANGE,
+    WNDCLASSW, WTS_CONSOLE_CONNECT, WTS_CONSOLE_DISCONNECT, WTS_REMOTE_CONNECT, WTS_REMOTE_DISCONNECT,
+    WTS_SESSION_LOCK, WTS_SESSION_UNLOCK,
+};
+
+/// Which desktop is receiving input
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum DesktopState {
+    /// The user's desktop is active
+    Normal,
+    /// The workstation is locked
+    Locked,
+    /// The secure desktop is active (UAC prompt, Ctrl+Alt+Del screen)
+    Secure,
+}
+
+/// Whether input currently goes to a desktop other than the user's default one
+///
+/// Processes outside the winlogon session cannot open the secure desktop, so a
+/// failure to open the input desktop is treated as secure as well.
+#[cfg(windows)]
+pub fn secure_desktop_active() -> bool {
+    unsafe {
+        let Ok(desktop) = OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_READOBJECTS) else {
+            return true;
+        };
+
+        let mut buffer = [0u16; 64];
+        let mut needed = 0u32;
+        let read = GetUserObjectInformationW(
+            HANDLE(desktop.0),
+            UOI_NAME,
+            Some(buffer.as_mut_ptr().cast()),
+            std::mem::size_of_val(&buffer) as u32,
+            Some(&mut needed),
+        );
+        let _ = CloseDesktop(desktop);
+        if read.is_err() {
+            return true;
+        }
+
+        let length = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        !String::from_utf16_lossy(&buffer[..length]).eq_ignore_ascii_case("Default")
+    }
+}
+
+/// Whether this session is displayed over Remote Desktop / terminal services
+///
+/// Remote sessions scale and move the cursor differently (absolute positions,
+/// client-side rendering), so analytics usually segment them from console ones.
+#[cfg(windows)]
+pub fn is_remote_session() -> bool {
+    unsafe { GetSystemMetrics(SM_REMOTESESSION) != 0 }
+}
+
+/// Why capture is paused, shared by every thread that records input
+#[cfg(windows)]
+#[derive(Debug, Default)]
+pub(crate) struct CapturePause {
+    /// The workstation is locked or the secure desktop is active
+    desktop: AtomicBool,
+    /// The application or its hotkey paused capture
+    user: AtomicBool,
+}
+
+#[cfg(windows)]
+impl CapturePause {
+    /// Whether input should be dropped for any reason
+    pub(crate) fn is_paused(&self) -> bool {
+        self.desktop.load(Ordering::Relaxed) || self.user.load(Ordering::Relaxed)
+    }
+
+    fn set_desktop(&self, paused: bool) {
+        self.desktop.store(paused, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_user(&self, paused: bool) {
+        self.user.store(paused, Ordering::Relaxed);
+    }
+
+    /// Flip the user pause, returning whether capture is now paused by the user
+    pub(crate) fn toggle_user(&self) -> bool {
+        !self.user.fetch_xor(true, Ordering::Relaxed)
+    }
+}
+
+/// State behind the session window and desktop switch hook of the watching thread
+#[cfg(windows)]
+struct SessionTarget {
+    event_queue: Arc<EventQueue<CursorEvent>>,
+    pause: Arc<CapturePause>,
+    clock: SharedClock,
+    locked: bool,
+    secure: bool,
+    reported: DesktopState,
+    remote: bool,
+}
+
+#[cfg(windows)]
+impl SessionTarget {
+    fn state(&self) -> DesktopState {
+        if self.secure {
+            DesktopState::Secure
+        } else if self.locked {
+            DesktopState::Locked
+        } else {
+            DesktopState::Normal
+        }
+    }
+
+    /// Pause capture outside the normal desktop and queue `DesktopChanged` when the state changed
+    fn report(&mut self) {
+        let state = self.state();
+        self.pause.set_desktop(state != DesktopState::Normal);
+        if state != self.reported {
+            self.reported = state;
+            let _ = self.event_queue.push(CursorEvent::DesktopChanged { state, timestamp: self.clock.now() });
+        }
+    }
+
+    /// Queue `RemoteSessionChanged` when the session moved between console and remote
+    fn report_remote(&mut self) {
+        let remote = is_remote_session();
+        if remote != self.remote {
+            self.remote = remote;
+            let _ = self.event_queue.push(CursorEvent::RemoteSessionChanged { remote, timestamp: self.clock.now() });
+        }
+    }
+}
+
+#[cfg(windows)]
+thread_local! {
+    // Window procedures and WinEvent callbacks carry no user data and run on the installing thread
+    static SESSION_TARGET: RefCell<Option<SessionTarget>> = const { RefCell::new(None) };
+}
+
+/// Session notification window and desktop switch hook installed on the calling thread
+///
+/// While the workstation is locked or the secure desktop is active, `pause` is set
+/// so the hook and raw input threads drop input instead of recording it.
+#[cfg(windows)]
+pub(crate) struct SessionWatcher {
+    window: HWND,
+    hook: HWINEVENTHOOK,
+}
+
+#[cfg(windows)]
+impl SessionWatcher {
+    pub(crate) fn install(
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        pause: Arc<CapturePause>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        unsafe {
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(session_proc),
+                lpszClassName: w!("LuumaSession"),
+                ..Default::default()
+            };
+            // Registering twice fails harmlessly when a detector is restarted
+            RegisterClassW(&class);
+
+            let window = CreateWindowExW(
+                WINDOW_EX_STYLE(0),
+                w!("LuumaSession"),
+                w!("LuumaSession"),
+                WINDOW_STYLE(0),
+                0,
+                0,
+                0,
+                0,
+                Some(HWND_MESSAGE),
+                None,
+                None,
+                None,
+            )
+            .map_err(|e| format!("Failed to create session window: {}", e))?;
+
+            if let Err(error) = WTSRegisterSessionNotification(window, NOTIFY_FOR_THIS_SESSION) {
+                let _ = DestroyWindow(window);
+                return Err(format!("Failed to register for session notifications: {}", error));
+            }
+
+            let hook = SetWinEventHook(
+                EVENT_SYSTEM_DESKTOPSWITCH,
+                EVENT_SYSTEM_DESKTOPSWITCH,
+                None,
+                Some(desktop_switch_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            if hook.is_invalid() {
+                let _ = WTSUnRegisterSessionNotification(window);
+                let _ = DestroyWindow(window);
+                return Err("Failed to install desktop switch hook".to_string());
+            }
+
+            let mut target = SessionTarget {
+                event_queue,
+                pause,
+                clock,
+                locked: false,
+                secure: secure_desktop_active(),
+                reported: DesktopState::Normal,
+                remote: is_remote_session(),
+            };
+            target.report();
+            SESSION_TARGET.with(|slot| *slot.borrow_mut() = Some(target));
+            Ok(Self { window, hook })
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for SessionWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnhookWinEvent(self.hook);
+            let _ = WTSUnRegisterSessionNotification(self.window);
+            let _ = DestroyWindow(self.window);
+        }
+        if let Some(target) = SESSION_TARGET.with(|slot| slot.borrow_mut().take()) {
+            target.pause.set_desktop(false);
+        }
+    }
+}
+
+/// Apply a change to the watching thread's session state
+#[cfg(windows)]
+fn update_target<F>(update: F)
+where
+    F: FnOnce(&mut SessionTarget),
+{
+    SESSION_TARGET.with(|slot| {
+        if let Ok(mut slot) = slot.try_borrow_mut() {
+            if let Some(target) = slot.as_mut() {
+                update(target);
+                target.report();
+            }
+        }
+    });
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn session_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if message == WM_WTSSESSION_CHANGE {
+        match wparam.0 as u32 {
+            WTS_SESSION_LOCK => update_target(|target| target.locked = true),
+            WTS_SESSION_UNLOCK => update_target(|target| target.locked = false),
+            // Reconnecting from another client switches the session between console and RDP
+            WTS_CONSOLE_CONNECT | WTS_CONSOLE_DISCONNECT | WTS_REMOTE_CONNECT | WTS_REMOTE_DISCONNECT => {
+                update_target(SessionTarget::report_remote)
+            }
+            _ => {}
+        }
+        return LRESULT(0);
+    }
+    DefWindowProcW(window, message, wparam, lparam)
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn desktop_switch_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    _hwnd: HWND,
+    _object: i32,
+    _child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    let secure = secure_desktop_active();
+    update_target(|target| target.secure = secure);
+}
@@ -0,0 +1,92 @@
+//! System-wide mouse settings from the Control Panel
+
+use crate::MouseButton;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use windows::Win32::UI::Input::KeyboardAndMouse::GetDoubleClickTime;
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetSystemMetrics, SystemParametersInfoW, SM_CXCURSOR, SM_CXDOUBLECLK, SM_CXDRAG, SM_CYCURSOR, SM_CYDOUBLECLK,
+    SM_CYDRAG, SM_SWAPBUTTON, SPI_GETMOUSE, SPI_GETMOUSEHOVERTIME, SPI_GETMOUSESPEED, SPI_GETWHEELSCROLLLINES,
+    SYSTEM_PARAMETERS_INFO_ACTION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+};
+
+/// Snapshot of the user's pointer settings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SystemPointerSettings {
+    /// Maximum time between the clicks of a double-click
+    #[serde(with = "crate::duration_ms")]
+    pub double_click_time: Duration,
+    /// Area in pixels the second click of a double-click must land in
+    pub double_click_size: (u32, u32),
+    /// Distance in pixels the cursor must travel with a button held before a drag starts
+    pub drag_threshold: (u32, u32),
+    /// Time the cursor must rest before hover tracking fires
+    #[serde(with = "crate::duration_ms")]
+    pub hover_time: Duration,
+    /// Whether the primary and secondary buttons are swapped for left-handed use
+    pub swapped_buttons: bool,
+    /// Pointer speed slider from 1 (slowest) to 20 (fastest), 10 by default
+    pub pointer_speed: u32,
+    /// Whether "Enhance pointer precision" (pointer acceleration) is on
+    pub enhanced_precision: bool,
+    /// Lines scrolled per wheel notch, `u32::MAX` when set to scroll one screen
+    pub wheel_scroll_lines: u32,
+    /// Nominal cursor image size in pixels
+    pub cursor_size: (u32, u32),
+}
+
+impl SystemPointerSettings {
+    /// Read the current settings
+    pub fn query() -> Result<Self, String> {
+        let mut mouse = [0i32; 3];
+        let mut speed = 0u32;
+        let mut hover_time = 0u32;
+        let mut wheel_lines = 0u32;
+        system_parameter(SPI_GETMOUSE, &mut mouse, "mouse acceleration")?;
+        system_parameter(SPI_GETMOUSESPEED, &mut speed, "pointer speed")?;
+        system_parameter(SPI_GETMOUSEHOVERTIME, &mut hover_time, "hover time")?;
+        system_parameter(SPI_GETWHEELSCROLLLINES, &mut wheel_lines, "wheel scroll lines")?;
+
+        let metric = |index| unsafe { GetSystemMetrics(index) }.max(0) as u32;
+        Ok(Self {
+            double_click_time: Duration::from_millis(unsafe { GetDoubleClickTime() } as u64),
+            double_click_size: (metric(SM_CXDOUBLECLK), metric(SM_CYDOUBLECLK)),
+            drag_threshold: (metric(SM_CXDRAG), metric(SM_CYDRAG)),
+            hover_time: Duration::from_millis(hover_time as u64),
+            swapped_buttons: buttons_swapped(),
+            pointer_speed: speed,
+            // The third value is the acceleration flag, the first two its legacy thresholds
+            enhanced_precision: mouse[2] != 0,
+            wheel_scroll_lines: wheel_lines,
+            cursor_size: (metric(SM_CXCURSOR), metric(SM_CYCURSOR)),
+        })
+    }
+
+    /// Logical button for a physical one, mapping the left and right buttons when they are swapped
+    ///
+    /// The low-level hook reports physical buttons, so with swapped buttons a physical
+    /// right press is the primary (logical left) click.
+    pub fn logical_button(&self, physical: MouseButton) -> MouseButton {
+        swap_buttons(physical, self.swapped_buttons)
+    }
+}
+
+/// Whether the primary and secondary buttons are currently swapped
+pub(crate) fn buttons_swapped() -> bool {
+    unsafe { GetSystemMetrics(SM_SWAPBUTTON) != 0 }
+}
+
+pub(crate) fn swap_buttons(button: MouseButton, swapped: bool) -> MouseButton {
+    match button {
+        MouseButton::Left if swapped => MouseButton::Right,
+        MouseButton::Right if swapped => MouseButton::Left,
+        button => button,
+    }
+}
+
+pub(crate) fn system_parameter<T>(action: SYSTEM_PARAMETERS_INFO_ACTION, value: &mut T, name: &str) -> Result<(), String> {
+    unsafe {
+        SystemParametersInfoW(action, 0, Some(value as *mut T as *mut _), SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0))
+    }
+    .map_err(|e| format!("Failed to read {}: {}", name, e))
+}
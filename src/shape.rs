@@ -0,0 +1,121 @@
+//! Cursor shape change tracking through WinEvent hooks
+
+use crate::clock::{Clock, SharedClock};
+use crate::queue::EventQueue;
+use crate::type_change::{TypeChangeFilter, TypeTransition, SETTLE_INTERVAL};
+use crate::{AtomicCursorState, CursorEvent};
+use std::cell::RefCell;
+use std::sync::Arc;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{
+    KillTimer, SetTimer, EVENT_OBJECT_NAMECHANGE, OBJID_CURSOR, WINEVENT_OUTOFCONTEXT,
+};
+
+type ShapeTarget = (Arc<AtomicCursorState>, Arc<EventQueue<CursorEvent>>, Arc<TypeChangeFilter>, SharedClock);
+
+thread_local! {
+    // WinEvent callbacks carry no user data and run on the thread that installed the hook
+    static SHAPE_TARGET: RefCell<Option<ShapeTarget>> = const { RefCell::new(None) };
+}
+
+/// Cursor shape hook installed on the calling thread, removed on drop
+///
+/// Applications switch to the wait cursor while the pointer rests after a click,
+/// so waiting for the next move would miss busy periods entirely. The type change
+/// filter is shared with the mouse hook so each change is reported once, and a
+/// timer reports changes the filter held back while the cursor rests.
+pub(crate) struct ShapeWatcher {
+    hook: HWINEVENTHOOK,
+    timer: Option<usize>,
+}
+
+impl ShapeWatcher {
+    pub(crate) fn install(
+        atomic_state: Arc<AtomicCursorState>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        type_changes: Arc<TypeChangeFilter>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        let hook = unsafe {
+            SetWinEventHook(
+                EVENT_OBJECT_NAMECHANGE,
+                EVENT_OBJECT_NAMECHANGE,
+                None,
+                Some(shape_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            )
+        };
+        if hook.is_invalid() {
+            return Err("Failed to install cursor shape hook".to_string());
+        }
+
+        // A thread timer with a procedure is dispatched by the hook thread's message loop
+        let timer = type_changes
+            .delays()
+            .then(|| unsafe { SetTimer(None, 0, SETTLE_INTERVAL.as_millis() as u32, Some(settle_proc)) });
+        SHAPE_TARGET.with(|slot| *slot.borrow_mut() = Some((atomic_state, event_queue, type_changes, clock)));
+        Ok(Self { hook, timer })
+    }
+}
+
+impl Drop for ShapeWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnhookWinEvent(self.hook);
+            if let Some(timer) = self.timer {
+                let _ = KillTimer(None, timer);
+            }
+        }
+        SHAPE_TARGET.with(|slot| slot.borrow_mut().take());
+    }
+}
+
+unsafe extern "system" fn shape_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    _hwnd: HWND,
+    object: i32,
+    _child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    if object != OBJID_CURSOR.0 {
+        return;
+    }
+
+    let Some(cursor_info) = crate::query_cursor_info() else {
+        return;
+    };
+    SHAPE_TARGET.with(|slot| {
+        if let Some((atomic_state, event_queue, type_changes, clock)) = slot.borrow().as_ref() {
+            atomic_state.cache_cursor(&cursor_info);
+            if let Some(transition) = type_changes.observe(crate::get_cached_cursor_type(cursor_info.hCursor)) {
+                report_type(atomic_state, event_queue, clock, transition);
+            }
+        }
+    });
+}
+
+unsafe extern "system" fn settle_proc(_hwnd: HWND, _message: u32, _id: usize, _time: u32) {
+    SHAPE_TARGET.with(|slot| {
+        if let Some((atomic_state, event_queue, type_changes, clock)) = slot.borrow().as_ref() {
+            if let Some(transition) = type_changes.settle() {
+                report_type(atomic_state, event_queue, clock, transition);
+            }
+        }
+    });
+}
+
+fn report_type(
+    atomic_state: &AtomicCursorState,
+    event_queue: &EventQueue<CursorEvent>,
+    clock: &SharedClock,
+    transition: TypeTransition,
+) {
+    for event in transition.into_events(atomic_state.get_position(), clock.now()) {
+        let _ = event_queue.push(event);
+    }
+}
@@ -0,0 +1,171 @@
+//! Event sinks that receive dispatched cursor events
+
+use crate::CursorEvent;
+#[cfg(windows)]
+use crate::filter::EventKindMask;
+#[cfg(windows)]
+use crate::middleware::RateLimit;
+#[cfg(windows)]
+use crate::transform::{Normalization, Normalizer};
+#[cfg(windows)]
+use std::any::Any;
+#[cfg(windows)]
+use std::panic::{catch_unwind, AssertUnwindSafe};
+#[cfg(windows)]
+use std::sync::{Arc, Mutex};
+
+/// Destination for events leaving the dispatch pipeline
+///
+/// Sinks run on the detector's processing thread, so `handle` should return quickly
+/// and hand slow work (network, disk) off to its own thread.
+pub trait EventSink: Send {
+    /// Handle a single dispatched event
+    fn handle(&mut self, event: &CursorEvent);
+
+    /// Flush any buffered events, called when monitoring stops
+    fn flush(&mut self) {}
+}
+
+impl<S: EventSink + ?Sized> EventSink for Box<S> {
+    fn handle(&mut self, event: &CursorEvent) {
+        (**self).handle(event);
+    }
+
+    fn flush(&mut self) {
+        (**self).flush();
+    }
+}
+
+/// Which events a named sink receives and how often
+#[cfg(windows)]
+#[derive(Debug, Clone, Default)]
+pub struct SinkFilter {
+    kinds: EventKindMask,
+    rate_limit: Option<RateLimit>,
+    normalizer: Option<Normalizer>,
+}
+
+#[cfg(windows)]
+impl SinkFilter {
+    /// Deliver every event
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Deliver only events of these kinds
+    pub fn kinds(kinds: EventKindMask) -> Self {
+        Self { kinds, rate_limit: None, normalizer: None }
+    }
+
+    /// Additionally thin the delivered events, e.g. a live stream at 30 moves per second
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Deliver positions as fractions 0..1 of the given bounds, e.g. for a web overlay
+    /// that does not know the desktop geometry
+    ///
+    /// Only this sink sees normalized positions. They are computed from screen pixels
+    /// and take the place of any
+    /// [`set_coordinate_transform`](crate::CursorDetector::set_coordinate_transform)
+    /// mapping for this sink.
+    pub fn normalized(mut self, normalization: Normalization) -> Self {
+        self.normalizer = Some(Normalizer::new(normalization));
+        self
+    }
+
+    /// Whether an event passes, updating the rate limit state when it does
+    fn accepts(&mut self, event: &CursorEvent) -> bool {
+        if !self.kinds.contains(event.kind()) {
+            return false;
+        }
+        self.rate_limit.as_mut().is_none_or(|rate_limit| rate_limit.allow(event.kind()))
+    }
+}
+
+/// A sink registered under a name together with its filter
+#[cfg(windows)]
+struct NamedSink {
+    name: String,
+    sink: Box<dyn EventSink>,
+    filter: SinkFilter,
+}
+
+/// Sinks shared between the detector, its handles and the processing thread
+#[cfg(windows)]
+#[derive(Default)]
+pub(crate) struct SinkSet {
+    sinks: Vec<NamedSink>,
+}
+
+#[cfg(windows)]
+pub(crate) type SharedSinks = Arc<Mutex<SinkSet>>;
+
+#[cfg(windows)]
+impl std::fmt::Debug for SinkSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.sinks.iter().map(|entry| &entry.name)).finish()
+    }
+}
+
+#[cfg(windows)]
+impl SinkSet {
+    /// Add a sink, replacing and flushing any sink with the same name
+    pub(crate) fn add(&mut self, name: &str, sink: Box<dyn EventSink>, filter: SinkFilter) {
+        self.remove(name);
+        self.sinks.push(NamedSink { name: name.to_string(), sink, filter });
+    }
+
+    /// Flush and remove a sink by name, reporting whether it existed
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        let Some(index) = self.sinks.iter().position(|entry| entry.name == name) else {
+            return false;
+        };
+        self.sinks.remove(index).sink.flush();
+        true
+    }
+
+    /// Names of the registered sinks in dispatch order
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.sinks.iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Hand an event to every sink whose filter accepts it
+    ///
+    /// `transformed` is the event after the coordinate transform, delivered to sinks
+    /// that do not normalize positions themselves. A sink that panics is reported to `on_panic` with its name and stays attached,
+    /// so one faulty sink never keeps events from the others.
+    pub(crate) fn dispatch(
+        &mut self,
+        event: &CursorEvent,
+        transformed: Option<&CursorEvent>,
+        on_panic: &mut dyn FnMut(&str, Box<dyn Any + Send>),
+    ) {
+        for entry in self.sinks.iter_mut() {
+            if entry.filter.accepts(event) {
+                let normalized = entry.filter.normalizer.as_mut().map(|normalizer| normalizer.apply(event));
+                let event = normalized.as_ref().or(transformed).unwrap_or(event);
+                if let Err(payload) = catch_unwind(AssertUnwindSafe(|| entry.sink.handle(event))) {
+                    on_panic(&format!("sink '{}'", entry.name), payload);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn flush(&mut self) {
+        for entry in self.sinks.iter_mut() {
+            if let Err(payload) = catch_unwind(AssertUnwindSafe(|| entry.sink.flush())) {
+                crate::CursorDetector::log_message(&format!(
+                    "Sink '{}' panicked while flushing: {}",
+                    entry.name,
+                    crate::panic_message(&*payload)
+                ));
+            }
+        }
+    }
+}
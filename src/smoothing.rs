@@ -0,0 +1,168 @@
+//! Jitter filters for the cursor position stream
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::time::Instant;
+
+/// Filter applied to positions, with its parameters
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SmoothingFilter {
+    /// Exponential moving average; `alpha` in 0.0..=1.0 is the weight of each new sample
+    Ema { alpha: f64 },
+    /// One Euro filter: smooth when slow, responsive when fast
+    ///
+    /// `min_cutoff` (Hz) sets the jitter removed at rest, `beta` how quickly the
+    /// cutoff rises with speed, `derivative_cutoff` (Hz) the smoothing of the speed itself.
+    OneEuro { min_cutoff: f64, beta: f64, derivative_cutoff: f64 },
+    /// Constant-velocity Kalman filter; more `measurement_noise` relative to
+    /// `process_noise` trusts the model over the samples
+    Kalman { process_noise: f64, measurement_noise: f64 },
+}
+
+impl SmoothingFilter {
+    /// One Euro filter tuned for mouse input in pixels
+    pub fn one_euro() -> Self {
+        Self::OneEuro { min_cutoff: 1.0, beta: 0.007, derivative_cutoff: 1.0 }
+    }
+
+    /// Exponential moving average with a moderate weight
+    pub fn ema() -> Self {
+        Self::Ema { alpha: 0.5 }
+    }
+
+    /// Kalman filter tuned for mouse input in pixels
+    pub fn kalman() -> Self {
+        Self::Kalman { process_noise: 5000.0, measurement_noise: 4.0 }
+    }
+}
+
+impl Default for SmoothingFilter {
+    fn default() -> Self {
+        Self::one_euro()
+    }
+}
+
+/// Low-pass state of one One Euro axis
+#[derive(Debug, Clone, Copy)]
+struct OneEuroAxis {
+    value: f64,
+    derivative: f64,
+}
+
+/// Constant-velocity Kalman state of one axis
+#[derive(Debug, Clone, Copy)]
+struct KalmanAxis {
+    position: f64,
+    velocity: f64,
+    /// Covariance of (position, velocity) as [p00, p01, p11]
+    covariance: [f64; 3],
+}
+
+impl KalmanAxis {
+    fn new(position: f64, measurement_noise: f64) -> Self {
+        Self { position, velocity: 0.0, covariance: [measurement_noise, 0.0, measurement_noise] }
+    }
+
+    fn update(&mut self, measured: f64, dt: f64, process_noise: f64, measurement_noise: f64) {
+        // Predict with white-noise acceleration
+        let [p00, p01, p11] = self.covariance;
+        self.position += self.velocity * dt;
+        let p00 = p00 + dt * (2.0 * p01 + dt * p11) + process_noise * dt.powi(3) / 3.0;
+        let p01 = p01 + dt * p11 + process_noise * dt.powi(2) / 2.0;
+        let p11 = p11 + process_noise * dt;
+
+        // Correct with the measured position
+        let innovation = measured - self.position;
+        let total = p00 + measurement_noise;
+        let (gain_position, gain_velocity) = (p00 / total, p01 / total);
+        self.position += gain_position * innovation;
+        self.velocity += gain_velocity * innovation;
+        self.covariance = [(1.0 - gain_position) * p00, (1.0 - gain_position) * p01, p11 - gain_velocity * p01];
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SmootherState {
+    Empty,
+    Ema((f64, f64)),
+    OneEuro([OneEuroAxis; 2]),
+    Kalman([KalmanAxis; 2]),
+}
+
+/// Smooths a stream of positions with a [`SmoothingFilter`]
+///
+/// The detector runs one on the hook thread when smoothing is enabled; it can also
+/// be used on recorded paths by feeding positions with their capture times.
+#[derive(Debug, Clone)]
+pub struct PositionSmoother {
+    filter: SmoothingFilter,
+    state: SmootherState,
+    last_time: Option<Instant>,
+}
+
+impl PositionSmoother {
+    /// Smoother with no history, seeded by the first position fed
+    pub fn new(filter: SmoothingFilter) -> Self {
+        Self { filter, state: SmootherState::Empty, last_time: None }
+    }
+
+    /// Forget the filtered history, e.g. after the cursor was warped
+    pub fn reset(&mut self) {
+        self.state = SmootherState::Empty;
+        self.last_time = None;
+    }
+
+    /// Feed a raw position captured at `now` and return the smoothed one
+    pub fn update(&mut self, position: (f64, f64), now: Instant) -> (f64, f64) {
+        let dt = self.last_time.map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.last_time = Some(now);
+        let measured = [position.0, position.1];
+
+        match (self.filter, &mut self.state) {
+            (SmoothingFilter::Ema { alpha }, SmootherState::Ema(smoothed)) => {
+                let alpha = alpha.clamp(0.0, 1.0);
+                smoothed.0 += alpha * (position.0 - smoothed.0);
+                smoothed.1 += alpha * (position.1 - smoothed.1);
+                *smoothed
+            }
+            (SmoothingFilter::OneEuro { min_cutoff, beta, derivative_cutoff }, SmootherState::OneEuro(axes)) => {
+                // Samples with the same timestamp carry no new timing information
+                if dt <= 0.0 {
+                    return (axes[0].value, axes[1].value);
+                }
+                for (axis, measured) in axes.iter_mut().zip(measured) {
+                    let derivative = (measured - axis.value) / dt;
+                    axis.derivative += smoothing_factor(derivative_cutoff, dt) * (derivative - axis.derivative);
+                    let cutoff = min_cutoff + beta * axis.derivative.abs();
+                    axis.value += smoothing_factor(cutoff, dt) * (measured - axis.value);
+                }
+                (axes[0].value, axes[1].value)
+            }
+            (SmoothingFilter::Kalman { process_noise, measurement_noise }, SmootherState::Kalman(axes)) => {
+                for (axis, measured) in axes.iter_mut().zip(measured) {
+                    axis.update(measured, dt, process_noise, measurement_noise);
+                }
+                (axes[0].position, axes[1].position)
+            }
+            // The first sample seeds the filter unchanged
+            (filter, state) => {
+                *state = match filter {
+                    SmoothingFilter::Ema { .. } => SmootherState::Ema(position),
+                    SmoothingFilter::OneEuro { .. } => {
+                        SmootherState::OneEuro(measured.map(|value| OneEuroAxis { value, derivative: 0.0 }))
+                    }
+                    SmoothingFilter::Kalman { measurement_noise, .. } => {
+                        SmootherState::Kalman(measured.map(|value| KalmanAxis::new(value, measurement_noise)))
+                    }
+                };
+                position
+            }
+        }
+    }
+}
+
+/// Exponential smoothing factor of a low-pass filter with `cutoff` Hz over `dt` seconds
+fn smoothing_factor(cutoff: f64, dt: f64) -> f64 {
+    let tau = 1.0 / (2.0 * PI * cutoff.max(f64::EPSILON));
+    1.0 / (1.0 + tau / dt)
+}
@@ -0,0 +1,76 @@
+//! Fixed-rate cursor state snapshots for polling-style consumers
+
+use crate::clock::{Clock, SharedClock};
+use crate::queue::EventQueue;
+use crate::session::CapturePause;
+use crate::{AtomicCursorState, CursorDetector, CursorEvent, CursorState};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Callback receiving a [`CursorState`] on every snapshot tick
+pub type SnapshotCallback = Box<dyn Fn(&CursorState) + Send>;
+
+/// Thread delivering snapshots at a steady rate until monitoring stops
+pub(crate) struct SnapshotThread {
+    rate_hz: u32,
+    /// Hands the callback back so it survives a restart
+    handle: thread::JoinHandle<SnapshotCallback>,
+}
+
+impl SnapshotThread {
+    pub(crate) fn spawn(
+        rate_hz: u32,
+        callback: SnapshotCallback,
+        atomic_state: Arc<AtomicCursorState>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        running: Arc<AtomicBool>,
+        pause: Arc<CapturePause>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        let interval = Duration::from_secs(1) / rate_hz.max(1);
+        let handle = thread::Builder::new()
+            .name("luuma-snapshot".to_string())
+            .spawn(move || {
+                let mut next_tick = Instant::now();
+                while running.load(Ordering::Relaxed) {
+                    // Nothing is captured while paused, snapshots included
+                    if !pause.is_paused() {
+                        let state = crate::read_state(&atomic_state, clock.now());
+                        if let Err(payload) = catch_unwind(AssertUnwindSafe(|| callback(&state))) {
+                            // Keep ticking; the next snapshot may well succeed
+                            let message = crate::panic_message(&*payload);
+                            CursorDetector::log_message(&format!("Snapshot callback panicked: {}", message));
+                            let handler = "snapshot callback".to_string();
+                            let _ = event_queue.push(CursorEvent::HandlerPanicked {
+                                handler,
+                                message,
+                                timestamp: clock.now(),
+                            });
+                        }
+                    }
+
+                    // Tick on a fixed schedule so slow callbacks do not drift the rate,
+                    // but skip missed ticks rather than delivering a burst
+                    next_tick += interval;
+                    let now = Instant::now();
+                    if next_tick < now {
+                        next_tick = now;
+                    }
+                    thread::sleep(next_tick - now);
+                }
+                callback
+            })
+            .map_err(|e| format!("Failed to spawn snapshot thread: {}", e))?;
+
+        Ok(Self { rate_hz, handle })
+    }
+
+    /// Wait for the thread, returning the rate and callback it ran with
+    pub(crate) fn stop(self) -> Result<(u32, SnapshotCallback), String> {
+        let callback = self.handle.join().map_err(|e| format!("Failed to join snapshot thread: {:?}", e))?;
+        Ok((self.rate_hz, callback))
+    }
+}
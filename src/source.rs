@@ -0,0 +1,120 @@
+//! Classification of where pointer input came from
+
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::sync::Mutex;
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+
+/// `dwExtraInfo` signature Windows stamps on mouse messages promoted from touch and pen
+#[cfg(windows)]
+const MI_WP_SIGNATURE: usize = 0xFF51_5700;
+#[cfg(windows)]
+const SIGNATURE_MASK: usize = 0xFFFF_FF00;
+/// Bit set in the promoted signature when the source was touch rather than pen
+#[cfg(windows)]
+const TOUCH_BIT: usize = 0x80;
+
+/// How long a digitizer report stays attached to the mouse messages promoted from it
+#[cfg(windows)]
+const CONTACT_MAX_AGE: Duration = Duration::from_millis(100);
+
+/// Physical kind of device behind a mouse message
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum PointerKind {
+    #[default]
+    Mouse,
+    Touch,
+    Pen,
+}
+
+/// Origin of an input event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub struct InputSource {
+    /// Device kind the input was promoted from
+    pub kind: PointerKind,
+    /// Input was synthesized (e.g. `SendInput`) rather than produced by hardware
+    pub injected: bool,
+    /// Injected by a process running at a lower integrity level
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lower_integrity: bool,
+}
+
+impl InputSource {
+    /// Classify a low-level hook sample from its `flags` and `dwExtraInfo`
+    #[cfg(windows)]
+    pub fn from_hook(flags: u32, extra_info: usize) -> Self {
+        use windows::Win32::UI::WindowsAndMessaging::{LLMHF_INJECTED, LLMHF_LOWER_IL_INJECTED};
+
+        let kind = if extra_info & SIGNATURE_MASK == MI_WP_SIGNATURE {
+            if extra_info & TOUCH_BIT != 0 {
+                PointerKind::Touch
+            } else {
+                PointerKind::Pen
+            }
+        } else {
+            PointerKind::Mouse
+        };
+
+        Self {
+            kind,
+            injected: flags & LLMHF_INJECTED != 0,
+            lower_integrity: flags & LLMHF_LOWER_IL_INJECTED != 0,
+        }
+    }
+
+    /// Whether the input came from real hardware
+    pub fn is_physical(&self) -> bool {
+        !self.injected
+    }
+}
+
+/// Pressure and tilt of the pen or touch contact behind a move or click
+///
+/// Each is `None` when the device does not report it, so its presence doubles as
+/// the capability flag. On Windows it comes from digitizer reports (the `pointer`
+/// feature with pointer input enabled); in the browser from pointer events.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct ContactData {
+    /// 0.0..=1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure: Option<f64>,
+    /// X/Y tilt in degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tilt: Option<(f64, f64)>,
+}
+
+impl ContactData {
+    /// Whether the device reports pressure
+    pub fn has_pressure(&self) -> bool {
+        self.pressure.is_some()
+    }
+
+    /// Whether the device reports tilt
+    pub fn has_tilt(&self) -> bool {
+        self.tilt.is_some()
+    }
+}
+
+/// Latest pen or touch contact decoded on the raw input thread, read by the mouse hook
+#[cfg(windows)]
+#[derive(Debug, Default)]
+pub(crate) struct LastContact(Mutex<Option<(PointerKind, ContactData, Instant)>>);
+
+#[cfg(windows)]
+impl LastContact {
+    #[cfg(feature = "pointer")]
+    pub(crate) fn record(&self, kind: PointerKind, contact: ContactData) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some((kind, contact, Instant::now()));
+    }
+
+    /// Contact behind a mouse message promoted from `kind`, if a report arrived just before it
+    pub(crate) fn get(&self, kind: PointerKind) -> Option<Box<ContactData>> {
+        if kind == PointerKind::Mouse {
+            return None;
+        }
+        let last = *self.0.lock().unwrap_or_else(|e| e.into_inner());
+        last.filter(|(recorded, _, at)| *recorded == kind && at.elapsed() <= CONTACT_MAX_AGE)
+            .map(|(_, contact, _)| Box::new(contact))
+    }
+}
@@ -0,0 +1,126 @@
+//! Watchdog noticing when the hook stops delivering while the mouse is in use
+
+use crate::clock::{Clock, SharedClock};
+use crate::dpi::PhysicalCoordinates;
+use crate::filter::SharedKindMask;
+use crate::queue::EventQueue;
+use crate::session::CapturePause;
+use crate::{CursorDetector, CursorEvent, EventKind};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::POINT;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+/// How often the probe samples the cursor position
+const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Probe samples with user-driven motion needed before silence counts as a stall
+const MIN_MOTION_SAMPLES: u32 = 2;
+
+/// Time of the latest hook callback, written on the hook thread
+#[derive(Debug)]
+pub(crate) struct HookActivity {
+    origin: Instant,
+    last_call_ms: AtomicU64,
+}
+
+impl HookActivity {
+    pub(crate) fn new() -> Self {
+        Self { origin: Instant::now(), last_call_ms: AtomicU64::new(0) }
+    }
+
+    /// Record a hook callback
+    pub(crate) fn mark(&self) {
+        self.last_call_ms.store(self.origin.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn since_last_call(&self) -> Duration {
+        let last = Duration::from_millis(self.last_call_ms.load(Ordering::Relaxed));
+        self.origin.elapsed().saturating_sub(last)
+    }
+}
+
+/// Thread polling the cursor independently of the hook and raising `FlowStalled`
+///
+/// The probe counts a sample as motion only when the position changed together
+/// with the session's last input time, so cursor moves made by other applications
+/// through `SetCursorPos` (which never reach hooks) are not mistaken for a stall.
+pub(crate) struct StallWatchdog {
+    handle: thread::JoinHandle<()>,
+}
+
+impl StallWatchdog {
+    pub(crate) fn spawn(
+        timeout: Duration,
+        activity: Arc<HookActivity>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        event_mask: Arc<SharedKindMask>,
+        running: Arc<AtomicBool>,
+        pause: Arc<CapturePause>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        let handle = thread::Builder::new()
+            .name("luuma-stall-watchdog".to_string())
+            .spawn(move || {
+                // Reported positions must be in the same physical pixels as hook positions
+                let _physical = PhysicalCoordinates::enter();
+                let mut last_sample = probe();
+                let mut motion_samples = 0;
+                let mut raised = false;
+                while running.load(Ordering::Relaxed) {
+                    thread::sleep(PROBE_INTERVAL);
+                    let sample = probe();
+                    let moved = sample.0 != last_sample.0 && sample.1 != last_sample.1;
+                    last_sample = sample;
+
+                    // The hook is not called on the secure desktop, where capture is paused anyway
+                    let silent = activity.since_last_call();
+                    if pause.is_paused() || silent < PROBE_INTERVAL {
+                        (motion_samples, raised) = (0, false);
+                        continue;
+                    }
+                    if moved {
+                        motion_samples += 1;
+                    }
+                    if raised || silent < timeout || motion_samples < MIN_MOTION_SAMPLES {
+                        continue;
+                    }
+
+                    raised = true;
+                    CursorDetector::log_message(&format!(
+                        "No hook events for {:.1} s while the mouse is moving",
+                        silent.as_secs_f64()
+                    ));
+                    if event_mask.allows(EventKind::FlowStalled) {
+                        let position = (sample.0.x as f64, sample.0.y as f64);
+                        let _ = event_queue.push(CursorEvent::FlowStalled {
+                            silent,
+                            position,
+                            timestamp: clock.now(),
+                        });
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn stall watchdog thread: {}", e))?;
+
+        Ok(Self { handle })
+    }
+
+    pub(crate) fn stop(self) -> Result<(), String> {
+        self.handle.join().map_err(|e| format!("Failed to join stall watchdog thread: {:?}", e))
+    }
+}
+
+/// Cursor position and the tick count of the latest user input
+fn probe() -> (POINT, u32) {
+    let mut position = POINT::default();
+    let mut input = LASTINPUTINFO { cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+    unsafe {
+        let _ = GetCursorPos(&mut position);
+        let _ = GetLastInputInfo(&mut input);
+    }
+    (position, input.dwTime)
+}
@@ -0,0 +1,367 @@
+//! Session statistics aggregated from the event stream
+
+use crate::activity::{ActivityTracker, AppActivity};
+use crate::clock::{Clock, SharedClock, SystemClock};
+use crate::ergonomics::ErgonomicsReport;
+use crate::hover::{HoverReport, HoverTracker};
+use crate::pointing::{PointingReport, PointingTracker};
+use crate::region::MonitorCache;
+use crate::responsiveness::{ResponsivenessReport, ResponsivenessTracker};
+use crate::{CursorEvent, CursorType, MouseButton, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Meters per inch, for converting pixel distances through the monitor DPI
+const METERS_PER_INCH: f64 = 0.0254;
+
+/// Totals accumulated since monitoring started or the last reset
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionStats {
+    /// When accumulation started
+    pub started: Timestamp,
+    /// Clicks per button
+    pub clicks: HashMap<MouseButton, u64>,
+    /// Distance traveled in physical pixels
+    pub distance_px: f64,
+    /// Distance traveled on screen in meters, estimated from each monitor's effective DPI
+    pub distance_m: f64,
+    /// Time with activity no more than the idle threshold apart
+    #[serde(with = "crate::duration_ms")]
+    pub active_time: Duration,
+    /// Time spent in gaps longer than the idle threshold
+    #[serde(with = "crate::duration_ms")]
+    pub idle_time: Duration,
+    /// Longest run of activity without a break
+    #[serde(default, with = "crate::duration_ms")]
+    pub longest_stretch: Duration,
+    /// Gaps in activity of at least [`BREAK_THRESHOLD`](Self::BREAK_THRESHOLD)
+    #[serde(default)]
+    pub breaks: u32,
+    /// Milliseconds each cursor type was shown
+    pub cursor_type_ms: HashMap<CursorType, u64>,
+    /// Clicks, distance, active time and scrolling per application, keyed by executable name
+    ///
+    /// Processes excluded by the privacy mask never appear: their windows and focus
+    /// changes are masked before statistics are recorded.
+    #[serde(default)]
+    pub applications: HashMap<String, AppActivity>,
+}
+
+impl SessionStats {
+    /// Gaps between activity longer than this count as idle time
+    pub const DEFAULT_IDLE_THRESHOLD: Duration = Duration::from_secs(5);
+
+    /// Gaps between activity at least this long count as a break
+    pub const BREAK_THRESHOLD: Duration = Duration::from_secs(120);
+
+    fn new(started: Timestamp) -> Self {
+        Self {
+            started,
+            clicks: HashMap::new(),
+            distance_px: 0.0,
+            distance_m: 0.0,
+            active_time: Duration::ZERO,
+            idle_time: Duration::ZERO,
+            longest_stretch: Duration::ZERO,
+            breaks: 0,
+            cursor_type_ms: HashMap::new(),
+            applications: HashMap::new(),
+        }
+    }
+
+    /// Totals over recorded events, e.g. from a session file
+    ///
+    /// Time runs from the first event to the last, so no idle time is counted after it.
+    pub fn from_events<'a, I>(events: I) -> Self
+    where
+        I: IntoIterator<Item = &'a CursorEvent>,
+    {
+        let mut events = events.into_iter().peekable();
+        let Some(first) = events.peek() else {
+            return Self::new(Timestamp::now());
+        };
+        let mut collector = StatsCollector::starting_at(Arc::new(SystemClock), first.timestamp());
+        let mut last = first.timestamp();
+        for event in events {
+            collector.record(event);
+            last = last.max(event.timestamp());
+        }
+        collector.snapshot_at(last)
+    }
+
+    /// Clicks of every button
+    pub fn total_clicks(&self) -> u64 {
+        self.clicks.values().sum()
+    }
+
+    /// Distance, clicks, speed and activity stretches with break recommendations
+    pub fn ergonomics(&self) -> ErgonomicsReport {
+        ErgonomicsReport::from_stats(self)
+    }
+
+    /// Convert statistics to JSON string
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Convert statistics to pretty-formatted JSON string
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Accumulates [`SessionStats`] on the processing thread
+pub(crate) struct StatsCollector {
+    clock: SharedClock,
+    stats: SessionStats,
+    idle_threshold: Duration,
+    last_position: Option<(f64, f64)>,
+    last_activity: Timestamp,
+    /// Start of the activity since the last break, once there was any
+    stretch_start: Option<Timestamp>,
+    current_type: Option<(CursorType, Timestamp)>,
+    monitors: MonitorCache,
+    responsiveness: ResponsivenessTracker,
+    pointing: PointingTracker,
+    activity: ActivityTracker,
+    hover: HoverTracker,
+}
+
+pub(crate) type SharedStats = Arc<Mutex<StatsCollector>>;
+
+impl std::fmt::Debug for StatsCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatsCollector").field("stats", &self.stats).finish_non_exhaustive()
+    }
+}
+
+impl StatsCollector {
+    pub(crate) fn new(clock: SharedClock) -> Self {
+        let now = clock.now();
+        Self::starting_at(clock, now)
+    }
+
+    fn starting_at(clock: SharedClock, now: Timestamp) -> Self {
+        Self {
+            clock,
+            stats: SessionStats::new(now),
+            idle_threshold: SessionStats::DEFAULT_IDLE_THRESHOLD,
+            last_position: None,
+            last_activity: now,
+            stretch_start: None,
+            current_type: None,
+            monitors: MonitorCache::default(),
+            responsiveness: ResponsivenessTracker::default(),
+            pointing: PointingTracker::new(PointingReport::DEFAULT_TARGET_WIDTH),
+            activity: ActivityTracker::default(),
+            hover: HoverTracker::default(),
+        }
+    }
+
+    /// Clock the statistics are measured on
+    pub(crate) fn clock(&self) -> SharedClock {
+        Arc::clone(&self.clock)
+    }
+
+    pub(crate) fn set_idle_threshold(&mut self, threshold: Duration) {
+        self.idle_threshold = threshold;
+    }
+
+    pub(crate) fn set_target_width(&mut self, width: f64) {
+        self.pointing = PointingTracker::new(width);
+    }
+
+    /// Start over from now, keeping the idle threshold, target width and foreground application
+    pub(crate) fn reset(&mut self) {
+        let idle_threshold = self.idle_threshold;
+        let target_width = self.pointing.target_width();
+        let activity = self.activity.restart();
+        *self = Self::new(Arc::clone(&self.clock));
+        self.idle_threshold = idle_threshold;
+        self.pointing = PointingTracker::new(target_width);
+        self.activity = activity;
+    }
+
+    /// Fold one event into the totals
+    pub(crate) fn record(&mut self, event: &CursorEvent) {
+        self.responsiveness.record(event);
+        self.pointing.record(event);
+        self.activity.record(event, self.idle_threshold);
+        self.hover.record(event);
+        match event {
+            CursorEvent::Move { position, cursor_type, timestamp, .. } => {
+                self.travel(*position);
+                self.show_type(*cursor_type, *timestamp);
+                self.activity(*timestamp);
+            }
+            CursorEvent::Click { button, position, timestamp, .. } => {
+                *self.stats.clicks.entry(*button).or_default() += 1;
+                self.travel(*position);
+                self.activity(*timestamp);
+            }
+            CursorEvent::Release { timestamp, .. }
+            | CursorEvent::RawMotion { timestamp, .. }
+            | CursorEvent::Scroll { timestamp, .. } => {
+                self.activity(*timestamp);
+            }
+            CursorEvent::TypeChange { new_type, timestamp, .. } => self.show_type(*new_type, *timestamp),
+            _ => {}
+        }
+    }
+
+    /// Totals including the time elapsed since the last event
+    pub(crate) fn snapshot(&self) -> SessionStats {
+        self.snapshot_at(self.clock.now())
+    }
+
+    /// Totals as of `now`, counting the gap since the last event
+    fn snapshot_at(&self, now: Timestamp) -> SessionStats {
+        let mut stats = self.stats.clone();
+        let (active, idle) = self.split_gap(now.duration_since(self.last_activity));
+        stats.active_time += active;
+        stats.idle_time += idle;
+        if let Some((cursor_type, since)) = self.current_type {
+            *stats.cursor_type_ms.entry(cursor_type).or_default() += now.duration_since(since).as_millis() as u64;
+        }
+        stats.applications = self.activity.report_at(now, self.idle_threshold);
+        stats
+    }
+
+    /// Busy cursor statistics per foreground application
+    pub(crate) fn responsiveness(&self) -> ResponsivenessReport {
+        self.responsiveness.report()
+    }
+
+    /// Movement metrics between consecutive clicks
+    pub(crate) fn pointing(&self) -> PointingReport {
+        self.pointing.report()
+    }
+
+    /// Time from hovering a region or link to clicking it
+    pub(crate) fn hover(&self) -> HoverReport {
+        self.hover.report()
+    }
+
+    fn travel(&mut self, position: (f64, f64)) {
+        if let Some(last) = self.last_position.replace(position) {
+            let distance = (position.0 - last.0).hypot(position.1 - last.1);
+            let dpi = self
+                .monitors
+                .monitors()
+                .iter()
+                .find(|monitor| monitor.bounds.contains(position.0, position.1))
+                .map(|monitor| monitor.dpi)
+                .unwrap_or(crate::dpi::BASE_DPI);
+            self.stats.distance_px += distance;
+            self.stats.distance_m += distance / dpi as f64 * METERS_PER_INCH;
+        }
+    }
+
+    fn show_type(&mut self, cursor_type: CursorType, timestamp: Timestamp) {
+        match self.current_type {
+            Some((current, _)) if current == cursor_type => {}
+            Some((current, since)) => {
+                *self.stats.cursor_type_ms.entry(current).or_default() += timestamp.duration_since(since).as_millis() as u64;
+                self.current_type = Some((cursor_type, timestamp));
+            }
+            None => self.current_type = Some((cursor_type, timestamp)),
+        }
+    }
+
+    fn activity(&mut self, timestamp: Timestamp) {
+        let gap = timestamp.duration_since(self.last_activity);
+        let (active, idle) = self.split_gap(gap);
+        self.stats.active_time += active;
+        self.stats.idle_time += idle;
+        let stretch_start = match self.stretch_start {
+            Some(start) if gap < SessionStats::BREAK_THRESHOLD => start,
+            Some(_) => {
+                self.stats.breaks += 1;
+                timestamp
+            }
+            None => timestamp,
+        };
+        self.stretch_start = Some(stretch_start);
+        self.last_activity = self.last_activity.max(timestamp);
+        let stretch = self.last_activity.duration_since(stretch_start);
+        self.stats.longest_stretch = self.stats.longest_stretch.max(stretch);
+    }
+
+    /// Attribute a gap between activity to active or idle time
+    fn split_gap(&self, gap: Duration) -> (Duration, Duration) {
+        if gap <= self.idle_threshold {
+            (gap, Duration::ZERO)
+        } else {
+            (Duration::ZERO, gap)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionStats, StatsCollector};
+    use crate::clock::ManualClock;
+    use crate::{CursorEvent, MouseButton, Timestamp};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const START: u64 = 1_738_324_800_000;
+
+    fn release(seconds: u64) -> CursorEvent {
+        CursorEvent::Release {
+            button: MouseButton::Left,
+            press_position: None,
+            held_for: Duration::ZERO,
+            source: Default::default(),
+            device_id: None,
+            precise_time: None,
+            timestamp: Timestamp::from_millis(START + seconds * 1000),
+        }
+    }
+
+    fn collector() -> (StatsCollector, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(Timestamp::from_millis(START)));
+        (StatsCollector::new(clock.clone()), clock)
+    }
+
+    #[test]
+    fn splits_gaps_into_active_and_idle_time() {
+        let (mut collector, clock) = collector();
+        for seconds in [1, 3, 13, 15] {
+            collector.record(&release(seconds));
+        }
+        let stats = collector.snapshot();
+        assert_eq!(stats.active_time, Duration::from_secs(5));
+        assert_eq!(stats.idle_time, Duration::from_secs(10));
+
+        // The gap since the last event counts too, as idle once it passes the threshold
+        clock.advance(Duration::from_secs(18));
+        assert_eq!(collector.snapshot().active_time, Duration::from_secs(8));
+        clock.advance(Duration::from_secs(10));
+        let stats = collector.snapshot();
+        assert_eq!((stats.active_time, stats.idle_time), (Duration::from_secs(5), Duration::from_secs(23)));
+    }
+
+    #[test]
+    fn breaks_end_activity_stretches() {
+        let (mut collector, clock) = collector();
+        collector.set_idle_threshold(Duration::from_secs(30));
+        // A stretch of 20 s, a break, then one of 10 s
+        for seconds in [10, 20, 30, 160, 165, 170] {
+            collector.record(&release(seconds));
+        }
+        clock.advance(Duration::from_secs(170));
+        let stats = collector.snapshot();
+        assert_eq!(stats.breaks, 1);
+        assert_eq!(stats.longest_stretch, Duration::from_secs(20));
+        assert_eq!(stats.idle_time, SessionStats::BREAK_THRESHOLD + Duration::from_secs(10));
+
+        // Gaps just short of a break stay within the stretch
+        collector.record(&release(170 + 119));
+        clock.advance(Duration::from_secs(119));
+        let stats = collector.snapshot();
+        assert_eq!((stats.breaks, stats.longest_stretch), (1, Duration::from_secs(129)));
+    }
+}
@@ -0,0 +1,75 @@
+//! Scheduling settings for the hook and processing threads
+
+use windows::Win32::System::Threading::{
+    GetCurrentThread, SetThreadAffinityMask, SetThreadPriority, THREAD_PRIORITY, THREAD_PRIORITY_ABOVE_NORMAL,
+    THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_HIGHEST, THREAD_PRIORITY_IDLE, THREAD_PRIORITY_LOWEST,
+    THREAD_PRIORITY_NORMAL, THREAD_PRIORITY_TIME_CRITICAL,
+};
+
+/// Windows thread priority levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPriority {
+    Idle,
+    Lowest,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    Highest,
+    TimeCritical,
+}
+
+impl ThreadPriority {
+    fn to_win32(self) -> THREAD_PRIORITY {
+        match self {
+            ThreadPriority::Idle => THREAD_PRIORITY_IDLE,
+            ThreadPriority::Lowest => THREAD_PRIORITY_LOWEST,
+            ThreadPriority::BelowNormal => THREAD_PRIORITY_BELOW_NORMAL,
+            ThreadPriority::Normal => THREAD_PRIORITY_NORMAL,
+            ThreadPriority::AboveNormal => THREAD_PRIORITY_ABOVE_NORMAL,
+            ThreadPriority::Highest => THREAD_PRIORITY_HIGHEST,
+            ThreadPriority::TimeCritical => THREAD_PRIORITY_TIME_CRITICAL,
+        }
+    }
+}
+
+/// Priority and core pinning for one of the detector's threads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadConfig {
+    /// Priority to set when the thread starts, `None` keeps the inherited one
+    pub priority: Option<ThreadPriority>,
+    /// Logical core index to pin the thread to
+    pub affinity: Option<usize>,
+}
+
+impl ThreadConfig {
+    /// Check the core index against the cores available to this process
+    pub(crate) fn validate(&self, name: &str) -> Result<(), String> {
+        if let Some(core) = self.affinity {
+            let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            if core >= cores.min(usize::BITS as usize) {
+                return Err(format!("Cannot pin {} thread to core {}: only {} cores available", name, core, cores));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the settings to the calling thread
+    pub(crate) fn apply_current(&self) -> Result<(), String> {
+        unsafe {
+            let thread = GetCurrentThread();
+
+            if let Some(priority) = self.priority {
+                SetThreadPriority(thread, priority.to_win32())
+                    .map_err(|e| format!("Failed to set thread priority {:?}: {}", priority, e))?;
+            }
+
+            if let Some(core) = self.affinity {
+                if SetThreadAffinityMask(thread, 1usize << core) == 0 {
+                    return Err(format!("Failed to pin thread to core {}", core));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
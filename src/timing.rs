@@ -0,0 +1,170 @@
+//! High-resolution timestamps and mapping events onto video frames
+
+use crate::{CursorEvent, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+#[cfg(windows)]
+use windows::Win32::System::Performance::{QueryPerformanceCounter, QueryPerformanceFrequency};
+
+/// Performance counter reading paired with the wall clock, to convert between the two
+struct ClockAnchor {
+    frequency: u64,
+    nanos: u64,
+    wall_nanos: u64,
+}
+
+static ANCHOR: OnceLock<ClockAnchor> = OnceLock::new();
+
+fn anchor() -> &'static ClockAnchor {
+    ANCHOR.get_or_init(|| {
+        let frequency = read_frequency().max(1);
+        #[cfg(all(target_arch = "wasm32", feature = "web"))]
+        let wall_nanos = (js_sys::Date::now() * 1e6) as u64;
+        #[cfg(not(all(target_arch = "wasm32", feature = "web")))]
+        let wall_nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        ClockAnchor { frequency, nanos: ticks_to_nanos(read_counter(), frequency), wall_nanos }
+    })
+}
+
+#[cfg(windows)]
+fn read_frequency() -> u64 {
+    let mut frequency = 0;
+    unsafe {
+        let _ = QueryPerformanceFrequency(&mut frequency);
+    }
+    frequency as u64
+}
+
+#[cfg(windows)]
+fn read_counter() -> u64 {
+    let mut ticks = 0;
+    unsafe {
+        let _ = QueryPerformanceCounter(&mut ticks);
+    }
+    ticks as u64
+}
+
+/// Elsewhere the counter ticks in nanoseconds
+#[cfg(not(windows))]
+fn read_frequency() -> u64 {
+    1_000_000_000
+}
+
+/// Nanoseconds on the page's `performance.now()` clock
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+fn read_counter() -> u64 {
+    (crate::web::performance_now() * 1e6) as u64
+}
+
+/// Nanoseconds since the clock was first read
+#[cfg(not(any(windows, all(target_arch = "wasm32", feature = "web"))))]
+fn read_counter() -> u64 {
+    static START: OnceLock<std::time::Instant> = OnceLock::new();
+    START.get_or_init(std::time::Instant::now).elapsed().as_nanos() as u64
+}
+
+fn ticks_to_nanos(ticks: u64, frequency: u64) -> u64 {
+    (ticks as u128 * 1_000_000_000 / frequency as u128) as u64
+}
+
+/// Monotonic time from the performance counter (QPC) in nanoseconds
+///
+/// Unlike [`Timestamp`] it has sub-microsecond resolution and never jumps when
+/// the wall clock is adjusted, so it is what screen recorders stamp frames with.
+/// In the browser it follows `performance.now()` instead.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct PreciseTime(u64);
+
+impl PreciseTime {
+    /// Read the performance counter
+    pub fn now() -> Self {
+        Self(ticks_to_nanos(read_counter(), anchor().frequency))
+    }
+
+    /// Create from raw `QueryPerformanceCounter` ticks, e.g. from a capture API
+    pub fn from_ticks(ticks: u64) -> Self {
+        Self(ticks_to_nanos(ticks, anchor().frequency))
+    }
+
+    /// Create from nanoseconds on the performance counter clock
+    pub fn from_nanos(nanos: u64) -> Self {
+        Self(nanos)
+    }
+
+    /// Nanoseconds on the performance counter clock
+    pub fn as_nanos(&self) -> u64 {
+        self.0
+    }
+
+    /// Time elapsed between an earlier reading and this one
+    pub fn duration_since(&self, earlier: PreciseTime) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(earlier.0))
+    }
+
+    /// Approximate performance counter time of a wall-clock timestamp
+    pub fn from_timestamp(timestamp: Timestamp) -> Self {
+        let anchor = anchor();
+        let wall_nanos = timestamp.as_millis().saturating_mul(1_000_000);
+        Self((anchor.nanos as i128 + wall_nanos as i128 - anchor.wall_nanos as i128).max(0) as u64)
+    }
+
+    /// Wall-clock time of this reading, to the millisecond
+    pub fn to_timestamp(&self) -> Timestamp {
+        let anchor = anchor();
+        let wall_nanos = anchor.wall_nanos as i128 + self.0 as i128 - anchor.nanos as i128;
+        Timestamp::from_millis((wall_nanos.max(0) / 1_000_000) as u64)
+    }
+
+    /// Precise capture time of an event, falling back to its millisecond timestamp
+    pub fn of_event(event: &CursorEvent) -> Self {
+        event.precise_time().unwrap_or_else(|| Self::from_timestamp(event.timestamp()))
+    }
+}
+
+/// A recording's frame clock, tied to event time by one known frame
+///
+/// Sync with a frame whose capture time is known, either from the recorder's own
+/// QPC frame timestamps or by emitting a marker when recording starts and
+/// pairing it with frame 0.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FrameClock {
+    /// Frames per second of the recording
+    pub fps: f64,
+    /// Capture time of `sync_frame`
+    pub sync_time: PreciseTime,
+    /// Frame index shown at `sync_time`
+    pub sync_frame: u64,
+}
+
+impl FrameClock {
+    /// Frame clock where frame `sync_frame` was captured at `sync_time`
+    pub fn new(fps: f64, sync_time: PreciseTime, sync_frame: u64) -> Self {
+        Self { fps, sync_time, sync_frame }
+    }
+
+    /// Frame clock synced to a marker (or any event) shown at `frame`
+    pub fn from_event(fps: f64, event: &CursorEvent, frame: u64) -> Self {
+        Self::new(fps, PreciseTime::of_event(event), frame)
+    }
+
+    /// Index of the frame on screen at `time`, negative before the recording started
+    pub fn frame_at(&self, time: PreciseTime) -> i64 {
+        let offset = (time.as_nanos() as i128 - self.sync_time.as_nanos() as i128) as f64 / 1e9;
+        self.sync_frame as i64 + (offset * self.fps).floor() as i64
+    }
+
+    /// Index of the frame on screen when an event was captured
+    pub fn frame_of(&self, event: &CursorEvent) -> i64 {
+        self.frame_at(PreciseTime::of_event(event))
+    }
+
+    /// Capture time of a frame
+    pub fn frame_time(&self, frame: i64) -> PreciseTime {
+        let offset = (frame - self.sync_frame as i64) as f64 / self.fps;
+        PreciseTime::from_nanos((self.sync_time.as_nanos() as f64 + offset * 1e9).max(0.0) as u64)
+    }
+}
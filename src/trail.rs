@@ -0,0 +1,225 @@
+//! Movement path recording, simplification and SVG export
+
+use crate::sink::EventSink;
+use crate::{CursorEvent, Rect, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// A sampled point of the movement path
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PathPoint {
+    pub position: (f64, f64),
+    pub timestamp: Timestamp,
+}
+
+/// Rendering settings for [`PathRecorder::to_svg`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SvgOptions {
+    /// Douglas-Peucker tolerance in pixels, 0 keeps every point
+    pub tolerance: f64,
+    /// Line width in pixels
+    pub stroke_width: f64,
+    /// Speed in pixels per second drawn in the hottest color, the path's fastest segment when `None`
+    pub max_speed: Option<f64>,
+    /// Number of colors between slow (blue) and fast (red)
+    pub speed_bands: usize,
+    /// Area shown in the figure, the path's bounding box when `None`
+    pub view: Option<Rect>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self { tolerance: 2.0, stroke_width: 2.0, max_speed: None, speed_bands: 8, view: None }
+    }
+}
+
+/// Records the cursor trajectory from Move events
+///
+/// Feed it events from a handler with [`record`](Self::record), or share it as a
+/// sink through `Arc<Mutex<PathRecorder>>`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PathRecorder {
+    points: Vec<PathPoint>,
+}
+
+impl PathRecorder {
+    /// Create an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the position of a Move event
+    pub fn record(&mut self, event: &CursorEvent) {
+        if let CursorEvent::Move { position, timestamp, .. } = event {
+            self.push(*position, *timestamp);
+        }
+    }
+
+    /// Append a point, skipping repeats of the last position
+    pub fn push(&mut self, position: (f64, f64), timestamp: Timestamp) {
+        if self.points.last().is_some_and(|last| last.position == position) {
+            return;
+        }
+        self.points.push(PathPoint { position, timestamp });
+    }
+
+    /// Recorded points in order
+    pub fn points(&self) -> &[PathPoint] {
+        &self.points
+    }
+
+    /// Number of recorded points
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Whether no points were recorded
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Forget every recorded point
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Simplify the path with Douglas-Peucker, keeping points farther than `tolerance` pixels from the line
+    pub fn simplify(&self, tolerance: f64) -> Vec<PathPoint> {
+        if self.points.len() < 3 || tolerance <= 0.0 {
+            return self.points.clone();
+        }
+
+        let mut keep = vec![false; self.points.len()];
+        keep[0] = true;
+        keep[self.points.len() - 1] = true;
+
+        // Explicit stack so long recordings cannot overflow the call stack
+        let mut spans = vec![(0, self.points.len() - 1)];
+        while let Some((first, last)) = spans.pop() {
+            let (start, end) = (self.points[first].position, self.points[last].position);
+            let farthest = (first + 1..last)
+                .map(|index| (index, segment_distance(self.points[index].position, start, end)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+
+            if let Some((index, _)) = farthest.filter(|(_, distance)| *distance > tolerance) {
+                keep[index] = true;
+                spans.push((first, index));
+                spans.push((index, last));
+            }
+        }
+
+        self.points.iter().zip(keep).filter(|(_, keep)| *keep).map(|(point, _)| *point).collect()
+    }
+
+    /// Render the simplified path as SVG polylines colored by speed
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let points = self.simplify(options.tolerance);
+        let view = options.view.unwrap_or_else(|| bounding_box(&points));
+        let bands = options.speed_bands.max(1);
+
+        let speeds: Vec<f64> = points.windows(2).map(|pair| speed(&pair[0], &pair[1])).collect();
+        let max_speed = options.max_speed.unwrap_or_else(|| speeds.iter().copied().fold(0.0, f64::max));
+        let band_of = |speed: f64| {
+            if max_speed > 0.0 {
+                ((speed / max_speed * bands as f64) as usize).min(bands - 1)
+            } else {
+                0
+            }
+        };
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+            view.left,
+            view.top,
+            view.width(),
+            view.height(),
+            view.width(),
+            view.height()
+        );
+
+        // Consecutive segments in the same speed band share one polyline
+        let mut start = 0;
+        while start < speeds.len() {
+            let band = band_of(speeds[start]);
+            let mut end = start + 1;
+            while end < speeds.len() && band_of(speeds[end]) == band {
+                end += 1;
+            }
+
+            let coordinates: Vec<String> = points[start..=end]
+                .iter()
+                .map(|point| format!("{:.1},{:.1}", point.position.0, point.position.1))
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"  <polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                coordinates.join(" "),
+                band_color(band, bands),
+                options.stroke_width
+            );
+            start = end;
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Render the path and write it to an SVG file
+    pub fn save_svg<P: AsRef<Path>>(&self, path: P, options: &SvgOptions) -> Result<(), String> {
+        std::fs::write(path.as_ref(), self.to_svg(options))
+            .map_err(|e| format!("Failed to write SVG file {:?}: {}", path.as_ref(), e))
+    }
+}
+
+impl EventSink for Arc<Mutex<PathRecorder>> {
+    fn handle(&mut self, event: &CursorEvent) {
+        self.lock().unwrap_or_else(|e| e.into_inner()).record(event);
+    }
+}
+
+/// Perpendicular distance from a point to the segment between `start` and `end`
+fn segment_distance(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return (point.0 - start.0).hypot(point.1 - start.1);
+    }
+
+    let t = (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared).clamp(0.0, 1.0);
+    (point.0 - (start.0 + t * dx)).hypot(point.1 - (start.1 + t * dy))
+}
+
+/// Speed in pixels per second between two points
+fn speed(from: &PathPoint, to: &PathPoint) -> f64 {
+    let distance = (to.position.0 - from.position.0).hypot(to.position.1 - from.position.1);
+    let seconds = to.timestamp.duration_since(from.timestamp).as_secs_f64();
+    // Points within the same millisecond count as the fastest possible motion
+    if seconds > 0.0 {
+        distance / seconds
+    } else {
+        distance * 1000.0
+    }
+}
+
+/// Smallest rectangle containing every point, padded by one pixel
+fn bounding_box(points: &[PathPoint]) -> Rect {
+    let Some(first) = points.first() else {
+        return Rect::new(0, 0, 1, 1);
+    };
+    let (mut min, mut max) = (first.position, first.position);
+    for point in points {
+        min = (min.0.min(point.position.0), min.1.min(point.position.1));
+        max = (max.0.max(point.position.0), max.1.max(point.position.1));
+    }
+    Rect::new(min.0.floor() as i32 - 1, min.1.floor() as i32 - 1, max.0.ceil() as i32 + 1, max.1.ceil() as i32 + 1)
+}
+
+/// Hue from blue for the slowest band to red for the fastest
+fn band_color(band: usize, bands: usize) -> String {
+    let fraction = if bands > 1 { band as f64 / (bands - 1) as f64 } else { 1.0 };
+    format!("hsl({:.0}, 90%, 50%)", 240.0 * (1.0 - fraction))
+}
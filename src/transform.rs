@@ -0,0 +1,225 @@
+//! Coordinate transforms applied to event positions before delivery
+
+#[cfg(windows)]
+use crate::monitor::monitor_at;
+use crate::Rect;
+#[cfg(windows)]
+use crate::{CursorDetector, CursorEvent};
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+
+/// Maps a screen position in physical pixels to the caller's coordinate space
+pub type CoordinateTransform = Box<dyn Fn(f64, f64) -> (f64, f64) + Send>;
+
+/// Transform mapping `bounds` onto 0..1 on both axes, e.g. the virtual screen for resolution-independent output
+pub fn normalize_to(bounds: Rect) -> impl Fn(f64, f64) -> (f64, f64) + Send + Sync + 'static {
+    let width = bounds.width().max(1) as f64;
+    let height = bounds.height().max(1) as f64;
+    move |x, y| ((x - bounds.left as f64) / width, (y - bounds.top as f64) / height)
+}
+
+/// Bounds positions are normalized against, see [`SinkFilter::normalized`](crate::SinkFilter::normalized)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Normalization {
+    /// The bounding rectangle of all monitors
+    VirtualScreen,
+    /// The monitor each point lies on, so every monitor spans 0..1
+    Monitor,
+}
+
+/// Maps event positions onto 0..1, caching the bounds so most events need no system call
+#[cfg(windows)]
+#[derive(Debug, Clone)]
+pub(crate) struct Normalizer {
+    normalization: Normalization,
+    bounds: Option<(Rect, Instant)>,
+}
+
+#[cfg(windows)]
+impl Normalizer {
+    /// Re-query at least this often so display configuration changes are noticed
+    const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub(crate) fn new(normalization: Normalization) -> Self {
+        Self { normalization, bounds: None }
+    }
+
+    /// Copy of `event` with its positions normalized
+    ///
+    /// Points off every monitor are left in screen pixels under [`Normalization::Monitor`].
+    pub(crate) fn apply(&mut self, event: &CursorEvent) -> CursorEvent {
+        let mut event = event.clone();
+        event.map_positions(|x, y| match self.bounds_at((x, y)) {
+            Some(bounds) => normalize_to(bounds)(x, y),
+            None => (x, y),
+        });
+        event
+    }
+
+    fn bounds_at(&mut self, position: (f64, f64)) -> Option<Rect> {
+        let cached = self.bounds.filter(|(bounds, checked)| {
+            checked.elapsed() < Self::REFRESH_INTERVAL
+                && (self.normalization == Normalization::VirtualScreen || bounds.contains(position.0, position.1))
+        });
+        if let Some((bounds, _)) = cached {
+            return Some(bounds);
+        }
+        let bounds = match self.normalization {
+            Normalization::VirtualScreen => CursorDetector::get_virtual_screen_bounds(),
+            Normalization::Monitor => monitor_at(position)?.bounds,
+        };
+        self.bounds = Some((bounds, Instant::now()));
+        Some(bounds)
+    }
+}
+
+/// Projective mapping between two planes, e.g. a screen and a projector surface seen by a camera
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Homography {
+    /// Row-major 3x3 matrix acting on `(x, y, 1)`
+    pub matrix: [[f64; 3]; 3],
+}
+
+impl Homography {
+    /// Mapping that sends each of four source corners to the matching destination corner
+    ///
+    /// `None` when three of the points are collinear, which leaves the mapping undefined.
+    pub fn from_points(from: [(f64, f64); 4], to: [(f64, f64); 4]) -> Option<Self> {
+        // Two equations per correspondence in the eight unknowns h11..h32, with h33 = 1
+        let mut system = [[0.0; 9]; 8];
+        for (i, (&(x, y), &(u, v))) in from.iter().zip(to.iter()).enumerate() {
+            system[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+            system[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+        }
+        let h = solve(system)?;
+        Some(Self { matrix: [[h[0], h[1], h[2]], [h[3], h[4], h[5]], [h[6], h[7], 1.0]] })
+    }
+
+    /// Map a point, returning it unchanged where the mapping sends it to infinity
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [a, b, c] = self.matrix;
+        let w = c[0] * x + c[1] * y + c[2];
+        if w.abs() < f64::EPSILON {
+            return (x, y);
+        }
+        ((a[0] * x + a[1] * y + a[2]) / w, (b[0] * x + b[1] * y + b[2]) / w)
+    }
+
+    /// The mapping as a transform for [`CursorDetector::set_coordinate_transform`](crate::CursorDetector::set_coordinate_transform)
+    pub fn into_transform(self) -> impl Fn(f64, f64) -> (f64, f64) + Send + Sync + 'static {
+        move |x, y| self.apply(x, y)
+    }
+}
+
+/// Solve an 8x8 linear system given as augmented rows, by Gaussian elimination with partial pivoting
+fn solve(mut system: [[f64; 9]; 8]) -> Option<[f64; 8]> {
+    for column in 0..8 {
+        let pivot = (column..8).max_by(|&a, &b| system[a][column].abs().total_cmp(&system[b][column].abs()))?;
+        if system[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        system.swap(column, pivot);
+        for row in 0..8 {
+            if row != column {
+                let factor = system[row][column] / system[column][column];
+                let pivot_row = system[column];
+                for (value, pivot_value) in system[row].iter_mut().zip(pivot_row.iter()).skip(column) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+    let mut solution = [0.0; 8];
+    for (i, value) in solution.iter_mut().enumerate() {
+        *value = system[i][8] / system[i][i];
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_to, Homography};
+    use crate::{CursorEvent, MouseButton, Rect, Timestamp, Viewport};
+    use std::time::Duration;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn identity_from_matching_corners() {
+        let square = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        let homography = Homography::from_points(square, square).unwrap();
+        for (x, y) in [(0.0, 0.0), (37.5, 80.25), (-20.0, 250.0)] {
+            assert_close(homography.apply(x, y), (x, y));
+        }
+    }
+
+    #[test]
+    fn recovers_a_projective_mapping() {
+        let known = Homography { matrix: [[1.2, 0.1, 30.0], [-0.05, 0.9, 10.0], [0.0004, -0.0002, 1.0]] };
+        let from = [(0.0, 0.0), (1920.0, 0.0), (1920.0, 1080.0), (0.0, 1080.0)];
+        let homography = Homography::from_points(from, from.map(|(x, y)| known.apply(x, y))).unwrap();
+        for (x, y) in [(960.0, 540.0), (100.0, 900.0), (1500.0, 200.0)] {
+            assert_close(homography.apply(x, y), known.apply(x, y));
+        }
+    }
+
+    #[test]
+    fn maps_a_screen_onto_a_unit_square() {
+        let screen = [(0.0, 0.0), (1920.0, 0.0), (1920.0, 1080.0), (0.0, 1080.0)];
+        let unit = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let homography = Homography::from_points(screen, unit).unwrap();
+        assert_close(homography.apply(960.0, 540.0), (0.5, 0.5));
+        assert_close(homography.apply(1920.0, 0.0), (1.0, 0.0));
+    }
+
+    #[test]
+    fn collinear_points_have_no_mapping() {
+        let line = [(0.0, 0.0), (10.0, 10.0), (20.0, 20.0), (0.0, 100.0)];
+        let square = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        assert_eq!(Homography::from_points(line, square), None);
+    }
+
+    #[test]
+    fn normalizes_against_bounds() {
+        let normalize = normalize_to(Rect::new(-1920, 0, 1920, 1080));
+        assert_close(normalize(0.0, 540.0), (0.5, 0.5));
+        assert_close(normalize(-1920.0, 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn maps_every_screen_point_of_an_event() {
+        let double = |x: f64, y: f64| (x * 2.0, y * 2.0);
+
+        let mut release = CursorEvent::Release {
+            button: MouseButton::Left,
+            press_position: Some((10.0, 20.0)),
+            held_for: Duration::ZERO,
+            source: Default::default(),
+            device_id: None,
+            precise_time: None,
+            timestamp: Timestamp::from_millis(0),
+        };
+        release.map_positions(double);
+        assert!(matches!(release, CursorEvent::Release { press_position: Some((20.0, 40.0)), .. }));
+
+        let viewport = Viewport { left: 100.0, top: 50.0, width: 200.0, height: 100.0, zoom: 2.0 };
+        let mut update = CursorEvent::ViewportUpdate { viewport, timestamp: Timestamp::from_millis(0) };
+        update.map_positions(double);
+        assert_eq!(update.position(), Some((400.0, 200.0)));
+        match update {
+            CursorEvent::ViewportUpdate { viewport, .. } => {
+                assert_eq!((viewport.left, viewport.top, viewport.width, viewport.height), (200.0, 100.0, 400.0, 200.0));
+                assert_eq!(viewport.zoom, 2.0);
+            }
+            other => panic!("expected ViewportUpdate, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! Notification area icon for pausing, resuming and stopping a detector without a console
+
+use crate::DetectorHandle;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use windows::core::{w, HSTRING, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM};
+use windows::Win32::Graphics::Gdi::{CreateBitmap, DeleteObject};
+use windows::Win32::System::Console::{GetConsoleProcessList, GetConsoleWindow};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Shell::{
+    ShellExecuteW, Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
+    NOTIFY_ICON_MESSAGE,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    AppendMenuW, CreateIconIndirect, CreatePopupMenu, CreateWindowExW, DefWindowProcW, DestroyIcon, DestroyMenu,
+    DestroyWindow, DispatchMessageW, GetCursorPos, GetMessageW, KillTimer, PostMessageW, PostThreadMessageW,
+    RegisterClassW, RegisterWindowMessageW, SetForegroundWindow, SetTimer, ShowWindow, TrackPopupMenu, HICON, ICONINFO,
+    MF_CHECKED, MF_GRAYED, MF_SEPARATOR, MF_STRING, MSG, SW_HIDE, SW_SHOW, SW_SHOWNORMAL, TPM_NONOTIFY, TPM_RETURNCMD,
+    TPM_RIGHTBUTTON, WINDOW_EX_STYLE, WM_APP, WM_LBUTTONUP, WM_NULL, WM_QUIT, WM_RBUTTONUP, WM_TIMER, WNDCLASSW,
+    WS_POPUP,
+};
+
+/// Message the shell sends for clicks on the icon
+const WM_TRAY: u32 = WM_APP + 1;
+
+/// Timer id refreshing the tooltip counters
+const TOOLTIP_TIMER: usize = 1;
+
+/// How often the tooltip counters are refreshed
+const TOOLTIP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Side of the generated icon in pixels; the shell scales it to the notification area
+const ICON_SIZE: i32 = 32;
+
+/// Icon colors as BGRA: red while capturing, gray while paused
+const CAPTURING_COLOR: u32 = 0xFFD0_3030;
+const PAUSED_COLOR: u32 = 0xFF90_9090;
+
+/// Menu command ids, returned by `TrackPopupMenu`
+const MENU_PAUSE: usize = 1;
+const MENU_OPEN_FOLDER: usize = 2;
+const MENU_QUIT: usize = 3;
+
+/// What the icon shows and offers
+#[derive(Debug, Clone, Default)]
+pub struct TrayConfig {
+    /// First line of the tooltip, e.g. `"luuma record"`
+    pub title: String,
+    /// Folder opened by the "Open log folder" item, which is disabled when `None`
+    pub folder: Option<PathBuf>,
+    /// Hide the console window while the icon is shown, when this process opened it
+    ///
+    /// A console shared with a shell the user typed into stays visible.
+    pub hide_console: bool,
+}
+
+/// Notification area icon controlling a running detector
+///
+/// The icon is red while capturing and gray while paused. Its tooltip shows the
+/// title with live click, distance and active time counters; its menu pauses and
+/// resumes capture (like [`DetectorHandle::set_capture_paused`]), opens the log
+/// folder and quits. Quitting only calls `on_quit`: the program decides how to
+/// stop, typically by stopping the detector so queued events are kept. The icon
+/// is removed when dropped.
+pub struct TrayIcon {
+    thread_id: u32,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl TrayIcon {
+    /// Show the icon for `detector`, calling `on_quit` when "Quit" is chosen
+    pub fn spawn<F>(detector: DetectorHandle, config: TrayConfig, on_quit: F) -> Result<Self, String>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("luuma-tray".to_string())
+            .spawn(move || {
+                let window = match create_window() {
+                    Ok(window) => window,
+                    Err(error) => {
+                        let _ = ready_tx.send(Err(error));
+                        return;
+                    }
+                };
+                let console = hide_own_console(config.hide_console);
+                let mut target = TrayTarget {
+                    window,
+                    detector,
+                    config,
+                    on_quit: Box::new(on_quit),
+                    icon: None,
+                    paused: false,
+                    taskbar_created: unsafe { RegisterWindowMessageW(w!("TaskbarCreated")) },
+                };
+                if let Err(error) = target.show(NIM_ADD) {
+                    target.remove();
+                    unsafe {
+                        let _ = DestroyWindow(window);
+                    }
+                    restore_console(console);
+                    let _ = ready_tx.send(Err(error));
+                    return;
+                }
+                TRAY_TARGET.with(|slot| *slot.borrow_mut() = Some(target));
+                let _ = ready_tx.send(Ok(unsafe { GetCurrentThreadId() }));
+
+                unsafe {
+                    SetTimer(Some(window), TOOLTIP_TIMER, TOOLTIP_INTERVAL.as_millis() as u32, None);
+                    let mut message = MSG::default();
+                    while GetMessageW(&mut message, None, 0, 0).as_bool() {
+                        DispatchMessageW(&message);
+                    }
+                    let _ = KillTimer(Some(window), TOOLTIP_TIMER);
+                }
+                if let Some(target) = TRAY_TARGET.with(|slot| slot.borrow_mut().take()) {
+                    target.remove();
+                }
+                unsafe {
+                    let _ = DestroyWindow(window);
+                }
+                restore_console(console);
+            })
+            .map_err(|e| format!("Failed to spawn tray thread: {}", e))?;
+
+        match ready_rx.recv() {
+            Ok(Ok(thread_id)) => Ok(Self { thread_id, handle: Some(handle) }),
+            Ok(Err(error)) => {
+                let _ = handle.join();
+                Err(error)
+            }
+            Err(_) => Err("Tray thread exited during start-up".to_string()),
+        }
+    }
+
+    /// Remove the icon and wait for its thread to exit
+    pub fn close(mut self) -> Result<(), String> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<(), String> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        handle.join().map_err(|e| format!("Failed to join tray thread: {:?}", e))
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+/// State behind the icon's window on the tray thread
+struct TrayTarget {
+    window: HWND,
+    detector: DetectorHandle,
+    config: TrayConfig,
+    on_quit: Box<dyn FnMut() + Send>,
+    icon: Option<HICON>,
+    paused: bool,
+    /// Sent to every top-level window when Explorer restarts and the notification area is empty again
+    taskbar_created: u32,
+}
+
+impl TrayTarget {
+    /// Add or update the icon, its picture following the pause state
+    fn show(&mut self, action: NOTIFY_ICON_MESSAGE) -> Result<(), String> {
+        let paused = self.detector.capture_paused();
+        if self.icon.is_none() || paused != self.paused {
+            let icon = dot_icon(if paused { PAUSED_COLOR } else { CAPTURING_COLOR })?;
+            if let Some(previous) = self.icon.replace(icon) {
+                unsafe {
+                    let _ = DestroyIcon(previous);
+                }
+            }
+            self.paused = paused;
+        }
+
+        let mut data = self.notify_data();
+        data.uFlags = NIF_MESSAGE | NIF_ICON | NIF_TIP;
+        data.uCallbackMessage = WM_TRAY;
+        data.hIcon = self.icon.unwrap_or_default();
+        let tooltip: Vec<u16> = self.tooltip().encode_utf16().take(data.szTip.len() - 1).collect();
+        data.szTip[..tooltip.len()].copy_from_slice(&tooltip);
+        if !unsafe { Shell_NotifyIconW(action, &data) }.as_bool() {
+            return Err("Failed to show the tray icon".to_string());
+        }
+        Ok(())
+    }
+
+    fn remove(mut self) {
+        unsafe {
+            let _ = Shell_NotifyIconW(NIM_DELETE, &self.notify_data());
+            if let Some(icon) = self.icon.take() {
+                let _ = DestroyIcon(icon);
+            }
+        }
+    }
+
+    fn notify_data(&self) -> NOTIFYICONDATAW {
+        NOTIFYICONDATAW {
+            cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+            hWnd: self.window,
+            uID: 1,
+            ..Default::default()
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        let stats = self.detector.stats();
+        let active = stats.active_time.as_secs();
+        format!(
+            "{}: {}\n{} clicks, {:.1} m\n{}:{:02}:{:02} active",
+            self.config.title,
+            if self.paused { "paused" } else { "capturing" },
+            stats.total_clicks(),
+            stats.distance_m,
+            active / 3600,
+            active / 60 % 60,
+            active % 60
+        )
+    }
+
+    /// Show the context menu at the cursor and carry out the chosen item
+    fn show_menu(&mut self) {
+        unsafe {
+            let Ok(menu) = CreatePopupMenu() else {
+                return;
+            };
+            let pause_flags = if self.paused { MF_STRING | MF_CHECKED } else { MF_STRING };
+            let folder_flags = if self.config.folder.is_some() { MF_STRING } else { MF_STRING | MF_GRAYED };
+            let _ = AppendMenuW(menu, pause_flags, MENU_PAUSE, w!("Paused"));
+            let _ = AppendMenuW(menu, folder_flags, MENU_OPEN_FOLDER, w!("Open log folder"));
+            let _ = AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null());
+            let _ = AppendMenuW(menu, MF_STRING, MENU_QUIT, w!("Quit"));
+
+            let mut position = POINT::default();
+            let _ = GetCursorPos(&mut position);
+            // Without the foreground the menu would not close when clicking elsewhere
+            let _ = SetForegroundWindow(self.window);
+            let chosen = TrackPopupMenu(
+                menu,
+                TPM_RETURNCMD | TPM_NONOTIFY | TPM_RIGHTBUTTON,
+                position.x,
+                position.y,
+                None,
+                self.window,
+                None,
+            );
+            let _ = PostMessageW(Some(self.window), WM_NULL, WPARAM(0), LPARAM(0));
+            let _ = DestroyMenu(menu);
+
+            match chosen.0 as usize {
+                MENU_PAUSE => {
+                    self.detector.set_capture_paused(!self.paused);
+                    let _ = self.show(NIM_MODIFY);
+                }
+                MENU_OPEN_FOLDER => {
+                    if let Some(folder) = &self.config.folder {
+                        ShellExecuteW(None, w!("open"), &HSTRING::from(folder.as_path()), None, None, SW_SHOWNORMAL);
+                    }
+                }
+                MENU_QUIT => (self.on_quit)(),
+                _ => {}
+            }
+        }
+    }
+}
+
+thread_local! {
+    // Window procedures carry no user data and run on the tray thread
+    static TRAY_TARGET: RefCell<Option<TrayTarget>> = const { RefCell::new(None) };
+}
+
+fn create_window() -> Result<HWND, String> {
+    unsafe {
+        let class = WNDCLASSW { lpfnWndProc: Some(tray_proc), lpszClassName: w!("LuumaTray"), ..Default::default() };
+        // Registering twice fails harmlessly when the icon is shown again
+        RegisterClassW(&class);
+
+        // A hidden top-level window rather than a message-only one, so it hears TaskbarCreated
+        CreateWindowExW(
+            WINDOW_EX_STYLE(0),
+            w!("LuumaTray"),
+            w!("LuumaTray"),
+            WS_POPUP,
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| format!("Failed to create tray window: {}", e))
+    }
+}
+
+unsafe extern "system" fn tray_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let handled = TRAY_TARGET.with(|slot| {
+        // The menu's modal loop dispatches messages while the target is borrowed
+        let Ok(mut slot) = slot.try_borrow_mut() else {
+            return false;
+        };
+        let Some(target) = slot.as_mut() else {
+            return false;
+        };
+        match message {
+            WM_TRAY if matches!(lparam.0 as u32, WM_LBUTTONUP | WM_RBUTTONUP) => target.show_menu(),
+            WM_TIMER if wparam.0 == TOOLTIP_TIMER => {
+                let _ = target.show(NIM_MODIFY);
+            }
+            _ if message == target.taskbar_created && message != 0 => {
+                let _ = target.show(NIM_ADD);
+            }
+            _ => return false,
+        }
+        true
+    });
+    if handled {
+        LRESULT(0)
+    } else {
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+/// Filled circle on a transparent square, in the given BGRA color
+fn dot_icon(color: u32) -> Result<HICON, String> {
+    let size = ICON_SIZE as usize;
+    let radius = ICON_SIZE as f64 / 2.0 - 2.0;
+    let center = ICON_SIZE as f64 / 2.0 - 0.5;
+    let pixels: Vec<u32> = (0..size * size)
+        .map(|index| {
+            let (x, y) = ((index % size) as f64, (index / size) as f64);
+            if (x - center).hypot(y - center) <= radius {
+                color
+            } else {
+                0
+            }
+        })
+        .collect();
+    // 32-bit color carries the alpha, so the monochrome mask stays all zero
+    let mask = vec![0u8; size * size / 8];
+    unsafe {
+        let color_bitmap = CreateBitmap(ICON_SIZE, ICON_SIZE, 1, 32, Some(pixels.as_ptr().cast()));
+        let mask_bitmap = CreateBitmap(ICON_SIZE, ICON_SIZE, 1, 1, Some(mask.as_ptr().cast()));
+        let info =
+            ICONINFO { fIcon: true.into(), xHotspot: 0, yHotspot: 0, hbmMask: mask_bitmap, hbmColor: color_bitmap };
+        let icon = CreateIconIndirect(&info);
+        let _ = DeleteObject(color_bitmap.into());
+        let _ = DeleteObject(mask_bitmap.into());
+        icon.map_err(|e| format!("Failed to create tray icon: {}", e))
+    }
+}
+
+/// Hide the console window if this process is the only one attached to it
+fn hide_own_console(hide: bool) -> Option<HWND> {
+    if !hide {
+        return None;
+    }
+    unsafe {
+        let window = GetConsoleWindow();
+        let mut processes = [0u32; 2];
+        // Started from Explorer or a shortcut; a shell would be attached as well
+        if window.is_invalid() || GetConsoleProcessList(&mut processes) != 1 {
+            return None;
+        }
+        let _ = ShowWindow(window, SW_HIDE);
+        Some(window)
+    }
+}
+
+fn restore_console(console: Option<HWND>) {
+    if let Some(window) = console {
+        unsafe {
+            let _ = ShowWindow(window, SW_SHOW);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Deduplication and hysteresis for `TypeChange` events, and the `TypeDwell` events between them
+
+use crate::clock::{Clock, SharedClock};
+use crate::{CursorEvent, CursorType, Timestamp};
+use std::iter;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often a change held back by the delays is checked again
+pub(crate) const SETTLE_INTERVAL: Duration = Duration::from_millis(16);
+
+/// A reported change of cursor type
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TypeTransition {
+    pub(crate) new_type: CursorType,
+    /// Type left behind and how long it was shown, unless this is the first type seen
+    pub(crate) left: Option<(CursorType, Duration)>,
+}
+
+impl TypeTransition {
+    /// `TypeDwell` for the type left behind, followed by `TypeChange`
+    pub(crate) fn into_events(self, position: (f64, f64), timestamp: Timestamp) -> impl Iterator<Item = CursorEvent> {
+        let dwell = self.left.map(|(cursor_type, duration)| CursorEvent::TypeDwell {
+            cursor_type,
+            duration,
+            position,
+            timestamp,
+        });
+        dwell.into_iter().chain(iter::once(CursorEvent::TypeChange { new_type: self.new_type, position, timestamp }))
+    }
+}
+
+#[derive(Debug, Default)]
+struct FilterState {
+    /// Type last reported, with the time it was first seen
+    reported: Option<(CursorType, Instant)>,
+    /// Type waiting out the delays, with the time it was first seen
+    pending: Option<(CursorType, Instant)>,
+    last_reported: Option<Instant>,
+}
+
+/// Decides which cursor type changes are reported, shared by the mouse hook and the shape hook
+///
+/// A change is only reported when the type differs from the last reported one, so
+/// distinct handles of the same type and flicker back to it produce nothing. With a
+/// hysteresis the new type must stay for that long, and with a minimum interval
+/// changes are spaced at least that far apart; a change held back by either is
+/// reported late by [`settle`](Self::settle) unless the cursor changed back meanwhile.
+#[derive(Debug)]
+pub(crate) struct TypeChangeFilter {
+    hysteresis: Duration,
+    min_interval: Duration,
+    clock: SharedClock,
+    state: Mutex<FilterState>,
+}
+
+impl TypeChangeFilter {
+    pub(crate) fn new(hysteresis: Duration, min_interval: Duration, clock: SharedClock) -> Self {
+        Self { hysteresis, min_interval, clock, state: Mutex::default() }
+    }
+
+    /// Whether changes can be held back and need [`settle`](Self::settle) called periodically
+    pub(crate) fn delays(&self) -> bool {
+        !self.hysteresis.is_zero() || !self.min_interval.is_zero()
+    }
+
+    /// Note the type now under the cursor, returning the change when it should be reported now
+    pub(crate) fn observe(&self, cursor_type: CursorType) -> Option<TypeTransition> {
+        let now = self.clock.instant();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.reported.map(|(reported, _)| reported) == Some(cursor_type) {
+            state.pending = None;
+            return None;
+        }
+        if state.pending.map(|(pending, _)| pending) != Some(cursor_type) {
+            state.pending = Some((cursor_type, now));
+        }
+        self.take_ready(&mut state, now)
+    }
+
+    /// Report a held back change whose delays have passed
+    pub(crate) fn settle(&self) -> Option<TypeTransition> {
+        let now = self.clock.instant();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.take_ready(&mut state, now)
+    }
+
+    fn take_ready(&self, state: &mut FilterState, now: Instant) -> Option<TypeTransition> {
+        let (cursor_type, since) = state.pending?;
+        if now.saturating_duration_since(since) < self.hysteresis {
+            return None;
+        }
+        if state.last_reported.is_some_and(|last| now.saturating_duration_since(last) < self.min_interval) {
+            return None;
+        }
+        // The old type was shown until the new one first appeared, however long that was held back
+        let left = state.reported.map(|(reported, shown)| (reported, since.saturating_duration_since(shown)));
+        state.pending = None;
+        state.reported = Some((cursor_type, since));
+        state.last_reported = Some(now);
+        Some(TypeTransition { new_type: cursor_type, left })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeChangeFilter;
+    use crate::clock::ManualClock;
+    use crate::{CursorType, Timestamp};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn filter(hysteresis: u64, min_interval: u64) -> (TypeChangeFilter, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(Timestamp::from_millis(0)));
+        let filter =
+            TypeChangeFilter::new(Duration::from_millis(hysteresis), Duration::from_millis(min_interval), clock.clone());
+        (filter, clock)
+    }
+
+    fn left(filter: &TypeChangeFilter, cursor_type: CursorType) -> Option<Option<(CursorType, Duration)>> {
+        filter.observe(cursor_type).map(|transition| transition.left)
+    }
+
+    #[test]
+    fn reports_only_changes_of_type() {
+        let (filter, clock) = filter(0, 0);
+        assert_eq!(left(&filter, CursorType::Arrow), Some(None));
+        clock.advance(Duration::from_millis(300));
+        assert_eq!(left(&filter, CursorType::Arrow), None);
+        assert_eq!(left(&filter, CursorType::IBeam), Some(Some((CursorType::Arrow, Duration::from_millis(300)))));
+    }
+
+    #[test]
+    fn hysteresis_ignores_flicker() {
+        let (filter, clock) = filter(100, 0);
+        assert_eq!(left(&filter, CursorType::Arrow), None);
+        clock.advance(Duration::from_millis(100));
+        assert!(filter.settle().is_some());
+
+        // Back to the reported type before the hysteresis passed
+        assert_eq!(left(&filter, CursorType::Hand), None);
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(left(&filter, CursorType::Arrow), None);
+        clock.advance(Duration::from_millis(200));
+        assert!(filter.settle().is_none());
+
+        // Held long enough, timed from when it first appeared
+        assert_eq!(left(&filter, CursorType::Hand), None);
+        clock.advance(Duration::from_millis(60));
+        assert!(filter.settle().is_none());
+        clock.advance(Duration::from_millis(40));
+        let transition = filter.settle().unwrap();
+        assert_eq!(transition.new_type, CursorType::Hand);
+        assert_eq!(transition.left, Some((CursorType::Arrow, Duration::from_millis(350))));
+    }
+
+    #[test]
+    fn min_interval_spaces_changes() {
+        let (filter, clock) = filter(0, 500);
+        assert!(filter.delays());
+        assert_eq!(left(&filter, CursorType::Arrow), Some(None));
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(left(&filter, CursorType::Wait), None);
+        clock.advance(Duration::from_millis(300));
+        assert!(filter.settle().is_none());
+        clock.advance(Duration::from_millis(100));
+        let transition = filter.settle().unwrap();
+        assert_eq!(transition.new_type, CursorType::Wait);
+        assert_eq!(transition.left, Some((CursorType::Arrow, Duration::from_millis(100))));
+    }
+}
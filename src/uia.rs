@@ -0,0 +1,166 @@
+//! UI Automation element resolution for clicks and dwells
+
+use crate::clock::{Clock, SharedClock};
+use crate::dpi::PhysicalCoordinates;
+use crate::queue::EventQueue;
+use crate::{AtomicCursorState, CursorEvent};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::POINT;
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED};
+use windows::Win32::UI::Accessibility::{CUIAutomation, IUIAutomation};
+
+/// Accessibility element found under the cursor
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UiElement {
+    /// Control type such as `Button` or `Edit`
+    pub control_type: String,
+    pub name: String,
+    /// Developer-assigned identifier, the most stable way to find the element again
+    pub automation_id: String,
+    pub class_name: String,
+    /// UI framework such as `Win32`, `WPF` or `Chrome`
+    pub framework: String,
+}
+
+/// Why an element was resolved
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ElementTrigger {
+    Click,
+    Dwell,
+}
+
+/// Default time the cursor must rest before the element under it is resolved
+pub const DEFAULT_DWELL_TIME: Duration = Duration::from_millis(600);
+
+/// Movement in pixels still counted as resting
+const DWELL_RADIUS: f64 = 4.0;
+
+/// Worker thread running UI Automation queries off the hook thread
+///
+/// `ElementFromPoint` can take hundreds of milliseconds against busy applications,
+/// long enough for Windows to drop a low-level hook, so it never runs in the callback.
+pub(crate) struct UiaWorker {
+    clicks: mpsc::Sender<(f64, f64)>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl UiaWorker {
+    pub(crate) fn spawn(
+        dwell_time: Duration,
+        atomic_state: Arc<AtomicCursorState>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        running: Arc<AtomicBool>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        let (clicks, click_rx) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("luuma-uia".to_string())
+            .spawn(move || {
+                // Hook positions are physical pixels
+                let _physical = PhysicalCoordinates::enter();
+                unsafe {
+                    let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+                }
+                match unsafe { CoCreateInstance::<_, IUIAutomation>(&CUIAutomation, None, CLSCTX_INPROC_SERVER) } {
+                    Ok(automation) => run(&automation, dwell_time, &atomic_state, &event_queue, &*clock, &running, &click_rx),
+                    Err(error) => crate::CursorDetector::log_message(&format!("Failed to create UI Automation: {}", error)),
+                }
+                unsafe {
+                    CoUninitialize();
+                }
+            })
+            .map_err(|e| format!("Failed to spawn UI Automation thread: {}", e))?;
+
+        Ok(Self { clicks, handle })
+    }
+
+    /// Sender the hook uses to request resolution at a click position
+    pub(crate) fn clicks(&self) -> mpsc::Sender<(f64, f64)> {
+        self.clicks.clone()
+    }
+
+    pub(crate) fn stop(self) -> Result<(), String> {
+        drop(self.clicks);
+        self.handle.join().map_err(|e| format!("Failed to join UI Automation thread: {:?}", e))
+    }
+}
+
+fn run(
+    automation: &IUIAutomation,
+    dwell_time: Duration,
+    atomic_state: &AtomicCursorState,
+    event_queue: &EventQueue<CursorEvent>,
+    clock: &dyn Clock,
+    running: &AtomicBool,
+    clicks: &mpsc::Receiver<(f64, f64)>,
+) {
+    let poll = Duration::from_millis(50);
+    let mut resting_at = atomic_state.get_position();
+    let mut resting_since = Instant::now();
+    let mut dwell_reported = false;
+
+    while running.load(Ordering::Relaxed) {
+        let (position, trigger) = match clicks.recv_timeout(poll) {
+            Ok(position) => (position, ElementTrigger::Click),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let position = atomic_state.get_position();
+                let moved = ((position.0 - resting_at.0).powi(2) + (position.1 - resting_at.1).powi(2)).sqrt();
+                if moved > DWELL_RADIUS {
+                    resting_at = position;
+                    resting_since = Instant::now();
+                    dwell_reported = false;
+                    continue;
+                }
+                if dwell_reported || resting_since.elapsed() < dwell_time {
+                    continue;
+                }
+                dwell_reported = true;
+                (position, ElementTrigger::Dwell)
+            }
+        };
+
+        if let Some(element) = element_at(automation, position) {
+            let element_event = CursorEvent::Element { trigger, position, element, timestamp: clock.now() };
+            let _ = event_queue.push(element_event);
+        }
+    }
+}
+
+/// Resolve the element at a screen position
+fn element_at(automation: &IUIAutomation, position: (f64, f64)) -> Option<UiElement> {
+    unsafe {
+        let element = automation.ElementFromPoint(POINT { x: position.0 as i32, y: position.1 as i32 }).ok()?;
+        let text = |value: windows::core::Result<windows::core::BSTR>| value.map(|s| s.to_string()).unwrap_or_default();
+
+        Some(UiElement {
+            control_type: element
+                .CurrentControlType()
+                .map(|id| control_type_name(id.0).to_string())
+                .unwrap_or_default(),
+            name: text(element.CurrentName()),
+            automation_id: text(element.CurrentAutomationId()),
+            class_name: text(element.CurrentClassName()),
+            framework: text(element.CurrentFrameworkId()),
+        })
+    }
+}
+
+/// Name of a `UIA_*ControlTypeId`
+fn control_type_name(id: i32) -> &'static str {
+    const NAMES: [&str; 41] = [
+        "Button", "Calendar", "CheckBox", "ComboBox", "Edit", "Hyperlink", "Image", "ListItem", "List", "Menu",
+        "MenuBar", "MenuItem", "ProgressBar", "RadioButton", "ScrollBar", "Slider", "Spinner", "StatusBar", "Tab",
+        "TabItem", "Text", "ToolBar", "ToolTip", "Tree", "TreeItem", "Custom", "Group", "Thumb", "DataGrid",
+        "DataItem", "Document", "SplitButton", "Window", "Pane", "Header", "HeaderItem", "Table", "TitleBar",
+        "Separator", "SemanticZoom", "AppBar",
+    ];
+    id.checked_sub(50000)
+        .and_then(|index| NAMES.get(index as usize))
+        .copied()
+        .unwrap_or("Unknown")
+}
@@ -0,0 +1,199 @@
+//! Smoothed camera rectangle following the cursor, for auto-zoom recordings
+
+use crate::Rect;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(windows)]
+use crate::clock::{Clock, SharedClock};
+#[cfg(windows)]
+use crate::dpi::PhysicalCoordinates;
+#[cfg(windows)]
+use crate::queue::EventQueue;
+#[cfg(windows)]
+use crate::session::CapturePause;
+#[cfg(windows)]
+use crate::{AtomicCursorState, CursorEvent};
+#[cfg(windows)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(windows)]
+use std::sync::Arc;
+#[cfg(windows)]
+use std::thread;
+#[cfg(windows)]
+use std::time::Instant;
+
+/// Distance in pixels below which the camera snaps onto its target and stops reporting
+#[cfg(windows)]
+const SETTLE_DISTANCE: f64 = 0.05;
+
+/// Screen area a zoomed recording should show, in physical pixels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Viewport {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Magnification the viewport was computed for
+    pub zoom: f64,
+}
+
+impl Viewport {
+    /// Center of the viewport
+    pub fn center(&self) -> (f64, f64) {
+        (self.left + self.width / 2.0, self.top + self.height / 2.0)
+    }
+
+    /// The viewport rounded to whole pixels
+    pub fn to_rect(&self) -> Rect {
+        let (left, top) = (self.left.round() as i32, self.top.round() as i32);
+        Rect::new(left, top, left + self.width.round() as i32, top + self.height.round() as i32)
+    }
+}
+
+/// How the camera follows the cursor
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ViewportConfig {
+    /// Magnification, e.g. 2.0 shows a quarter of the screen area
+    pub zoom: f64,
+    /// Fraction of the viewport, around its center, the cursor moves in without panning
+    pub deadzone: f64,
+    /// Easing time constant; each period the camera covers about 63% of the remaining distance
+    #[serde(with = "crate::duration_ms")]
+    pub easing: Duration,
+    /// Camera updates per second
+    pub rate_hz: u32,
+    /// Area the viewport stays inside, the whole virtual desktop when `None`
+    pub bounds: Option<Rect>,
+}
+
+impl Default for ViewportConfig {
+    fn default() -> Self {
+        Self { zoom: 2.0, deadzone: 0.4, easing: Duration::from_millis(250), rate_hz: 60, bounds: None }
+    }
+}
+
+/// Deadzone and easing state of the camera
+#[cfg(windows)]
+pub(crate) struct ViewportCamera {
+    config: ViewportConfig,
+    bounds: Rect,
+    center: Option<(f64, f64)>,
+    target: (f64, f64),
+}
+
+#[cfg(windows)]
+impl ViewportCamera {
+    pub(crate) fn new(config: ViewportConfig, bounds: Rect) -> Self {
+        Self { config, bounds, center: None, target: (0.0, 0.0) }
+    }
+
+    fn size(&self) -> (f64, f64) {
+        let zoom = self.config.zoom.max(1.0);
+        (self.bounds.width() as f64 / zoom, self.bounds.height() as f64 / zoom)
+    }
+
+    /// Keep a camera center far enough from the edges that the viewport stays in bounds
+    fn clamp_center(&self, center: (f64, f64)) -> (f64, f64) {
+        let (width, height) = self.size();
+        let (left, top) = (self.bounds.left as f64 + width / 2.0, self.bounds.top as f64 + height / 2.0);
+        let (right, bottom) = (self.bounds.right as f64 - width / 2.0, self.bounds.bottom as f64 - height / 2.0);
+        (center.0.clamp(left, right.max(left)), center.1.clamp(top, bottom.max(top)))
+    }
+
+    /// Advance the camera by `elapsed`, returning the viewport when it moved
+    pub(crate) fn update(&mut self, cursor: (f64, f64), elapsed: Duration) -> Option<Viewport> {
+        let (width, height) = self.size();
+        let Some(center) = self.center else {
+            // Start on the cursor rather than gliding in from a corner
+            let center = self.clamp_center(cursor);
+            self.center = Some(center);
+            self.target = center;
+            return Some(self.viewport(center));
+        };
+
+        // Pan only once the cursor pushes against the deadzone edge
+        let deadzone = self.config.deadzone.clamp(0.0, 1.0);
+        let (half_x, half_y) = (width * deadzone / 2.0, height * deadzone / 2.0);
+        let target = (
+            self.target.0.clamp(cursor.0 - half_x, cursor.0 + half_x),
+            self.target.1.clamp(cursor.1 - half_y, cursor.1 + half_y),
+        );
+        self.target = self.clamp_center(target);
+
+        let (dx, dy) = (self.target.0 - center.0, self.target.1 - center.1);
+        if dx.hypot(dy) < SETTLE_DISTANCE {
+            if center == self.target {
+                return None;
+            }
+            self.center = Some(self.target);
+            return Some(self.viewport(self.target));
+        }
+
+        let progress = 1.0 - (-elapsed.as_secs_f64() / self.config.easing.as_secs_f64().max(0.001)).exp();
+        let center = (center.0 + dx * progress, center.1 + dy * progress);
+        self.center = Some(center);
+        Some(self.viewport(center))
+    }
+
+    fn viewport(&self, center: (f64, f64)) -> Viewport {
+        let (width, height) = self.size();
+        Viewport {
+            left: center.0 - width / 2.0,
+            top: center.1 - height / 2.0,
+            width,
+            height,
+            zoom: self.config.zoom.max(1.0),
+        }
+    }
+}
+
+/// Thread moving the camera at a steady rate and emitting `ViewportUpdate` events
+#[cfg(windows)]
+pub(crate) struct ViewportThread {
+    handle: thread::JoinHandle<()>,
+}
+
+#[cfg(windows)]
+impl ViewportThread {
+    pub(crate) fn spawn(
+        config: ViewportConfig,
+        atomic_state: Arc<AtomicCursorState>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        running: Arc<AtomicBool>,
+        pause: Arc<CapturePause>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        let interval = Duration::from_secs(1) / config.rate_hz.max(1);
+        let handle = thread::Builder::new()
+            .name("luuma-viewport".to_string())
+            .spawn(move || {
+                // Bounds must be in the same physical pixels as hook positions
+                let bounds = {
+                    let _physical = PhysicalCoordinates::enter();
+                    config.bounds.unwrap_or_else(crate::CursorDetector::get_virtual_screen_bounds)
+                };
+                let mut camera = ViewportCamera::new(config, bounds);
+                let mut last_tick = Instant::now();
+                while running.load(Ordering::Relaxed) {
+                    thread::sleep(interval);
+                    let now = Instant::now();
+                    let elapsed = now.duration_since(last_tick);
+                    last_tick = now;
+
+                    if pause.is_paused() {
+                        continue;
+                    }
+                    if let Some(viewport) = camera.update(atomic_state.get_position(), elapsed) {
+                        let _ = event_queue.push(CursorEvent::ViewportUpdate { viewport, timestamp: clock.now() });
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn viewport thread: {}", e))?;
+
+        Ok(Self { handle })
+    }
+
+    pub(crate) fn stop(self) -> Result<(), String> {
+        self.handle.join().map_err(|e| format!("Failed to join viewport thread: {:?}", e))
+    }
+}
@@ -0,0 +1,102 @@
+//! Cursor show/hide tracking through WinEvent hooks
+
+use crate::clock::{Clock, SharedClock};
+use crate::queue::EventQueue;
+use crate::{AtomicCursorState, CursorEvent};
+use std::cell::RefCell;
+use std::sync::Arc;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CURSORINFO, CURSOR_SHOWING, CURSOR_SUPPRESSED, EVENT_OBJECT_HIDE, EVENT_OBJECT_SHOW, OBJID_CURSOR,
+    WINEVENT_OUTOFCONTEXT,
+};
+
+type VisibilityTarget = (Arc<AtomicCursorState>, Arc<EventQueue<CursorEvent>>, SharedClock);
+
+thread_local! {
+    // WinEvent callbacks carry no user data and run on the thread that installed the hook
+    static VISIBILITY_TARGET: RefCell<Option<VisibilityTarget>> = const { RefCell::new(None) };
+}
+
+/// Whether the cursor is drawn, treating touch and pen suppression as hidden
+pub(crate) fn cursor_visible(cursor_info: &CURSORINFO) -> bool {
+    cursor_info.flags.0 & CURSOR_SHOWING.0 != 0 && cursor_info.flags.0 & CURSOR_SUPPRESSED.0 == 0
+}
+
+/// Record the visibility and queue a `VisibilityChanged` event when it flipped
+pub(crate) fn report_visibility(
+    atomic_state: &AtomicCursorState,
+    event_queue: &EventQueue<CursorEvent>,
+    clock: &dyn Clock,
+    visible: bool,
+) {
+    if atomic_state.swap_visible(visible) != visible {
+        let visibility_event = CursorEvent::VisibilityChanged {
+            visible,
+            position: atomic_state.get_position(),
+            timestamp: clock.now(),
+        };
+        let _ = event_queue.push(visibility_event);
+    }
+}
+
+/// Cursor show/hide hook installed on the calling thread, removed on drop
+///
+/// Applications usually hide the cursor while it is idle, so waiting for the next
+/// move would report the change late or never.
+pub(crate) struct VisibilityWatcher {
+    hook: HWINEVENTHOOK,
+}
+
+impl VisibilityWatcher {
+    pub(crate) fn install(
+        atomic_state: Arc<AtomicCursorState>,
+        event_queue: Arc<EventQueue<CursorEvent>>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+        let hook = unsafe {
+            SetWinEventHook(EVENT_OBJECT_SHOW, EVENT_OBJECT_HIDE, None, Some(visibility_proc), 0, 0, WINEVENT_OUTOFCONTEXT)
+        };
+        if hook.is_invalid() {
+            return Err("Failed to install cursor visibility hook".to_string());
+        }
+
+        VISIBILITY_TARGET.with(|slot| *slot.borrow_mut() = Some((atomic_state, event_queue, clock)));
+        Ok(Self { hook })
+    }
+}
+
+impl Drop for VisibilityWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnhookWinEvent(self.hook);
+        }
+        VISIBILITY_TARGET.with(|slot| slot.borrow_mut().take());
+    }
+}
+
+unsafe extern "system" fn visibility_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    _hwnd: HWND,
+    object: i32,
+    _child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    if object != OBJID_CURSOR.0 {
+        return;
+    }
+
+    // Re-read the cursor rather than trusting the event, which can arrive out of order
+    let Some(cursor_info) = crate::query_cursor_info() else {
+        return;
+    };
+    VISIBILITY_TARGET.with(|slot| {
+        if let Some((atomic_state, event_queue, clock)) = slot.borrow().as_ref() {
+            atomic_state.cache_cursor(&cursor_info);
+            report_visibility(atomic_state, event_queue, &**clock, cursor_visible(&cursor_info));
+        }
+    });
+}
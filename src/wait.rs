@@ -0,0 +1,132 @@
+//! Blocking and async waits for a cursor state condition
+
+use crate::clock::{Clock, SharedClock};
+use crate::{AtomicCursorState, CursorState};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest a waiter sleeps before re-reading the state on its own
+///
+/// Shape and visibility changes under a resting cursor do not pass through the
+/// hook, so they are only noticed by re-reading.
+const RECHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Wakes waiters when the hook updates the cursor state
+#[derive(Debug, Default)]
+pub(crate) struct StateWatch {
+    waiters: AtomicUsize,
+    generation: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl StateWatch {
+    /// Wake every waiter, nearly free while nobody waits
+    pub(crate) fn notify(&self) {
+        if self.waiters.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        *self.generation.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        self.changed.notify_all();
+    }
+
+    /// Block until `predicate` accepts the state or `timeout` passes
+    pub(crate) fn wait_for<F>(
+        &self,
+        atomic_state: &AtomicCursorState,
+        clock: &dyn Clock,
+        predicate: F,
+        timeout: Duration,
+    ) -> Option<CursorState>
+    where
+        F: Fn(&CursorState) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        self.waiters.fetch_add(1, Ordering::Relaxed);
+        let result = loop {
+            let generation = *self.generation.lock().unwrap_or_else(|e| e.into_inner());
+            let state = crate::read_state(atomic_state, clock.now());
+            if predicate(&state) {
+                break Some(state);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                break None;
+            }
+
+            // Skip the sleep if the hook already moved on since the state was read
+            let guard = self.generation.lock().unwrap_or_else(|e| e.into_inner());
+            if *guard == generation {
+                let _ = self
+                    .changed
+                    .wait_timeout(guard, (deadline - now).min(RECHECK_INTERVAL))
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+        };
+        self.waiters.fetch_sub(1, Ordering::Relaxed);
+        result
+    }
+}
+
+/// Outcome shared between a waiting thread and its future
+#[derive(Default)]
+struct WaitSlot {
+    result: Option<Option<CursorState>>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the first state accepting a predicate, or `None` on timeout
+///
+/// Runs the wait on its own thread, so it works with any executor.
+pub struct WaitFor {
+    slot: Arc<Mutex<WaitSlot>>,
+}
+
+impl WaitFor {
+    pub(crate) fn spawn<F>(
+        watch: Arc<StateWatch>,
+        atomic_state: Arc<AtomicCursorState>,
+        clock: SharedClock,
+        predicate: F,
+        timeout: Duration,
+    ) -> Result<Self, String>
+    where
+        F: Fn(&CursorState) -> bool + Send + 'static,
+    {
+        let slot = Arc::new(Mutex::new(WaitSlot::default()));
+        let thread_slot = Arc::clone(&slot);
+        thread::Builder::new()
+            .name("luuma-wait".to_string())
+            .spawn(move || {
+                let result = watch.wait_for(&atomic_state, &*clock, predicate, timeout);
+                let mut slot = thread_slot.lock().unwrap_or_else(|e| e.into_inner());
+                slot.result = Some(result);
+                if let Some(waker) = slot.waker.take() {
+                    waker.wake();
+                }
+            })
+            .map_err(|e| format!("Failed to spawn wait thread: {}", e))?;
+
+        Ok(Self { slot })
+    }
+}
+
+impl Future for WaitFor {
+    type Output = Option<CursorState>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Browser pointer events as cursor events, for sharing analytics code with wasm32 builds
+
+use crate::source::{ContactData, InputSource, PointerKind};
+use crate::timing::PreciseTime;
+use crate::{CursorEvent, CursorType, MouseButton, Timestamp};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{EventTarget, PointerEvent};
+
+/// Milliseconds on the page's high-resolution clock, the one event `timeStamp`s use
+pub(crate) fn performance_now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map(|performance| performance.now())
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+type PointerClosure = Closure<dyn FnMut(PointerEvent)>;
+
+/// Event time stamp in milliseconds and position of each held button, for the hold time on release
+type Presses = HashMap<MouseButton, (f64, (f64, f64))>;
+
+/// Pointer listeners on a DOM event target, turning browser input into [`CursorEvent`]s
+///
+/// Produces `Move`, `Click` and `Release` events with positions in CSS pixels relative
+/// to the viewport (`clientX`/`clientY`). The browser does not expose the cursor shape,
+/// so moves report [`CursorType::Default`]. Listeners are removed when dropped.
+pub struct PointerListener {
+    target: EventTarget,
+    listeners: Vec<(&'static str, PointerClosure)>,
+}
+
+impl PointerListener {
+    /// Listen on the page's window, seeing pointer input anywhere in the document
+    pub fn attach_window<F>(handler: F) -> Result<Self, String>
+    where
+        F: FnMut(CursorEvent) + 'static,
+    {
+        let window = web_sys::window().ok_or_else(|| "No window in this context".to_string())?;
+        Self::attach(&window, handler)
+    }
+
+    /// Listen on an element or any other event target
+    pub fn attach<F>(target: &EventTarget, handler: F) -> Result<Self, String>
+    where
+        F: FnMut(CursorEvent) + 'static,
+    {
+        let handler = Rc::new(RefCell::new(handler));
+        let presses = Rc::new(RefCell::new(Presses::new()));
+        let mut listener = Self { target: target.clone(), listeners: Vec::new() };
+        for name in ["pointermove", "pointerdown", "pointerup"] {
+            let handler = Rc::clone(&handler);
+            let presses = Rc::clone(&presses);
+            let closure = PointerClosure::new(move |event: PointerEvent| {
+                if let Some(event) = convert(name, &event, &mut presses.borrow_mut()) {
+                    // A handler that re-enters the event loop synchronously would otherwise panic
+                    if let Ok(mut handler) = handler.try_borrow_mut() {
+                        handler(event);
+                    }
+                }
+            });
+            target
+                .add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+                .map_err(|e| format!("Failed to add {} listener: {:?}", name, e))?;
+            listener.listeners.push((name, closure));
+        }
+        Ok(listener)
+    }
+}
+
+impl Drop for PointerListener {
+    fn drop(&mut self) {
+        for (name, closure) in &self.listeners {
+            let _ = self.target.remove_event_listener_with_callback(name, closure.as_ref().unchecked_ref());
+        }
+    }
+}
+
+/// Build the cursor event for a DOM pointer event, `None` for buttons without a mapping
+fn convert(name: &str, event: &PointerEvent, presses: &mut Presses) -> Option<CursorEvent> {
+    let position = (event.client_x() as f64, event.client_y() as f64);
+    let source = InputSource {
+        kind: match event.pointer_type().as_str() {
+            "touch" => PointerKind::Touch,
+            "pen" => PointerKind::Pen,
+            _ => PointerKind::Mouse,
+        },
+        injected: !event.is_trusted(),
+        lower_integrity: false,
+    };
+    // Browsers report mouse pressure as 0.5 while a button is held, so only pen and touch carry contact data
+    let contact = match source.kind {
+        PointerKind::Pen => Some(Box::new(ContactData {
+            pressure: Some(event.pressure() as f64),
+            tilt: Some((event.tilt_x() as f64, event.tilt_y() as f64)),
+        })),
+        PointerKind::Touch => Some(Box::new(ContactData { pressure: Some(event.pressure() as f64), tilt: None })),
+        PointerKind::Mouse => None,
+    };
+    // Event time stamps share the performance clock PreciseTime reads in the browser
+    let precise_time = Some(PreciseTime::from_nanos((event.time_stamp() * 1e6) as u64));
+    let timestamp = Timestamp::now();
+
+    match name {
+        "pointermove" => Some(CursorEvent::Move {
+            position,
+            cursor_type: CursorType::Default,
+            smoothed: None,
+            monitor: None,
+            window: None,
+            client: None,
+            source,
+            contact,
+            device_id: None,
+            precise_time,
+            timestamp,
+        }),
+        "pointerdown" => {
+            let button = button(event.button())?;
+            presses.insert(button, (event.time_stamp(), position));
+            Some(CursorEvent::Click {
+                button,
+                position,
+                monitor: None,
+                window: None,
+                client: None,
+                source,
+                contact,
+                overshoot_px: None,
+                device_id: None,
+                precise_time,
+                timestamp,
+            })
+        }
+        _ => {
+            let button = button(event.button())?;
+            let press = presses.remove(&button);
+            let held_ms = press.map_or(0.0, |(pressed_at, _)| (event.time_stamp() - pressed_at).max(0.0));
+            Some(CursorEvent::Release {
+                button,
+                press_position: press.map(|(_, position)| position),
+                held_for: Duration::from_secs_f64(held_ms / 1000.0),
+                source,
+                device_id: None,
+                precise_time,
+                timestamp,
+            })
+        }
+    }
+}
+
+/// DOM `button` numbering: 0 primary, 1 auxiliary, 2 secondary
+fn button(button: i16) -> Option<MouseButton> {
+    match button {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
@@ -0,0 +1,375 @@
+//! Webhook sink that POSTs selected events as JSON batches
+
+use crate::json::JsonFormat;
+use crate::sink::EventSink;
+use crate::{CursorDetector, CursorEvent, EventKind};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Networking::WinHttp::{
+    WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest, WinHttpQueryHeaders,
+    WinHttpReceiveResponse, WinHttpSendRequest, WinHttpSetTimeouts, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+    WINHTTP_FLAG_SECURE, WINHTTP_OPEN_REQUEST_FLAGS, WINHTTP_QUERY_FLAG_NUMBER, WINHTTP_QUERY_STATUS_CODE,
+};
+
+/// Parsed webhook target
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WebhookUrl {
+    secure: bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl WebhookUrl {
+    /// Split an `http://` or `https://` URL into its parts
+    fn parse(url: &str) -> Result<Self, String> {
+        let (secure, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(format!("Unsupported webhook URL scheme: {}", url));
+        };
+
+        // The authority ends at the path, the query or the fragment, whichever comes first
+        let (authority, path) = match rest.find(['/', '?', '#']) {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, ""),
+        };
+        // Fragments stay on the client
+        let path = path.split('#').next().unwrap_or_default();
+        let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+
+        // IPv6 literals are bracketed so their colons are not mistaken for the port separator
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) =
+                    bracketed.split_once(']').ok_or_else(|| format!("Unterminated IPv6 host in webhook URL: {}", url))?;
+                match after {
+                    "" => (host, None),
+                    _ => match after.strip_prefix(':') {
+                        Some(port) => (host, Some(port)),
+                        None => return Err(format!("Invalid webhook host: {}", authority)),
+                    },
+                }
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port.parse::<u16>().map_err(|_| format!("Invalid webhook port: {}", port))?,
+            None if secure => 443,
+            None => 80,
+        };
+
+        if host.is_empty() {
+            return Err(format!("Missing webhook host: {}", url));
+        }
+
+        Ok(Self { secure, host: host.to_string(), port, path })
+    }
+}
+
+/// Messages sent from the sink to its delivery thread
+enum WebhookMessage {
+    Event(CursorEvent),
+    Flush,
+}
+
+/// Delivery settings shared with the worker thread
+#[derive(Debug, Clone)]
+struct WebhookSettings {
+    batch_size: usize,
+    debounce: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+    format: JsonFormat,
+}
+
+/// Sink that POSTs selected events as JSON arrays to a URL
+///
+/// Events are collected on a background thread and sent once the batch is full or
+/// no new event arrived for the debounce interval. Failed requests (network errors,
+/// 429 and 5xx responses) are retried with exponential backoff.
+pub struct WebhookSink {
+    url: WebhookUrl,
+    kinds: Option<Vec<EventKind>>,
+    settings: WebhookSettings,
+    sender: Option<Sender<WebhookMessage>>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl WebhookSink {
+    /// Create a webhook sink targeting an `http://` or `https://` URL
+    pub fn new(url: &str) -> Result<Self, String> {
+        Ok(Self {
+            url: WebhookUrl::parse(url)?,
+            kinds: None,
+            settings: WebhookSettings {
+                batch_size: 20,
+                debounce: Duration::from_millis(500),
+                max_retries: 3,
+                retry_backoff: Duration::from_millis(500),
+                format: JsonFormat::default(),
+            },
+            sender: None,
+            worker: None,
+        })
+    }
+
+    /// Only forward events of the given kinds (all kinds by default)
+    pub fn with_kinds(mut self, kinds: &[EventKind]) -> Self {
+        self.kinds = Some(kinds.to_vec());
+        self
+    }
+
+    /// Maximum number of events per request
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.settings.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Quiet period to wait for more events before posting a partial batch
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.settings.debounce = debounce;
+        self
+    }
+
+    /// Number of retries after a failed request, with the initial backoff delay
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.settings.max_retries = max_retries;
+        self.settings.retry_backoff = backoff;
+        self
+    }
+
+    /// Field naming and schema version of the posted events
+    pub fn with_json_format(mut self, format: JsonFormat) -> Self {
+        self.settings.format = format;
+        self
+    }
+
+    /// Start the delivery thread on first use
+    fn sender(&mut self) -> &Sender<WebhookMessage> {
+        if self.sender.is_none() {
+            let (tx, rx) = mpsc::channel();
+            let url = self.url.clone();
+            let settings = self.settings.clone();
+            self.worker = Some(thread::spawn(move || {
+                let mut batch = Vec::with_capacity(settings.batch_size);
+                loop {
+                    // Wait for the next event, posting the partial batch once things go quiet
+                    let message = if batch.is_empty() {
+                        rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                    } else {
+                        rx.recv_timeout(settings.debounce)
+                    };
+
+                    match message {
+                        Ok(WebhookMessage::Event(event)) => {
+                            batch.push(event);
+                            if batch.len() >= settings.batch_size {
+                                deliver(&url, &settings, &mut batch);
+                            }
+                        }
+                        Ok(WebhookMessage::Flush) | Err(RecvTimeoutError::Timeout) => {
+                            deliver(&url, &settings, &mut batch);
+                        }
+                        Err(RecvTimeoutError::Disconnected) => {
+                            deliver(&url, &settings, &mut batch);
+                            break;
+                        }
+                    }
+                }
+            }));
+            self.sender = Some(tx);
+        }
+        self.sender.as_ref().expect("webhook worker started")
+    }
+}
+
+impl EventSink for WebhookSink {
+    fn handle(&mut self, event: &CursorEvent) {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&event.kind()) {
+                return;
+            }
+        }
+        let _ = self.sender().send(WebhookMessage::Event(event.clone()));
+    }
+
+    fn flush(&mut self) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(WebhookMessage::Flush);
+        }
+    }
+}
+
+impl Drop for WebhookSink {
+    fn drop(&mut self) {
+        // Closing the channel makes the worker post what is left and exit
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Post a batch with retries, clearing it afterwards
+fn deliver(url: &WebhookUrl, settings: &WebhookSettings, batch: &mut Vec<CursorEvent>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let events: Vec<_> = batch.iter().map(|event| settings.format.to_value(event)).collect();
+    let body = match serde_json::to_string(&events) {
+        Ok(body) => body,
+        Err(error) => {
+            CursorDetector::log_message(&format!("Webhook serialization failed: {}", error));
+            batch.clear();
+            return;
+        }
+    };
+
+    let started = Instant::now();
+    let mut backoff = settings.retry_backoff;
+    for attempt in 0..=settings.max_retries {
+        match post_json(url, body.as_bytes()) {
+            Ok(status) if (200..300).contains(&status) => break,
+            Ok(status) if status != 429 && status < 500 => {
+                // Client errors will not succeed on retry
+                CursorDetector::log_message(&format!("Webhook rejected batch with status {}", status));
+                break;
+            }
+            result => {
+                if attempt == settings.max_retries {
+                    CursorDetector::log_message(&format!(
+                        "Webhook delivery failed after {} attempts ({:?}), dropping {} events after {:?}",
+                        attempt + 1,
+                        result,
+                        batch.len(),
+                        started.elapsed()
+                    ));
+                } else {
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+
+    batch.clear();
+}
+
+/// Send a single JSON POST request and return the HTTP status code
+fn post_json(url: &WebhookUrl, body: &[u8]) -> Result<u32, String> {
+    unsafe {
+        let session = WinHttpOpen(
+            &HSTRING::from("luuma_cursor_helper"),
+            WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+            PCWSTR::null(),
+            PCWSTR::null(),
+            0,
+        );
+        if session.is_null() {
+            return Err(format!("WinHttpOpen failed: {}", windows::core::Error::from_win32()));
+        }
+        let _ = WinHttpSetTimeouts(session, 5000, 5000, 10000, 10000);
+
+        let connection = WinHttpConnect(session, &HSTRING::from(url.host.as_str()), url.port, 0);
+        if connection.is_null() {
+            let error = windows::core::Error::from_win32();
+            let _ = WinHttpCloseHandle(session);
+            return Err(format!("WinHttpConnect failed: {}", error));
+        }
+
+        let flags = if url.secure { WINHTTP_FLAG_SECURE } else { WINHTTP_OPEN_REQUEST_FLAGS(0) };
+        let request = WinHttpOpenRequest(
+            connection,
+            &HSTRING::from("POST"),
+            &HSTRING::from(url.path.as_str()),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            std::ptr::null(),
+            flags,
+        );
+        if request.is_null() {
+            let error = windows::core::Error::from_win32();
+            let _ = WinHttpCloseHandle(connection);
+            let _ = WinHttpCloseHandle(session);
+            return Err(format!("WinHttpOpenRequest failed: {}", error));
+        }
+
+        let headers: Vec<u16> = "Content-Type: application/json\r\n".encode_utf16().collect();
+        let result = WinHttpSendRequest(
+            request,
+            Some(&headers),
+            Some(body.as_ptr() as *const _),
+            body.len() as u32,
+            body.len() as u32,
+            0,
+        )
+        .and_then(|_| WinHttpReceiveResponse(request, std::ptr::null_mut()))
+        .and_then(|_| {
+            let mut status: u32 = 0;
+            let mut length = std::mem::size_of::<u32>() as u32;
+            WinHttpQueryHeaders(
+                request,
+                WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+                PCWSTR::null(),
+                Some(&mut status as *mut u32 as *mut _),
+                &mut length,
+                std::ptr::null_mut(),
+            )
+            .map(|_| status)
+        })
+        .map_err(|error| format!("Webhook request failed: {}", error));
+
+        let _ = WinHttpCloseHandle(request);
+        let _ = WinHttpCloseHandle(connection);
+        let _ = WinHttpCloseHandle(session);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WebhookUrl;
+
+    fn parse(url: &str) -> (bool, String, u16, String) {
+        let url = WebhookUrl::parse(url).unwrap();
+        (url.secure, url.host, url.port, url.path)
+    }
+
+    #[test]
+    fn parses_host_port_and_path() {
+        assert_eq!(parse("https://example.com/hook"), (true, "example.com".into(), 443, "/hook".into()));
+        assert_eq!(parse("http://example.com:8080"), (false, "example.com".into(), 8080, "/".into()));
+    }
+
+    #[test]
+    fn parses_ipv6_literals() {
+        assert_eq!(parse("http://[::1]:8080/x"), (false, "::1".into(), 8080, "/x".into()));
+        assert_eq!(parse("http://[::1]/x"), (false, "::1".into(), 80, "/x".into()));
+        assert_eq!(parse("https://[fe80::1]"), (true, "fe80::1".into(), 443, "/".into()));
+        assert!(WebhookUrl::parse("http://[::1/x").is_err());
+        assert!(WebhookUrl::parse("http://[::1]x/").is_err());
+    }
+
+    #[test]
+    fn query_and_fragment_end_the_authority() {
+        assert_eq!(parse("http://host?a=1"), (false, "host".into(), 80, "/?a=1".into()));
+        assert_eq!(parse("http://host:81#top"), (false, "host".into(), 81, "/".into()));
+        assert_eq!(parse("http://host/p?a=1#top"), (false, "host".into(), 80, "/p?a=1".into()));
+    }
+
+    #[test]
+    fn rejects_bad_urls() {
+        assert!(WebhookUrl::parse("ftp://host/").is_err());
+        assert!(WebhookUrl::parse("http://host:http/").is_err());
+        assert!(WebhookUrl::parse("http:///path").is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! Serving events to browsers and other WebSocket clients
+
+use crate::json::JsonFormat;
+use crate::sink::EventSink;
+use crate::{CursorDetector, CursorEvent, EventKind};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use windows::Win32::Security::Cryptography::{BCryptHash, BCRYPT_SHA1_ALG_HANDLE};
+
+/// Messages waiting to be broadcast before new ones are dropped
+const QUEUE_CAPACITY: usize = 1024;
+
+/// How often the accept loop checks for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Clients that do not finish the handshake or take a frame within this long are dropped
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Appended to the client's key to prove the server speaks WebSocket (RFC 6455)
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Sink broadcasting every event as a JSON text message to connected WebSocket clients
+///
+/// Clients connect to `ws://<address>/` with any path, e.g. `new WebSocket("ws://localhost:9001")`
+/// from a browser. Messages go out from a background thread; a client that stops reading is
+/// disconnected rather than holding up the others. Messages from clients are ignored.
+pub struct WebSocketSink {
+    kinds: Option<Vec<EventKind>>,
+    format: JsonFormat,
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+    local_address: SocketAddr,
+    sender: Option<SyncSender<String>>,
+    running: Arc<AtomicBool>,
+    acceptor: Option<thread::JoinHandle<()>>,
+    broadcaster: Option<thread::JoinHandle<()>>,
+}
+
+impl WebSocketSink {
+    /// Listen on `address`, e.g. `"127.0.0.1:9001"`
+    pub fn bind(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("Failed to bind {}: {}", address, e))?;
+        let local_address = listener.local_addr().map_err(|e| format!("Failed to read bound address: {}", e))?;
+        listener.set_nonblocking(true).map_err(|e| format!("Failed to configure listener: {}", e))?;
+
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let (accept_clients, accept_running) = (Arc::clone(&clients), Arc::clone(&running));
+        let acceptor = thread::Builder::new()
+            .name("luuma-websocket".to_string())
+            .spawn(move || {
+                while accept_running.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, address)) => accept(&accept_clients, stream, address),
+                        Err(error) if error.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                        Err(_) => {}
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn WebSocket thread: {}", e))?;
+
+        let (sender, receiver) = mpsc::sync_channel::<String>(QUEUE_CAPACITY);
+        let broadcast_clients = Arc::clone(&clients);
+        let broadcaster = thread::Builder::new()
+            .name("luuma-websocket-broadcast".to_string())
+            .spawn(move || {
+                for message in receiver {
+                    let frame = text_frame(&message);
+                    let mut clients = broadcast_clients.lock().unwrap_or_else(|e| e.into_inner());
+                    clients.retain_mut(|client| client.write_all(&frame).is_ok());
+                }
+            })
+            .map_err(|e| format!("Failed to spawn WebSocket broadcast thread: {}", e))?;
+
+        Ok(Self {
+            kinds: None,
+            format: JsonFormat::default(),
+            clients,
+            local_address,
+            sender: Some(sender),
+            running,
+            acceptor: Some(acceptor),
+            broadcaster: Some(broadcaster),
+        })
+    }
+
+    /// Only broadcast events of the given kinds (all kinds by default)
+    pub fn with_kinds(mut self, kinds: &[EventKind]) -> Self {
+        self.kinds = Some(kinds.to_vec());
+        self
+    }
+
+    /// Field naming and schema version of the broadcast events
+    pub fn with_json_format(mut self, format: JsonFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Address the server is listening on, useful after binding port 0
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    /// Clients currently connected
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl EventSink for WebSocketSink {
+    fn handle(&mut self, event: &CursorEvent) {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&event.kind()) {
+                return;
+            }
+        }
+        // Serialize once here rather than per client on the broadcast thread
+        if let Some(sender) = &self.sender {
+            let _ = sender.try_send(self.format.to_string(event));
+        }
+    }
+}
+
+impl Drop for WebSocketSink {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        // Closing the channel makes the broadcaster send what is queued and exit
+        self.sender.take();
+        for worker in [self.acceptor.take(), self.broadcaster.take()].into_iter().flatten() {
+            let _ = worker.join();
+        }
+        for mut client in self.clients.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+            // Close frame with no status, then hang up
+            let _ = client.write_all(&[0x88, 0x00]);
+            let _ = client.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Complete the opening handshake and add the connection to the broadcast list
+fn accept(clients: &Arc<Mutex<Vec<TcpStream>>>, stream: TcpStream, address: SocketAddr) {
+    let clients = Arc::clone(clients);
+    // A slow client must not hold up the accept loop
+    let _ = thread::Builder::new().name("luuma-websocket-handshake".to_string()).spawn(move || {
+        match handshake(stream) {
+            Ok(stream) => clients.lock().unwrap_or_else(|e| e.into_inner()).push(stream),
+            Err(error) => CursorDetector::log_message(&format!("WebSocket handshake with {} failed: {}", address, error)),
+        }
+    });
+}
+
+/// Read the HTTP upgrade request and switch the connection to WebSocket
+fn handshake(stream: TcpStream) -> Result<TcpStream, String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT)).map_err(|e| e.to_string())?;
+    let _ = stream.set_nodelay(true);
+
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut key = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Err("Connection closed during handshake".to_string());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Sec-WebSocket-Key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut stream = stream;
+    let Some(key) = key else {
+        let _ = stream.write_all(b"HTTP/1.1 426 Upgrade Required\r\nConnection: close\r\nContent-Length: 0\r\n\r\n");
+        return Err("Not a WebSocket upgrade request".to_string());
+    };
+    let accept = base64(&sha1(format!("{}{}", key, HANDSHAKE_GUID).as_bytes())?);
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    );
+    stream.write_all(response.as_bytes()).map_err(|e| e.to_string())?;
+    // Nothing is read after the handshake
+    stream.set_read_timeout(None).map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+/// Unmasked, unfragmented text frame carrying `message`
+fn text_frame(message: &str) -> Vec<u8> {
+    let payload = message.as_bytes();
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x81);
+    match payload.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn sha1(data: &[u8]) -> Result<[u8; 20], String> {
+    let mut digest = [0u8; 20];
+    unsafe { BCryptHash(BCRYPT_SHA1_ALG_HANDLE, None, data, &mut digest) }
+        .ok()
+        .map_err(|e| format!("SHA-1 failed: {}", e))?;
+    Ok(digest)
+}
+
+/// Standard padded base64, as the handshake and obs-websocket authentication use
+pub(crate) fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        let bytes = [group[0], *group.get(1).unwrap_or(&0), *group.get(2).unwrap_or(&0)];
+        let bits = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for index in 0..4 {
+            if index <= group.len() {
+                encoded.push(ALPHABET[((bits >> (18 - 6 * index)) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,222 @@
+//! Resolution of the window under the cursor
+
+#[cfg(windows)]
+use crate::CursorEvent;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::sync::Arc;
+#[cfg(windows)]
+use windows::core::PWSTR;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, HWND, POINT, RECT};
+#[cfg(windows)]
+use windows::Win32::Graphics::Gdi::ScreenToClient;
+#[cfg(windows)]
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetAncestor, GetClassNameW, GetClientRect, GetWindowTextW, GetWindowThreadProcessId, WindowFromPoint, GA_ROOT,
+};
+
+/// Top-level window and owning process
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WindowContext {
+    /// Raw `HWND` value
+    pub hwnd: usize,
+    pub title: String,
+    pub class_name: String,
+    /// Executable file name such as `explorer.exe`, empty when access is denied
+    pub process_name: String,
+    pub pid: u32,
+}
+
+impl WindowContext {
+    /// Describe a window from its raw `HWND` value
+    #[cfg(windows)]
+    pub fn from_hwnd(hwnd: usize) -> Option<Self> {
+        let window = HWND(hwnd as *mut _);
+        if window.is_invalid() {
+            return None;
+        }
+
+        unsafe {
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(window, Some(&mut pid));
+
+            Some(Self {
+                hwnd,
+                title: read_wide(|buffer| GetWindowTextW(window, buffer)),
+                class_name: read_wide(|buffer| GetClassNameW(window, buffer)),
+                process_name: process_name(pid).unwrap_or_default(),
+                pid,
+            })
+        }
+    }
+}
+
+/// Position relative to a window's client area
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ClientPosition {
+    /// Raw `HWND` the coordinates are relative to
+    pub hwnd: usize,
+    /// Coordinates relative to the top-left of the client area
+    pub position: (f64, f64),
+    /// Whether the point lies inside the client area
+    pub inside: bool,
+}
+
+/// Convert a screen position to a window's client-area coordinates
+#[cfg(windows)]
+pub fn client_position(hwnd: usize, position: (f64, f64)) -> Option<ClientPosition> {
+    let window = HWND(hwnd as *mut _);
+    let mut point = POINT { x: position.0 as i32, y: position.1 as i32 };
+    let mut client = RECT::default();
+
+    unsafe {
+        if !ScreenToClient(window, &mut point).as_bool() || GetClientRect(window, &mut client).is_err() {
+            return None;
+        }
+    }
+
+    // Keep the sub-pixel part of the screen position
+    let local = (point.x as f64 + position.0.fract(), point.y as f64 + position.1.fract());
+    Some(ClientPosition {
+        hwnd,
+        position: local,
+        inside: local.0 >= 0.0 && local.1 >= 0.0 && local.0 < client.right as f64 && local.1 < client.bottom as f64,
+    })
+}
+
+/// Raw `HWND` of the top-level window under a screen position
+#[cfg(windows)]
+pub fn window_handle_at(position: (f64, f64)) -> Option<usize> {
+    unsafe {
+        let window = WindowFromPoint(POINT { x: position.0 as i32, y: position.1 as i32 });
+        if window.is_invalid() {
+            return None;
+        }
+        let root = GetAncestor(window, GA_ROOT);
+        let root = if root.is_invalid() { window } else { root };
+        Some(root.0 as usize)
+    }
+}
+
+/// Describe the top-level window under a screen position
+#[cfg(windows)]
+pub fn window_at(position: (f64, f64)) -> Option<WindowContext> {
+    window_handle_at(position).and_then(WindowContext::from_hwnd)
+}
+
+/// Executable file name of a process
+#[cfg(windows)]
+pub(crate) fn process_name(pid: u32) -> Option<String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 260];
+        let mut length = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut length);
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&buffer[..length as usize]);
+        Some(path.rsplit('\\').next().unwrap_or(&path).to_string())
+    }
+}
+
+#[cfg(windows)]
+fn read_wide<F>(read: F) -> String
+where
+    F: FnOnce(&mut [u16]) -> i32,
+{
+    let mut buffer = [0u16; 512];
+    let length = read(&mut buffer).clamp(0, buffer.len() as i32) as usize;
+    String::from_utf16_lossy(&buffer[..length])
+}
+
+/// Caches the hovered window handle on the hook thread
+///
+/// Only the handle is filled in: reading a title sends the window a message and
+/// process names need the process opened, either of which can stall the low-level
+/// hook. [`WindowResolver`] describes the window on the processing thread.
+#[cfg(windows)]
+pub(crate) struct WindowTracker {
+    current: Option<Arc<WindowContext>>,
+}
+
+#[cfg(windows)]
+impl WindowTracker {
+    pub(crate) fn new() -> Self {
+        Self { current: None }
+    }
+
+    /// Handle of the window under a position, flagging whether it differs from the last one
+    pub(crate) fn update(&mut self, position: (f64, f64)) -> (Option<Arc<WindowContext>>, bool) {
+        let handle = window_handle_at(position);
+        if let (Some(current), Some(handle)) = (&self.current, handle) {
+            if current.hwnd == handle {
+                return (Some(Arc::clone(current)), false);
+            }
+        }
+
+        self.current = handle.map(|hwnd| {
+            Arc::new(WindowContext {
+                hwnd,
+                title: String::new(),
+                class_name: String::new(),
+                process_name: String::new(),
+                pid: 0,
+            })
+        });
+        (self.current.clone(), self.current.is_some())
+    }
+}
+
+/// Describes the windows attached by [`WindowTracker`], before events are recorded or delivered
+///
+/// Details are fetched once per hovered window and shared by the events that follow,
+/// and fetched again whenever the cursor enters a window.
+#[cfg(windows)]
+pub(crate) struct WindowResolver {
+    current: Option<Arc<WindowContext>>,
+}
+
+#[cfg(windows)]
+impl WindowResolver {
+    pub(crate) fn new() -> Self {
+        Self { current: None }
+    }
+
+    pub(crate) fn resolve(&mut self, event: &mut CursorEvent) {
+        let entered = matches!(event, CursorEvent::WindowChanged { .. });
+        let window = match event {
+            CursorEvent::Move { window: Some(window), .. }
+            | CursorEvent::Click { window: Some(window), .. }
+            | CursorEvent::WindowChanged { window, .. } => window,
+            _ => return,
+        };
+        *window = self.describe(window, entered);
+    }
+
+    /// Described top-level window under a position, for events that carry none
+    pub(crate) fn at(&mut self, position: (f64, f64)) -> Option<Arc<WindowContext>> {
+        let hwnd = window_handle_at(position)?;
+        match &self.current {
+            Some(current) if current.hwnd == hwnd => Some(Arc::clone(current)),
+            _ => {
+                self.current = WindowContext::from_hwnd(hwnd).map(Arc::new);
+                self.current.clone()
+            }
+        }
+    }
+
+    fn describe(&mut self, window: &Arc<WindowContext>, entered: bool) -> Arc<WindowContext> {
+        let current = match &self.current {
+            Some(current) if !entered && current.hwnd == window.hwnd => Arc::clone(current),
+            _ => WindowContext::from_hwnd(window.hwnd).map(Arc::new).unwrap_or_else(|| Arc::clone(window)),
+        };
+        self.current = Some(Arc::clone(&current));
+        current
+    }
+}